@@ -1,4 +1,4 @@
-use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
 use opendb::{Memory, OpenDB, OpenDBOptions};
 use std::collections::HashMap;
 use std::hint::black_box;
@@ -43,9 +43,12 @@ fn memory_benchmarks(c: &mut Criterion) {
                 id: format!("mem_{}", counter),
                 content: "Test memory content for benchmarking".to_string(),
                 embedding: vec![0.1; 384],
+                embeddings: HashMap::new(),
                 importance: 0.5,
                 timestamp: chrono::Utc::now().timestamp(),
                 metadata: HashMap::new(),
+                expires_at: None,
+                last_decayed: None,
             };
             counter += 1;
             db.insert_memory(&memory).unwrap();
@@ -64,9 +67,12 @@ fn memory_benchmarks(c: &mut Criterion) {
                 id: format!("mem_{}", i),
                 content: format!("Memory {}", i),
                 embedding: vec![0.1; 384],
+                embeddings: HashMap::new(),
                 importance: 0.5,
                 timestamp: chrono::Utc::now().timestamp(),
                 metadata: HashMap::new(),
+                expires_at: None,
+                last_decayed: None,
             };
             ids.push(memory.id.clone());
             db.insert_memory(&memory).unwrap();
@@ -83,6 +89,42 @@ fn memory_benchmarks(c: &mut Criterion) {
     group.finish();
 }
 
+fn value_compression_benchmarks(c: &mut Criterion) {
+    use rand::{Rng, SeedableRng};
+
+    let mut group = c.benchmark_group("value_compression");
+
+    // ~100KB of words sampled from the README; zstd stores it in ~29KB
+    let words: Vec<&str> = include_str!("../README.md").split_whitespace().collect();
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let mut text = String::new();
+    while text.len() < 100 * 1024 {
+        text.push_str(words[rng.gen_range(0..words.len())]);
+        text.push(' ');
+    }
+
+    for compress in [false, true] {
+        group.bench_with_input(
+            BenchmarkId::new("insert_100kb", compress),
+            &compress,
+            |b, &compress| {
+                let temp_dir = TempDir::new().unwrap();
+                let options = OpenDBOptions::with_dimension(384).with_value_compression(compress);
+                let db = OpenDB::open_with_options(temp_dir.path(), options).unwrap();
+                let mut counter = 0;
+
+                b.iter(|| {
+                    let memory = Memory::new(format!("mem_{}", counter), text.clone(), vec![], 0.5);
+                    counter += 1;
+                    db.insert_memory(&memory).unwrap();
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
 fn vector_search_benchmarks(c: &mut Criterion) {
     let mut group = c.benchmark_group("vector_search");
 
@@ -100,9 +142,12 @@ fn vector_search_benchmarks(c: &mut Criterion) {
                     id: format!("mem_{}", i),
                     content: format!("Test memory {}", i),
                     embedding,
+                    embeddings: HashMap::new(),
                     importance: ((i % 100) as f32) / 100.0,
                     timestamp: chrono::Utc::now().timestamp(),
                     metadata: HashMap::new(),
+                    expires_at: None,
+                    last_decayed: None,
                 };
                 db.insert_memory(&memory).unwrap();
             }
@@ -118,6 +163,51 @@ fn vector_search_benchmarks(c: &mut Criterion) {
     group.finish();
 }
 
+fn threshold_search_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("threshold_search");
+    let dimension = 1536;
+
+    for early_exit in [true, false] {
+        let label = if early_exit { "early_exit" } else { "full" };
+        group.bench_with_input(
+            BenchmarkId::from_parameter(label),
+            &early_exit,
+            |b, &early_exit| {
+                let temp_dir = TempDir::new().unwrap();
+                let options =
+                    OpenDBOptions::with_dimension(dimension).with_threshold_early_exit(early_exit);
+                let db = OpenDB::open_with_options(temp_dir.path(), options).unwrap();
+
+                for i in 0..1000 {
+                    let embedding: Vec<f32> = (0..dimension)
+                        .map(|j| (i as f32 + j as f32) / 1000.0)
+                        .collect();
+                    let memory = Memory {
+                        id: format!("mem_{}", i),
+                        content: format!("Test memory {}", i),
+                        embedding,
+                        embeddings: HashMap::new(),
+                        importance: ((i % 100) as f32) / 100.0,
+                        timestamp: chrono::Utc::now().timestamp(),
+                        metadata: HashMap::new(),
+                        expires_at: None,
+                        last_decayed: None,
+                    };
+                    db.insert_memory(&memory).unwrap();
+                }
+
+                let query: Vec<f32> = (0..dimension).map(|j| j as f32 / 1000.0).collect();
+
+                b.iter(|| {
+                    black_box(db.search_within_distance(&query, 1.0).unwrap());
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
 fn graph_benchmarks(c: &mut Criterion) {
     let mut group = c.benchmark_group("graph_operations");
 
@@ -132,9 +222,12 @@ fn graph_benchmarks(c: &mut Criterion) {
                 id: format!("mem_{}", i),
                 content: format!("Test memory {}", i),
                 embedding: vec![0.1; 384],
+                embeddings: HashMap::new(),
                 importance: 0.5,
                 timestamp: chrono::Utc::now().timestamp(),
                 metadata: HashMap::new(),
+                expires_at: None,
+                last_decayed: None,
             };
             db.insert_memory(&memory).unwrap();
         }
@@ -159,15 +252,17 @@ fn graph_benchmarks(c: &mut Criterion) {
                 id: format!("mem_{}", i),
                 content: format!("Test memory {}", i),
                 embedding: vec![0.1; 384],
+                embeddings: HashMap::new(),
                 importance: 0.5,
                 timestamp: chrono::Utc::now().timestamp(),
                 metadata: HashMap::new(),
+                expires_at: None,
+                last_decayed: None,
             };
             db.insert_memory(&memory).unwrap();
 
             if i > 0 {
-                db.link(&format!("mem_0"), "related", &format!("mem_{}", i))
-                    .unwrap();
+                db.link("mem_0", "related", &format!("mem_{}", i)).unwrap();
             }
         }
 
@@ -198,12 +293,69 @@ fn transaction_benchmarks(c: &mut Criterion) {
     group.finish();
 }
 
+fn bulk_load_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bulk_load");
+    group.sample_size(10);
+
+    // Text-only records, so the write path rather than HNSW dominates
+    const RECORDS: usize = 100_000;
+    let memories: Vec<Memory> = (0..RECORDS)
+        .map(|i| {
+            Memory::new(
+                format!("mem_{:06}", i),
+                format!("Record {}", i),
+                Vec::new(),
+                0.5,
+            )
+        })
+        .collect();
+    let fresh_db = || {
+        let temp_dir = TempDir::new().unwrap();
+        let db = OpenDB::open(temp_dir.path()).unwrap();
+        (db, temp_dir)
+    };
+
+    // Same batch size as bulk_load, but every batch goes through the WAL
+    group.bench_function("100k_with_wal", |b| {
+        b.iter_batched(
+            fresh_db,
+            |(db, _temp)| {
+                for chunk in memories.chunks(2_500) {
+                    db.insert_memories(chunk).unwrap();
+                }
+            },
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.bench_function("100k_without_wal", |b| {
+        b.iter_batched(
+            fresh_db,
+            |(db, _temp)| {
+                db.bulk_load(|loader| {
+                    for memory in &memories {
+                        loader.insert_memory(memory)?;
+                    }
+                    Ok(())
+                })
+                .unwrap();
+            },
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     kv_benchmarks,
     memory_benchmarks,
+    value_compression_benchmarks,
     vector_search_benchmarks,
+    threshold_search_benchmarks,
     graph_benchmarks,
-    transaction_benchmarks
+    transaction_benchmarks,
+    bulk_load_benchmarks
 );
 criterion_main!(benches);
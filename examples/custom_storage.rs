@@ -119,7 +119,7 @@ fn main() -> Result<()> {
         // Insert tenant-specific data
         let memory = Memory::new(
             "tenant_data",
-            &format!("Data for {}", tenant),
+            format!("Data for {}", tenant),
             vec![0.1; 384],
             0.8,
         );
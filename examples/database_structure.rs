@@ -39,8 +39,8 @@ fn main() -> Result<()> {
     println!("{}", "Inserting sample data...".bright_yellow());
     for i in 0..5 {
         let memory = Memory::new(
-            &format!("demo_{}", i),
-            &format!("Sample content {}", i),
+            format!("demo_{}", i),
+            format!("Sample content {}", i),
             vec![0.1 * i as f32; 384],
             0.5 + (i as f32 * 0.1),
         );
@@ -150,29 +150,24 @@ fn main() -> Result<()> {
     println!();
 
     println!(
-        "  {} {}",
-        "✅".green(),
-        "Higher write throughput (sequential WAL writes)"
+        "  {} Higher write throughput (sequential WAL writes)",
+        "✅".green()
     );
     println!(
-        "  {} {}",
-        "✅".green(),
-        "Better compression (data compressed in SST files)"
+        "  {} Better compression (data compressed in SST files)",
+        "✅".green()
     );
     println!(
-        "  {} {}",
-        "✅".green(),
-        "Efficient compaction (background merging)"
+        "  {} Efficient compaction (background merging)",
+        "✅".green()
     );
     println!(
-        "  {} {}",
-        "✅".green(),
-        "Crash recovery (WAL enables reliable recovery)"
+        "  {} Crash recovery (WAL enables reliable recovery)",
+        "✅".green()
     );
     println!(
-        "  {} {}",
-        "✅".green(),
-        "Horizontal scaling (easier to distribute files)"
+        "  {} Horizontal scaling (easier to distribute files)",
+        "✅".green()
     );
     println!();
 
@@ -45,7 +45,7 @@ fn main() -> Result<()> {
         println!(
             "  - {} (relevance: {:.2})",
             result.memory.content,
-            1.0 / (1.0 + result.distance)
+            result.relevance()
         );
     }
 
@@ -58,7 +58,7 @@ fn main() -> Result<()> {
         println!(
             "  - {} (relevance: {:.2})",
             result.memory.content,
-            1.0 / (1.0 + result.distance)
+            result.relevance()
         );
     }
 
@@ -21,7 +21,7 @@ fn main() -> Result<()> {
 
     // Open database with 384-dimensional embeddings (common for AI models)
     let options = OpenDBOptions::with_dimension(384);
-    let _db = OpenDB::open_with_options("./data/multimodal_agent", options)?;
+    let db = OpenDB::open_with_options("./data/multimodal_agent", options)?;
 
     // Example 1: Process a PDF document
     println!("{}", "📄 Processing PDF document...".bright_yellow());
@@ -189,7 +189,19 @@ fn main() -> Result<()> {
         );
     }
 
-    // Demonstrate semantic search across multimodal documents
+    // Store all documents; chunk embeddings are indexed for search
+    println!();
+    println!("{}", "💾 Storing documents...".bright_magenta());
+    for doc in [&pdf_doc, &docx_doc, &audio_doc, &video_doc, &text_doc] {
+        db.insert_document(doc)?;
+    }
+    println!(
+        "  {} Stored {} documents",
+        "✓".green(),
+        db.list_documents("doc_")?.len()
+    );
+
+    // Semantic search over document chunks
     println!();
     println!("{}", "🔎 Semantic search example:".bright_magenta());
     println!(
@@ -197,15 +209,16 @@ fn main() -> Result<()> {
         "Query".bright_white(),
         "machine learning research".green()
     );
-    println!(
-        "  This would search across {} document types:",
-        "ALL".bright_red()
-    );
-    println!("    {} PDF research papers", "•".bright_blue());
-    println!("    {} Audio transcriptions", "•".bright_blue());
-    println!("    {} Video captions", "•".bright_blue());
-    println!("    {} Text notes", "•".bright_blue());
-    println!("    {} DOCX meeting notes", "•".bright_blue());
+    let query = generate_embedding("machine learning research");
+    for result in db.search_chunks_dedup(&query, 3)? {
+        println!(
+            "  {} {} (distance: {:.4}): {}",
+            "•".bright_blue(),
+            result.id.cyan(),
+            result.distance,
+            result.memory.content
+        );
+    }
 
     // Production workflow summary
     println!();
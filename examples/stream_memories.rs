@@ -0,0 +1,52 @@
+// Streaming example for OpenDB
+//
+// This demonstrates processing every memory with constant memory use:
+// `iter_memories` reads and decodes one record at a time instead of
+// collecting the whole records column family like `list_memories`.
+
+use opendb::{Memory, OpenDB, OpenDBOptions, Result};
+
+fn main() -> Result<()> {
+    println!("=== OpenDB Streaming ===\n");
+
+    let db = OpenDB::open_with_options("./stream_memories_db", OpenDBOptions::with_dimension(4))?;
+
+    // Load a corpus in batches
+    let memories: Vec<Memory> = (0..10_000)
+        .map(|i| {
+            let importance = (i % 100) as f32 / 100.0;
+            Memory::new(
+                format!("memory_{:05}", i),
+                format!("Observation number {}", i),
+                vec![i as f32, 1.0, 0.0, 0.0],
+                importance,
+            )
+        })
+        .collect();
+    for batch in memories.chunks(1000) {
+        db.insert_memories(batch)?;
+    }
+    println!("✓ Inserted {} memories", memories.len());
+    drop(memories);
+
+    // An empty prefix streams every memory; only one is decoded at a time
+    let mut important = 0;
+    for memory in db.iter_memories("")? {
+        if memory?.importance >= 0.9 {
+            important += 1;
+        }
+    }
+    println!("✓ {} memories have importance >= 0.9", important);
+
+    // Iterator adapters work too, and stop reading as soon as they can
+    let first_important = db
+        .iter_memories("")?
+        .filter_map(|memory| memory.ok())
+        .find(|memory| memory.importance >= 0.99);
+    if let Some(memory) = first_important {
+        println!("✓ First memory with importance >= 0.99: {}", memory.id);
+    }
+
+    println!("\n=== Streaming Complete ===");
+    Ok(())
+}
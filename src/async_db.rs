@@ -0,0 +1,294 @@
+// Async API (feature `async`)
+//
+// `AsyncOpenDB` wraps a sync `OpenDB` and runs every call on a small pool
+// of blocking worker threads, so async handlers never block their executor.
+// Futures are completed through their waker and don't depend on any
+// particular runtime.
+//
+// This deliberately doesn't use tokio's `spawn_blocking`: the pool is plain
+// threads on a crossbeam channel (already a dependency), so the feature adds
+// no runtime dependency and works under any executor. The tradeoff is that
+// the pool is sized here rather than shared with the application's runtime.
+
+use crate::database::OpenDB;
+use crate::error::{Error, Result};
+use crate::types::{Edge, Memory, MultimodalDocument, SearchResult};
+use crossbeam::channel::{self, Sender};
+use parking_lot::Mutex;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+
+/// Work item run by a pool thread
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Async handle to an `OpenDB`
+///
+/// Cloning is cheap; clones share the database and the worker pool. When the
+/// last clone is dropped, the drop waits for queued and running operations
+/// to finish, then the workers exit.
+#[derive(Clone)]
+pub struct AsyncOpenDB {
+    db: Arc<OpenDB>,
+    pool: Arc<WorkerPool>,
+}
+
+/// Blocking worker threads fed from one job queue
+struct WorkerPool {
+    /// Taken on drop to close the queue
+    jobs: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        // Workers drain the queue, then exit once it's closed
+        self.jobs.take();
+        let current = thread::current().id();
+        for worker in self.workers.drain(..) {
+            // A job may hold the last clone; a worker can't join itself
+            if worker.thread().id() != current {
+                let _ = worker.join();
+            }
+        }
+    }
+}
+
+impl AsyncOpenDB {
+    /// Wrap a database with one worker per available CPU
+    pub fn new(db: OpenDB) -> Result<Self> {
+        let threads = thread::available_parallelism().map_or(4, |n| n.get());
+        Self::with_threads(db, threads)
+    }
+
+    /// Wrap a database with a fixed number of worker threads
+    ///
+    /// At most `threads` operations run at once; the rest wait in a queue.
+    /// Fails with `Error::Io` if a worker thread can't be spawned.
+    pub fn with_threads(db: OpenDB, threads: usize) -> Result<Self> {
+        let (jobs, queue) = channel::unbounded::<Job>();
+        let mut pool = WorkerPool {
+            jobs: Some(jobs),
+            workers: Vec::with_capacity(threads.max(1)),
+        };
+        for i in 0..threads.max(1) {
+            let queue = queue.clone();
+            // On failure, dropping `pool` shuts down the workers already spawned
+            let worker = thread::Builder::new()
+                .name(format!("opendb-blocking-{}", i))
+                .spawn(move || {
+                    for job in queue {
+                        job();
+                    }
+                })?;
+            pool.workers.push(worker);
+        }
+
+        Ok(Self {
+            db: Arc::new(db),
+            pool: Arc::new(pool),
+        })
+    }
+
+    /// The wrapped database, for sync calls or operations not wrapped here
+    pub fn sync(&self) -> &OpenDB {
+        &self.db
+    }
+
+    /// Run `f` against the database on a worker thread
+    ///
+    /// This is the escape hatch for any sync method without an async
+    /// counterpart. A panic in `f` resumes in the awaiting task.
+    pub fn run<T, F>(&self, f: F) -> impl Future<Output = Result<T>> + use<T, F>
+    where
+        T: Send + 'static,
+        F: FnOnce(&OpenDB) -> Result<T> + Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(TaskState {
+            outcome: None,
+            waker: None,
+        }));
+
+        let db = Arc::clone(&self.db);
+        let task_state = Arc::clone(&state);
+        let job: Job = Box::new(move || {
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| f(&db)));
+            let mut state = task_state.lock();
+            state.outcome = Some(outcome);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+
+        let submitted = self
+            .pool
+            .jobs
+            .as_ref()
+            .and_then(|jobs| jobs.send(job).ok())
+            .ok_or_else(|| Error::Internal("OpenDB worker pool has shut down".to_string()));
+        BlockingTask { state, submitted }
+    }
+
+    // ===== Key-Value Operations =====
+
+    /// Get a value by key
+    pub async fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let key = key.to_vec();
+        self.run(move |db| db.get(&key)).await
+    }
+
+    /// Put a key-value pair
+    pub async fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        let (key, value) = (key.to_vec(), value.to_vec());
+        self.run(move |db| db.put(&key, &value)).await
+    }
+
+    /// Delete a key
+    pub async fn delete(&self, key: &[u8]) -> Result<()> {
+        let key = key.to_vec();
+        self.run(move |db| db.delete(&key)).await
+    }
+
+    // ===== Memory Record Operations =====
+
+    /// Insert or update a memory record
+    pub async fn insert_memory(&self, memory: &Memory) -> Result<()> {
+        let memory = memory.clone();
+        self.run(move |db| db.insert_memory(&memory)).await
+    }
+
+    /// Insert or update many memory records at once
+    pub async fn insert_memories(&self, memories: &[Memory]) -> Result<()> {
+        let memories = memories.to_vec();
+        self.run(move |db| db.insert_memories(&memories)).await
+    }
+
+    /// Get a memory record by ID
+    pub async fn get_memory(&self, id: &str) -> Result<Option<Memory>> {
+        let id = id.to_string();
+        self.run(move |db| db.get_memory(&id)).await
+    }
+
+    /// Get several memory records at once
+    pub async fn get_memories(&self, ids: &[&str]) -> Result<Vec<Option<Memory>>> {
+        let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        self.run(move |db| {
+            let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+            db.get_memories(&ids)
+        })
+        .await
+    }
+
+    /// Delete a memory record
+    pub async fn delete_memory(&self, id: &str) -> Result<()> {
+        let id = id.to_string();
+        self.run(move |db| db.delete_memory(&id)).await
+    }
+
+    /// List all memories with a prefix
+    pub async fn list_memories(&self, prefix: &str) -> Result<Vec<Memory>> {
+        let prefix = prefix.to_string();
+        self.run(move |db| db.list_memories(&prefix)).await
+    }
+
+    // ===== Multimodal Document Operations =====
+
+    /// Insert or replace a multimodal document
+    pub async fn insert_document(&self, document: &MultimodalDocument) -> Result<()> {
+        let document = document.clone();
+        self.run(move |db| db.insert_document(&document)).await
+    }
+
+    /// Get a multimodal document by ID
+    pub async fn get_document(&self, id: &str) -> Result<Option<MultimodalDocument>> {
+        let id = id.to_string();
+        self.run(move |db| db.get_document(&id)).await
+    }
+
+    // ===== Graph Operations =====
+
+    /// Create a relationship between two entities
+    pub async fn link(&self, from: &str, relation: &str, to: &str) -> Result<()> {
+        let (from, relation, to) = (from.to_string(), relation.to_string(), to.to_string());
+        self.run(move |db| db.link(&from, &relation, &to)).await
+    }
+
+    /// Remove a relationship
+    pub async fn unlink(&self, from: &str, relation: &str, to: &str) -> Result<()> {
+        let (from, relation, to) = (from.to_string(), relation.to_string(), to.to_string());
+        self.run(move |db| db.unlink(&from, &relation, &to)).await
+    }
+
+    /// Get entities related to `id` by `relation`
+    pub async fn get_related(&self, id: &str, relation: &str) -> Result<Vec<String>> {
+        let (id, relation) = (id.to_string(), relation.to_string());
+        self.run(move |db| db.get_related(&id, &relation)).await
+    }
+
+    /// Get all outgoing edges from an entity
+    pub async fn get_outgoing(&self, from: &str) -> Result<Vec<Edge>> {
+        let from = from.to_string();
+        self.run(move |db| db.get_outgoing(&from)).await
+    }
+
+    /// Get all incoming edges to an entity
+    pub async fn get_incoming(&self, to: &str) -> Result<Vec<Edge>> {
+        let to = to.to_string();
+        self.run(move |db| db.get_incoming(&to)).await
+    }
+
+    // ===== Vector Search Operations =====
+
+    /// Search for similar memories using vector similarity
+    pub async fn search_similar(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+        let query = query.to_vec();
+        self.run(move |db| db.search_similar(&query, k)).await
+    }
+
+    // ===== Utility Operations =====
+
+    /// Flush writes to disk
+    pub async fn flush(&self) -> Result<()> {
+        self.run(|db| db.flush()).await
+    }
+}
+
+/// Shared between a `BlockingTask` and the job completing it
+struct TaskState<T> {
+    outcome: Option<std::thread::Result<Result<T>>>,
+    waker: Option<Waker>,
+}
+
+/// Future resolving to the outcome of a job on the worker pool
+struct BlockingTask<T> {
+    state: Arc<Mutex<TaskState<T>>>,
+    /// Err when the job couldn't be queued
+    submitted: Result<()>,
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Err(e) = std::mem::replace(&mut this.submitted, Ok(())) {
+            return Poll::Ready(Err(e));
+        }
+
+        let mut state = this.state.lock();
+        match state.outcome.take() {
+            Some(Ok(result)) => Poll::Ready(result),
+            Some(Err(payload)) => {
+                drop(state);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
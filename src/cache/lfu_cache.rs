@@ -0,0 +1,279 @@
+// LFU cache implementation
+//
+// Provides a least-frequently-used eviction policy for the cache layer.
+// Ties between entries with the same access count go to the one used least
+// recently, so a cache where every entry is read once behaves like an LRU.
+
+use crate::cache::ByteLimit;
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Cached value with its access count and last-use tick
+struct LfuEntry<V> {
+    value: V,
+    frequency: u64,
+    tick: u64,
+}
+
+/// Entries plus an eviction order keyed by `(frequency, tick)`
+struct LfuState<K, V> {
+    entries: HashMap<K, LfuEntry<V>>,
+    order: BTreeMap<(u64, u64), K>,
+    next_tick: u64,
+    capacity: usize,
+    /// Sum of the byte limit's cost over the entries
+    used_bytes: usize,
+}
+
+impl<K, V> LfuState<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    fn tick(&mut self) -> u64 {
+        self.next_tick += 1;
+        self.next_tick
+    }
+
+    /// Count one access to `key`, returning its entry
+    fn touch(&mut self, key: &K) -> Option<&LfuEntry<V>> {
+        let tick = self.tick();
+        let entry = self.entries.get_mut(key)?;
+        let owned = self
+            .order
+            .remove(&(entry.frequency, entry.tick))
+            .expect("LFU order out of sync with entries");
+        entry.frequency = entry.frequency.saturating_add(1);
+        entry.tick = tick;
+        self.order.insert((entry.frequency, entry.tick), owned);
+        self.entries.get(key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<LfuEntry<V>> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&(entry.frequency, entry.tick));
+        Some(entry)
+    }
+
+    /// Drop the least frequently used entry
+    fn evict(&mut self) -> Option<V> {
+        let (_, key) = self.order.pop_first()?;
+        self.entries.remove(&key).map(|entry| entry.value)
+    }
+}
+
+/// Thread-safe LFU cache
+pub struct LfuMemoryCache<K, V> {
+    state: RwLock<LfuState<K, V>>,
+    limit: Option<ByteLimit<V>>,
+}
+
+impl<K, V> LfuMemoryCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Create a new LFU cache with the given capacity
+    #[allow(dead_code)]
+    pub fn new(capacity: usize) -> Self {
+        Self::with_byte_limit(capacity, None)
+    }
+
+    /// Create a new LFU cache that also stays under a byte budget
+    pub fn with_byte_limit(capacity: usize, limit: Option<ByteLimit<V>>) -> Self {
+        let capacity = if capacity == 0 { 100 } else { capacity };
+        Self {
+            state: RwLock::new(LfuState {
+                entries: HashMap::new(),
+                order: BTreeMap::new(),
+                next_tick: 0,
+                capacity,
+                used_bytes: 0,
+            }),
+            limit,
+        }
+    }
+
+    fn cost(&self, value: &V) -> usize {
+        self.limit.map_or(0, |limit| (limit.cost)(value))
+    }
+
+    /// Get a cloned value, counting the access
+    pub fn get_cloned(&self, key: &K) -> Option<V> {
+        self.state
+            .write()
+            .touch(key)
+            .map(|entry| entry.value.clone())
+    }
+
+    /// Peek at a value without counting the access
+    #[allow(dead_code)]
+    pub fn peek(&self, key: &K) -> Option<V> {
+        self.state
+            .read()
+            .entries
+            .get(key)
+            .map(|entry| entry.value.clone())
+    }
+
+    /// Check for a key without counting the access or cloning the value
+    pub fn contains(&self, key: &K) -> bool {
+        self.state.read().entries.contains_key(key)
+    }
+
+    /// Insert or replace a value
+    ///
+    /// Replacing counts as an access; a new key starts at one. Least
+    /// frequently used entries are evicted until the value fits the entry
+    /// and byte limits. A value costing more than the whole byte budget isn't
+    /// cached.
+    pub fn insert(&self, key: K, value: V) {
+        let mut state = self.state.write();
+        let frequency = match state.remove(&key) {
+            Some(old) => {
+                state.used_bytes -= self.cost(&old.value);
+                old.frequency.saturating_add(1)
+            }
+            None => 1,
+        };
+
+        let cost = self.cost(&value);
+        let max_bytes = self.limit.map_or(usize::MAX, |limit| limit.max_bytes);
+        if cost > max_bytes {
+            return;
+        }
+        while state.entries.len() >= state.capacity || state.used_bytes + cost > max_bytes {
+            match state.evict() {
+                Some(evicted) => state.used_bytes -= self.cost(&evicted),
+                None => break,
+            }
+        }
+
+        let tick = state.tick();
+        state.order.insert((frequency, tick), key.clone());
+        state.entries.insert(
+            key,
+            LfuEntry {
+                value,
+                frequency,
+                tick,
+            },
+        );
+        state.used_bytes += cost;
+    }
+
+    /// Remove a value
+    pub fn invalidate(&self, key: &K) -> Option<V> {
+        let mut state = self.state.write();
+        let entry = state.remove(key)?;
+        state.used_bytes -= self.cost(&entry.value);
+        Some(entry.value)
+    }
+
+    /// Estimated bytes held (always 0 without a byte limit)
+    pub fn used_bytes(&self) -> usize {
+        self.state.read().used_bytes
+    }
+
+    /// Get cache capacity
+    pub fn capacity(&self) -> usize {
+        self.state.read().capacity
+    }
+
+    /// Number of cached entries
+    pub fn len(&self) -> usize {
+        self.state.read().entries.len()
+    }
+
+    /// Byte budget, if one is set
+    pub fn max_bytes(&self) -> Option<usize> {
+        self.limit.map(|limit| limit.max_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::lru_cache::LruMemoryCache;
+
+    #[test]
+    fn test_lfu_basic() {
+        let cache = LfuMemoryCache::new(2);
+
+        cache.insert("key1".to_string(), "value1".to_string());
+        cache.insert("key2".to_string(), "value2".to_string());
+        cache.get_cloned(&"key2".to_string());
+
+        // key1 has the fewest accesses, so key3 evicts it
+        cache.insert("key3".to_string(), "value3".to_string());
+        assert!(!cache.contains(&"key1".to_string()));
+        assert!(cache.contains(&"key2".to_string()));
+        assert!(cache.contains(&"key3".to_string()));
+
+        // Replacing keeps the entry; invalidating removes it
+        cache.insert("key2".to_string(), "updated".to_string());
+        assert_eq!(
+            cache.get_cloned(&"key2".to_string()),
+            Some("updated".to_string())
+        );
+        assert_eq!(
+            cache.invalidate(&"key2".to_string()),
+            Some("updated".to_string())
+        );
+        assert!(!cache.contains(&"key2".to_string()));
+    }
+
+    #[test]
+    fn test_lfu_byte_limit() {
+        let limit = ByteLimit {
+            max_bytes: 10,
+            cost: String::len,
+        };
+        let cache = LfuMemoryCache::with_byte_limit(100, Some(limit));
+
+        cache.insert(1, "aaaa".to_string());
+        cache.insert(2, "bbbb".to_string());
+        cache.get_cloned(&1);
+
+        // Evicts the least frequently used entry until the value fits
+        cache.insert(3, "cccccc".to_string());
+        assert!(cache.contains(&1));
+        assert!(!cache.contains(&2));
+        assert_eq!(cache.used_bytes(), 10);
+
+        cache.insert(4, "x".repeat(11));
+        assert!(!cache.contains(&4));
+        assert_eq!(cache.used_bytes(), 10);
+    }
+
+    #[test]
+    fn test_lfu_retains_hot_keys_through_scan() {
+        let lru = LruMemoryCache::new(10);
+        let lfu = LfuMemoryCache::new(10);
+        let hot: Vec<String> = (0..3).map(|i| format!("hot_{}", i)).collect();
+
+        // A few keys read repeatedly...
+        for key in &hot {
+            lru.insert(key.clone(), 0);
+            lfu.insert(key.clone(), 0);
+        }
+        for _ in 0..5 {
+            for key in &hot {
+                lru.get_cloned(key);
+                lfu.get_cloned(key);
+            }
+        }
+
+        // ...then a long scan touching each of many cold keys once
+        for i in 0..100 {
+            let key = format!("scan_{}", i);
+            lru.insert(key.clone(), i);
+            lfu.insert(key, i);
+        }
+
+        for key in &hot {
+            assert!(lfu.contains(key), "LFU evicted {}", key);
+            assert!(!lru.contains(key), "LRU kept {}", key);
+        }
+    }
+}
@@ -2,15 +2,19 @@
 //
 // Provides a least-recently-used eviction policy for the cache layer.
 
-use crate::cache::Cache;
+use crate::cache::{ByteLimit, Cache};
 use lru::LruCache;
 use parking_lot::RwLock;
 use std::hash::Hash;
 use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Thread-safe LRU cache
 pub struct LruMemoryCache<K, V> {
     cache: RwLock<LruCache<K, V>>,
+    limit: Option<ByteLimit<V>>,
+    /// Sum of `limit.cost` over the entries; only changed under the write lock
+    used_bytes: AtomicUsize,
 }
 
 impl<K, V> LruMemoryCache<K, V>
@@ -18,12 +22,29 @@ where
     K: Hash + Eq,
 {
     /// Create a new LRU cache with the given capacity
+    #[allow(dead_code)]
     pub fn new(capacity: usize) -> Self {
+        Self::with_byte_limit(capacity, None)
+    }
+
+    /// Create a new LRU cache that also stays under a byte budget
+    pub fn with_byte_limit(capacity: usize, limit: Option<ByteLimit<V>>) -> Self {
         let cap = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::new(100).unwrap());
         Self {
             cache: RwLock::new(LruCache::new(cap)),
+            limit,
+            used_bytes: AtomicUsize::new(0),
         }
     }
+
+    fn cost(&self, value: &V) -> usize {
+        self.limit.map_or(0, |limit| (limit.cost)(value))
+    }
+
+    fn release(&self, value: &V) {
+        self.used_bytes
+            .fetch_sub(self.cost(value), Ordering::Relaxed);
+    }
 }
 
 impl<K, V> Cache<K, V> for LruMemoryCache<K, V>
@@ -39,15 +60,16 @@ where
     }
 
     fn put(&mut self, key: K, value: V) {
-        self.cache.write().put(key, value);
+        self.insert(key, value);
     }
 
     fn remove(&mut self, key: &K) -> Option<V> {
-        self.cache.write().pop(key)
+        self.invalidate(key)
     }
 
     fn clear(&mut self) {
         self.cache.write().clear();
+        self.used_bytes.store(0, Ordering::Relaxed);
     }
 
     fn len(&self) -> usize {
@@ -72,21 +94,71 @@ where
         self.cache.read().peek(key).cloned()
     }
 
+    /// Check for a key without updating recency or cloning the value
+    pub fn contains(&self, key: &K) -> bool {
+        self.cache.read().contains(key)
+    }
+
     /// Put a value (convenience method)
+    ///
+    /// With a byte limit, least recently used entries are evicted until the
+    /// new value fits. A value costing more than the whole budget isn't
+    /// cached.
     pub fn insert(&self, key: K, value: V) {
-        self.cache.write().put(key, value);
+        let mut cache = self.cache.write();
+        let Some(limit) = self.limit else {
+            cache.put(key, value);
+            return;
+        };
+
+        if let Some(old) = cache.pop(&key) {
+            self.release(&old);
+        }
+        let cost = (limit.cost)(&value);
+        if cost > limit.max_bytes {
+            return;
+        }
+        while self.used_bytes.load(Ordering::Relaxed) + cost > limit.max_bytes {
+            match cache.pop_lru() {
+                Some((_, evicted)) => self.release(&evicted),
+                None => break,
+            }
+        }
+        if let Some((_, evicted)) = cache.push(key, value) {
+            self.release(&evicted);
+        }
+        self.used_bytes.fetch_add(cost, Ordering::Relaxed);
     }
 
     /// Remove a value (convenience method)
     pub fn invalidate(&self, key: &K) -> Option<V> {
-        self.cache.write().pop(key)
+        let mut cache = self.cache.write();
+        let removed = cache.pop(key);
+        if let Some(value) = &removed {
+            self.release(value);
+        }
+        removed
+    }
+
+    /// Estimated bytes held (always 0 without a byte limit)
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes.load(Ordering::Relaxed)
     }
 
     /// Get cache capacity
-    #[allow(dead_code)]
     pub fn capacity(&self) -> usize {
         self.cache.read().cap().get()
     }
+
+    /// Number of cached entries
+    pub fn len(&self) -> usize {
+        self.cache.read().len()
+    }
+
+    /// Byte budget, if one is set
+    pub fn max_bytes(&self) -> Option<usize> {
+        self.limit.map(|limit| limit.max_bytes)
+    }
 }
 
 #[cfg(test)]
@@ -122,4 +194,35 @@ mod tests {
             Some("value3".to_string())
         );
     }
+
+    #[test]
+    fn test_lru_byte_limit() {
+        let limit = ByteLimit {
+            max_bytes: 10,
+            cost: String::len,
+        };
+        let cache = LruMemoryCache::with_byte_limit(100, Some(limit));
+
+        cache.insert(1, "aaaa".to_string());
+        cache.insert(2, "bbbb".to_string());
+        assert_eq!(cache.used_bytes(), 8);
+
+        // 8 + 4 > 10: the least recently used entry makes room
+        cache.insert(3, "cccc".to_string());
+        assert!(!cache.contains(&1));
+        assert_eq!(cache.used_bytes(), 8);
+
+        // Replacing re-costs the entry
+        cache.insert(3, "cccccc".to_string());
+        assert!(cache.contains(&2));
+        assert_eq!(cache.used_bytes(), 10);
+
+        // A value larger than the budget isn't cached and drops the old copy
+        cache.insert(2, "x".repeat(11));
+        assert!(!cache.contains(&2));
+        assert_eq!(cache.used_bytes(), 6);
+
+        cache.invalidate(&3);
+        assert_eq!(cache.used_bytes(), 0);
+    }
 }
@@ -1,9 +1,162 @@
 // In-memory cache layer
 //
-// This module provides LRU caching for hot data with write-through semantics.
+// This module provides LRU and LFU caching for hot data with write-through
+// semantics.
 
+pub mod lfu_cache;
 pub mod lru_cache;
 
+use crate::types::CacheStats;
+use lfu_cache::LfuMemoryCache;
+use lru_cache::LruMemoryCache;
+use std::hash::Hash;
+
+/// Eviction policy for the KV and record caches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CachePolicy {
+    /// Evict the least recently used entry (default)
+    #[default]
+    Lru,
+
+    /// Evict the least frequently used entry
+    ///
+    /// Keeps a small set of hot keys cached through long scans that would
+    /// flush an LRU cache. Access counts never decay, so keys that were hot
+    /// once and are no longer read stay cached until invalidated.
+    Lfu,
+}
+
+/// In-process cache settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheConfig {
+    /// Eviction policy
+    pub policy: CachePolicy,
+
+    /// Approximate memory budget of each cache in bytes (None = entry count
+    /// only)
+    ///
+    /// The KV and record caches each get this budget. The entry count limits
+    /// still apply on top of it.
+    pub max_bytes: Option<usize>,
+}
+
+impl CacheConfig {
+    /// Set the eviction policy (chainable)
+    pub fn with_policy(mut self, policy: CachePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set the per-cache byte budget (chainable)
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+}
+
+/// Byte budget of a cache and the function estimating each value's size
+pub struct ByteLimit<V> {
+    pub max_bytes: usize,
+    pub cost: fn(&V) -> usize,
+}
+
+// Manual impls: deriving would require `V: Copy`
+impl<V> Clone for ByteLimit<V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for ByteLimit<V> {}
+
+/// Thread-safe cache using the eviction policy chosen at construction
+pub enum MemoryCache<K, V> {
+    Lru(LruMemoryCache<K, V>),
+    Lfu(LfuMemoryCache<K, V>),
+}
+
+impl<K, V> MemoryCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Create a cache holding up to `capacity` entries
+    ///
+    /// With `config.max_bytes` set, `cost` estimates each value's size and
+    /// the cache also evicts to stay under the byte budget.
+    pub fn new(capacity: usize, config: CacheConfig, cost: fn(&V) -> usize) -> Self {
+        let limit = config
+            .max_bytes
+            .map(|max_bytes| ByteLimit { max_bytes, cost });
+        match config.policy {
+            CachePolicy::Lru => Self::Lru(LruMemoryCache::with_byte_limit(capacity, limit)),
+            CachePolicy::Lfu => Self::Lfu(LfuMemoryCache::with_byte_limit(capacity, limit)),
+        }
+    }
+
+    /// Get a cloned value, recording the access
+    pub fn get_cloned(&self, key: &K) -> Option<V> {
+        match self {
+            Self::Lru(cache) => cache.get_cloned(key),
+            Self::Lfu(cache) => cache.get_cloned(key),
+        }
+    }
+
+    /// Check for a key without recording an access
+    pub fn contains(&self, key: &K) -> bool {
+        match self {
+            Self::Lru(cache) => cache.contains(key),
+            Self::Lfu(cache) => cache.contains(key),
+        }
+    }
+
+    /// Insert or replace a value
+    pub fn insert(&self, key: K, value: V) {
+        match self {
+            Self::Lru(cache) => cache.insert(key, value),
+            Self::Lfu(cache) => cache.insert(key, value),
+        }
+    }
+
+    /// Remove a value
+    pub fn invalidate(&self, key: &K) -> Option<V> {
+        match self {
+            Self::Lru(cache) => cache.invalidate(key),
+            Self::Lfu(cache) => cache.invalidate(key),
+        }
+    }
+
+    /// Get cache capacity
+    pub fn capacity(&self) -> usize {
+        match self {
+            Self::Lru(cache) => cache.capacity(),
+            Self::Lfu(cache) => cache.capacity(),
+        }
+    }
+
+    /// Estimated bytes held (always 0 without a byte budget)
+    pub fn used_bytes(&self) -> usize {
+        match self {
+            Self::Lru(cache) => cache.used_bytes(),
+            Self::Lfu(cache) => cache.used_bytes(),
+        }
+    }
+
+    /// Configured size and current occupancy
+    pub fn stats(&self) -> CacheStats {
+        let (entries, max_bytes) = match self {
+            Self::Lru(cache) => (cache.len(), cache.max_bytes()),
+            Self::Lfu(cache) => (cache.len(), cache.max_bytes()),
+        };
+        CacheStats {
+            capacity: self.capacity(),
+            entries,
+            max_bytes,
+            used_bytes: self.used_bytes(),
+        }
+    }
+}
+
 /// Cache trait for different caching strategies
 #[allow(dead_code)]
 pub trait Cache<K, V>: Send + Sync {
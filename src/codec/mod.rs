@@ -4,52 +4,240 @@
 // using rkyv for zero-copy deserialization.
 
 use crate::error::{Error, Result};
-use crate::types::{Edge, Memory};
-use rkyv::{AlignedVec, Deserialize};
+use crate::types::{DeletedMemory, Edge, Memory, MultimodalDocument};
+use rkyv::{AlignedVec, Archive, Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Schema version for backwards compatibility
 const SCHEMA_VERSION: u8 = 1;
 
-/// Encode a Memory record
-pub fn encode_memory(memory: &Memory) -> Result<Vec<u8>> {
+/// Memory schema version (v2 added named field embeddings, expiry, the last
+/// decay time and the compression marker)
+const MEMORY_SCHEMA_VERSION: u8 = 2;
+
+/// Edge schema version (v2 added edge ids, metadata and the last decay time)
+const EDGE_SCHEMA_VERSION: u8 = 2;
+
+/// Document schema version
+const DOCUMENT_SCHEMA_VERSION: u8 = 1;
+
+/// Recycle bin entry schema version
+const DELETED_MEMORY_SCHEMA_VERSION: u8 = 1;
+
+/// Compression marker: the payload is stored as-is
+const UNCOMPRESSED: u8 = 0;
+
+/// Compression marker: the payload is a zstd frame
+const ZSTD_COMPRESSED: u8 = 1;
+
+/// Payloads smaller than this are never compressed
+const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// zstd compression level (the library default)
+const ZSTD_LEVEL: i32 = 3;
+
+/// Largest payload a zstd frame may decompress to (64MB)
+const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+/// Edge layout written under schema version 1
+///
+/// Upgraded edges get an empty id and metadata, and have never been decayed.
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct EdgeV1 {
+    from: String,
+    relation: String,
+    to: String,
+    weight: f32,
+    timestamp: i64,
+}
+
+impl From<EdgeV1> for Edge {
+    fn from(v1: EdgeV1) -> Self {
+        Edge {
+            id: String::new(),
+            from: v1.from,
+            relation: v1.relation,
+            to: v1.to,
+            weight: v1.weight,
+            timestamp: v1.timestamp,
+            metadata: HashMap::new(),
+            last_decayed: None,
+        }
+    }
+}
+
+/// Memory layout written under schema version 1
+///
+/// Upgraded memories have no field embeddings, never expire and have never
+/// been decayed.
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct MemoryV1 {
+    id: String,
+    content: String,
+    embedding: Vec<f32>,
+    importance: f32,
+    timestamp: i64,
+    metadata: HashMap<String, String>,
+}
+
+impl From<MemoryV1> for Memory {
+    fn from(v1: MemoryV1) -> Self {
+        Memory {
+            id: v1.id,
+            content: v1.content,
+            embedding: v1.embedding,
+            embeddings: HashMap::new(),
+            importance: v1.importance,
+            timestamp: v1.timestamp,
+            metadata: v1.metadata,
+            expires_at: None,
+            last_decayed: None,
+        }
+    }
+}
+
+/// Compress `data` into a single zstd frame
+fn zstd_compress(data: &[u8]) -> Result<Vec<u8>> {
+    zstd::bulk::compress(data, ZSTD_LEVEL)
+        .map_err(|e| Error::Codec(format!("Failed to compress value: {}", e)))
+}
+
+/// Decompress a single zstd frame written by `zstd_compress`
+///
+/// Frames that decompress to more than `MAX_DECOMPRESSED_SIZE` bytes are
+/// rejected rather than trusting the size in a possibly corrupt header.
+fn zstd_decompress(frame: &[u8]) -> Result<Vec<u8>> {
+    zstd::bulk::decompress(frame, MAX_DECOMPRESSED_SIZE).map_err(|e| {
+        Error::Codec(format!(
+            "Invalid compressed value or larger than {} bytes: {}",
+            MAX_DECOMPRESSED_SIZE, e
+        ))
+    })
+}
+
+/// Prefix `payload` with a schema version and compression marker
+///
+/// With `compress`, payloads of at least `COMPRESSION_THRESHOLD` bytes are
+/// stored as a zstd frame when that makes them smaller. Payloads above
+/// `MAX_DECOMPRESSED_SIZE` are always stored as-is so they can be read back.
+fn frame_value(version: u8, payload: &[u8], compress: bool) -> Result<Vec<u8>> {
+    if compress && (COMPRESSION_THRESHOLD..=MAX_DECOMPRESSED_SIZE).contains(&payload.len()) {
+        let compressed = zstd_compress(payload)?;
+        if compressed.len() < payload.len() {
+            let mut result = Vec::with_capacity(compressed.len() + 2);
+            result.extend_from_slice(&[version, ZSTD_COMPRESSED]);
+            result.extend_from_slice(&compressed);
+            return Ok(result);
+        }
+    }
+
+    let mut result = Vec::with_capacity(payload.len() + 2);
+    result.extend_from_slice(&[version, UNCOMPRESSED]);
+    result.extend_from_slice(payload);
+    Ok(result)
+}
+
+/// Read the compression marker of a framed value into an aligned payload
+fn unframe_value(data: &[u8]) -> Result<AlignedVec> {
+    let Some((&marker, payload)) = data.split_first() else {
+        return Err(Error::Codec("Missing compression marker".to_string()));
+    };
+
+    let mut aligned = AlignedVec::new();
+    match marker {
+        UNCOMPRESSED => aligned.extend_from_slice(payload),
+        ZSTD_COMPRESSED => aligned.extend_from_slice(&zstd_decompress(payload)?),
+        _ => {
+            return Err(Error::Codec(format!(
+                "Unsupported compression marker: {}",
+                marker
+            )));
+        }
+    }
+    Ok(aligned)
+}
+
+/// Encode a Memory record, compressing large records when `compress` is set
+pub fn encode_memory(memory: &Memory, compress: bool) -> Result<Vec<u8>> {
     let bytes = rkyv::to_bytes::<_, 256>(memory)
         .map_err(|e| Error::Codec(format!("Failed to serialize Memory: {}", e)))?;
 
-    // Prepend schema version
-    let mut result = vec![SCHEMA_VERSION];
-    result.extend_from_slice(&bytes);
-    Ok(result)
+    frame_value(MEMORY_SCHEMA_VERSION, &bytes, compress)
 }
 
 /// Decode a Memory record
+///
+/// Compressed records are decompressed transparently. Records written under
+/// an older schema are upgraded by `migrate_memory`.
 pub fn decode_memory(bytes: &[u8]) -> Result<Memory> {
-    if bytes.is_empty() {
+    let Some((&version, data)) = bytes.split_first() else {
         return Err(Error::Codec("Empty byte array".to_string()));
-    }
+    };
 
-    // Check schema version
-    let version = bytes[0];
-    if version != SCHEMA_VERSION {
-        return Err(Error::Codec(format!(
-            "Unsupported schema version: {}",
-            version
-        )));
+    if version == MEMORY_SCHEMA_VERSION {
+        return decode_archived_memory::<Memory>(&unframe_value(data)?);
     }
 
-    let data = &bytes[1..];
-
     // Copy to aligned buffer for rkyv
     let mut aligned = AlignedVec::new();
     aligned.extend_from_slice(data);
+    migrate_memory(version, &aligned)
+}
+
+/// Upgrade a Memory archived under an older schema version
+///
+/// Each past version keeps its layout as a `MemoryV{n}` struct that is
+/// deserialized as-is and converted with `From`. When `Memory` changes, copy
+/// its current layout into a new `MemoryV{n}`, add an arm here and bump
+/// `MEMORY_SCHEMA_VERSION`.
+fn migrate_memory(version: u8, bytes: &[u8]) -> Result<Memory> {
+    match version {
+        // v1: the original layout, without a compression marker
+        SCHEMA_VERSION => decode_archived_memory::<MemoryV1>(bytes).map(Memory::from),
+        _ => Err(Error::Codec(format!(
+            "Unsupported schema version: {}",
+            version
+        ))),
+    }
+}
 
-    let archived = rkyv::check_archived_root::<Memory>(&aligned)
+/// Validate and deserialize an archived Memory layout from aligned bytes
+fn decode_archived_memory<T>(bytes: &[u8]) -> Result<T>
+where
+    T: Archive,
+    T::Archived: for<'a> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'a>>
+        + Deserialize<T, rkyv::Infallible>,
+{
+    let archived = rkyv::check_archived_root::<T>(bytes)
         .map_err(|e| Error::Codec(format!("Failed to validate archived Memory: {}", e)))?;
 
-    let memory: Memory = archived
+    archived
         .deserialize(&mut rkyv::Infallible)
-        .map_err(|e| Error::Codec(format!("Failed to deserialize Memory: {}", e)))?;
+        .map_err(|e| Error::Codec(format!("Failed to deserialize Memory: {}", e)))
+}
+
+/// Encode a recycle bin entry, compressing large entries when `compress` is set
+pub fn encode_deleted_memory(deleted: &DeletedMemory, compress: bool) -> Result<Vec<u8>> {
+    let bytes = rkyv::to_bytes::<_, 256>(deleted)
+        .map_err(|e| Error::Codec(format!("Failed to serialize deleted memory: {}", e)))?;
+
+    frame_value(DELETED_MEMORY_SCHEMA_VERSION, &bytes, compress)
+}
 
-    Ok(memory)
+/// Decode a recycle bin entry
+pub fn decode_deleted_memory(bytes: &[u8]) -> Result<DeletedMemory> {
+    match bytes.split_first() {
+        Some((&DELETED_MEMORY_SCHEMA_VERSION, data)) => {
+            decode_archived_memory::<DeletedMemory>(&unframe_value(data)?)
+        }
+        Some((&version, _)) => Err(Error::Codec(format!(
+            "Unsupported schema version: {}",
+            version
+        ))),
+        None => Err(Error::Codec("Empty byte array".to_string())),
+    }
 }
 
 /// Encode an Edge
@@ -58,7 +246,7 @@ pub fn encode_edge(edge: &Edge) -> Result<Vec<u8>> {
     let bytes = rkyv::to_bytes::<_, 256>(edge)
         .map_err(|e| Error::Codec(format!("Failed to serialize Edge: {}", e)))?;
 
-    let mut result = vec![SCHEMA_VERSION];
+    let mut result = vec![EDGE_SCHEMA_VERSION];
     result.extend_from_slice(&bytes);
     Ok(result)
 }
@@ -71,68 +259,107 @@ pub fn decode_edge(bytes: &[u8]) -> Result<Edge> {
     }
 
     let version = bytes[0];
-    if version != SCHEMA_VERSION {
-        return Err(Error::Codec(format!(
-            "Unsupported schema version: {}",
-            version
-        )));
-    }
-
     let data = &bytes[1..];
 
     // Copy to aligned buffer for rkyv
     let mut aligned = AlignedVec::new();
     aligned.extend_from_slice(data);
 
-    let archived = rkyv::check_archived_root::<Edge>(&aligned)
+    match version {
+        EDGE_SCHEMA_VERSION => decode_archived_edge::<Edge>(&aligned),
+        // Upgrade edges from the original layout
+        SCHEMA_VERSION => decode_archived_edge::<EdgeV1>(&aligned).map(Edge::from),
+        _ => Err(Error::Codec(format!(
+            "Unsupported schema version: {}",
+            version
+        ))),
+    }
+}
+
+/// Validate and deserialize an archived edge layout from aligned bytes
+fn decode_archived_edge<T>(bytes: &[u8]) -> Result<T>
+where
+    T: Archive,
+    T::Archived: for<'a> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'a>>
+        + Deserialize<T, rkyv::Infallible>,
+{
+    let archived = rkyv::check_archived_root::<T>(bytes)
         .map_err(|e| Error::Codec(format!("Failed to validate archived Edge: {}", e)))?;
 
-    let edge: Edge = archived
+    archived
         .deserialize(&mut rkyv::Infallible)
-        .map_err(|e| Error::Codec(format!("Failed to deserialize Edge: {}", e)))?;
-
-    Ok(edge)
+        .map_err(|e| Error::Codec(format!("Failed to deserialize Edge: {}", e)))
 }
 
 /// Encode a list of edges
+///
+/// Lists are only found in the legacy adjacency-list graph layout; tests
+/// write them to exercise the upgrade.
+#[cfg(test)]
 pub fn encode_edges(edges: &[Edge]) -> Result<Vec<u8>> {
     let edges_vec: Vec<Edge> = edges.to_vec();
     let bytes = rkyv::to_bytes::<_, 256>(&edges_vec)
         .map_err(|e| Error::Codec(format!("Failed to serialize edges: {}", e)))?;
 
-    let mut result = vec![SCHEMA_VERSION];
+    let mut result = vec![EDGE_SCHEMA_VERSION];
     result.extend_from_slice(&bytes);
     Ok(result)
 }
 
-/// Decode a list of edges
+/// Decode a list of edges, as stored by the legacy adjacency-list layout
 pub fn decode_edges(bytes: &[u8]) -> Result<Vec<Edge>> {
     if bytes.is_empty() {
         return Ok(Vec::new());
     }
 
     let version = bytes[0];
-    if version != SCHEMA_VERSION {
-        return Err(Error::Codec(format!(
-            "Unsupported schema version: {}",
-            version
-        )));
-    }
-
     let data = &bytes[1..];
 
     // Copy to aligned buffer for rkyv
     let mut aligned = AlignedVec::new();
     aligned.extend_from_slice(data);
 
-    let archived = rkyv::check_archived_root::<Vec<Edge>>(&aligned)
-        .map_err(|e| Error::Codec(format!("Failed to validate archived edges: {}", e)))?;
+    match version {
+        EDGE_SCHEMA_VERSION => decode_archived_edge::<Vec<Edge>>(&aligned),
+        // Upgrade lists from the original layout
+        SCHEMA_VERSION => decode_archived_edge::<Vec<EdgeV1>>(&aligned)
+            .map(|edges| edges.into_iter().map(Edge::from).collect()),
+        _ => Err(Error::Codec(format!(
+            "Unsupported schema version: {}",
+            version
+        ))),
+    }
+}
+
+/// Encode a MultimodalDocument, compressing large documents when `compress` is set
+pub fn encode_document(document: &MultimodalDocument, compress: bool) -> Result<Vec<u8>> {
+    let bytes = rkyv::to_bytes::<_, 1024>(document)
+        .map_err(|e| Error::Codec(format!("Failed to serialize document: {}", e)))?;
+
+    frame_value(DOCUMENT_SCHEMA_VERSION, &bytes, compress)
+}
 
-    let edges: Vec<Edge> = archived
+/// Decode a MultimodalDocument
+pub fn decode_document(bytes: &[u8]) -> Result<MultimodalDocument> {
+    let aligned = match bytes.split_first() {
+        Some((&DOCUMENT_SCHEMA_VERSION, data)) => unframe_value(data)?,
+        Some((&version, _)) => {
+            return Err(Error::Codec(format!(
+                "Unsupported schema version: {}",
+                version
+            )));
+        }
+        None => return Err(Error::Codec("Empty byte array".to_string())),
+    };
+
+    let archived = rkyv::check_archived_root::<MultimodalDocument>(&aligned)
+        .map_err(|e| Error::Codec(format!("Failed to validate archived document: {}", e)))?;
+
+    let document: MultimodalDocument = archived
         .deserialize(&mut rkyv::Infallible)
-        .map_err(|e| Error::Codec(format!("Failed to deserialize edges: {}", e)))?;
+        .map_err(|e| Error::Codec(format!("Failed to deserialize document: {}", e)))?;
 
-    Ok(edges)
+    Ok(document)
 }
 
 #[cfg(test)]
@@ -143,13 +370,54 @@ mod tests {
     fn test_memory_encode_decode() {
         let memory = Memory::new("test_id", "test content", vec![1.0, 2.0, 3.0], 0.5);
 
-        let encoded = encode_memory(&memory).unwrap();
+        let encoded = encode_memory(&memory, false).unwrap();
         let decoded = decode_memory(&encoded).unwrap();
 
         assert_eq!(memory.id, decoded.id);
         assert_eq!(memory.content, decoded.content);
         assert_eq!(memory.embedding, decoded.embedding);
         assert_eq!(memory.importance, decoded.importance);
+
+        let mut expiring = Memory::new("ttl", "c", vec![], 0.5).with_ttl(60);
+        expiring.last_decayed = Some(expiring.timestamp + 1);
+        let decoded = decode_memory(&encode_memory(&expiring, false).unwrap()).unwrap();
+        assert_eq!(decoded.expires_at, expiring.expires_at);
+        assert_eq!(decoded.last_decayed, expiring.last_decayed);
+    }
+
+    #[test]
+    fn test_memory_decode_v1() {
+        let v1 = MemoryV1 {
+            id: "old_id".to_string(),
+            content: "old content".to_string(),
+            embedding: vec![1.0, 2.0],
+            importance: 0.5,
+            timestamp: 42,
+            metadata: HashMap::new(),
+        };
+        let mut encoded = vec![SCHEMA_VERSION];
+        encoded.extend_from_slice(&rkyv::to_bytes::<_, 256>(&v1).unwrap());
+
+        let decoded = decode_memory(&encoded).unwrap();
+
+        assert_eq!(decoded.id, "old_id");
+        assert_eq!(decoded.embedding, vec![1.0, 2.0]);
+        assert_eq!(decoded.timestamp, 42);
+        assert!(decoded.embeddings.is_empty());
+        assert_eq!(decoded.expires_at, None);
+        assert_eq!(decoded.last_decayed, None);
+    }
+
+    #[test]
+    fn test_memory_decode_rejects_unknown_version() {
+        let memory = Memory::new("id", "content", vec![], 0.5);
+        let mut encoded = encode_memory(&memory, false).unwrap();
+
+        for version in [0, MEMORY_SCHEMA_VERSION + 1] {
+            encoded[0] = version;
+            assert!(matches!(decode_memory(&encoded), Err(Error::Codec(_))));
+        }
+        assert!(matches!(decode_memory(&[]), Err(Error::Codec(_))));
     }
 
     #[test]
@@ -163,4 +431,111 @@ mod tests {
         assert_eq!(edge.relation, decoded.relation);
         assert_eq!(edge.to, decoded.to);
     }
+
+    #[test]
+    fn test_edge_metadata_encode_decode() {
+        let edge = Edge::new("a", "cites", "b")
+            .with_weight(0.7)
+            .with_metadata("source", "paper.pdf")
+            .with_metadata("confidence", "0.9");
+
+        let decoded = decode_edge(&encode_edge(&edge).unwrap()).unwrap();
+        assert_eq!(decoded.id, edge.id);
+        assert_eq!(decoded.weight, 0.7);
+        assert_eq!(decoded.metadata, edge.metadata);
+
+        let decoded = decode_edges(&encode_edges(std::slice::from_ref(&edge)).unwrap()).unwrap();
+        assert_eq!(decoded[0].metadata, edge.metadata);
+    }
+
+    #[test]
+    fn test_edges_decode_v1() {
+        let v1 = vec![EdgeV1 {
+            from: "a".to_string(),
+            relation: "knows".to_string(),
+            to: "b".to_string(),
+            weight: 0.5,
+            timestamp: 42,
+        }];
+        let mut encoded = vec![SCHEMA_VERSION];
+        encoded.extend_from_slice(&rkyv::to_bytes::<_, 256>(&v1).unwrap());
+
+        let decoded = decode_edges(&encoded).unwrap();
+
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].from, "a");
+        assert_eq!(decoded[0].to, "b");
+        assert_eq!(decoded[0].weight, 0.5);
+        assert!(decoded[0].id.is_empty());
+    }
+
+    #[test]
+    fn test_document_encode_decode() {
+        use crate::types::{DocumentChunk, FileType};
+
+        let mut document =
+            MultimodalDocument::new("doc", "a.pdf", FileType::Pdf, 10, "text", vec![1.0]);
+        document.add_chunk(DocumentChunk::new("c0", "chunk", vec![0.5], 0, 5));
+
+        let decoded = decode_document(&encode_document(&document, false).unwrap()).unwrap();
+
+        assert_eq!(decoded.id, "doc");
+        assert_eq!(decoded.file_type, FileType::Pdf);
+        assert_eq!(decoded.chunks.len(), 1);
+        assert_eq!(decoded.chunks[0].content, "chunk");
+    }
+
+    #[test]
+    fn test_value_compression() {
+        use rand::{Rng, SeedableRng};
+
+        // ~100KB of words sampled from the README
+        let words: Vec<&str> = include_str!("../../README.md").split_whitespace().collect();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut text = String::new();
+        while text.len() < 100 * 1024 {
+            text.push_str(words[rng.gen_range(0..words.len())]);
+            text.push(' ');
+        }
+        let memory = Memory::new("big", text.clone(), vec![], 0.5);
+
+        let plain = encode_memory(&memory, false).unwrap();
+        let compressed = encode_memory(&memory, true).unwrap();
+        assert_eq!(plain[1], UNCOMPRESSED);
+        assert_eq!(compressed[1], ZSTD_COMPRESSED);
+        // ~102KB -> ~29KB
+        assert!(compressed.len() * 3 < plain.len());
+
+        // Both forms decode transparently
+        assert_eq!(decode_memory(&compressed).unwrap().content, text);
+        assert_eq!(decode_memory(&plain).unwrap().content, text);
+
+        // Small values are left alone
+        let small = Memory::new("small", "short", vec![], 0.5);
+        assert_eq!(encode_memory(&small, true).unwrap()[1], UNCOMPRESSED);
+
+        // Corrupt frames fail cleanly
+        let mut corrupt = compressed.clone();
+        corrupt.truncate(corrupt.len() / 2);
+        assert!(matches!(decode_memory(&corrupt), Err(Error::Codec(_))));
+
+        // Frames that would inflate past the limit are rejected
+        let bomb = zstd_compress(&vec![0; MAX_DECOMPRESSED_SIZE + 1]).unwrap();
+        let mut oversized = vec![MEMORY_SCHEMA_VERSION, ZSTD_COMPRESSED];
+        oversized.extend_from_slice(&bomb);
+        assert!(matches!(decode_memory(&oversized), Err(Error::Codec(_))));
+
+        let mut document = MultimodalDocument::new(
+            "doc",
+            "a.txt",
+            crate::types::FileType::Text,
+            1,
+            &text,
+            vec![],
+        );
+        document.extracted_text = text.clone();
+        let encoded = encode_document(&document, true).unwrap();
+        assert!(encoded.len() * 3 < text.len());
+        assert_eq!(decode_document(&encoded).unwrap().extracted_text, text);
+    }
 }
@@ -1,16 +1,74 @@
 // Main database module
 
-use crate::error::Result;
+use crate::cache::{CacheConfig, CachePolicy};
+use crate::codec;
+use crate::documents::DocumentsManager;
+use crate::error::{Error, Result};
+use crate::export::{Importer, write_export};
 use crate::graph::GraphManager;
-use crate::kv::KvStore;
+use crate::kv::{KvStore, typed::TypedStore};
 use crate::records::RecordsManager;
-use crate::storage::{SharedStorage, rocksdb_backend::RocksDBBackend};
-use crate::transaction::{Transaction, manager::TransactionManager};
-use crate::types::{Memory, SearchResult};
-use crate::vector::VectorManager;
-use std::path::Path;
+use crate::storage::{
+    BatchOp, SharedStorage, StorageBackend,
+    column_families::ColumnFamilies,
+    memory_backend::InMemoryBackend,
+    read_only_backend::ReadOnlyRocksDBBackend,
+    rocksdb_backend::{RocksDBBackend, RocksDBTuning},
+};
+use crate::transaction::{MemoryWrites, Transaction, manager::TransactionManager};
+use crate::types::{
+    Bundle, ChunkSearchResult, ColumnFamilyStats, ConflictPolicy, DbStats, DedupOutcome,
+    DeletedMemory, DistanceMetric, DocumentChunk, Edge, GraphStats, IdPolicy, Manifest, Memory,
+    MemoryPatch, MergeStats, MultimodalDocument, ReadOpts, SearchResult,
+};
+use crate::vector::{VectorManager, hnsw_index::HnswParams};
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+/// File in the database directory holding the JSON manifest
+pub const MANIFEST_FILE: &str = "MANIFEST.opendb";
+
+/// Current manifest format version
+const MANIFEST_FORMAT_VERSION: u32 = 1;
+
+/// Reserved metadata key used by `ping` for its write/read round-trip
+const PING_KEY: &[u8] = b"__opendb_ping__";
+
+/// Reserved metadata key holding the configuration the database was created with
+const CONFIG_KEY: &[u8] = b"__opendb_config__";
+
+/// Current on-disk storage format version
+const STORAGE_FORMAT_VERSION: u32 = 1;
+
+/// Smallest importance change `decay_memories` rewrites a record for
+const DECAY_EPSILON: f32 = 0.001;
+
+/// Buffered operations after which `BulkLoader` writes a batch
+const BULK_BATCH_OPS: usize = 10_000;
+
+/// Memories `merge_from` inserts per batch
+const MERGE_BATCH_SIZE: usize = 1024;
+
+/// Nearest vectors `insert_memory_dedup` checks for a stored memory, so a few
+/// document chunk hits don't hide one
+const DEDUP_CANDIDATES: usize = 8;
+
+/// Settings that must stay the same for the lifetime of a database
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedConfig {
+    format_version: u32,
+    vector_dimension: usize,
+    distance_metric: DistanceMetric,
+}
+
 /// OpenDB - High-performance hybrid embedded database
 ///
 /// This is the main entry point for interacting with OpenDB.
@@ -34,9 +92,13 @@ pub struct OpenDB {
     storage: SharedStorage,
     kv: KvStore,
     records: RecordsManager,
+    documents: DocumentsManager,
     graph: GraphManager,
     vector: VectorManager,
     txn_manager: TransactionManager,
+    options: OpenDBOptions,
+    /// Serializes `insert_memory_dedup` calls
+    dedup_lock: Mutex<()>,
 }
 
 impl OpenDB {
@@ -54,24 +116,185 @@ impl OpenDB {
     }
 
     /// Open with custom options
+    ///
+    /// The vector dimension, distance metric and storage format are recorded
+    /// when a database is created. Reopening with options that disagree fails
+    /// with `Error::ConfigMismatch` rather than returning wrong search results.
+    ///
+    /// `storage_engine` picks the backend. With `StorageEngine::InMemory`
+    /// the path is ignored and nothing touches the filesystem.
     pub fn open_with_options<P: AsRef<Path>>(path: P, options: OpenDBOptions) -> Result<Self> {
-        let backend = RocksDBBackend::open(path)?;
-        let storage: SharedStorage = Arc::new(backend);
+        if options.storage_engine == StorageEngine::InMemory {
+            return Self::open_in_memory_with_options(options);
+        }
+
+        let backend = RocksDBBackend::open_with_options(path, &options)?;
+        Self::check_persisted_config(&backend, &options)?;
+        let db = Self::from_storage(Arc::new(backend), options);
+        if db.options.opens_cf(ColumnFamilies::GRAPH_FORWARD)
+            && db.options.opens_cf(ColumnFamilies::GRAPH_BACKWARD)
+        {
+            db.graph.upgrade_layout()?;
+        }
+        if db.options.opens_cf(ColumnFamilies::RECORDS)
+            && db.options.opens_cf(ColumnFamilies::IMPORTANCE_INDEX)
+        {
+            db.records.upgrade_importance_index()?;
+        }
+        if db.options.opens_cf(ColumnFamilies::RECORDS)
+            && db.options.opens_cf(ColumnFamilies::TIME_INDEX)
+        {
+            db.records.upgrade_time_index()?;
+        }
+        if db.options.repair_on_open {
+            db.repair_vector_index()?;
+        }
+        db.write_manifest()?;
+        Ok(db)
+    }
+
+    /// Create an empty database that lives only in memory
+    ///
+    /// Nothing touches the filesystem and all data is lost when the database
+    /// is dropped, which suits tests and throwaway databases. Transactions
+    /// are atomic but have no conflict detection: the last commit wins.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::open_in_memory_with_options(OpenDBOptions::default())
+    }
+
+    /// Create an in-memory database with custom options
+    ///
+    /// Options that only concern files on disk (such as `create_mode`) are
+    /// ignored, and so is `storage_engine`.
+    pub fn open_in_memory_with_options(options: OpenDBOptions) -> Result<Self> {
+        let backend = InMemoryBackend::with_options(&options);
+        Self::check_persisted_config(&backend, &options)?;
+        Ok(Self::from_storage(Arc::new(backend), options))
+    }
+
+    /// Open an existing database for reading only
+    ///
+    /// Unlike `open`, this doesn't take the database's exclusive lock, so
+    /// any number of readers can open a database that another process is
+    /// writing to. Readers see the data as of the moment they opened. All
+    /// writes (including transactions) fail with `Error::InvalidInput`, and
+    /// nothing in the directory is created or modified.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_read_only_with_options(path, OpenDBOptions::default())
+    }
+
+    /// Open an existing database for reading only, with custom options
+    ///
+    /// The options must still match the persisted configuration.
+    /// `create_mode` is ignored: the database must exist. Only the RocksDB
+    /// engine can be opened read-only.
+    pub fn open_read_only_with_options<P: AsRef<Path>>(
+        path: P,
+        options: OpenDBOptions,
+    ) -> Result<Self> {
+        if options.storage_engine != StorageEngine::RocksDb {
+            return Err(Error::InvalidInput(format!(
+                "Read-only mode is not supported by the {:?} storage engine",
+                options.storage_engine
+            )));
+        }
+        let backend = ReadOnlyRocksDBBackend::open_with_options(path, &options)?;
+        Self::check_persisted_config(&backend, &options)?;
+        Ok(Self::from_storage(Arc::new(backend), options))
+    }
+
+    /// Helper: Compare options against the persisted configuration
+    ///
+    /// Databases without a recorded configuration (new, or created before it
+    /// was recorded) adopt the requested options.
+    fn check_persisted_config(storage: &dyn StorageBackend, options: &OpenDBOptions) -> Result<()> {
+        let requested = PersistedConfig {
+            format_version: STORAGE_FORMAT_VERSION,
+            vector_dimension: options.vector_dimension,
+            distance_metric: options.distance_metric,
+        };
+
+        let Some(bytes) = storage.get(ColumnFamilies::METADATA, CONFIG_KEY)? else {
+            if storage.is_read_only() {
+                return Ok(());
+            }
+            let json = serde_json::to_vec(&requested)
+                .map_err(|e| Error::Codec(format!("Failed to serialize config: {}", e)))?;
+            return storage.put(ColumnFamilies::METADATA, CONFIG_KEY, &json);
+        };
+        let persisted: PersistedConfig = serde_json::from_slice(&bytes)
+            .map_err(|e| Error::Codec(format!("Failed to deserialize config: {}", e)))?;
+
+        let mismatch = |field, expected: String, got: String| Error::ConfigMismatch {
+            field,
+            expected,
+            got,
+        };
+        if persisted.format_version != requested.format_version {
+            return Err(mismatch(
+                "format_version",
+                persisted.format_version.to_string(),
+                requested.format_version.to_string(),
+            ));
+        }
+        if persisted.vector_dimension != requested.vector_dimension {
+            return Err(mismatch(
+                "vector_dimension",
+                persisted.vector_dimension.to_string(),
+                requested.vector_dimension.to_string(),
+            ));
+        }
+        if persisted.distance_metric != requested.distance_metric {
+            return Err(mismatch(
+                "distance_metric",
+                format!("{:?}", persisted.distance_metric),
+                format!("{:?}", requested.distance_metric),
+            ));
+        }
+        Ok(())
+    }
 
-        let kv = KvStore::new(Arc::clone(&storage), options.kv_cache_size);
-        let records = RecordsManager::new(Arc::clone(&storage), options.record_cache_size);
-        let graph = GraphManager::new(Arc::clone(&storage));
-        let vector = VectorManager::new(Arc::clone(&storage), options.vector_dimension);
+    /// Build an OpenDB on top of an already opened storage backend
+    pub(crate) fn from_storage(storage: SharedStorage, options: OpenDBOptions) -> Self {
+        let kv = KvStore::new(Arc::clone(&storage), options.kv_cache_size)
+            .with_cache_config(options.cache);
+        let indexed_metadata_keys = if options.opens_cf(ColumnFamilies::METADATA_INDEX) {
+            options.indexed_metadata_keys.clone()
+        } else {
+            Vec::new()
+        };
+        let records = RecordsManager::new(Arc::clone(&storage), options.record_cache_size)
+            .with_cache_config(options.cache)
+            .with_value_compression(options.value_compression)
+            .with_indexed_metadata_keys(indexed_metadata_keys)
+            .with_importance_index(options.opens_cf(ColumnFamilies::IMPORTANCE_INDEX))
+            .with_time_index(options.opens_cf(ColumnFamilies::TIME_INDEX))
+            .with_limits(options.max_content_bytes, options.max_metadata_entries);
+        let documents = DocumentsManager::new(Arc::clone(&storage))
+            .with_value_compression(options.value_compression);
+        let graph = GraphManager::new(Arc::clone(&storage))
+            .with_multigraph(options.multigraph)
+            .with_max_traversal_nodes(options.max_traversal_nodes);
+        let vector = VectorManager::new(Arc::clone(&storage), options.vector_dimension)
+            .with_field_dimensions(options.field_dimensions.clone())
+            .with_metric(options.distance_metric)
+            .with_hnsw_params(options.hnsw_params)
+            .with_normalization(options.normalize_embeddings)
+            .with_quantization(options.quantize_vectors, options.quantization_rerank_factor)
+            .with_index_persistence(options.opens_cf(ColumnFamilies::VECTOR_INDEX));
         let txn_manager = TransactionManager::new(Arc::clone(&storage));
 
-        Ok(Self {
+        Self {
             storage,
             kv,
             records,
+            documents,
             graph,
             vector,
             txn_manager,
-        })
+            options,
+            dedup_lock: Mutex::new(()),
+        }
     }
 
     // ===== Key-Value Operations =====
@@ -91,6 +314,43 @@ impl OpenDB {
         self.kv.delete(key)
     }
 
+    /// Delete every key starting with `prefix`, e.g. a tenant's namespace
+    ///
+    /// Only the key-value store is cleared; use `delete_memories_prefix` for
+    /// memory records. The deletes are applied in one atomic batch and the
+    /// affected cache entries are dropped. Returns how many keys were deleted.
+    /// An empty prefix is rejected with `Error::InvalidInput` rather than
+    /// clearing the whole store.
+    pub fn delete_prefix(&self, prefix: &[u8]) -> Result<usize> {
+        if prefix.is_empty() {
+            return Err(Error::InvalidInput("Prefix must not be empty".to_string()));
+        }
+        self.kv.delete_prefix(prefix)
+    }
+
+    /// Atomically add `delta` to a counter, returning the new total
+    ///
+    /// The value is stored as a little-endian `i64` and a missing key counts
+    /// as 0, so `increment(key, 0)` reads a counter. Safe to call from many
+    /// threads at once: no update is lost.
+    pub fn increment(&self, key: &[u8], delta: i64) -> Result<i64> {
+        self.kv.increment(key, delta)
+    }
+
+    /// Set `key` to `new` only if its current value equals `expected`
+    ///
+    /// `expected = None` requires the key to be absent. Returns `true` if the
+    /// value was swapped; concurrent swaps from the same value can't both
+    /// succeed.
+    pub fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool> {
+        self.kv.compare_and_swap(key, expected, new)
+    }
+
     /// Check if a key exists
     pub fn exists(&self, key: &[u8]) -> Result<bool> {
         self.kv.exists(key)
@@ -101,183 +361,2762 @@ impl OpenDB {
         self.kv.scan_prefix(prefix)
     }
 
+    /// Scan keys from `start` (inclusive) to `end` (exclusive), in key order
+    ///
+    /// Keys compare as raw bytes, so e.g. `b"2024-01"..b"2024-07"` covers the
+    /// first half of 2024 for date-prefixed keys. `start >= end` returns an
+    /// empty result. Bounds need not be existing keys.
+    pub fn scan_range(&self, start: &[u8], end: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.kv.scan_range(start, end, false)
+    }
+
+    /// Like `scan_range`, but from the largest key in the range down
+    pub fn scan_range_rev(&self, start: &[u8], end: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.kv.scan_range(start, end, true)
+    }
+
+    /// Serde-typed view of one KV namespace
+    ///
+    /// Keys are stored under `{namespace}:` and values as JSON, so stores
+    /// with different namespaces never collide. See `TypedStore` for how
+    /// keys are encoded.
+    ///
+    /// ```no_run
+    /// # fn main() -> opendb::Result<()> {
+    /// # let db = opendb::OpenDB::open("./db")?;
+    /// let scores = db.typed_store::<String, u32>("scores");
+    /// scores.put(&"alice".to_string(), &42)?;
+    /// assert_eq!(scores.get(&"alice".to_string())?, Some(42));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn typed_store<K, V>(&self, namespace: &str) -> TypedStore<'_, K, V>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+    {
+        TypedStore::new(&self.kv, namespace)
+    }
+
     // ===== Memory Record Operations =====
 
     /// Insert or update a memory record
+    ///
+    /// A memory with an empty embedding is stored as a text-only record: it
+    /// gets no vector index entry and is never returned by `search_similar`.
+    ///
+    /// A non-empty embedding whose length differs from the configured
+    /// dimension is adjusted per `OpenDBOptions::dimension_coercion` first.
     pub fn insert_memory(&self, memory: &Memory) -> Result<()> {
+        let coerced = self.coerce_embedding(memory);
+        let memory = coerced.as_ref().unwrap_or(memory);
+
         // Store the record
         self.records.put(memory)?;
 
-        // Index the vector
-        self.vector.insert(memory)?;
+        // Index the vector (text-only records drop any previous vector)
+        if memory.embedding.is_empty() {
+            self.vector.delete(&memory.id)?;
+        } else {
+            self.vector.insert(memory)?;
+        }
+        self.vector.set_fields(memory)?;
 
         Ok(())
     }
 
-    /// Get a memory record by ID
-    pub fn get_memory(&self, id: &str) -> Result<Option<Memory>> {
-        self.records.get(id)
-    }
+    /// Insert or update many memory records at once
+    ///
+    /// Every embedding is validated (after `dimension_coercion`) before
+    /// anything is written; on error nothing is stored. Records and vectors
+    /// are then written in a single atomic batch and the vector index is
+    /// updated once, which is much faster than repeated `insert_memory` calls.
+    /// Ids must be unique within the batch.
+    pub fn insert_memories(&self, memories: &[Memory]) -> Result<()> {
+        let memories: Vec<Memory> = memories
+            .iter()
+            .map(|memory| {
+                self.coerce_embedding(memory)
+                    .unwrap_or_else(|| memory.clone())
+            })
+            .collect();
 
-    /// Delete a memory record
-    pub fn delete_memory(&self, id: &str) -> Result<()> {
-        self.records.delete(id)?;
-        self.vector.delete(id)?;
-        Ok(())
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = memories.iter().find(|memory| !seen.insert(&memory.id)) {
+            return Err(Error::InvalidInput(format!(
+                "Duplicate memory id in batch: {}",
+                duplicate.id
+            )));
+        }
+
+        let mut ops = self.vector.batch_ops(&memories)?;
+        for memory in &memories {
+            ops.extend(self.records.put_ops(memory)?);
+        }
+        self.storage.write_batch(&ops)?;
+
+        for memory in &memories {
+            self.records.invalidate(&memory.id);
+        }
+        self.vector.index_batch(&memories)
     }
 
-    /// List all memory IDs with a prefix
-    pub fn list_memory_ids(&self, prefix: &str) -> Result<Vec<String>> {
-        self.records.list_ids(prefix)
+    /// Insert a memory only if its id isn't already stored
+    ///
+    /// Returns the stored memory and whether it was inserted now. An existing
+    /// record is returned untouched, so a retried ingestion never replaces a
+    /// richer record with a placeholder. The check and insert run in one
+    /// transaction: of several concurrent callers with the same id, exactly
+    /// one inserts and the rest get its record. The vector index is only
+    /// updated when an insert happens. An expired record counts as absent.
+    pub fn get_or_insert_memory(&self, memory: &Memory) -> Result<(Memory, bool)> {
+        let mut txn = self.begin_transaction()?;
+
+        let existing = txn
+            .get_for_update(ColumnFamilies::RECORDS, memory.id.as_bytes())?
+            .map(|bytes| codec::decode_memory(&bytes))
+            .transpose()?;
+        if let Some(existing) = existing
+            && !existing.is_expired()
+        {
+            txn.rollback()?;
+            return Ok((existing, false));
+        }
+
+        let memory = self
+            .coerce_embedding(memory)
+            .unwrap_or_else(|| memory.clone());
+        txn.insert_memory(&memory)?;
+        txn.commit()?;
+        Ok((memory, true))
     }
 
-    /// List all memories with a prefix
-    pub fn list_memories(&self, prefix: &str) -> Result<Vec<Memory>> {
-        self.records.list(prefix)
+    /// Insert a memory unless a near-duplicate is already stored
+    ///
+    /// Searches for the nearest stored memory to `memory.embedding`. If its
+    /// relevance (see `SearchResult::relevance`) is at least
+    /// `similarity_threshold`, that memory is updated instead: it keeps its
+    /// content and embedding, takes the higher importance and the later
+    /// timestamp, and gains the new metadata (new values win on conflicting
+    /// keys). Otherwise the memory is inserted as with `insert_memory`.
+    ///
+    /// Dedup inserts are serialized, so concurrent calls with the same fact
+    /// leave a single memory. Plain inserts running alongside are not held
+    /// back. A memory with an empty embedding can't be compared and is always
+    /// inserted. The threshold must lie in `0.0..=1.0`.
+    pub fn insert_memory_dedup(
+        &self,
+        memory: &Memory,
+        similarity_threshold: f32,
+    ) -> Result<DedupOutcome> {
+        if !(0.0..=1.0).contains(&similarity_threshold) {
+            return Err(Error::InvalidInput(format!(
+                "Similarity threshold must be in 0.0..=1.0, got {}",
+                similarity_threshold
+            )));
+        }
+        let coerced = self.coerce_embedding(memory);
+        let memory = coerced.as_ref().unwrap_or(memory);
+        if memory.embedding.is_empty() {
+            self.insert_memory(memory)?;
+            return Ok(DedupOutcome::Inserted(memory.id.clone()));
+        }
+
+        let _guard = self.dedup_lock.lock();
+        let metric = self.vector.metric();
+        let mut nearest = None;
+        for (id, distance) in self.vector.search(&memory.embedding, DEDUP_CANDIDATES)? {
+            // Chunk hits and the memory's own id are not duplicates
+            if id != memory.id && self.get_memory(&id)?.is_some() {
+                nearest = Some((id, distance));
+                break;
+            }
+        }
+        let Some((id, distance)) = nearest else {
+            self.insert_memory(memory)?;
+            return Ok(DedupOutcome::Inserted(memory.id.clone()));
+        };
+        if metric.normalize(distance) < similarity_threshold {
+            self.insert_memory(memory)?;
+            return Ok(DedupOutcome::Inserted(memory.id.clone()));
+        }
+
+        let mut txn = self.begin_transaction()?;
+        let existing = txn
+            .get_for_update(ColumnFamilies::RECORDS, id.as_bytes())?
+            .map(|bytes| codec::decode_memory(&bytes))
+            .transpose()?;
+        let Some(mut merged) = existing.filter(|existing| !existing.is_expired()) else {
+            // Deleted or expired since the search
+            txn.rollback()?;
+            self.insert_memory(memory)?;
+            return Ok(DedupOutcome::Inserted(memory.id.clone()));
+        };
+        merged.importance = merged.importance.max(memory.importance);
+        merged.timestamp = merged.timestamp.max(memory.timestamp);
+        merged.metadata.extend(memory.metadata.clone());
+        txn.insert_memory(&merged)?;
+        txn.commit()?;
+        Ok(DedupOutcome::MergedInto(id))
     }
 
-    // ===== Graph Operations =====
+    /// Apply the configured dimension coercion, if the embedding needs it
+    fn coerce_embedding(&self, memory: &Memory) -> Option<Memory> {
+        self.options
+            .dimension_coercion
+            .apply(memory, self.options.vector_dimension)
+    }
 
-    /// Create a link between two entities
+    /// Get a memory record by ID
     ///
-    /// # Arguments
+    /// An expired memory (see `Memory::with_ttl`) reads as absent and is
+    /// deleted along with its vectors.
+    pub fn get_memory(&self, id: &str) -> Result<Option<Memory>> {
+        let memory = self.records.get_including_expired(id)?;
+        self.drop_expired(memory)
+    }
+
+    /// Get several memory records at once
     ///
-    /// * `from` - Source entity ID
-    /// * `relation` - Relationship type
-    /// * `to` - Target entity ID
-    pub fn link(&self, from: &str, relation: &str, to: &str) -> Result<()> {
-        self.graph.link(from, relation, to)
+    /// Cached records are served from the cache and the rest are fetched in
+    /// a single batched lookup, which is much faster than calling
+    /// `get_memory` in a loop. Returns results in input order, with `None`
+    /// for missing or expired ids.
+    pub fn get_memories(&self, ids: &[&str]) -> Result<Vec<Option<Memory>>> {
+        self.records
+            .get_many(ids)?
+            .into_iter()
+            .map(|memory| self.drop_expired(memory))
+            .collect()
     }
 
-    /// Remove a link
-    pub fn unlink(&self, from: &str, relation: &str, to: &str) -> Result<()> {
-        self.graph.unlink(from, relation, to)
+    /// Get a memory record with per-call read options
+    ///
+    /// With `bypass_cache`, the record is read from storage and the cache is
+    /// refreshed with the result, so later plain reads see it too.
+    pub fn get_memory_opts(&self, id: &str, opts: ReadOpts) -> Result<Option<Memory>> {
+        let memory = if opts.bypass_cache {
+            self.records.get_fresh(id)?
+        } else {
+            self.records.get_including_expired(id)?
+        };
+        self.drop_expired(memory)
     }
 
-    /// Get related entity IDs
-    pub fn get_related(&self, id: &str, relation: &str) -> Result<Vec<String>> {
-        self.graph.get_related(id, relation)
+    /// Helper: Delete a memory that was read after it expired
+    fn drop_expired(&self, memory: Option<Memory>) -> Result<Option<Memory>> {
+        match memory {
+            Some(memory) if memory.is_expired() => {
+                if !self.is_read_only() {
+                    self.delete_memory(&memory.id)?;
+                }
+                Ok(None)
+            }
+            memory => Ok(memory),
+        }
     }
 
-    /// Get all outgoing edges from an entity
-    pub fn get_outgoing(&self, from: &str) -> Result<Vec<crate::types::Edge>> {
-        self.graph.get_outgoing(from, None)
+    /// Delete every expired memory and its vectors
+    ///
+    /// Expired memories already read as absent; this sweep reclaims the ones
+    /// nobody has touched since. Returns how many were deleted.
+    pub fn purge_expired(&self) -> Result<usize> {
+        let expired = self.records.purge_expired()?;
+        for id in &expired {
+            self.vector.delete(id)?;
+            self.vector.delete_fields(id)?;
+        }
+        Ok(expired.len())
     }
 
-    /// Get all incoming edges to an entity
-    pub fn get_incoming(&self, to: &str) -> Result<Vec<crate::types::Edge>> {
-        self.graph.get_incoming(to, None)
+    /// The `n` most important memories, highest importance first
+    ///
+    /// Read from the importance index, so only the returned records are
+    /// loaded. Expired memories are skipped.
+    pub fn top_memories(&self, n: usize) -> Result<Vec<Memory>> {
+        let ids = self
+            .records
+            .ids_by_importance(f32::NEG_INFINITY, f32::INFINITY)?;
+        self.memories_by_ids(&ids, n)
     }
 
-    // ===== Vector Search Operations =====
+    /// Memories with importance in `[min, max]`, highest importance first
+    ///
+    /// Both bounds are inclusive; `min > max` returns nothing. Expired
+    /// memories are skipped.
+    pub fn memories_in_importance_range(&self, min: f32, max: f32) -> Result<Vec<Memory>> {
+        let ids = self.records.ids_by_importance(min, max)?;
+        self.memories_by_ids(&ids, usize::MAX)
+    }
 
-    /// Search for similar memories by vector
+    /// Memories with a timestamp in `[start_ts, end_ts)`, oldest first
     ///
-    /// # Arguments
+    /// The start is inclusive and the end exclusive, so consecutive windows
+    /// never overlap; `start_ts >= end_ts` returns nothing. Memories sharing
+    /// a timestamp come back in id order. Read from the time index, so only
+    /// the returned records are loaded. Expired memories are skipped.
+    pub fn memories_between(&self, start_ts: i64, end_ts: i64) -> Result<Vec<Memory>> {
+        let ids = self.records.ids_by_time(start_ts, Some(end_ts), false)?;
+        self.memories_by_ids(&ids, usize::MAX)
+    }
+
+    /// The `n` newest memories by timestamp, newest first
     ///
-    /// * `query` - Query embedding vector
-    /// * `k` - Number of results to return
+    /// Memories sharing a timestamp come back in reverse id order. Expired
+    /// memories are skipped.
+    pub fn recent_memories(&self, n: usize) -> Result<Vec<Memory>> {
+        let ids = self.records.ids_by_time(i64::MIN, None, true)?;
+        self.memories_by_ids(&ids, n)
+    }
+
+    /// Helper: Load up to `limit` live memories in the order of `ids`
+    fn memories_by_ids(&self, ids: &[String], limit: usize) -> Result<Vec<Memory>> {
+        let mut memories = Vec::new();
+        // Fetch in batches so a small `limit` doesn't load every record
+        for chunk in ids.chunks(limit.clamp(1, 256)) {
+            let chunk: Vec<&str> = chunk.iter().map(String::as_str).collect();
+            for memory in self.get_memories(&chunk)?.into_iter().flatten() {
+                if memories.len() == limit {
+                    return Ok(memories);
+                }
+                memories.push(memory);
+            }
+        }
+        Ok(memories)
+    }
+
+    /// Fade memory importance with age
     ///
-    /// # Returns
+    /// Each importance is multiplied by `0.5^(age / half_life_secs)`, where
+    /// age is measured from the later of `Memory::timestamp` and
+    /// `Memory::last_decayed`. Records whose importance changes by at least
+    /// 0.001 are rewritten with `last_decayed` set to now, so repeated calls
+    /// only decay by the time elapsed since a record was last decayed or
+    /// updated; smaller changes keep accumulating until they matter. The
+    /// creation timestamp, embeddings and edges are untouched.
     ///
-    /// List of search results with distances
-    pub fn search_similar(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>> {
-        let results = self.vector.search(query, k)?;
+    /// Returns the number of rewritten memories.
+    pub fn decay_memories(&self, half_life_secs: i64) -> Result<usize> {
+        if half_life_secs <= 0 {
+            return Err(Error::InvalidInput(format!(
+                "Half-life must be positive, got {}",
+                half_life_secs
+            )));
+        }
 
-        let mut search_results = Vec::new();
-        for (id, distance) in results {
-            if let Some(memory) = self.get_memory(&id)? {
-                search_results.push(SearchResult {
-                    id: id.clone(),
-                    distance,
-                    memory,
-                });
+        let now = Utc::now().timestamp();
+        let mut decayed = Vec::new();
+        for memory in self.records.iter("")? {
+            let mut memory = memory?;
+            let since = memory
+                .last_decayed
+                .map_or(memory.timestamp, |decayed| decayed.max(memory.timestamp));
+            let age = (now - since).max(0) as f64;
+            let importance = memory.importance * 0.5f64.powf(age / half_life_secs as f64) as f32;
+            if (memory.importance - importance).abs() >= DECAY_EPSILON {
+                memory.importance = importance;
+                memory.last_decayed = Some(now);
+                decayed.push(memory);
             }
         }
 
-        Ok(search_results)
-    }
+        let mut ops = Vec::with_capacity(decayed.len());
+        for memory in &decayed {
+            ops.extend(self.records.put_ops(memory)?);
+        }
+        self.storage.write_batch(&ops)?;
+        for memory in &decayed {
+            self.records.invalidate(&memory.id);
+        }
 
-    /// Rebuild the vector index
-    pub fn rebuild_vector_index(&self) -> Result<()> {
-        self.vector.rebuild_index()
+        Ok(decayed.len())
     }
 
-    // ===== Transaction Operations =====
+    /// Delete every memory whose importance is below `threshold`
+    ///
+    /// Meant to follow `decay_memories` as a periodic cleanup. Evicted
+    /// memories lose their vectors and every edge into or out of them, so no
+    /// dangling references remain. Returns the number of evicted memories.
+    pub fn evict_below(&self, threshold: f32) -> Result<usize> {
+        let mut evicted = Vec::new();
+        for memory in self.records.iter("")? {
+            let memory = memory?;
+            if memory.importance < threshold {
+                evicted.push(memory.id);
+            }
+        }
 
-    /// Begin a new transaction
-    pub fn begin_transaction(&self) -> Result<Transaction> {
-        self.txn_manager.begin()
+        for id in &evicted {
+            self.delete_memory(id)?;
+            self.graph.remove_node(id)?;
+        }
+
+        Ok(evicted.len())
     }
 
-    /// Flush all pending writes to disk
-    pub fn flush(&self) -> Result<()> {
-        self.storage.flush()
+    /// Get several memories as they were at a single instant
+    ///
+    /// Reads all ids against one snapshot, so a concurrent update can't show
+    /// up for some ids but not others. Returns results in input order, with
+    /// `None` for missing ids.
+    pub fn get_memories_consistent(&self, ids: &[&str]) -> Result<Vec<Option<Memory>>> {
+        self.records.get_many_consistent(ids)
     }
-}
 
-/// Configuration options for OpenDB
-#[derive(Debug, Clone)]
-pub struct OpenDBOptions {
-    /// KV cache size (number of entries)
-    pub kv_cache_size: usize,
+    /// Check whether a memory exists without decoding it
+    ///
+    /// Cheaper than `get_memory(id)?.is_some()` and doesn't load the record
+    /// into the cache, which suits existence checks during bulk imports.
+    pub fn memory_exists(&self, id: &str) -> Result<bool> {
+        self.records.exists_fast(id)
+    }
 
-    /// Record cache size (number of entries)
-    pub record_cache_size: usize,
+    /// Check which of several memories exist, in a single batched lookup
+    ///
+    /// Returns one presence flag per id, in order. Values are never decoded,
+    /// which makes this far cheaper than calling `get_memory` per id.
+    pub fn memories_exist(&self, ids: &[&str]) -> Result<Vec<bool>> {
+        self.records.exists_many(ids)
+    }
 
-    /// Vector dimension
-    pub vector_dimension: usize,
+    /// Apply a partial update to an existing memory record
+    ///
+    /// Only the fields set in `patch` change. The vector index is updated only
+    /// when the patch carries a new embedding, which is adjusted per
+    /// `OpenDBOptions::dimension_coercion` like in `insert_memory`.
+    ///
+    /// Returns the updated memory, or `Error::NotFound` if `id` doesn't exist.
+    pub fn patch_memory(&self, id: &str, patch: MemoryPatch) -> Result<Memory> {
+        let mut memory = self
+            .records
+            .get(id)?
+            .ok_or_else(|| Error::not_found("Memory", id))?;
 
-    /// Database storage path (optional - will use path from open() if not set)
-    pub storage_path: Option<String>,
-}
+        let reindex = patch.embedding.is_some();
+        patch.apply_to(&mut memory);
+        if let Some(coerced) = self.coerce_embedding(&memory) {
+            memory = coerced;
+        }
+        self.records.validate(&memory)?;
 
-impl Default for OpenDBOptions {
-    fn default() -> Self {
-        Self {
-            kv_cache_size: 1000,
-            record_cache_size: 500,
-            vector_dimension: 384, // Common dimension for sentence transformers
-            storage_path: None,
+        // Index first so a bad embedding leaves the record untouched
+        if reindex {
+            if memory.embedding.is_empty() {
+                self.vector.delete(id)?;
+            } else {
+                self.vector.insert(&memory)?;
+            }
         }
+        self.records.put(&memory)?;
+
+        Ok(memory)
     }
-}
 
-impl OpenDBOptions {
-    /// Create new options with default values
-    pub fn new() -> Self {
-        Self::default()
+    /// Ids of memories whose metadata maps `key` to `value`, in id order
+    ///
+    /// Answered by a prefix scan of the metadata index instead of decoding
+    /// every record, so `key` must be listed in
+    /// `OpenDBOptions::indexed_metadata_keys`; other keys fail with
+    /// `Error::InvalidInput`. Expired memories that haven't been purged yet
+    /// are included.
+    pub fn find_by_metadata(&self, key: &str, value: &str) -> Result<Vec<String>> {
+        self.records.find_by_metadata(key, value)
     }
 
-    /// Create options with a specific vector dimension
-    pub fn with_dimension(dimension: usize) -> Self {
-        Self {
-            vector_dimension: dimension,
-            ..Default::default()
+    /// Rebuild the metadata index for the current `indexed_metadata_keys`
+    ///
+    /// Needed after changing the indexed keys of an existing database: the
+    /// index is only maintained as records are written. Returns the number
+    /// of index entries written.
+    pub fn reindex_metadata(&self) -> Result<usize> {
+        self.records.reindex_metadata()
+    }
+
+    /// Delete a memory record
+    pub fn delete_memory(&self, id: &str) -> Result<()> {
+        self.records.delete(id)?;
+        self.vector.delete(id)?;
+        self.vector.delete_fields(id)?;
+        Ok(())
+    }
+
+    /// Delete every memory whose id starts with `prefix`
+    ///
+    /// Like `delete_memory` for each match, plus `remove_node`: the records
+    /// and their index entries go in one atomic batch, then their vectors in
+    /// a second, then every edge into or out of them. A crash in between
+    /// leaves orphaned vectors or edges, which searches and traversals skip;
+    /// `repair_vector_index` removes the vectors. Expired memories are
+    /// included. Returns how many memories were deleted. An empty prefix is
+    /// rejected with `Error::InvalidInput`.
+    pub fn delete_memories_prefix(&self, prefix: &str) -> Result<usize> {
+        if prefix.is_empty() {
+            return Err(Error::InvalidInput("Prefix must not be empty".to_string()));
+        }
+
+        let ids = self.records.delete_prefix(prefix)?;
+        let mut ops = Vec::new();
+        for id in &ids {
+            ops.extend(self.vector.delete_ops(id)?);
+        }
+        self.storage.write_batch(&ops)?;
+        self.vector.unindex(&ids)?;
+
+        for id in &ids {
+            self.graph.remove_node(id)?;
         }
+        Ok(ids.len())
     }
 
-    /// Set vector dimension (chainable)
-    pub fn dimension(mut self, dimension: usize) -> Self {
-        self.vector_dimension = dimension;
-        self
+    // ===== Recycle Bin =====
+
+    /// Delete a memory recoverably
+    ///
+    /// The record and every edge into or out of it are moved to the recycle
+    /// bin, then removed from the live records, vector index and graph just
+    /// like `delete_memory` plus `remove_node`. Soft-deleting an id that is
+    /// already in the bin replaces the older entry.
+    ///
+    /// Returns `Error::NotFound` if the memory doesn't exist.
+    pub fn soft_delete_memory(&self, id: &str) -> Result<()> {
+        let Some(memory) = self.records.get_including_expired(id)? else {
+            return Err(Error::not_found("Memory", id));
+        };
+
+        let mut edges = self.graph.get_outgoing(id, None)?;
+        // Self-loops appear in both directions
+        edges.extend(
+            self.graph
+                .get_incoming(id, None)?
+                .into_iter()
+                .filter(|edge| edge.from != id),
+        );
+        let deleted = DeletedMemory {
+            memory,
+            edges,
+            deleted_at: Utc::now().timestamp(),
+        };
+
+        // The bin entry lands first, so an interrupted delete loses nothing
+        self.storage.put(
+            ColumnFamilies::DELETED_RECORDS,
+            id.as_bytes(),
+            &codec::encode_deleted_memory(&deleted, self.options.value_compression)?,
+        )?;
+        self.delete_memory(id)?;
+        self.graph.remove_node(id)?;
+        Ok(())
     }
 
-    /// Set custom storage path (chainable)
-    pub fn with_storage_path<S: Into<String>>(mut self, path: S) -> Self {
-        self.storage_path = Some(path.into());
-        self
+    /// Bring a soft-deleted memory back from the recycle bin
+    ///
+    /// The record is re-inserted (re-indexing its vectors) and its edges are
+    /// relinked, then the bin entry is removed.
+    ///
+    /// Returns `Error::NotFound` if the id isn't in the bin, or
+    /// `Error::InvalidInput` without changing anything if a live memory with
+    /// the same id exists.
+    pub fn restore_memory(&self, id: &str) -> Result<()> {
+        let Some(bytes) = self
+            .storage
+            .get(ColumnFamilies::DELETED_RECORDS, id.as_bytes())?
+        else {
+            return Err(Error::not_found("Deleted memory", id));
+        };
+        if self.get_memory(id)?.is_some() {
+            return Err(Error::InvalidInput(format!(
+                "Cannot restore {}: a live memory with that id exists",
+                id
+            )));
+        }
+
+        let deleted = codec::decode_deleted_memory(&bytes)?;
+        self.insert_memory(&deleted.memory)?;
+        for edge in &deleted.edges {
+            self.graph.add_edge(edge)?;
+        }
+        self.storage
+            .delete(ColumnFamilies::DELETED_RECORDS, id.as_bytes())
     }
 
-    /// Set KV cache size (chainable)
-    pub fn with_kv_cache_size(mut self, size: usize) -> Self {
-        self.kv_cache_size = size;
-        self
+    /// Every memory in the recycle bin, in id order
+    pub fn list_deleted(&self) -> Result<Vec<DeletedMemory>> {
+        self.storage
+            .scan_prefix_iter(ColumnFamilies::DELETED_RECORDS, &[])?
+            .map(|pair| codec::decode_deleted_memory(&pair?.1))
+            .collect()
     }
 
-    /// Set record cache size (chainable)
-    pub fn with_record_cache_size(mut self, size: usize) -> Self {
-        self.record_cache_size = size;
-        self
+    /// Permanently remove recycle bin entries deleted at least
+    /// `older_than_secs` seconds ago
+    ///
+    /// `purge_deleted(0)` empties the bin. Returns the number of entries
+    /// removed.
+    pub fn purge_deleted(&self, older_than_secs: i64) -> Result<usize> {
+        if older_than_secs < 0 {
+            return Err(Error::InvalidInput(format!(
+                "Age must not be negative, got {}",
+                older_than_secs
+            )));
+        }
+
+        let cutoff = Utc::now().timestamp() - older_than_secs;
+        let mut ops = Vec::new();
+        self.storage
+            .for_each_prefix(ColumnFamilies::DELETED_RECORDS, &[], &mut |key, value| {
+                if codec::decode_deleted_memory(value)?.deleted_at <= cutoff {
+                    ops.push(BatchOp::delete(ColumnFamilies::DELETED_RECORDS, key));
+                }
+                Ok(ControlFlow::Continue(()))
+            })?;
+        self.storage.write_batch(&ops)?;
+        Ok(ops.len())
+    }
+
+    /// Atomically swap the contents of two memory records
+    ///
+    /// The records and their vector entries are exchanged in a single
+    /// transaction, so each id ends up holding the other's content, embeddings,
+    /// importance and metadata. Graph edges stay attached to the ids and are
+    /// not moved.
+    ///
+    /// Returns `Error::NotFound` if either id doesn't exist.
+    pub fn swap_memories(&self, id_a: &str, id_b: &str) -> Result<()> {
+        let mut txn = self.begin_transaction()?;
+
+        let mut mem_a = match txn.get_for_update(ColumnFamilies::RECORDS, id_a.as_bytes())? {
+            Some(bytes) => codec::decode_memory(&bytes)?,
+            None => return Err(Error::not_found("Memory", id_a)),
+        };
+        let mut mem_b = match txn.get_for_update(ColumnFamilies::RECORDS, id_b.as_bytes())? {
+            Some(bytes) => codec::decode_memory(&bytes)?,
+            None => return Err(Error::not_found("Memory", id_b)),
+        };
+
+        if id_a == id_b {
+            return txn.rollback();
+        }
+
+        // Each id takes the other's content. Writing both through the
+        // transaction replaces their vectors and index entries, and the commit
+        // re-indexes just these two ids and refreshes their cached records.
+        std::mem::swap(&mut mem_a.id, &mut mem_b.id);
+        txn.insert_memory(&mem_b)?;
+        txn.insert_memory(&mem_a)?;
+        txn.commit()
+    }
+
+    /// List all memory IDs with a prefix
+    pub fn list_memory_ids(&self, prefix: &str) -> Result<Vec<String>> {
+        self.records.list_ids(prefix)
+    }
+
+    /// Count memories with a prefix
+    ///
+    /// Exact and O(N) in the number of matching memories: every key is
+    /// visited, but no value is read or decoded, so this is far cheaper than
+    /// `list_memories(prefix)?.len()`. No O(1) estimate is available (see
+    /// `stats`). Expired memories that haven't been purged yet are counted.
+    pub fn count_memories(&self, prefix: &str) -> Result<usize> {
+        self.records.count(prefix)
+    }
+
+    /// List all memories with a prefix
+    pub fn list_memories(&self, prefix: &str) -> Result<Vec<Memory>> {
+        self.records.list(prefix)
+    }
+
+    /// Iterate over memories with a prefix without loading them all
+    ///
+    /// Memories are read and decoded as the iterator advances, in id order,
+    /// so `take(n)` or filters stop reading early. Expired memories are
+    /// skipped. Decoding errors are yielded as items.
+    pub fn iter_memories(&self, prefix: &str) -> Result<impl Iterator<Item = Result<Memory>> + '_> {
+        self.records.iter(prefix)
+    }
+
+    /// Get one page of memories with a prefix, in id order
+    ///
+    /// Returns at most `limit` memories after skipping the first `offset`.
+    /// Skipped memories are still read, so deep pages get slower; use
+    /// `list_memories_after` to page with a cursor instead.
+    pub fn list_memories_paged(
+        &self,
+        prefix: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Memory>> {
+        self.records.list_paged(prefix, offset, limit)
+    }
+
+    /// Get at most `limit` memories with a prefix whose ids sort after `start_after`
+    ///
+    /// Pass the id of the last memory of the previous page as the cursor, or
+    /// `None` for the first page. Storage seeks straight to the cursor, so
+    /// every page costs the same.
+    pub fn list_memories_after(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Memory>> {
+        self.records.list_after(prefix, start_after, limit)
+    }
+
+    /// Find memories whose content contains `substring`, up to `limit` of them
+    ///
+    /// A fallback for exact-match lookups or memories without useful
+    /// embeddings. This is an O(N) scan that decodes every record until
+    /// `limit` matches are found; there is no text index behind it. With
+    /// `ignore_case`, both sides are lowercased (Unicode-aware) before
+    /// matching. Matches come back in id order.
+    pub fn search_text_contains(
+        &self,
+        substring: &str,
+        limit: usize,
+        ignore_case: bool,
+    ) -> Result<Vec<Memory>> {
+        self.records.search_contains(substring, limit, ignore_case)
+    }
+
+    // ===== Document Operations =====
+
+    /// Insert or replace a multimodal document
+    ///
+    /// Each chunk with a non-empty embedding is indexed for vector search
+    /// under `{document_id}#{chunk_id}`, so chunks show up in `search_similar`
+    /// alongside memories. Chunk vectors of a replaced document are removed
+    /// first. The document-level embedding is stored but not indexed.
+    ///
+    /// Document ids may not contain `#`, and chunk embeddings must match the
+    /// configured dimension.
+    pub fn insert_document(&self, document: &MultimodalDocument) -> Result<()> {
+        if document.id.contains(MultimodalDocument::CHUNK_ID_SEPARATOR) {
+            return Err(Error::InvalidInput(format!(
+                "Document id may not contain '{}': {}",
+                MultimodalDocument::CHUNK_ID_SEPARATOR,
+                document.id
+            )));
+        }
+        // Validate every chunk before writing anything
+        for chunk in &document.chunks {
+            if !chunk.embedding.is_empty() {
+                self.vector.check_embedding(&chunk.embedding)?;
+            }
+        }
+
+        if let Some(previous) = self.documents.get(&document.id)? {
+            self.delete_chunk_vectors(&previous)?;
+        }
+        self.documents.put(document)?;
+
+        for chunk in &document.chunks {
+            if !chunk.embedding.is_empty() {
+                let vector_id = MultimodalDocument::chunk_vector_id(&document.id, &chunk.chunk_id);
+                self.vector.insert_embedding(&vector_id, &chunk.embedding)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get a multimodal document by ID
+    pub fn get_document(&self, id: &str) -> Result<Option<MultimodalDocument>> {
+        self.documents.get(id)
+    }
+
+    /// Delete a multimodal document and its chunk vectors
+    pub fn delete_document(&self, id: &str) -> Result<()> {
+        if let Some(document) = self.documents.get(id)? {
+            self.delete_chunk_vectors(&document)?;
+        }
+        self.documents.delete(id)
+    }
+
+    /// List all documents with an ID prefix
+    pub fn list_documents(&self, prefix: &str) -> Result<Vec<MultimodalDocument>> {
+        self.documents.list(prefix)
+    }
+
+    /// Helper: Remove the indexed vectors of a document's chunks
+    fn delete_chunk_vectors(&self, document: &MultimodalDocument) -> Result<()> {
+        for chunk in &document.chunks {
+            self.vector.delete(&MultimodalDocument::chunk_vector_id(
+                &document.id,
+                &chunk.chunk_id,
+            ))?;
+        }
+        Ok(())
+    }
+
+    // ===== Graph Operations =====
+
+    /// Create a link between two entities
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Source entity ID
+    /// * `relation` - Relationship type
+    /// * `to` - Target entity ID
+    pub fn link(&self, from: &str, relation: &str, to: &str) -> Result<()> {
+        self.graph.link(from, relation, to)
+    }
+
+    /// Create many links at once, as `(from, relation, to)` triples
+    ///
+    /// Every edge is written in one atomic batch. Links that already exist or
+    /// repeat within the batch are skipped. Returns the number of edges added.
+    pub fn link_many(&self, links: &[(&str, &str, &str)]) -> Result<usize> {
+        self.graph.link_many(links)
+    }
+
+    /// Create a weighted link, e.g. how strongly two memories associate
+    ///
+    /// Weights must be finite. Linking an existing `(from, relation, to)`
+    /// again keeps the existing edge and its weight.
+    pub fn link_weighted(&self, from: &str, relation: &str, to: &str, weight: f32) -> Result<()> {
+        self.graph.link_weighted(from, relation, to, weight)
+    }
+
+    /// Create a link from a fully built edge
+    ///
+    /// Use this to attach metadata such as provenance or confidence:
+    /// `Edge::new(from, relation, to).with_metadata("source", "chat")`.
+    /// The metadata comes back from `get_outgoing` and `get_incoming`.
+    /// Weights must be finite, and linking an existing `(from, relation, to)`
+    /// again keeps the existing edge.
+    pub fn link_with(&self, edge: &Edge) -> Result<()> {
+        self.graph.link_with(edge)
+    }
+
+    /// Change the weight of an existing link in place
+    ///
+    /// Fails with `Error::Graph` if the link doesn't exist.
+    pub fn set_weight(&self, from: &str, relation: &str, to: &str, weight: f32) -> Result<()> {
+        self.graph.set_weight(from, relation, to, weight)
+    }
+
+    /// Find the nodes reachable from `start` within `max_depth` hops
+    ///
+    /// Walks outgoing edges breadth-first, following only `relation` edges
+    /// if given, and returns `(id, hops)` pairs in visit order. The start node
+    /// is not included and each node is visited once, so cycles are safe.
+    /// At most `OpenDBOptions::max_traversal_nodes` nodes are returned; use
+    /// `traverse_page` to walk larger neighborhoods.
+    pub fn traverse(
+        &self,
+        start: &str,
+        relation: Option<&str>,
+        max_depth: usize,
+    ) -> Result<Vec<(String, usize)>> {
+        self.graph.traverse(start, relation, max_depth)
+    }
+
+    /// Get one page of the nodes reachable from `start`, as `(id, hops)` pairs
+    ///
+    /// Walks outgoing edges breadth-first (optionally only `relation` edges)
+    /// up to `max_depth` hops, skips the first `offset` nodes and returns at
+    /// most `limit`. The start node itself is not included. Visit order is
+    /// deterministic, so paging with `offset += limit` yields disjoint pages
+    /// as long as the graph doesn't change in between.
+    pub fn traverse_page(
+        &self,
+        start: &str,
+        relation: Option<&str>,
+        max_depth: usize,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<(String, usize)>> {
+        self.graph
+            .traverse_page(start, relation, max_depth, offset, limit)
+    }
+
+    /// Extract the connected subgraph around `root` as `(nodes, edges)`
+    ///
+    /// Follows outgoing edges breadth-first up to `max_depth` hops and stops
+    /// at `max_nodes` nodes, `root` included. The edges are every edge
+    /// between two returned nodes, each listed once, so the pair can be fed
+    /// straight into a graph renderer. Cycles are safe.
+    pub fn subgraph(
+        &self,
+        root: &str,
+        max_depth: usize,
+        max_nodes: usize,
+    ) -> Result<(Vec<String>, Vec<Edge>)> {
+        self.graph.subgraph(root, max_depth, max_nodes)
+    }
+
+    /// Store a prebuilt edge, keeping its id, weight and timestamp
+    ///
+    /// Outside multigraph mode an edge with the same `(from, relation, to)`
+    /// as an existing one is ignored.
+    pub fn add_edge(&self, edge: &Edge) -> Result<()> {
+        self.graph.add_edge(edge)
+    }
+
+    /// Remove a single edge by id, returning whether it existed
+    pub fn unlink_edge(&self, from: &str, edge_id: &str) -> Result<bool> {
+        self.graph.unlink_edge(from, edge_id)
+    }
+
+    /// Remove a link
+    ///
+    /// Fails with `Error::InvalidInput` in multigraph mode, where the triple
+    /// may match several edges; use `unlink_edge` there.
+    pub fn unlink(&self, from: &str, relation: &str, to: &str) -> Result<()> {
+        self.graph.unlink(from, relation, to)
+    }
+
+    /// Get related entity IDs
+    pub fn get_related(&self, id: &str, relation: &str) -> Result<Vec<String>> {
+        self.graph.get_related(id, relation)
+    }
+
+    /// Get related entity IDs with edge weights, sorted by descending weight
+    pub fn get_related_weighted(&self, id: &str, relation: &str) -> Result<Vec<(String, f32)>> {
+        self.graph.get_related_weighted(id, relation)
+    }
+
+    /// Get outgoing target IDs grouped by relation type
+    pub fn outgoing_by_relation(&self, id: &str) -> Result<HashMap<String, Vec<String>>> {
+        self.graph.outgoing_by_relation(id)
+    }
+
+    /// Get the distinct relation types on an entity's outgoing edges, sorted
+    pub fn relations_of(&self, id: &str) -> Result<Vec<String>> {
+        self.graph.relations_of(id)
+    }
+
+    /// Get every distinct relation type used in the graph, sorted
+    pub fn all_relations(&self) -> Result<Vec<String>> {
+        self.graph.all_relations()
+    }
+
+    /// Decay edge weights by age with the given half-life, pruning edges below `floor`
+    ///
+    /// Returns the number of pruned edges. See `Edge::last_decayed` for how
+    /// repeated decay is tracked.
+    pub fn decay_edge_weights(&self, half_life_secs: f64, floor: Option<f32>) -> Result<usize> {
+        self.graph.decay_edge_weights(half_life_secs, floor)
+    }
+
+    /// Rank entities by PageRank over the forward edges
+    ///
+    /// Each iteration is one pass over every edge, so the cost is
+    /// `O(iterations * edges)` and the whole graph is held in memory while
+    /// ranking. With the usual `damping = 0.85`, 20-50 iterations are
+    /// typically enough for scores to settle; the error shrinks roughly by a
+    /// factor of `damping` per iteration. Scores sum to ~1.0.
+    pub fn pagerank(&self, damping: f32, iterations: usize) -> Result<HashMap<String, f32>> {
+        self.graph.pagerank(damping, iterations, false)
+    }
+
+    /// Like `pagerank`, but rank flows along edges in proportion to their weight
+    pub fn pagerank_weighted(
+        &self,
+        damping: f32,
+        iterations: usize,
+    ) -> Result<HashMap<String, f32>> {
+        self.graph.pagerank(damping, iterations, true)
+    }
+
+    /// Visit every edge in the graph once without collecting them
+    ///
+    /// Return `ControlFlow::Break(())` from the visitor to stop early.
+    pub fn for_each_edge<F>(&self, visitor: F) -> Result<()>
+    where
+        F: FnMut(&Edge) -> ControlFlow<()>,
+    {
+        self.graph.for_each_edge(visitor)
+    }
+
+    /// Count edges in the graph
+    ///
+    /// Exact and O(N) in the number of edges: every forward index key is
+    /// visited, but no value is read. Cheaper than `graph_stats`, which also
+    /// collects distinct nodes.
+    pub fn count_edges(&self) -> Result<usize> {
+        self.graph.edge_count()
+    }
+
+    /// Count nodes and edges in the graph
+    pub fn graph_stats(&self) -> Result<GraphStats> {
+        self.graph.graph_stats()
+    }
+
+    /// Get all outgoing edges from an entity
+    pub fn get_outgoing(&self, from: &str) -> Result<Vec<Edge>> {
+        self.graph.get_outgoing(from, None)
+    }
+
+    /// Get all incoming edges to an entity
+    pub fn get_incoming(&self, to: &str) -> Result<Vec<Edge>> {
+        self.graph.get_incoming(to, None)
+    }
+
+    // ===== Bundle Operations =====
+
+    /// Export a memory and its ego-graph as a portable bundle
+    ///
+    /// Collects every entity within `depth` hops of `root_id`, following
+    /// edges in both directions, plus the edges connecting them. Entities
+    /// without a memory record appear only as edge endpoints.
+    ///
+    /// Returns `Error::NotFound` if `root_id` has no memory.
+    pub fn export_bundle(&self, root_id: &str, depth: usize) -> Result<Bundle> {
+        let root = self
+            .get_memory(root_id)?
+            .ok_or_else(|| Error::not_found("Memory", root_id))?;
+
+        // Breadth-first over both edge directions
+        let mut visited: HashSet<String> = HashSet::from([root_id.to_string()]);
+        let mut order = vec![root_id.to_string()];
+        let mut queue = VecDeque::from([(root_id.to_string(), 0)]);
+        while let Some((id, hops)) = queue.pop_front() {
+            if hops == depth {
+                continue;
+            }
+            let outgoing = self
+                .graph
+                .get_outgoing(&id, None)?
+                .into_iter()
+                .map(|e| e.to);
+            let incoming = self
+                .graph
+                .get_incoming(&id, None)?
+                .into_iter()
+                .map(|e| e.from);
+            for next in outgoing.chain(incoming) {
+                if visited.insert(next.clone()) {
+                    order.push(next.clone());
+                    queue.push_back((next, hops + 1));
+                }
+            }
+        }
+
+        let mut memories = vec![root];
+        let mut edges = Vec::new();
+        for id in &order {
+            if id != root_id
+                && let Some(memory) = self.get_memory(id)?
+            {
+                memories.push(memory);
+            }
+            edges.extend(
+                self.graph
+                    .get_outgoing(id, None)?
+                    .into_iter()
+                    .filter(|e| visited.contains(&e.to)),
+            );
+        }
+
+        Ok(Bundle {
+            root_id: root_id.to_string(),
+            memories,
+            edges,
+        })
+    }
+
+    /// Import a bundle produced by `export_bundle`
+    ///
+    /// IDs are assigned according to `policy`; edge weights and timestamps are
+    /// kept. Returns the ID the bundle's root was imported under.
+    pub fn import_bundle(&self, bundle: &Bundle, policy: IdPolicy) -> Result<String> {
+        if policy == IdPolicy::FailOnConflict {
+            for memory in &bundle.memories {
+                if self.get_memory(&memory.id)?.is_some() {
+                    return Err(Error::InvalidInput(format!(
+                        "Memory already exists: {}",
+                        memory.id
+                    )));
+                }
+            }
+        }
+
+        for memory in &bundle.memories {
+            let mut memory = memory.clone();
+            memory.id = policy.apply(&memory.id);
+            self.insert_memory(&memory)?;
+        }
+        for edge in &bundle.edges {
+            let mut edge = edge.clone();
+            edge.from = policy.apply(&edge.from);
+            edge.to = policy.apply(&edge.to);
+            self.graph.add_edge(&edge)?;
+        }
+
+        Ok(policy.apply(&bundle.root_id))
+    }
+
+    /// Export the whole database as one JSON document
+    ///
+    /// The document holds every memory (expired ones are skipped), edge, KV
+    /// pair and multimodal document, and is streamed to `writer` item by item.
+    /// KV keys and values are written as strings when they are valid UTF-8
+    /// and as byte arrays otherwise. Read it back with `import_json`.
+    ///
+    /// Column families are read live, so writes made during the export may
+    /// show up in some sections and not others. Use `export_json_consistent`
+    /// for a point-in-time dump.
+    pub fn export_json(&self, writer: impl Write) -> Result<()> {
+        write_export(writer, |cf, visitor| {
+            self.storage.for_each_prefix(cf, &[], visitor)
+        })
+    }
+
+    /// Export the whole database as of a single point in time
+    ///
+    /// Same document as `export_json`, but every column family is read
+    /// through one storage snapshot taken before anything is written, so
+    /// writes that land during the export are left out entirely. Writers
+    /// are never blocked. The snapshot is held until the export finishes,
+    /// which keeps RocksDB from deleting the SST files it references; a
+    /// long export under heavy writes temporarily uses extra disk space.
+    pub fn export_json_consistent(&self, writer: impl Write) -> Result<()> {
+        let snapshot = self.storage.snapshot()?;
+        write_export(writer, |cf, visitor| {
+            snapshot.for_each_prefix(cf, &[], visitor)
+        })
+    }
+
+    /// Import a document written by `export_json`
+    ///
+    /// Items are applied while the document is parsed, so memory use stays
+    /// flat: memories are upserted (and their vectors indexed) in batches,
+    /// edges keep their ids, weights and timestamps, and documents have their
+    /// chunk vectors indexed. Existing data is kept unless an imported item
+    /// replaces it. On error, items applied before it stay written.
+    pub fn import_json(&self, reader: impl Read) -> Result<()> {
+        let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+        Importer::new(self).run(&mut deserializer)?;
+        deserializer
+            .end()
+            .map_err(|e| Error::Codec(format!("Invalid export document: {}", e)))
+    }
+
+    /// Copy every memory, edge, KV pair and document of `other` into this database
+    ///
+    /// Items that already exist here are resolved by `on_conflict`: memories
+    /// and documents by id, KV pairs by key, and edges by
+    /// `(from, relation, to)` (by edge id in multigraph mode). Merged
+    /// memories and document chunks have their vectors indexed, and merged
+    /// edges are written to both graph indexes. Expired memories of `other`
+    /// are skipped.
+    ///
+    /// Fails with `Error::DimensionMismatch` before writing anything if the
+    /// databases have different vector dimensions. The merge is not atomic:
+    /// on error, items merged before it stay written.
+    pub fn merge_from(&self, other: &OpenDB, on_conflict: ConflictPolicy) -> Result<MergeStats> {
+        if other.options.vector_dimension != self.options.vector_dimension {
+            return Err(Error::DimensionMismatch {
+                expected: self.options.vector_dimension,
+                got: other.options.vector_dimension,
+                field: None,
+            });
+        }
+
+        let mut stats = MergeStats::default();
+
+        // Batched, so vectors are indexed once per batch
+        let mut batch = Vec::with_capacity(MERGE_BATCH_SIZE);
+        for memory in other.iter_memories("")? {
+            let memory = memory?;
+            let existing = self.get_memory(&memory.id)?.map(|m| Some(m.timestamp));
+            if stats.record(on_conflict, existing, Some(memory.timestamp)) {
+                batch.push(memory);
+                if batch.len() == MERGE_BATCH_SIZE {
+                    self.insert_memories(&batch)?;
+                    batch.clear();
+                }
+            }
+        }
+        if !batch.is_empty() {
+            self.insert_memories(&batch)?;
+        }
+
+        other
+            .storage
+            .for_each_prefix(ColumnFamilies::GRAPH_FORWARD, &[], &mut |_, value| {
+                let edge = codec::decode_edge(value)?;
+                let conflicting = self.graph.conflicting_edges(&edge)?;
+                let existing = conflicting.iter().map(|e| e.timestamp).max();
+                let existing = (!conflicting.is_empty()).then_some(existing);
+                if stats.record(on_conflict, existing, Some(edge.timestamp)) {
+                    self.graph.replace_edge(&edge)?;
+                }
+                Ok(ControlFlow::Continue(()))
+            })?;
+
+        other
+            .storage
+            .for_each_prefix(ColumnFamilies::DEFAULT, &[], &mut |key, value| {
+                let existing = self.get(key)?.map(|_| None);
+                if stats.record(on_conflict, existing, None) {
+                    self.put(key, value)?;
+                }
+                Ok(ControlFlow::Continue(()))
+            })?;
+
+        other
+            .storage
+            .for_each_prefix(ColumnFamilies::DOCUMENTS, &[], &mut |_, value| {
+                let document = codec::decode_document(value)?;
+                let existing = self.get_document(&document.id)?.map(|d| Some(d.timestamp));
+                if stats.record(on_conflict, existing, Some(document.timestamp)) {
+                    self.insert_document(&document)?;
+                }
+                Ok(ControlFlow::Continue(()))
+            })?;
+
+        Ok(stats)
+    }
+
+    // ===== Vector Search Operations =====
+
+    /// Search for similar memories by vector
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query embedding vector
+    /// * `k` - Number of results to return
+    ///
+    /// # Returns
+    ///
+    /// List of search results with distances
+    pub fn search_similar(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+        let k = self.check_search_k(k)?;
+        let results = self.vector.search(query, k)?;
+        self.hydrate_results(results)
+    }
+
+    /// Search for similar memories for several queries at once
+    ///
+    /// Returns one result list per query, in query order, each matching what
+    /// `search_similar` would return for that query. All query dimensions are
+    /// validated before any search runs, and the queries share one index load
+    /// and are searched in parallel, which is cheaper than calling
+    /// `search_similar` in a loop.
+    pub fn search_similar_batch(
+        &self,
+        queries: &[Vec<f32>],
+        k: usize,
+    ) -> Result<Vec<Vec<SearchResult>>> {
+        let k = self.check_search_k(k)?;
+        self.vector
+            .search_batch(queries, k)?
+            .into_iter()
+            .map(|results| self.hydrate_results(results))
+            .collect()
+    }
+
+    /// Search for similar memories that satisfy a predicate
+    ///
+    /// The filter runs before truncating to `k`, so filtered-out neighbors do
+    /// not shrink the result. Like `search_chunks_dedup`, this over-fetches:
+    /// it starts at `4 * k` candidates and doubles until `k` matches are found
+    /// or the index is exhausted. Results are ordered by distance.
+    pub fn search_similar_filtered(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: impl Fn(&Memory) -> bool,
+    ) -> Result<Vec<SearchResult>> {
+        let k = self.check_search_k(k)?;
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut fetch = k.saturating_mul(4);
+        loop {
+            let hits = self.vector.search(query, fetch)?;
+            let exhausted = hits.len() < fetch;
+
+            let mut results = self.hydrate_results(hits)?;
+            results.retain(|result| filter(&result.memory));
+            if results.len() >= k || exhausted {
+                results.truncate(k);
+                return Ok(results);
+            }
+            fetch = fetch.saturating_mul(2);
+        }
+    }
+
+    /// Find every memory within `max_distance` of the query, closest first
+    ///
+    /// Unlike `search_similar` there is no `k`: all matches are returned, so
+    /// pick a threshold suited to the metric (Euclidean 0..∞, cosine 0..2,
+    /// dot product: the negated minimum dot product).
+    /// Distance computations stop early once they exceed the threshold unless
+    /// `OpenDBOptions::with_threshold_early_exit(false)` is set.
+    pub fn search_within_distance(
+        &self,
+        query: &[f32],
+        max_distance: f32,
+    ) -> Result<Vec<SearchResult>> {
+        if max_distance.is_nan() {
+            return Err(Error::InvalidInput(
+                "Distance threshold must not be NaN".to_string(),
+            ));
+        }
+
+        let results =
+            self.vector
+                .search_within(query, max_distance, self.options.threshold_early_exit)?;
+        self.hydrate_results(results)
+    }
+
+    /// Search for similar memories, keeping only the closest percentile
+    ///
+    /// Fetches `candidate_k` neighbors and keeps those whose distance is at or
+    /// below the `percentile`-th percentile (nearest-rank, `0 < percentile <=
+    /// 100`) of the candidates' distances, so the cutoff adapts per query.
+    /// E.g. `percentile = 25.0` keeps roughly the closest quarter.
+    pub fn search_similar_percentile(
+        &self,
+        query: &[f32],
+        candidate_k: usize,
+        percentile: f32,
+    ) -> Result<Vec<SearchResult>> {
+        if !(percentile > 0.0 && percentile <= 100.0) {
+            return Err(Error::InvalidInput(format!(
+                "Percentile must be in (0, 100], got {}",
+                percentile
+            )));
+        }
+
+        let candidate_k = self.check_search_k(candidate_k)?;
+        let mut results = self.vector.search(query, candidate_k)?;
+        if results.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Results are sorted by distance, so the nearest-rank cutoff is an index
+        let rank = ((percentile / 100.0) * results.len() as f32).ceil() as usize;
+        let cutoff = results[rank.clamp(1, results.len()) - 1].1;
+        results.retain(|(_, distance)| *distance <= cutoff);
+
+        self.hydrate_results(results)
+    }
+
+    /// Search for similar memories by one named field embedding
+    ///
+    /// Only memories carrying a non-empty embedding for `field` are
+    /// candidates. The query must match the field's dimension (see
+    /// `OpenDBOptions::with_field_dimension`).
+    pub fn search_similar_field(
+        &self,
+        field: &str,
+        query: &[f32],
+        k: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let k = self.check_search_k(k)?;
+        let results = self.vector.search_field(field, query, k)?;
+        self.hydrate_results(results)
+    }
+
+    /// Search chunk memories, returning at most one (the best) chunk per document
+    ///
+    /// Chunks are memories whose `parent_doc` metadata names their document
+    /// (see `DocumentChunk::PARENT_METADATA_KEY`); other memories are skipped.
+    ///
+    /// Because one document can crowd the top of the ranking, this over-fetches:
+    /// it starts at `4 * k` candidates and doubles until `k` distinct documents
+    /// are found or the index is exhausted. Results are ordered by distance.
+    pub fn search_chunks_dedup(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>> {
+        let k = self.check_search_k(k)?;
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut fetch = k.saturating_mul(4);
+        loop {
+            let hits = self.vector.search(query, fetch)?;
+            let exhausted = hits.len() < fetch;
+
+            let mut seen = HashSet::new();
+            let mut results = Vec::new();
+            for result in self.hydrate_results(hits)? {
+                let Some(parent) = result
+                    .memory
+                    .metadata
+                    .get(DocumentChunk::PARENT_METADATA_KEY)
+                else {
+                    continue;
+                };
+                if seen.insert(parent.clone()) {
+                    results.push(result);
+                    if results.len() == k {
+                        return Ok(results);
+                    }
+                }
+            }
+
+            if exhausted {
+                return Ok(results);
+            }
+            fetch = fetch.saturating_mul(2);
+        }
+    }
+
+    /// Search document chunks only, returning each hit with its chunk
+    ///
+    /// Unlike `search_similar`, memory vectors are skipped and every result
+    /// carries the matching `DocumentChunk` and its document id, so callers
+    /// can cite chunk metadata such as page numbers. Chunk vectors are told
+    /// apart by their `{document_id}#{chunk_id}` ids; a memory whose id has
+    /// the same form is still treated as a memory. Like
+    /// `search_chunks_dedup`, this over-fetches until `k` chunks are found or
+    /// the index is exhausted. Results are ordered by distance.
+    pub fn search_chunks(&self, query: &[f32], k: usize) -> Result<Vec<ChunkSearchResult>> {
+        let k = self.check_search_k(k)?;
+        if k == 0 || !self.is_cf_opened(ColumnFamilies::DOCUMENTS) {
+            return Ok(Vec::new());
+        }
+
+        let mut fetch = k.saturating_mul(4);
+        loop {
+            let hits = self.vector.search(query, fetch)?;
+            let exhausted = hits.len() < fetch;
+
+            let mut documents: HashMap<String, Option<MultimodalDocument>> = HashMap::new();
+            let mut results = Vec::new();
+            for (id, distance) in hits {
+                let Some((document_id, chunk_id)) = MultimodalDocument::split_chunk_vector_id(&id)
+                else {
+                    continue;
+                };
+                if self.records.exists_fast(&id)? {
+                    continue;
+                }
+                if !documents.contains_key(document_id) {
+                    documents.insert(document_id.to_string(), self.documents.get(document_id)?);
+                }
+                let chunk = documents[document_id]
+                    .as_ref()
+                    .and_then(|document| document.chunks.iter().find(|c| c.chunk_id == chunk_id));
+                if let Some(chunk) = chunk {
+                    results.push(ChunkSearchResult {
+                        document_id: document_id.to_string(),
+                        chunk: chunk.clone(),
+                        distance,
+                    });
+                    if results.len() == k {
+                        return Ok(results);
+                    }
+                }
+            }
+
+            if exhausted {
+                return Ok(results);
+            }
+            fetch = fetch.saturating_mul(2);
+        }
+    }
+
+    /// Helper: Load the memories (or document chunks) behind search hits
+    fn hydrate_results(&self, results: Vec<(String, f32)>) -> Result<Vec<SearchResult>> {
+        let ids: Vec<&str> = results.iter().map(|(id, _)| id.as_str()).collect();
+        let memories = self.get_memories(&ids)?;
+
+        let mut search_results = Vec::new();
+        for ((id, distance), memory) in results.iter().zip(memories) {
+            let memory = match memory {
+                Some(memory) => Some(memory),
+                None => self.chunk_memory(id)?,
+            };
+            if let Some(memory) = memory {
+                search_results.push(SearchResult {
+                    id: id.clone(),
+                    distance: *distance,
+                    metric: self.vector.metric(),
+                    score: Some(self.vector.metric().normalize(*distance)),
+                    memory,
+                });
+            }
+        }
+
+        Ok(search_results)
+    }
+
+    /// Helper: Memory view of the document chunk behind a chunk vector id
+    fn chunk_memory(&self, vector_id: &str) -> Result<Option<Memory>> {
+        let Some((document_id, chunk_id)) = MultimodalDocument::split_chunk_vector_id(vector_id)
+        else {
+            return Ok(None);
+        };
+        if !self.is_cf_opened(ColumnFamilies::DOCUMENTS) {
+            return Ok(None);
+        }
+        let Some(document) = self.documents.get(document_id)? else {
+            return Ok(None);
+        };
+        Ok(document
+            .chunks
+            .iter()
+            .find(|chunk| chunk.chunk_id == chunk_id)
+            .map(|chunk| chunk.to_memory(&document)))
+    }
+
+    /// Count stored embeddings (memories and document chunks)
+    ///
+    /// Exact and O(N) in the number of vectors: every vector key is visited,
+    /// but no value is read. Named field embeddings are not included.
+    pub fn count_vectors(&self) -> Result<usize> {
+        self.vector.count()
+    }
+
+    /// Ids of memories whose stored embedding doesn't match `vector_dimension`
+    ///
+    /// Such embeddings are left out of the vector index, so searches return
+    /// results from the remaining ones only. If every stored embedding
+    /// mismatches, searches fail with `Error::VectorIndex` instead.
+    pub fn mismatched_vectors(&self) -> Result<Vec<String>> {
+        self.vector.mismatched_vectors()
+    }
+
+    /// Delete vectors that no longer belong to a memory or document chunk
+    ///
+    /// `delete_memory` removes the record before its vectors, so a crash in
+    /// between leaves vectors behind that searches silently skip. This scan
+    /// removes every such orphan from storage and the index. Returns how
+    /// many vectors were removed. See `OpenDBOptions::repair_on_open`.
+    pub fn repair_vector_index(&self) -> Result<usize> {
+        let mut documents: HashMap<String, Option<MultimodalDocument>> = HashMap::new();
+        self.vector.remove_orphans(&mut |id| {
+            // Storage is the authority here, not the record cache
+            if self
+                .storage
+                .exists(ColumnFamilies::RECORDS, id.as_bytes())?
+            {
+                return Ok(true);
+            }
+            let Some((document_id, chunk_id)) = MultimodalDocument::split_chunk_vector_id(id)
+            else {
+                return Ok(false);
+            };
+            if !documents.contains_key(document_id) {
+                documents.insert(document_id.to_string(), self.documents.get(document_id)?);
+            }
+            Ok(documents[document_id]
+                .as_ref()
+                .is_some_and(|document| document.chunks.iter().any(|c| c.chunk_id == chunk_id)))
+        })
+    }
+
+    /// Rebuild the vector index
+    pub fn rebuild_vector_index(&self) -> Result<()> {
+        self.vector.rebuild_index(&|_, _| {})?;
+        Ok(())
+    }
+
+    /// Rebuild the vector index, reporting progress as `(processed, total)`
+    ///
+    /// The callback runs once with `(0, total)` before any vector is
+    /// inserted and again after each one, so a CLI can drive a progress bar.
+    /// The new graph is built alongside the current one and replaces the
+    /// persisted graph in a single transaction, so a crash mid-rebuild leaves
+    /// the old graph intact. Searches and vector writes wait for the rebuild
+    /// to finish. Returns the number of vectors indexed.
+    pub fn rebuild_vector_index_with_progress(
+        &self,
+        progress: impl Fn(usize, usize),
+    ) -> Result<usize> {
+        self.vector.rebuild_index(&progress)
+    }
+
+    /// Free the in-memory vector cache to reclaim RAM
+    ///
+    /// Nothing is lost: the next search rebuilds the cache from storage, at
+    /// the cost of a full scan of the vector data.
+    pub fn release_vector_cache(&self) {
+        self.vector.release_cache()
+    }
+
+    /// Whether the vector cache is built, so the next search won't rebuild it
+    pub fn is_vector_index_ready(&self) -> bool {
+        self.vector.is_ready()
+    }
+
+    /// Helper: Apply the configured `max_search_k` guard to a requested k
+    fn check_search_k(&self, k: usize) -> Result<usize> {
+        let max_k = self.options.max_search_k;
+        if k <= max_k {
+            return Ok(k);
+        }
+
+        match self.options.search_k_policy {
+            SearchKPolicy::Clamp => Ok(max_k),
+            SearchKPolicy::Reject => Err(Error::InvalidInput(format!(
+                "Requested k = {} exceeds the maximum of {}",
+                k, max_k
+            ))),
+        }
+    }
+
+    // ===== Transaction Operations =====
+
+    /// Begin a new transaction
+    pub fn begin_transaction(&self) -> Result<Transaction> {
+        let memories = MemoryWrites::new(
+            self.records.clone(),
+            self.vector.clone(),
+            self.options.dimension_coercion,
+            self.options.vector_dimension,
+        );
+        Ok(self.txn_manager.begin()?.with_memory_writes(memories))
+    }
+
+    /// Run `f` in a transaction spanning records, vectors, graph and KV
+    ///
+    /// Everything written through the `TransactionScope` passed to `f`
+    /// commits atomically when `f` returns `Ok`. If `f` returns an error or
+    /// panics, the transaction rolls back and nothing it wrote is visible.
+    ///
+    /// ```no_run
+    /// # fn main() -> opendb::Result<()> {
+    /// # let db = opendb::OpenDB::open("./db")?;
+    /// let memory = opendb::Memory::new("a", "content", vec![0.0; 384], 0.5);
+    /// db.transaction(|tx| {
+    ///     tx.insert_memory(&memory)?;
+    ///     tx.link("a", "related_to", "b")?;
+    ///     tx.put(b"k", b"v")?;
+    ///     Ok(())
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn transaction<T>(
+        &self,
+        f: impl FnOnce(&mut TransactionScope<'_>) -> Result<T>,
+    ) -> Result<T> {
+        let mut scope = TransactionScope {
+            db: self,
+            txn: self.begin_transaction()?,
+            kv_keys: Vec::new(),
+            links: HashSet::new(),
+        };
+        let value = f(&mut scope)?;
+        scope.commit()?;
+        Ok(value)
+    }
+
+    /// List the column families that physically exist in the database
+    ///
+    /// This can differ from the reserved set after version upgrades or when
+    /// extra column families were requested, so it is useful for spotting drift.
+    pub fn list_column_families(&self) -> Result<Vec<String>> {
+        self.storage.list_column_families()
+    }
+
+    /// Whether this database was opened with `open_read_only`
+    pub fn is_read_only(&self) -> bool {
+        self.storage.is_read_only()
+    }
+
+    /// Flush all pending writes to disk
+    ///
+    /// Fsyncs the write-ahead log, so every write that returned before this
+    /// call survives a crash or power loss. Not needed with
+    /// `OpenDBOptions::sync_writes`, where each write is already synced.
+    pub fn flush(&self) -> Result<()> {
+        self.storage.flush()
+    }
+
+    /// Write a full logical copy of the database to `dest` while it stays open
+    ///
+    /// Every key of every column family is copied out of a single snapshot,
+    /// so the copy reflects one point in time and concurrent writes are
+    /// neither blocked nor partially included. Unlike a native RocksDB
+    /// checkpoint, nothing is hard-linked: the copy takes as much space as
+    /// the live data and time proportional to it. It can be opened like any
+    /// other database. `dest` must not exist yet; a failed copy removes it.
+    pub fn full_checkpoint<P: AsRef<Path>>(&self, dest: P) -> Result<()> {
+        let dest = dest.as_ref();
+        if self.is_in_memory() {
+            return Err(Error::InvalidInput(
+                "In-memory databases can't be checkpointed".to_string(),
+            ));
+        }
+        if dest.exists() {
+            return Err(Error::InvalidInput(format!(
+                "Checkpoint destination already exists: {}",
+                dest.display()
+            )));
+        }
+        let result = self.storage.checkpoint(dest);
+        if result.is_err() && dest.exists() {
+            std::fs::remove_dir_all(dest)?;
+        }
+        result
+    }
+
+    /// Add a full backup of the database to `backup_dir`
+    ///
+    /// Each backup is a `full_checkpoint` stored in a numbered subdirectory
+    /// (`1`, `2`, ...), so earlier backups are kept. Backups are not
+    /// incremental: every one is a complete copy. Writes continue meanwhile.
+    /// Restore the latest one with `OpenDB::restore_full_backup`.
+    pub fn create_full_backup<P: AsRef<Path>>(&self, backup_dir: P) -> Result<()> {
+        if self.is_in_memory() {
+            return Err(Error::InvalidInput(
+                "In-memory databases can't be backed up".to_string(),
+            ));
+        }
+        self.storage.create_backup(backup_dir.as_ref())
+    }
+
+    /// Restore the latest full backup in `backup_dir` into a new database at `dest`
+    ///
+    /// `dest` must not exist yet, and is removed again if the restore fails.
+    /// Open it afterwards with the options the backed-up database was
+    /// created with.
+    pub fn restore_full_backup<P: AsRef<Path>, Q: AsRef<Path>>(
+        backup_dir: P,
+        dest: Q,
+    ) -> Result<()> {
+        let dest = dest.as_ref();
+        if dest.exists() {
+            return Err(Error::InvalidInput(format!(
+                "Restore destination already exists: {}",
+                dest.display()
+            )));
+        }
+        RocksDBBackend::restore_latest_backup(backup_dir.as_ref(), dest)
+    }
+
+    /// Load data in bulk, skipping the write-ahead log
+    ///
+    /// Writes made through the `BulkLoader` passed to `f` are buffered and
+    /// written in large batches that bypass the WAL, which makes loading a
+    /// fresh database much faster. When `f` returns, the remaining writes are
+    /// written and visible to reads.
+    ///
+    /// **The load is durable only once the `OpenDB` is closed cleanly.**
+    /// Without the WAL, loaded data reaches disk when RocksDB flushes its
+    /// memtables: as they fill up, and on a clean close. `flush` only syncs
+    /// the WAL, so it doesn't help here, and forcing a memtable flush needs
+    /// the raw transactional database handle, which the `rocksdb` crate keeps
+    /// private. Until the database is dropped, a crash can lose any part of
+    /// the load. If `f` fails, writes still buffered are discarded; earlier
+    /// batches stay written.
+    pub fn bulk_load(&self, f: impl FnOnce(&BulkLoader<'_>) -> Result<()>) -> Result<()> {
+        let loader = BulkLoader {
+            db: self,
+            pending: RefCell::new(BulkBatch::default()),
+        };
+        f(&loader)?;
+        loader.write_pending()
+    }
+
+    /// Fully compact every column family, returning the reopened database
+    /// and the bytes reclaimed
+    ///
+    /// Compaction drops the tombstones left by deletes, decay and eviction,
+    /// which otherwise slow scans until background compaction reaches them.
+    /// The `rocksdb` crate only exposes manual compaction (`compact_range_cf`)
+    /// on plain database handles, not on the transactional one `open`
+    /// creates, so this syncs the WAL, closes the database, compacts it like
+    /// `compact_offline` with this handle's options, and opens it again. It
+    /// takes `self` because nothing may use the database meanwhile; finish
+    /// any open transactions first, since they keep it open.
+    ///
+    /// This rewrites all data and blocks until done, which can take a long
+    /// time on large databases, so run it in maintenance windows. Reclaimed
+    /// space is the shrinkage of the SST and WAL files, so it is approximate
+    /// and can be 0. In-memory databases are returned as they are, since
+    /// deletes free memory right away; read-only ones fail with
+    /// `Error::InvalidInput`.
+    pub fn compact(self) -> Result<(Self, u64)> {
+        self.compact_and_reopen(None, None, None)
+    }
+
+    /// Fully compact one column family (see `compact`)
+    ///
+    /// Fails with `Error::InvalidInput`, before closing anything, if `cf`
+    /// doesn't exist.
+    pub fn compact_cf(self, cf: &str) -> Result<(Self, u64)> {
+        self.compact_and_reopen(Some(cf), None, None)
+    }
+
+    /// Compact the keys of `cf` from `start` to `end` (see `compact`)
+    ///
+    /// `None` bounds extend to the first or last key.
+    pub fn compact_range(
+        self,
+        cf: &str,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Result<(Self, u64)> {
+        self.compact_and_reopen(Some(cf), start, end)
+    }
+
+    /// Helper: Close the database, compact it through a plain RocksDB handle
+    /// and open it again with the same options
+    fn compact_and_reopen(
+        self,
+        cf: Option<&str>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Result<(Self, u64)> {
+        if let Some(cf) = cf
+            && !self.list_column_families()?.iter().any(|name| name == cf)
+        {
+            return Err(Error::InvalidInput(format!(
+                "Column family not found: {}",
+                cf
+            )));
+        }
+        if self.is_in_memory() {
+            return Ok((self, 0));
+        }
+        if self.is_read_only() {
+            return Err(Error::InvalidInput(
+                "Read-only databases can't be compacted".to_string(),
+            ));
+        }
+        let path = self.storage.path().to_path_buf();
+        let options = self.options.clone();
+
+        self.flush()?;
+        drop(self);
+
+        let reclaimed = RocksDBBackend::compact_offline(&path, &options, cf, start, end)?;
+        Ok((Self::open_with_options(&path, options)?, reclaimed))
+    }
+
+    /// Fully compact a closed database at `path`, returning the bytes reclaimed
+    ///
+    /// Opens the directory without transactions, compacts every column
+    /// family with the RocksDB tuning in `options`, and closes it again. The
+    /// database must not be open elsewhere; to compact one this process has
+    /// open, use `compact`. Reclaimed space is the shrinkage of the SST and
+    /// WAL files, so it is approximate and can be 0. This rewrites all data
+    /// and may take a long time on large databases.
+    pub fn compact_offline<P: AsRef<Path>>(path: P, options: &OpenDBOptions) -> Result<u64> {
+        RocksDBBackend::compact_offline(path.as_ref(), options, None, None, None)
+    }
+
+    /// Close the database and atomically move its directory to `final_path`
+    ///
+    /// Intended for zero-downtime swaps: build a database in a temporary
+    /// directory, then promote it over the live location. The WAL is synced
+    /// and the lock released before the rename. An existing directory at
+    /// `final_path` is moved aside, replaced, and then deleted; if the rename
+    /// fails it is restored.
+    ///
+    /// Both paths must be on the same filesystem, otherwise this returns
+    /// `Error::InvalidInput` and leaves both directories untouched. Finish any
+    /// open transactions first, since they keep the database open.
+    pub fn close_and_promote<P: AsRef<Path>>(self, final_path: P) -> Result<()> {
+        let final_path = final_path.as_ref();
+        if self.is_in_memory() {
+            return Err(Error::InvalidInput(
+                "In-memory databases have no directory to promote".to_string(),
+            ));
+        }
+        let source = self.storage.path().to_path_buf();
+
+        self.flush()?;
+        drop(self);
+
+        // Directories can't be renamed over non-empty ones, so park the old one
+        let displaced = final_path.exists().then(|| {
+            let mut name = final_path.as_os_str().to_owned();
+            name.push(".opendb-replaced");
+            PathBuf::from(name)
+        });
+        if let Some(displaced) = &displaced {
+            std::fs::rename(final_path, displaced)?;
+        }
+
+        if let Err(e) = std::fs::rename(&source, final_path) {
+            if let Some(displaced) = &displaced {
+                std::fs::rename(displaced, final_path)?;
+            }
+            return Err(if e.kind() == ErrorKind::CrossesDevices {
+                Error::InvalidInput(format!(
+                    "Cannot promote {} to {}: paths are on different filesystems",
+                    source.display(),
+                    final_path.display()
+                ))
+            } else {
+                Error::Io(e)
+            });
+        }
+
+        if let Some(displaced) = displaced {
+            std::fs::remove_dir_all(displaced)?;
+        }
+
+        Ok(())
+    }
+
+    /// Describe the database's layout and contents for external tooling
+    ///
+    /// Counting memories and edges walks the keys of the records and forward
+    /// graph indexes, so the cost grows with database size. The copy written to
+    /// `MANIFEST.opendb` on open leaves both counts out for that reason.
+    /// Column families that weren't opened (see `with_open_column_families`)
+    /// count as empty.
+    pub fn manifest(&self) -> Result<Manifest> {
+        let opened = |cf: &str| self.is_cf_opened(cf);
+
+        let memory_count = if opened(ColumnFamilies::RECORDS) {
+            self.count_memories("")?
+        } else {
+            0
+        };
+        let edge_count =
+            if opened(ColumnFamilies::GRAPH_FORWARD) && opened(ColumnFamilies::GRAPH_BACKWARD) {
+                self.count_edges()?
+            } else {
+                0
+            };
+
+        Ok(Manifest {
+            memory_count: Some(memory_count),
+            edge_count: Some(edge_count),
+            ..self.layout_manifest()?
+        })
+    }
+
+    /// Helper: The manifest without counts, cheap enough to build on open
+    fn layout_manifest(&self) -> Result<Manifest> {
+        let mut column_families = self.list_column_families()?;
+        column_families.sort();
+
+        Ok(Manifest {
+            format_version: MANIFEST_FORMAT_VERSION,
+            opendb_version: env!("CARGO_PKG_VERSION").to_string(),
+            vector_dimension: self.options.vector_dimension,
+            distance_metric: self.options.distance_metric,
+            multigraph: self.options.multigraph,
+            column_families,
+            memory_count: None,
+            edge_count: None,
+        })
+    }
+
+    /// Helper: Whether a reserved column family was opened
+    fn is_cf_opened(&self, cf: &str) -> bool {
+        self.options.opens_cf(cf)
+    }
+
+    /// Helper: Whether the database lives only in memory (see `open_in_memory`)
+    fn is_in_memory(&self) -> bool {
+        self.storage.path().as_os_str().is_empty()
+    }
+
+    /// Helper: Write the layout manifest to `MANIFEST.opendb`
+    ///
+    /// Everything it records is fixed once the database is open, so writing
+    /// it on open is enough.
+    fn write_manifest(&self) -> Result<()> {
+        if self.is_in_memory() || self.is_read_only() {
+            return Ok(());
+        }
+        let manifest = self.layout_manifest()?;
+        let json = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| Error::Codec(format!("Failed to serialize manifest: {}", e)))?;
+        std::fs::write(self.storage.path().join(MANIFEST_FILE), json)?;
+        Ok(())
+    }
+
+    /// Report key counts, storage footprint and cache occupancy
+    ///
+    /// Key counts are exact: RocksDB's key estimates are per column family
+    /// and the transactional database only exposes those of the default one,
+    /// so every opened column family's keys are walked (values are not read).
+    /// That makes this O(N) in the number of keys; call it from admin
+    /// endpoints or periodic logging, not hot paths. SST
+    /// and WAL sizes are summed from the files in the database directory
+    /// (both 0 in memory). Memtable usage comes from RocksDB's memory usage
+    /// API, summed over all column families; in memory it is the size of all
+    /// stored keys and values.
+    pub fn stats(&self) -> Result<DbStats> {
+        let mut column_families = Vec::new();
+        for cf in ColumnFamilies::all() {
+            if !self.is_cf_opened(cf) {
+                continue;
+            }
+            let mut keys = 0;
+            self.storage.for_each_key(cf, &[], &mut |_| {
+                keys += 1;
+                Ok(ControlFlow::Continue(()))
+            })?;
+            column_families.push(ColumnFamilyStats {
+                name: cf.to_string(),
+                keys,
+            });
+        }
+
+        let footprint = self.storage.footprint()?;
+        Ok(DbStats {
+            column_families,
+            sst_bytes: footprint.sst_bytes,
+            wal_bytes: footprint.wal_bytes,
+            memtable_bytes: footprint.memtable_bytes,
+            kv_cache: self.kv.cache_stats(),
+            record_cache: self.records.cache_stats(),
+        })
+    }
+
+    /// Check that the storage engine is responsive
+    ///
+    /// Writes a timestamp to a reserved metadata key and reads it back, so it
+    /// never touches user data. On a read-only handle, where writes fail, it
+    /// only reads that key. Cheap enough for frequent readiness probes.
+    pub fn ping(&self) -> Result<()> {
+        if self.is_read_only() {
+            self.storage.get(ColumnFamilies::METADATA, PING_KEY)?;
+            return Ok(());
+        }
+
+        let token = chrono::Utc::now()
+            .timestamp_nanos_opt()
+            .unwrap_or_default()
+            .to_le_bytes();
+        self.storage
+            .put(ColumnFamilies::METADATA, PING_KEY, &token)?;
+
+        match self.storage.get(ColumnFamilies::METADATA, PING_KEY)? {
+            Some(value) if value == token => Ok(()),
+            _ => Err(Error::Storage(
+                "Ping failed: read-back did not match write".to_string(),
+            )),
+        }
+    }
+}
+
+/// Write handle passed to the closure of `OpenDB::bulk_load`
+///
+/// Writes are buffered and land in storage in batches that skip the
+/// write-ahead log, so they may not be visible to reads through the
+/// `OpenDB` until the load finishes.
+pub struct BulkLoader<'a> {
+    db: &'a OpenDB,
+    pending: RefCell<BulkBatch>,
+}
+
+/// Writes buffered by a `BulkLoader` and not yet written
+#[derive(Default)]
+struct BulkBatch {
+    ops: Vec<BatchOp>,
+    kv_keys: Vec<Vec<u8>>,
+    memories: Vec<Memory>,
+    memory_ids: HashSet<String>,
+    links: HashSet<(String, String, String)>,
+}
+
+impl BulkLoader<'_> {
+    /// Put a key-value pair
+    pub fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut pending = self.pending.borrow_mut();
+        pending
+            .ops
+            .push(BatchOp::put(ColumnFamilies::DEFAULT, key, value.to_vec()));
+        pending.kv_keys.push(key.to_vec());
+        drop(pending);
+        self.write_if_full()
+    }
+
+    /// Insert or update a memory record, as with `OpenDB::insert_memory`
+    ///
+    /// The record is validated right away.
+    pub fn insert_memory(&self, memory: &Memory) -> Result<()> {
+        let db = self.db;
+        let memory = db
+            .coerce_embedding(memory)
+            .unwrap_or_else(|| memory.clone());
+
+        // Index writes are derived from the stored record, so a repeated id
+        // must see the earlier write
+        if self.pending.borrow().memory_ids.contains(&memory.id) {
+            self.write_pending()?;
+        }
+        let mut ops = db.vector.batch_ops(std::slice::from_ref(&memory))?;
+        ops.extend(db.records.put_ops(&memory)?);
+
+        let mut pending = self.pending.borrow_mut();
+        pending.ops.extend(ops);
+        pending.memory_ids.insert(memory.id.clone());
+        pending.memories.push(memory);
+        drop(pending);
+        self.write_if_full()
+    }
+
+    /// Create a link, as with `OpenDB::link`
+    pub fn link(&self, from: &str, relation: &str, to: &str) -> Result<()> {
+        let triple = (from.to_string(), relation.to_string(), to.to_string());
+        let multigraph = self.db.options.multigraph;
+        if !multigraph && self.pending.borrow().links.contains(&triple) {
+            return Ok(());
+        }
+        let ops = self.db.graph.link_ops(from, relation, to)?;
+
+        let mut pending = self.pending.borrow_mut();
+        pending.ops.extend(ops);
+        if !multigraph {
+            pending.links.insert(triple);
+        }
+        drop(pending);
+        self.write_if_full()
+    }
+
+    fn write_if_full(&self) -> Result<()> {
+        if self.pending.borrow().ops.len() >= BULK_BATCH_OPS {
+            self.write_pending()?;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered operations and bring caches and the vector index
+    /// in line
+    fn write_pending(&self) -> Result<()> {
+        let batch = self.pending.take();
+        if batch.ops.is_empty() {
+            return Ok(());
+        }
+        let db = self.db;
+        db.storage.write_batch_unlogged(&batch.ops)?;
+
+        for key in &batch.kv_keys {
+            db.kv.invalidate(key);
+        }
+        for memory in &batch.memories {
+            db.records.invalidate(&memory.id);
+        }
+        db.vector.index_batch(&batch.memories)
+    }
+}
+
+/// Write handle passed to the closure of `OpenDB::transaction`
+///
+/// Every write is buffered in one storage transaction. Caches and the vector
+/// index are only updated once it commits.
+pub struct TransactionScope<'a> {
+    db: &'a OpenDB,
+    txn: Transaction,
+    kv_keys: Vec<Vec<u8>>,
+    links: HashSet<(String, String, String)>,
+}
+
+impl TransactionScope<'_> {
+    /// Get a value, seeing writes made earlier in this transaction
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.txn.get(ColumnFamilies::DEFAULT, key)
+    }
+
+    /// Put a key-value pair
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.txn.put(ColumnFamilies::DEFAULT, key, value)?;
+        self.kv_keys.push(key.to_vec());
+        Ok(())
+    }
+
+    /// Delete a key
+    pub fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.txn.delete(ColumnFamilies::DEFAULT, key)?;
+        self.kv_keys.push(key.to_vec());
+        Ok(())
+    }
+
+    /// Insert or update a memory record and its vectors
+    ///
+    /// See `Transaction::insert_memory`.
+    pub fn insert_memory(&mut self, memory: &Memory) -> Result<()> {
+        self.txn.insert_memory(memory)
+    }
+
+    /// Delete a memory record and its vectors, leaving graph edges in place
+    pub fn delete_memory(&mut self, id: &str) -> Result<()> {
+        self.txn.delete_memory(id)
+    }
+
+    /// Create a link, as with `OpenDB::link`
+    ///
+    /// Outside multigraph mode, links already committed or made earlier in
+    /// this transaction are kept unchanged.
+    pub fn link(&mut self, from: &str, relation: &str, to: &str) -> Result<()> {
+        let triple = (from.to_string(), relation.to_string(), to.to_string());
+        let multigraph = self.db.options.multigraph;
+        if !multigraph && self.links.contains(&triple) {
+            return Ok(());
+        }
+        let ops = self.db.graph.link_ops(from, relation, to)?;
+        self.txn.write_ops(&ops)?;
+        if !multigraph {
+            self.links.insert(triple);
+        }
+        Ok(())
+    }
+
+    /// Commit the transaction and drop stale KV cache entries
+    fn commit(self) -> Result<()> {
+        self.txn.commit()?;
+        for key in &self.kv_keys {
+            self.db.kv.invalidate(key);
+        }
+        Ok(())
+    }
+}
+
+/// Configuration options for OpenDB
+#[derive(Debug, Clone)]
+pub struct OpenDBOptions {
+    /// KV cache size (number of entries)
+    pub kv_cache_size: usize,
+
+    /// Record cache size (number of entries)
+    pub record_cache_size: usize,
+
+    /// Eviction policy and other settings for the KV and record caches
+    pub cache: CacheConfig,
+
+    /// Vector dimension
+    pub vector_dimension: usize,
+
+    /// Database storage path (optional - will use path from open() if not set)
+    pub storage_path: Option<String>,
+
+    /// Whether to create, require, or forbid an existing database on open
+    pub create_mode: CreateMode,
+
+    /// Storage engine `open_with_options` builds the database on
+    pub storage_engine: StorageEngine,
+
+    /// Largest `k` accepted by vector search
+    pub max_search_k: usize,
+
+    /// What to do when a search asks for more than `max_search_k` results
+    pub search_k_policy: SearchKPolicy,
+
+    /// Background flush/compaction I/O cap in bytes per second (None = unlimited)
+    pub compaction_rate_bytes_per_sec: Option<i64>,
+
+    /// How `insert_memory` handles embeddings of the wrong dimension
+    pub dimension_coercion: Coerce,
+
+    /// Dimensions of named field embeddings (unlisted fields use `vector_dimension`)
+    pub field_dimensions: HashMap<String, usize>,
+
+    /// Additional column families to create alongside the reserved ones
+    pub extra_column_families: Vec<String>,
+
+    /// Distance metric used by vector search
+    pub distance_metric: DistanceMetric,
+
+    /// Open only these column families (None = all reserved column families)
+    pub open_column_families: Option<Vec<String>>,
+
+    /// Allow parallel edges with the same `(from, relation, to)`
+    pub multigraph: bool,
+
+    /// Stop distance computations early in threshold searches
+    pub threshold_early_exit: bool,
+
+    /// Most nodes a single `traverse` call returns
+    pub max_traversal_nodes: usize,
+
+    /// HNSW graph parameters for vector search
+    pub hnsw_params: HnswParams,
+
+    /// RocksDB memtable, compaction and compression settings
+    pub rocksdb_tuning: RocksDBTuning,
+
+    /// Compress large memory records and documents with zstd before storing
+    /// them (default: false)
+    ///
+    /// Only affects writes: compressed and uncompressed values always decode,
+    /// so this can be changed between opens.
+    pub value_compression: bool,
+
+    /// L2-normalize embeddings and queries before indexing and search
+    /// (default: false)
+    ///
+    /// Only the indexed copy is normalized; memory records keep the original
+    /// vector. Vectors indexed while this was off are not rewritten, so set
+    /// it when the database is created.
+    pub normalize_embeddings: bool,
+
+    /// Hold the in-memory vector index as int8 with one scale factor per
+    /// vector (default: false)
+    ///
+    /// Cuts the cached vectors to a quarter of their size. Graph distances
+    /// become approximate, which lowers recall; `quantization_rerank_factor`
+    /// wins most of it back. Storage keeps full-precision vectors, so this
+    /// can be changed between opens. Named field vectors are not quantized.
+    pub quantize_vectors: bool,
+
+    /// With `quantize_vectors`, how many times `k` candidates a search
+    /// fetches and re-ranks at full precision (default: 4)
+    ///
+    /// Each candidate costs one read of its stored vector. A factor of 1
+    /// skips re-ranking and returns approximate distances.
+    pub quantization_rerank_factor: usize,
+
+    /// Metadata keys to maintain a secondary index for (default: none)
+    ///
+    /// `find_by_metadata` only answers for these keys. Changing the list
+    /// doesn't touch existing records; call `reindex_metadata` afterwards.
+    pub indexed_metadata_keys: Vec<String>,
+
+    /// fsync the write-ahead log on every write (default: false)
+    ///
+    /// Without it, a write is in the OS page cache when it returns: it
+    /// survives a process crash but not a power loss or kernel panic until
+    /// the next `flush`. With it, every write is durable on return at the
+    /// cost of one fsync each, which typically makes small writes one to
+    /// two orders of magnitude slower.
+    pub sync_writes: bool,
+
+    /// Largest memory `content` accepted by writes, in bytes (default: no
+    /// limit)
+    ///
+    /// Oversized records fail with `Error::InvalidInput` before anything is
+    /// written.
+    pub max_content_bytes: Option<usize>,
+
+    /// Most metadata entries a memory may carry (default: no limit)
+    pub max_metadata_entries: Option<usize>,
+
+    /// Run `OpenDB::repair_vector_index` when a database is opened
+    /// read-write (default: false)
+    ///
+    /// This scans every stored vector, so opening takes longer on large
+    /// databases.
+    pub repair_on_open: bool,
+}
+
+impl Default for OpenDBOptions {
+    fn default() -> Self {
+        Self {
+            kv_cache_size: 1000,
+            record_cache_size: 500,
+            cache: CacheConfig::default(),
+            vector_dimension: 384, // Common dimension for sentence transformers
+            storage_path: None,
+            create_mode: CreateMode::default(),
+            storage_engine: StorageEngine::default(),
+            max_search_k: 10_000,
+            search_k_policy: SearchKPolicy::default(),
+            compaction_rate_bytes_per_sec: None,
+            dimension_coercion: Coerce::default(),
+            field_dimensions: HashMap::new(),
+            extra_column_families: Vec::new(),
+            distance_metric: DistanceMetric::default(),
+            open_column_families: None,
+            multigraph: false,
+            threshold_early_exit: true,
+            max_traversal_nodes: 10_000,
+            hnsw_params: HnswParams::default(),
+            rocksdb_tuning: RocksDBTuning::default(),
+            value_compression: false,
+            normalize_embeddings: false,
+            quantize_vectors: false,
+            quantization_rerank_factor: 4,
+            indexed_metadata_keys: Vec::new(),
+            sync_writes: false,
+            max_content_bytes: None,
+            max_metadata_entries: None,
+            repair_on_open: false,
+        }
+    }
+}
+
+impl OpenDBOptions {
+    /// Create new options with default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create options with a specific vector dimension
+    pub fn with_dimension(dimension: usize) -> Self {
+        Self {
+            vector_dimension: dimension,
+            ..Default::default()
+        }
+    }
+
+    /// Set vector dimension (chainable)
+    pub fn dimension(mut self, dimension: usize) -> Self {
+        self.vector_dimension = dimension;
+        self
+    }
+
+    /// Set custom storage path (chainable)
+    pub fn with_storage_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.storage_path = Some(path.into());
+        self
+    }
+
+    /// Set KV cache size (chainable)
+    pub fn with_kv_cache_size(mut self, size: usize) -> Self {
+        self.kv_cache_size = size;
+        self
+    }
+
+    /// Set record cache size (chainable)
+    pub fn with_record_cache_size(mut self, size: usize) -> Self {
+        self.record_cache_size = size;
+        self
+    }
+
+    /// Set the KV and record cache settings (chainable)
+    pub fn with_cache_config(mut self, cache: CacheConfig) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Set the eviction policy of the KV and record caches (chainable)
+    pub fn with_cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache.policy = policy;
+        self
+    }
+
+    /// Bound the KV and record caches by approximate size (chainable)
+    ///
+    /// Each cache evicts entries until its values fit in `bytes`, in addition
+    /// to the `kv_cache_size` / `record_cache_size` entry limits.
+    pub fn with_max_cache_bytes(mut self, bytes: usize) -> Self {
+        self.cache.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Set the create mode used when opening (chainable)
+    pub fn with_create_mode(mut self, mode: CreateMode) -> Self {
+        self.create_mode = mode;
+        self
+    }
+
+    /// Set the storage engine used by `open_with_options` (chainable)
+    pub fn with_storage_engine(mut self, engine: StorageEngine) -> Self {
+        self.storage_engine = engine;
+        self
+    }
+
+    /// Set the largest `k` accepted by vector search (chainable)
+    pub fn with_max_search_k(mut self, max_k: usize) -> Self {
+        self.max_search_k = max_k;
+        self
+    }
+
+    /// Set whether excessive `k` values are clamped or rejected (chainable)
+    pub fn with_search_k_policy(mut self, policy: SearchKPolicy) -> Self {
+        self.search_k_policy = policy;
+        self
+    }
+
+    /// Cap background flush/compaction I/O in bytes per second (chainable)
+    ///
+    /// Keeps compaction from saturating disk I/O on shared hardware. Setting
+    /// it too low lets compaction fall behind the write rate, which makes
+    /// RocksDB stall foreground writes until it catches up.
+    pub fn with_compaction_rate_bytes_per_sec(mut self, bytes_per_sec: i64) -> Self {
+        self.compaction_rate_bytes_per_sec = Some(bytes_per_sec);
+        self
+    }
+
+    /// Set how mismatched embedding dimensions are handled on insert (chainable)
+    ///
+    /// Meant for models that occasionally emit off-by-one dimensions.
+    /// Truncating drops trailing components and zero-padding adds empty ones,
+    /// so coerced vectors are distorted and their similarity scores are less
+    /// reliable than those of correctly sized embeddings.
+    pub fn with_dimension_coercion(mut self, coercion: Coerce) -> Self {
+        self.dimension_coercion = coercion;
+        self
+    }
+
+    /// Set the dimension of a named field embedding (chainable)
+    ///
+    /// Each field is indexed on its own, so fields may come from different
+    /// models. Every embedding stored under `field`, and every query passed
+    /// to `search_similar_field` for it, must have exactly this length.
+    /// Dimension coercion only applies to the default embedding.
+    pub fn with_field_dimension(mut self, field: impl Into<String>, dimension: usize) -> Self {
+        self.field_dimensions.insert(field.into(), dimension);
+        self
+    }
+
+    /// Create an additional column family on open (chainable)
+    ///
+    /// Column families already on disk are always opened, so an extra column
+    /// family only needs to be requested once.
+    pub fn with_extra_column_family(mut self, name: impl Into<String>) -> Self {
+        self.extra_column_families.push(name.into());
+        self
+    }
+
+    /// Set the distance metric used by vector search (chainable)
+    pub fn with_distance_metric(mut self, metric: DistanceMetric) -> Self {
+        self.distance_metric = metric;
+        self
+    }
+
+    /// Open only the listed column families (chainable)
+    ///
+    /// Meant for lightweight tools, e.g. a KV inspector that only needs
+    /// `default`. The `default` and `metadata` column families are always
+    /// opened. RocksDB can't open a database without all of its existing
+    /// column families, so opening fails with `Error::InvalidInput` naming
+    /// any that were omitted. Operations on column families that weren't
+    /// opened (records, graph, vectors) fail with `Error::Storage`.
+    pub fn with_open_column_families(mut self, names: &[&str]) -> Self {
+        self.open_column_families = Some(names.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
+    /// Enable multigraph mode (chainable)
+    ///
+    /// By default an edge is unique on `(from, relation, to)` and linking the
+    /// same triple twice is a no-op. In multigraph mode every `link` adds a
+    /// new edge with its own `Edge::id`, so the same pair can be connected
+    /// several times with different weights. Edges are then removed with
+    /// `unlink_edge(from, edge_id)`; `unlink` by triple returns an error.
+    pub fn with_multigraph(mut self, multigraph: bool) -> Self {
+        self.multigraph = multigraph;
+        self
+    }
+
+    /// Set whether threshold searches stop distance computations early (chainable)
+    ///
+    /// On by default. Early exit never changes which memories match; turning
+    /// it off is only useful for benchmarking the difference.
+    pub fn with_threshold_early_exit(mut self, early_exit: bool) -> Self {
+        self.threshold_early_exit = early_exit;
+        self
+    }
+
+    /// Cap the nodes returned by a single `traverse` call (chainable)
+    ///
+    /// Guards against runaway traversals on dense graphs; the walk stops once
+    /// the cap is reached.
+    pub fn with_max_traversal_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_traversal_nodes = max_nodes;
+        self
+    }
+
+    /// Set the HNSW graph parameters used by vector search (chainable)
+    ///
+    /// `max_connections` and `ef_construction` shape the graph as vectors are
+    /// inserted; changing `max_connections` on an existing database rebuilds
+    /// the graph on first use. `ef_search` is the candidate list size per
+    /// query: higher values improve recall at the cost of speed.
+    pub fn with_hnsw_params(mut self, params: HnswParams) -> Self {
+        self.hnsw_params = params;
+        self
+    }
+
+    /// Set the RocksDB performance settings (chainable)
+    ///
+    /// The default matches a mid-sized server. `RocksDBTuning::low_memory()`
+    /// keeps memtables small for embedded use; `high_throughput()` trades
+    /// memory and CPU for write speed. Takes effect when the database is opened.
+    pub fn with_rocksdb_tuning(mut self, tuning: RocksDBTuning) -> Self {
+        self.rocksdb_tuning = tuning;
+        self
+    }
+
+    /// Compress large memory records and documents with zstd
+    pub fn with_value_compression(mut self, compress: bool) -> Self {
+        self.value_compression = compress;
+        self
+    }
+
+    /// L2-normalize embeddings and queries, e.g. for cosine search
+    pub fn with_normalize_embeddings(mut self, normalize: bool) -> Self {
+        self.normalize_embeddings = normalize;
+        self
+    }
+
+    /// Hold the in-memory vector index int8-quantized (chainable)
+    pub fn with_quantize_vectors(mut self, quantize: bool) -> Self {
+        self.quantize_vectors = quantize;
+        self
+    }
+
+    /// Set how many times `k` quantized candidates are re-ranked (chainable)
+    pub fn with_quantization_rerank_factor(mut self, factor: usize) -> Self {
+        self.quantization_rerank_factor = factor;
+        self
+    }
+
+    /// Index these metadata keys for `find_by_metadata` (chainable)
+    pub fn with_indexed_metadata_keys(mut self, keys: &[&str]) -> Self {
+        self.indexed_metadata_keys = keys.iter().map(|key| key.to_string()).collect();
+        self
+    }
+
+    /// fsync the write-ahead log on every write (chainable)
+    pub fn with_sync_writes(mut self, sync: bool) -> Self {
+        self.sync_writes = sync;
+        self
+    }
+
+    /// Remove orphaned vectors when opening (chainable)
+    pub fn with_repair_on_open(mut self, repair: bool) -> Self {
+        self.repair_on_open = repair;
+        self
+    }
+
+    /// Reject memories whose content exceeds `bytes` (chainable)
+    pub fn with_max_content_bytes(mut self, bytes: usize) -> Self {
+        self.max_content_bytes = Some(bytes);
+        self
+    }
+
+    /// Reject memories with more than `entries` metadata entries (chainable)
+    pub fn with_max_metadata_entries(mut self, entries: usize) -> Self {
+        self.max_metadata_entries = Some(entries);
+        self
+    }
+
+    /// Whether a column family is opened under these options
+    pub(crate) fn opens_cf(&self, cf: &str) -> bool {
+        self.open_column_families
+            .as_ref()
+            .is_none_or(|names| names.iter().any(|name| name == cf))
+    }
+}
+
+/// How `open` treats a missing or already existing database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CreateMode {
+    /// Open the database, creating it if it doesn't exist (default)
+    #[default]
+    CreateIfMissing,
+
+    /// Fail if the database doesn't already exist (e.g. a misconfigured path)
+    MustExist,
+
+    /// Fail if the database already exists (fresh databases only)
+    MustNotExist,
+}
+
+/// Storage engine behind a database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum StorageEngine {
+    /// RocksDB on disk (default)
+    #[default]
+    RocksDb,
+
+    /// Process memory only, as with `OpenDB::open_in_memory`
+    InMemory,
+}
+
+/// How vector search handles a `k` larger than `max_search_k`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchKPolicy {
+    /// Silently lower `k` to `max_search_k` (default)
+    #[default]
+    Clamp,
+
+    /// Fail with `Error::InvalidInput`
+    Reject,
+}
+
+/// How `insert_memory` handles an embedding whose length differs from
+/// `vector_dimension`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Coerce {
+    /// Fail with `Error::VectorIndex` (default)
+    #[default]
+    Reject,
+
+    /// Drop trailing components of embeddings that are too long
+    Truncate,
+
+    /// Append zeros to embeddings that are too short
+    ZeroPad,
+}
+
+impl Coerce {
+    /// Resize the embedding of `memory` to `dim` if this policy applies
+    ///
+    /// Returns `None` when the memory should be stored (or rejected) as-is.
+    pub(crate) fn apply(self, memory: &Memory, dim: usize) -> Option<Memory> {
+        let len = memory.embedding.len();
+        let applies = match self {
+            Coerce::Reject => false,
+            Coerce::Truncate => len > dim,
+            Coerce::ZeroPad => len > 0 && len < dim,
+        };
+        if !applies {
+            return None;
+        }
+
+        let mut coerced = memory.clone();
+        coerced.embedding.resize(dim, 0.0);
+        Some(coerced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_open_rejects_other_storage_format() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let options = OpenDBOptions::with_dimension(3);
+        drop(OpenDB::open_with_options(temp_dir.path(), options.clone())?);
+
+        // Simulate a database written by a future storage format
+        {
+            let backend = RocksDBBackend::open_with_options(temp_dir.path(), &options)?;
+            let config = PersistedConfig {
+                format_version: STORAGE_FORMAT_VERSION + 1,
+                vector_dimension: 3,
+                distance_metric: DistanceMetric::Euclidean,
+            };
+            backend.put(
+                ColumnFamilies::METADATA,
+                CONFIG_KEY,
+                &serde_json::to_vec(&config).unwrap(),
+            )?;
+        }
+
+        match OpenDB::open_with_options(temp_dir.path(), options) {
+            Err(Error::ConfigMismatch { field, .. }) => assert_eq!(field, "format_version"),
+            other => panic!("expected ConfigMismatch, got {:?}", other.err()),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mismatched_vectors_are_skipped() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let db = OpenDB::open_with_options(temp_dir.path(), OpenDBOptions::with_dimension(3))?;
+            db.insert_memory(&Memory::new("a", "a", vec![1.0, 0.0, 0.0], 0.5))?;
+            db.insert_memory(&Memory::new("b", "b", vec![0.0, 1.0, 0.0], 0.5))?;
+            db.flush()?;
+        }
+
+        // Simulate a database from before the config was persisted
+        {
+            let options = OpenDBOptions::default();
+            let backend = RocksDBBackend::open_with_options(temp_dir.path(), &options)?;
+            backend.delete(ColumnFamilies::METADATA, CONFIG_KEY)?;
+        }
+
+        let db = OpenDB::open_with_options(temp_dir.path(), OpenDBOptions::with_dimension(4))?;
+        match db.search_similar(&[1.0, 0.0, 0.0, 0.0], 2) {
+            Err(Error::VectorIndex(message)) => assert!(message.contains("dimension 4")),
+            other => panic!("expected VectorIndex, got {:?}", other.map(|r| r.len())),
+        }
+        assert_eq!(db.mismatched_vectors()?, vec!["a", "b"]);
+
+        // Vectors of the right dimension are still searchable
+        db.insert_memory(&Memory::new("c", "c", vec![1.0, 0.0, 0.0, 0.0], 0.5))?;
+        let results = db.search_similar(&[1.0, 0.0, 0.0, 0.0], 2)?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].memory.id, "c");
+
+        Ok(())
     }
 }
@@ -0,0 +1,63 @@
+// Document management for MultimodalDocument data
+
+use crate::codec;
+use crate::error::Result;
+use crate::storage::{SharedStorage, column_families::ColumnFamilies};
+use crate::types::MultimodalDocument;
+
+/// Documents manager for MultimodalDocument CRUD operations
+pub struct DocumentsManager {
+    storage: SharedStorage,
+    compress: bool,
+}
+
+impl DocumentsManager {
+    /// Create a new documents manager
+    pub fn new(storage: SharedStorage) -> Self {
+        Self {
+            storage,
+            compress: false,
+        }
+    }
+
+    /// Compress large documents with zstd before storing them
+    pub fn with_value_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Insert or update a document
+    pub fn put(&self, document: &MultimodalDocument) -> Result<()> {
+        let value = codec::encode_document(document, self.compress)?;
+        self.storage
+            .put(ColumnFamilies::DOCUMENTS, document.id.as_bytes(), &value)
+    }
+
+    /// Get a document by ID
+    pub fn get(&self, id: &str) -> Result<Option<MultimodalDocument>> {
+        match self.storage.get(ColumnFamilies::DOCUMENTS, id.as_bytes())? {
+            Some(bytes) => Ok(Some(codec::decode_document(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Delete a document
+    pub fn delete(&self, id: &str) -> Result<()> {
+        self.storage
+            .delete(ColumnFamilies::DOCUMENTS, id.as_bytes())
+    }
+
+    /// List all documents with a given ID prefix
+    pub fn list(&self, prefix: &str) -> Result<Vec<MultimodalDocument>> {
+        let pairs = self
+            .storage
+            .scan_prefix(ColumnFamilies::DOCUMENTS, prefix.as_bytes())?;
+
+        let mut documents = Vec::new();
+        for (_, value) in pairs {
+            documents.push(codec::decode_document(&value)?);
+        }
+
+        Ok(documents)
+    }
+}
@@ -23,9 +23,14 @@ pub enum Error {
     #[error("Codec error: {0}\n\nIf this error persists, please report it at: {GITHUB_ISSUES_URL}")]
     Codec(String),
 
-    /// Record not found
-    #[error("Record not found: {0}")]
-    NotFound(String),
+    /// A requested item doesn't exist
+    #[error("{kind} not found: {id}")]
+    NotFound {
+        /// What was looked up, e.g. "Memory" or "Backup"
+        kind: &'static str,
+        /// Identifier that wasn't found
+        id: String,
+    },
 
     /// Transaction errors
     #[error(
@@ -47,10 +52,35 @@ pub enum Error {
     #[error("Graph error: {0}\n\nIf this error persists, please report it at: {GITHUB_ISSUES_URL}")]
     Graph(String),
 
+    /// An embedding or query has the wrong number of dimensions
+    #[error(
+        "Dimension mismatch{}: expected {expected}, got {got}",
+        .field.as_ref().map(|field| format!(" for field '{}'", field)).unwrap_or_default()
+    )]
+    DimensionMismatch {
+        /// Dimension the index was configured with
+        expected: usize,
+        /// Dimension that was supplied
+        got: usize,
+        /// Named embedding field, or `None` for the default embedding
+        field: Option<String>,
+    },
+
     /// Invalid input
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    /// Options conflict with the configuration the database was created with
+    #[error("Configuration mismatch for {field}: database has {expected}, options request {got}")]
+    ConfigMismatch {
+        /// Name of the conflicting setting
+        field: &'static str,
+        /// Value persisted in the database
+        expected: String,
+        /// Value requested by the options
+        got: String,
+    },
+
     /// I/O errors
     #[error("I/O error: {0}\n\nIf this error persists, please report it at: {GITHUB_ISSUES_URL}")]
     Io(#[from] std::io::Error),
@@ -69,6 +99,14 @@ pub enum Error {
 }
 
 impl Error {
+    /// A `NotFound` error for `kind` with identifier `id`
+    pub(crate) fn not_found(kind: &'static str, id: impl Into<String>) -> Self {
+        Error::NotFound {
+            kind,
+            id: id.into(),
+        }
+    }
+
     /// Get the GitHub issues URL for error reporting
     pub fn issues_url() -> &'static str {
         GITHUB_ISSUES_URL
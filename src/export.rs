@@ -0,0 +1,310 @@
+// JSON export and import
+//
+// `OpenDB::export_json` writes one JSON object with a section per data type:
+//
+//   {"opendb_export":1,"memories":[...],"edges":[...],"kv":[...],"documents":[...]}
+//
+// `export_json_consistent` writes the same document from a storage snapshot.
+//
+// Both directions stream: sections are written item by item and read back
+// through a serde visitor that applies items as they are parsed, so neither
+// side holds the whole database in memory.
+
+use crate::codec;
+use crate::database::OpenDB;
+use crate::error::{Error, Result};
+use crate::storage::{ScanVisitor, column_families::ColumnFamilies};
+use crate::types::{Edge, Memory, MultimodalDocument};
+use chrono::Utc;
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::io::{BufWriter, Write};
+use std::ops::ControlFlow;
+
+/// Version of the export document layout
+pub(crate) const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Memories inserted per batch while importing
+const IMPORT_BATCH_SIZE: usize = 1024;
+
+/// Key or value bytes of a KV pair: a string when valid UTF-8, else a byte array
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum KvBytes {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl KvBytes {
+    fn new(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self::Text(text.to_string()),
+            Err(_) => Self::Binary(bytes.to_vec()),
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Text(text) => text.as_bytes(),
+            Self::Binary(bytes) => bytes,
+        }
+    }
+}
+
+/// One key-value pair in the `kv` section
+#[derive(Serialize, Deserialize)]
+struct KvEntry {
+    key: KvBytes,
+    value: KvBytes,
+}
+
+/// Streams the sections of an export document to a writer
+pub(crate) struct ExportWriter<W: Write> {
+    writer: W,
+    first_item: bool,
+}
+
+impl<W: Write> ExportWriter<W> {
+    /// Write the document header
+    pub(crate) fn new(mut writer: W) -> Result<Self> {
+        write!(writer, "{{\"opendb_export\":{}", EXPORT_FORMAT_VERSION)?;
+        Ok(Self {
+            writer,
+            first_item: true,
+        })
+    }
+
+    /// Open the array of a section
+    pub(crate) fn begin_section(&mut self, name: &str) -> Result<()> {
+        write!(self.writer, ",\"{}\":[", name)?;
+        self.first_item = true;
+        Ok(())
+    }
+
+    /// Append one item to the open section
+    pub(crate) fn item<T: Serialize>(&mut self, item: &T) -> Result<()> {
+        if !self.first_item {
+            self.writer.write_all(b",")?;
+        }
+        self.first_item = false;
+        serde_json::to_writer(&mut self.writer, item)
+            .map_err(|e| Error::Codec(format!("Failed to serialize export item: {}", e)))
+    }
+
+    /// Append one KV pair to the open section
+    pub(crate) fn kv_item(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.item(&KvEntry {
+            key: KvBytes::new(key),
+            value: KvBytes::new(value),
+        })
+    }
+
+    /// Close the open section
+    pub(crate) fn end_section(&mut self) -> Result<()> {
+        self.writer.write_all(b"]")?;
+        Ok(())
+    }
+
+    /// Close the document and flush the writer
+    pub(crate) fn finish(mut self) -> Result<()> {
+        self.writer.write_all(b"}")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Stream an export document, reading each column family through `scan`
+pub(crate) fn write_export(
+    writer: impl Write,
+    scan: impl Fn(&str, &mut ScanVisitor<'_>) -> Result<()>,
+) -> Result<()> {
+    let mut out = ExportWriter::new(BufWriter::new(writer))?;
+
+    // One clock reading, so expiry is judged at a single instant
+    let now = Utc::now().timestamp();
+    out.begin_section("memories")?;
+    scan(ColumnFamilies::RECORDS, &mut |_, value| {
+        let memory = codec::decode_memory(value)?;
+        if !memory.is_expired_at(now) {
+            out.item(&memory)?;
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+    out.end_section()?;
+
+    out.begin_section("edges")?;
+    scan(ColumnFamilies::GRAPH_FORWARD, &mut |_, value| {
+        out.item(&codec::decode_edge(value)?)?;
+        Ok(ControlFlow::Continue(()))
+    })?;
+    out.end_section()?;
+
+    out.begin_section("kv")?;
+    scan(ColumnFamilies::DEFAULT, &mut |key, value| {
+        out.kv_item(key, value)?;
+        Ok(ControlFlow::Continue(()))
+    })?;
+    out.end_section()?;
+
+    out.begin_section("documents")?;
+    scan(ColumnFamilies::DOCUMENTS, &mut |_, value| {
+        out.item(&codec::decode_document(value)?)?;
+        Ok(ControlFlow::Continue(()))
+    })?;
+    out.end_section()?;
+
+    out.finish()
+}
+
+/// Applies an export document to a database while it is parsed
+pub(crate) struct Importer<'a> {
+    db: &'a OpenDB,
+    /// First database error; parsing stops as soon as one occurs
+    error: Option<Error>,
+}
+
+impl<'a> Importer<'a> {
+    pub(crate) fn new(db: &'a OpenDB) -> Self {
+        Self { db, error: None }
+    }
+
+    /// Run `f`, stashing a database error so serde can unwind
+    fn apply<E: de::Error>(
+        &mut self,
+        f: impl FnOnce(&OpenDB) -> Result<()>,
+    ) -> std::result::Result<(), E> {
+        f(self.db).map_err(|e| {
+            self.error = Some(e);
+            E::custom("import aborted")
+        })
+    }
+
+    /// Parse the whole document, applying every section
+    pub(crate) fn run<'de, D: Deserializer<'de>>(mut self, deserializer: D) -> Result<()> {
+        let parsed = (&mut self).deserialize(deserializer);
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        parsed.map_err(|e| Error::Codec(format!("Invalid export document: {}", e)))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for &mut Importer<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for &mut Importer<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an OpenDB export document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            let section = match key.as_str() {
+                "opendb_export" => {
+                    let version: u32 = map.next_value()?;
+                    if version != EXPORT_FORMAT_VERSION {
+                        return Err(de::Error::custom(format!(
+                            "unsupported export format version {}",
+                            version
+                        )));
+                    }
+                    continue;
+                }
+                "memories" => Section::Memories,
+                "edges" => Section::Edges,
+                "kv" => Section::Kv,
+                "documents" => Section::Documents,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                    continue;
+                }
+            };
+            map.next_value_seed(SectionSeed {
+                importer: &mut *self,
+                section,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// The sections of an export document
+#[derive(Clone, Copy)]
+enum Section {
+    Memories,
+    Edges,
+    Kv,
+    Documents,
+}
+
+/// Applies the items of one section as they are parsed
+struct SectionSeed<'i, 'a> {
+    importer: &'i mut Importer<'a>,
+    section: Section,
+}
+
+impl<'de> DeserializeSeed<'de> for SectionSeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for SectionSeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of exported items")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        let importer = self.importer;
+        match self.section {
+            Section::Memories => {
+                // Batched, so vectors are indexed once per batch
+                let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+                while let Some(memory) = seq.next_element::<Memory>()? {
+                    batch.push(memory);
+                    if batch.len() == IMPORT_BATCH_SIZE {
+                        importer.apply(|db| db.insert_memories(&batch))?;
+                        batch.clear();
+                    }
+                }
+                if !batch.is_empty() {
+                    importer.apply(|db| db.insert_memories(&batch))?;
+                }
+            }
+            Section::Edges => {
+                while let Some(edge) = seq.next_element::<Edge>()? {
+                    importer.apply(|db| db.add_edge(&edge))?;
+                }
+            }
+            Section::Kv => {
+                while let Some(entry) = seq.next_element::<KvEntry>()? {
+                    importer.apply(|db| db.put(entry.key.as_bytes(), entry.value.as_bytes()))?;
+                }
+            }
+            Section::Documents => {
+                while let Some(document) = seq.next_element::<MultimodalDocument>()? {
+                    importer.apply(|db| db.insert_document(&document))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -3,19 +3,121 @@
 pub mod relation;
 
 use crate::codec;
-use crate::error::Result;
-use crate::storage::{SharedStorage, column_families::ColumnFamilies};
-use crate::types::Edge;
+use crate::error::{Error, Result};
+use crate::storage::{BatchOp, SharedStorage, column_families::ColumnFamilies};
+use crate::types::{Edge, GraphStats};
+use chrono::Utc;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::ops::ControlFlow;
+
+/// Metadata key recording that the graph uses one key per edge
+const LAYOUT_KEY: &[u8] = b"__opendb_graph_layout__";
+
+/// Separator between the parts of an index key
+const SEP: u8 = 0;
+
+/// Helper: Key of an edge in one of the graph indexes
+///
+/// Forward keys are `from\0relation\0to\0id` and backward keys
+/// `to\0relation\0from\0id`, so byte order matches the canonical order and
+/// a node's edges (or its edges of one relation) share a key prefix.
+fn index_key(cf: &str, edge: &Edge) -> Vec<u8> {
+    let (node, other) = if cf == ColumnFamilies::GRAPH_BACKWARD {
+        (&edge.to, &edge.from)
+    } else {
+        (&edge.from, &edge.to)
+    };
+    let mut key = key_prefix(&[node, &edge.relation, other]);
+    key.extend_from_slice(edge.id.as_bytes());
+    key
+}
+
+/// Helper: Key prefix shared by every index key starting with `parts`
+fn key_prefix(parts: &[&str]) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(parts.iter().map(|p| p.len() + 1).sum());
+    for part in parts {
+        prefix.extend_from_slice(part.as_bytes());
+        prefix.push(SEP);
+    }
+    prefix
+}
+
+/// Helper: The `n`th part of an index key
+fn key_part(key: &[u8], n: usize) -> &[u8] {
+    key.split(|&b| b == SEP).nth(n).unwrap_or_default()
+}
+
+/// Helper: Reject ids that would corrupt the key layout
+fn check_key_parts(edge: &Edge) -> Result<()> {
+    for part in [&edge.from, &edge.relation, &edge.to, &edge.id] {
+        if part.as_bytes().contains(&SEP) {
+            return Err(Error::InvalidInput(format!(
+                "Graph ids and relations must not contain NUL bytes: {:?}",
+                part
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Helper: Writes storing `edge` in both indexes
+fn put_ops(edge: &Edge) -> Result<[BatchOp; 2]> {
+    let value = codec::encode_edge(edge)?;
+    Ok([
+        BatchOp::put(
+            ColumnFamilies::GRAPH_FORWARD,
+            &index_key(ColumnFamilies::GRAPH_FORWARD, edge),
+            value.clone(),
+        ),
+        BatchOp::put(
+            ColumnFamilies::GRAPH_BACKWARD,
+            &index_key(ColumnFamilies::GRAPH_BACKWARD, edge),
+            value,
+        ),
+    ])
+}
+
+/// Helper: Writes removing `edge` from both indexes
+fn delete_ops(edge: &Edge) -> [BatchOp; 2] {
+    [
+        BatchOp::delete(
+            ColumnFamilies::GRAPH_FORWARD,
+            &index_key(ColumnFamilies::GRAPH_FORWARD, edge),
+        ),
+        BatchOp::delete(
+            ColumnFamilies::GRAPH_BACKWARD,
+            &index_key(ColumnFamilies::GRAPH_BACKWARD, edge),
+        ),
+    ]
+}
 
 /// Graph manager for relationship operations
 pub struct GraphManager {
     storage: SharedStorage,
+    multigraph: bool,
+    max_traversal_nodes: usize,
 }
 
 impl GraphManager {
     /// Create a new graph manager
     pub fn new(storage: SharedStorage) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            multigraph: false,
+            max_traversal_nodes: usize::MAX,
+        }
+    }
+
+    /// Cap the number of nodes a single `traverse` call may return
+    pub fn with_max_traversal_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_traversal_nodes = max_nodes;
+        self
+    }
+
+    /// Allow several edges with the same `(from, relation, to)`, told apart by id
+    pub fn with_multigraph(mut self, multigraph: bool) -> Self {
+        self.multigraph = multigraph;
+        self
     }
 
     /// Create a link between two entities
@@ -26,26 +128,216 @@ impl GraphManager {
     /// * `relation` - Relationship type
     /// * `to` - Target entity ID
     pub fn link(&self, from: &str, relation: &str, to: &str) -> Result<()> {
+        self.add_edge(&Edge::new(from, relation, to))
+    }
+
+    /// Create many links at once, as `(from, relation, to)` triples
+    ///
+    /// All edges land in one atomic batch. Links that already exist, or
+    /// repeat earlier in the batch, are skipped the same way `link` skips
+    /// them.
+    ///
+    /// Returns the number of edges added.
+    pub fn link_many(&self, links: &[(&str, &str, &str)]) -> Result<usize> {
+        let mut seen = HashSet::new();
+        let mut ops = Vec::with_capacity(links.len() * 2);
+        for &(from, relation, to) in links {
+            let edge = Edge::new(from, relation, to);
+            check_key_parts(&edge)?;
+            if !self.multigraph && (!seen.insert((from, relation, to)) || self.has_link(&edge)?) {
+                continue;
+            }
+            ops.extend(put_ops(&edge)?);
+        }
+
+        if !ops.is_empty() {
+            self.storage.write_batch(&ops)?;
+        }
+        Ok(ops.len() / 2)
+    }
+
+    /// Batch writes creating a link, or none if it already exists
+    ///
+    /// Like `link`, outside multigraph mode an existing link is kept. Links
+    /// buffered in other unwritten batches aren't seen.
+    pub fn link_ops(&self, from: &str, relation: &str, to: &str) -> Result<Vec<BatchOp>> {
         let edge = Edge::new(from, relation, to);
+        check_key_parts(&edge)?;
+        if !self.multigraph && self.has_link(&edge)? {
+            return Ok(Vec::new());
+        }
+        Ok(put_ops(&edge)?.to_vec())
+    }
 
-        // Store in forward index (from -> to)
-        self.add_to_adjacency_list(ColumnFamilies::GRAPH_FORWARD, &edge.from, &edge)?;
+    /// Create a link with a relationship strength
+    ///
+    /// Like `link`, outside multigraph mode an existing link with the same
+    /// `(from, relation, to)` is kept unchanged.
+    pub fn link_weighted(&self, from: &str, relation: &str, to: &str, weight: f32) -> Result<()> {
+        if !weight.is_finite() {
+            return Err(Error::InvalidInput(format!(
+                "Edge weight must be finite, got {}",
+                weight
+            )));
+        }
+        self.add_edge(&Edge::new(from, relation, to).with_weight(weight))
+    }
 
-        // Store in backward index (to -> from)
-        self.add_to_adjacency_list(ColumnFamilies::GRAPH_BACKWARD, &edge.to, &edge)?;
+    /// Create a link from a fully built edge, e.g. one carrying metadata
+    ///
+    /// The edge's id, weight, timestamp and metadata are stored as given.
+    /// Weights must be finite. Like `link`, outside multigraph mode an
+    /// existing link with the same `(from, relation, to)` is kept unchanged.
+    pub fn link_with(&self, edge: &Edge) -> Result<()> {
+        if !edge.weight.is_finite() {
+            return Err(Error::InvalidInput(format!(
+                "Edge weight must be finite, got {}",
+                edge.weight
+            )));
+        }
+        self.add_edge(edge)
+    }
 
-        Ok(())
+    /// Change the weight of an existing link in place
+    ///
+    /// Both indexes are rewritten in one atomic batch, so readers never see
+    /// them disagree. In multigraph mode every parallel edge with the same
+    /// `(from, relation, to)` is updated. Fails with `Error::Graph` if the
+    /// link doesn't exist.
+    pub fn set_weight(&self, from: &str, relation: &str, to: &str, weight: f32) -> Result<()> {
+        if !weight.is_finite() {
+            return Err(Error::InvalidInput(format!(
+                "Edge weight must be finite, got {}",
+                weight
+            )));
+        }
+
+        let edges = self.link_edges(from, relation, to)?;
+        if edges.is_empty() {
+            return Err(Error::Graph(format!(
+                "No edge {} --[{}]--> {}",
+                from, relation, to
+            )));
+        }
+
+        let mut ops = Vec::with_capacity(edges.len() * 2);
+        for mut edge in edges {
+            edge.weight = weight;
+            ops.extend(put_ops(&edge)?);
+        }
+        self.storage.write_batch(&ops)
+    }
+
+    /// Store an edge as-is, keeping its weight and timestamp
+    ///
+    /// Outside multigraph mode an existing link with the same
+    /// `(from, relation, to)` is kept; in multigraph mode edges are unique by id.
+    pub fn add_edge(&self, edge: &Edge) -> Result<()> {
+        check_key_parts(edge)?;
+
+        let duplicate = if self.multigraph {
+            self.storage.exists(
+                ColumnFamilies::GRAPH_FORWARD,
+                &index_key(ColumnFamilies::GRAPH_FORWARD, edge),
+            )?
+        } else {
+            self.has_link(edge)?
+        };
+        if duplicate {
+            return Ok(());
+        }
+
+        // One key in each index, written together
+        self.storage.write_batch(&put_ops(edge)?)
+    }
+
+    /// Stored edges that `edge` collides with
+    ///
+    /// Outside multigraph mode these are the edges with the same
+    /// `(from, relation, to)`; in multigraph mode, the edge with the same id.
+    pub fn conflicting_edges(&self, edge: &Edge) -> Result<Vec<Edge>> {
+        let mut edges = self.link_edges(&edge.from, &edge.relation, &edge.to)?;
+        if self.multigraph {
+            edges.retain(|existing| existing.id == edge.id);
+        }
+        Ok(edges)
+    }
+
+    /// Store `edge` in place of the edges it collides with
+    ///
+    /// The old edges are removed and the new one written in one atomic batch.
+    pub fn replace_edge(&self, edge: &Edge) -> Result<()> {
+        check_key_parts(edge)?;
+
+        let mut ops: Vec<BatchOp> = self
+            .conflicting_edges(edge)?
+            .iter()
+            .flat_map(delete_ops)
+            .collect();
+        ops.extend(put_ops(edge)?);
+        self.storage.write_batch(&ops)
     }
 
     /// Remove a link between two entities
+    ///
+    /// In multigraph mode a triple can match several edges, so this fails with
+    /// `Error::InvalidInput`; use `unlink_edge` instead.
     pub fn unlink(&self, from: &str, relation: &str, to: &str) -> Result<()> {
-        // Remove from forward index
-        self.remove_from_adjacency_list(ColumnFamilies::GRAPH_FORWARD, from, relation, to)?;
+        if self.multigraph {
+            return Err(Error::InvalidInput(
+                "Multigraph mode: unlink requires an edge id (use unlink_edge)".to_string(),
+            ));
+        }
 
-        // Remove from backward index
-        self.remove_from_adjacency_list(ColumnFamilies::GRAPH_BACKWARD, to, relation, from)?;
+        let ops: Vec<BatchOp> = self
+            .link_edges(from, relation, to)?
+            .iter()
+            .flat_map(delete_ops)
+            .collect();
+        if ops.is_empty() {
+            return Ok(()); // Nothing to remove
+        }
+        self.storage.write_batch(&ops)
+    }
 
-        Ok(())
+    /// Remove a single edge by id
+    ///
+    /// Returns `false` if `from` has no outgoing edge with that id.
+    pub fn unlink_edge(&self, from: &str, edge_id: &str) -> Result<bool> {
+        let Some(edge) = self
+            .get_outgoing(from, None)?
+            .into_iter()
+            .find(|e| e.id == edge_id)
+        else {
+            return Ok(false);
+        };
+
+        self.storage.write_batch(&delete_ops(&edge))?;
+        Ok(true)
+    }
+
+    /// Remove every edge into or out of an entity
+    ///
+    /// Both indexes are cleaned, including the entries stored under the
+    /// entities at the other end. Returns the number of edges removed.
+    pub fn remove_node(&self, id: &str) -> Result<usize> {
+        let outgoing = self.get_outgoing(id, None)?;
+        // Self-loops appear in both directions
+        let incoming: Vec<Edge> = self
+            .get_incoming(id, None)?
+            .into_iter()
+            .filter(|e| e.from != id)
+            .collect();
+
+        let ops: Vec<BatchOp> = outgoing
+            .iter()
+            .chain(&incoming)
+            .flat_map(delete_ops)
+            .collect();
+        if !ops.is_empty() {
+            self.storage.write_batch(&ops)?;
+        }
+        Ok(outgoing.len() + incoming.len())
     }
 
     /// Get all outgoing edges from an entity
@@ -64,78 +356,628 @@ impl GraphManager {
         Ok(edges.into_iter().map(|e| e.to).collect())
     }
 
-    /// Helper: Add edge to adjacency list
-    fn add_to_adjacency_list(&self, cf: &str, key: &str, edge: &Edge) -> Result<()> {
-        let key_bytes = key.as_bytes();
+    /// Get related entity IDs with their edge weights, strongest first
+    ///
+    /// Ties keep the canonical order by target id.
+    pub fn get_related_weighted(&self, id: &str, relation: &str) -> Result<Vec<(String, f32)>> {
+        let mut related: Vec<(String, f32)> = self
+            .get_outgoing(id, Some(relation))?
+            .into_iter()
+            .map(|e| (e.to, e.weight))
+            .collect();
+        related.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(related)
+    }
 
-        // Get existing edges
-        let mut edges = if let Some(bytes) = self.storage.get(cf, key_bytes)? {
-            codec::decode_edges(&bytes)?
-        } else {
-            Vec::new()
-        };
+    /// Get the distinct relation types on an entity's outgoing edges, sorted
+    pub fn relations_of(&self, id: &str) -> Result<Vec<String>> {
+        let mut relations: Vec<String> = self
+            .get_outgoing(id, None)?
+            .into_iter()
+            .map(|e| e.relation)
+            .collect();
+        // Canonical order already groups edges by relation
+        relations.dedup();
+        Ok(relations)
+    }
 
-        // Add new edge (avoid duplicates)
-        if !edges
-            .iter()
-            .any(|e| e.from == edge.from && e.to == edge.to && e.relation == edge.relation)
-        {
-            edges.push(edge.clone());
+    /// Get every distinct relation type used in the graph, sorted
+    ///
+    /// Streams the keys of both indexes without decoding any edge.
+    pub fn all_relations(&self) -> Result<Vec<String>> {
+        let mut relations = BTreeSet::new();
+        for cf in [
+            ColumnFamilies::GRAPH_FORWARD,
+            ColumnFamilies::GRAPH_BACKWARD,
+        ] {
+            self.storage.for_each_prefix(cf, &[], &mut |key, _| {
+                let relation = key_part(key, 1);
+                if !relations.contains(relation) {
+                    relations.insert(relation.to_vec());
+                }
+                Ok(ControlFlow::Continue(()))
+            })?;
         }
+        Ok(relations
+            .into_iter()
+            .map(|r| String::from_utf8_lossy(&r).into_owned())
+            .collect())
+    }
 
-        // Store back
-        let encoded = codec::encode_edges(&edges)?;
-        self.storage.put(cf, key_bytes, &encoded)?;
+    /// Get outgoing target IDs grouped by relation type
+    ///
+    /// Computed from a single prefix scan; targets keep canonical order.
+    pub fn outgoing_by_relation(&self, id: &str) -> Result<HashMap<String, Vec<String>>> {
+        let mut grouped: HashMap<String, Vec<String>> = HashMap::new();
+        for edge in self.get_outgoing(id, None)? {
+            grouped.entry(edge.relation).or_default().push(edge.to);
+        }
+        Ok(grouped)
+    }
 
+    /// Breadth-first walk over outgoing edges, reporting `(node, hops)` pairs
+    ///
+    /// Nodes are reported in discovery order, which is deterministic because
+    /// edge keys sort in canonical order. The start node is not
+    /// reported and nodes are never revisited, so cycles are safe.
+    fn walk_breadth_first<F>(
+        &self,
+        start: &str,
+        relation: Option<&str>,
+        max_depth: usize,
+        mut visit: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&str, usize) -> ControlFlow<()>,
+    {
+        let mut visited: HashSet<String> = HashSet::from([start.to_string()]);
+        let mut queue = VecDeque::from([(start.to_string(), 0)]);
+        while let Some((id, hops)) = queue.pop_front() {
+            if hops == max_depth {
+                continue;
+            }
+            for edge in self.get_outgoing(&id, relation)? {
+                if visited.insert(edge.to.clone()) {
+                    if visit(&edge.to, hops + 1).is_break() {
+                        return Ok(());
+                    }
+                    queue.push_back((edge.to, hops + 1));
+                }
+            }
+        }
         Ok(())
     }
 
-    /// Helper: Remove edge from adjacency list
-    fn remove_from_adjacency_list(
+    /// Breadth-first traversal over outgoing edges
+    ///
+    /// Returns `(node, hops)` pairs for every node reachable from `start`
+    /// within `max_depth` hops, optionally following only `relation` edges.
+    /// Nodes are visited once, so cycles are safe. Stops after the configured
+    /// maximum number of nodes.
+    pub fn traverse(
         &self,
-        cf: &str,
-        key: &str,
-        relation: &str,
-        target: &str,
-    ) -> Result<()> {
-        let key_bytes = key.as_bytes();
-
-        // Get existing edges
-        let mut edges = if let Some(bytes) = self.storage.get(cf, key_bytes)? {
-            codec::decode_edges(&bytes)?
-        } else {
-            return Ok(()); // Nothing to remove
+        start: &str,
+        relation: Option<&str>,
+        max_depth: usize,
+    ) -> Result<Vec<(String, usize)>> {
+        let mut reached = Vec::new();
+        if self.max_traversal_nodes == 0 {
+            return Ok(reached);
+        }
+
+        self.walk_breadth_first(start, relation, max_depth, |id, hops| {
+            reached.push((id.to_string(), hops));
+            if reached.len() == self.max_traversal_nodes {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })?;
+
+        Ok(reached)
+    }
+
+    /// One page of a breadth-first traversal: skip `offset` nodes, return up to `limit`
+    ///
+    /// The visit order is stable across calls while the graph is unchanged,
+    /// so consecutive pages never overlap.
+    pub fn traverse_page(
+        &self,
+        start: &str,
+        relation: Option<&str>,
+        max_depth: usize,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<(String, usize)>> {
+        let mut page = Vec::new();
+        if limit == 0 {
+            return Ok(page);
+        }
+
+        let mut skipped = 0;
+        self.walk_breadth_first(start, relation, max_depth, |id, hops| {
+            if skipped < offset {
+                skipped += 1;
+                return ControlFlow::Continue(());
+            }
+            page.push((id.to_string(), hops));
+            if page.len() == limit {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })?;
+
+        Ok(page)
+    }
+
+    /// Connected subgraph around `root`, for visualization
+    ///
+    /// Collects `root` and the nodes reachable from it over outgoing edges
+    /// within `max_depth` hops, in breadth-first order, stopping once
+    /// `max_nodes` nodes (root included) are collected. Returns those nodes
+    /// and every edge between two of them, each edge once. Nodes are
+    /// visited once, so cycles are safe.
+    pub fn subgraph(
+        &self,
+        root: &str,
+        max_depth: usize,
+        max_nodes: usize,
+    ) -> Result<(Vec<String>, Vec<Edge>)> {
+        if max_nodes == 0 {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let mut nodes = vec![root.to_string()];
+        if max_nodes > 1 {
+            self.walk_breadth_first(root, None, max_depth, |id, _| {
+                nodes.push(id.to_string());
+                if nodes.len() == max_nodes {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })?;
+        }
+
+        // Each node's outgoing edges are read once, so no edge repeats
+        let members: HashSet<&str> = nodes.iter().map(String::as_str).collect();
+        let mut edges = Vec::new();
+        for id in &nodes {
+            edges.extend(
+                self.get_outgoing(id, None)?
+                    .into_iter()
+                    .filter(|edge| members.contains(edge.to.as_str())),
+            );
+        }
+
+        Ok((nodes, edges))
+    }
+
+    /// Visit every edge once, streaming the forward index
+    ///
+    /// Edges are decoded one at a time, so the full edge set is never
+    /// materialized. Return `ControlFlow::Break(())` to stop early.
+    pub fn for_each_edge<F>(&self, mut visitor: F) -> Result<()>
+    where
+        F: FnMut(&Edge) -> ControlFlow<()>,
+    {
+        self.storage
+            .for_each_prefix(ColumnFamilies::GRAPH_FORWARD, &[], &mut |_, value| {
+                Ok(visitor(&codec::decode_edge(value)?))
+            })
+    }
+
+    /// Count nodes and edges in the graph
+    pub fn graph_stats(&self) -> Result<GraphStats> {
+        let mut nodes = HashSet::new();
+        let mut edge_count = 0;
+
+        self.storage
+            .for_each_prefix(ColumnFamilies::GRAPH_FORWARD, &[], &mut |key, _| {
+                nodes.insert(key_part(key, 0).to_vec());
+                edge_count += 1;
+                Ok(ControlFlow::Continue(()))
+            })?;
+
+        // Pure sinks only show up in the backward index
+        self.storage
+            .for_each_prefix(ColumnFamilies::GRAPH_BACKWARD, &[], &mut |key, _| {
+                nodes.insert(key_part(key, 0).to_vec());
+                Ok(ControlFlow::Continue(()))
+            })?;
+
+        Ok(GraphStats {
+            node_count: nodes.len(),
+            edge_count,
+        })
+    }
+
+    /// Count edges by walking the forward index keys
+    pub fn edge_count(&self) -> Result<usize> {
+        let mut count = 0;
+        self.storage
+            .for_each_key(ColumnFamilies::GRAPH_FORWARD, &[], &mut |_| {
+                count += 1;
+                Ok(ControlFlow::Continue(()))
+            })?;
+        Ok(count)
+    }
+
+    /// Decay every edge weight by its age, optionally pruning weak edges
+    ///
+    /// Each weight is multiplied by `0.5^(age / half_life_secs)`, where age is
+    /// measured from `Edge::last_decayed`, or `Edge::timestamp` for edges
+    /// never decayed. `last_decayed` is then set to now, so repeated calls
+    /// only decay by the time elapsed since the previous call; the creation
+    /// timestamp is kept. Edges whose decayed weight falls below `floor` are
+    /// removed from both indexes.
+    ///
+    /// Returns the number of pruned edges.
+    pub fn decay_edge_weights(&self, half_life_secs: f64, floor: Option<f32>) -> Result<usize> {
+        if half_life_secs.is_nan() || half_life_secs <= 0.0 {
+            return Err(Error::InvalidInput(format!(
+                "Half-life must be positive, got {}",
+                half_life_secs
+            )));
+        }
+
+        let now = Utc::now().timestamp();
+        let mut ops = Vec::new();
+        let mut pruned = 0;
+        for (_, value) in self
+            .storage
+            .scan_prefix(ColumnFamilies::GRAPH_FORWARD, &[])?
+        {
+            let mut edge = codec::decode_edge(&value)?;
+            let since = edge.last_decayed.unwrap_or(edge.timestamp);
+            let age = (now - since).max(0) as f64;
+            edge.weight *= 0.5f64.powf(age / half_life_secs) as f32;
+            edge.last_decayed = Some(now);
+
+            if floor.is_some_and(|floor| edge.weight < floor) {
+                ops.extend(delete_ops(&edge));
+                pruned += 1;
+            } else {
+                ops.extend(put_ops(&edge)?);
+            }
+        }
+
+        if !ops.is_empty() {
+            self.storage.write_batch(&ops)?;
+        }
+        Ok(pruned)
+    }
+
+    /// Compute PageRank scores over the forward edges
+    ///
+    /// With `weighted`, each node splits its rank across outgoing edges in
+    /// proportion to edge weight instead of evenly. Rank held by nodes with no
+    /// outgoing edges is spread evenly over all nodes, so scores sum to ~1.0.
+    pub fn pagerank(
+        &self,
+        damping: f32,
+        iterations: usize,
+        weighted: bool,
+    ) -> Result<HashMap<String, f32>> {
+        if !(0.0..=1.0).contains(&damping) {
+            return Err(Error::InvalidInput(format!(
+                "Damping must be in [0, 1], got {}",
+                damping
+            )));
+        }
+
+        // Load the graph once as index-based adjacency lists
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut out: Vec<Vec<(usize, f32)>> = Vec::new();
+        let mut intern = |id: &str, out: &mut Vec<Vec<(usize, f32)>>| {
+            *index.entry(id.to_string()).or_insert_with(|| {
+                out.push(Vec::new());
+                out.len() - 1
+            })
         };
+        self.for_each_edge(|edge| {
+            let from = intern(&edge.from, &mut out);
+            let to = intern(&edge.to, &mut out);
+            let weight = if weighted { edge.weight.max(0.0) } else { 1.0 };
+            out[from].push((to, weight));
+            ControlFlow::Continue(())
+        })?;
 
-        // Remove matching edges
-        edges.retain(|e| !(e.relation == relation && (e.from == target || e.to == target)));
+        let n = out.len();
+        if n == 0 {
+            return Ok(HashMap::new());
+        }
 
-        // Store back
-        if edges.is_empty() {
-            self.storage.delete(cf, key_bytes)?;
-        } else {
-            let encoded = codec::encode_edges(&edges)?;
-            self.storage.put(cf, key_bytes, &encoded)?;
+        let totals: Vec<f32> = out
+            .iter()
+            .map(|edges| edges.iter().map(|(_, w)| w).sum())
+            .collect();
+        let mut rank = vec![1.0 / n as f32; n];
+        for _ in 0..iterations {
+            let dangling: f32 = (0..n).filter(|&i| totals[i] == 0.0).map(|i| rank[i]).sum();
+            let base = (1.0 - damping + damping * dangling) / n as f32;
+            let mut next = vec![base; n];
+            for (from, edges) in out.iter().enumerate() {
+                if totals[from] == 0.0 {
+                    continue;
+                }
+                let share = damping * rank[from] / totals[from];
+                for &(to, weight) in edges {
+                    next[to] += share * weight;
+                }
+            }
+            rank = next;
         }
 
-        Ok(())
+        Ok(index.into_iter().map(|(id, i)| (id, rank[i])).collect())
     }
 
-    /// Helper: Get edges for an entity
-    fn get_edges(&self, cf: &str, key: &str, relation: Option<&str>) -> Result<Vec<Edge>> {
-        let key_bytes = key.as_bytes();
+    /// Upgrade adjacency lists written before edges had one key each
+    ///
+    /// Older databases stored each node's edges as a single list under the
+    /// node id. Each list is split into per-edge keys, and a marker in the
+    /// metadata column family makes later calls a single lookup. Returns the
+    /// number of lists upgraded.
+    pub fn upgrade_layout(&self) -> Result<usize> {
+        if self.storage.exists(ColumnFamilies::METADATA, LAYOUT_KEY)? {
+            return Ok(0);
+        }
 
-        let edges = if let Some(bytes) = self.storage.get(cf, key_bytes)? {
-            codec::decode_edges(&bytes)?
-        } else {
-            Vec::new()
+        let mut ops = Vec::new();
+        let mut upgraded = 0;
+        for cf in [
+            ColumnFamilies::GRAPH_FORWARD,
+            ColumnFamilies::GRAPH_BACKWARD,
+        ] {
+            for (key, value) in self.storage.scan_prefix(cf, &[])? {
+                // Per-edge keys always contain separators; node ids never do
+                if key.contains(&SEP) {
+                    continue;
+                }
+                for edge in codec::decode_edges(&value)? {
+                    ops.push(BatchOp::put(
+                        cf,
+                        &index_key(cf, &edge),
+                        codec::encode_edge(&edge)?,
+                    ));
+                }
+                ops.push(BatchOp::delete(cf, &key));
+                upgraded += 1;
+            }
+        }
+
+        ops.push(BatchOp::put(
+            ColumnFamilies::METADATA,
+            LAYOUT_KEY,
+            b"2".to_vec(),
+        ));
+        self.storage.write_batch(&ops)?;
+        Ok(upgraded)
+    }
+
+    /// Helper: Every edge stored for a `(from, relation, to)` link
+    fn link_edges(&self, from: &str, relation: &str, to: &str) -> Result<Vec<Edge>> {
+        self.storage
+            .scan_prefix_iter(
+                ColumnFamilies::GRAPH_FORWARD,
+                &key_prefix(&[from, relation, to]),
+            )?
+            .map(|pair| codec::decode_edge(&pair?.1))
+            .collect()
+    }
+
+    /// Helper: Whether the link `edge` describes already exists
+    fn has_link(&self, edge: &Edge) -> Result<bool> {
+        let prefix = key_prefix(&[&edge.from, &edge.relation, &edge.to]);
+        Ok(self
+            .storage
+            .scan_prefix_iter(ColumnFamilies::GRAPH_FORWARD, &prefix)?
+            .next()
+            .transpose()?
+            .is_some())
+    }
+
+    /// Helper: Get edges for an entity, in canonical order
+    fn get_edges(&self, cf: &str, key: &str, relation: Option<&str>) -> Result<Vec<Edge>> {
+        let prefix = match relation {
+            Some(rel) => key_prefix(&[key, rel]),
+            None => key_prefix(&[key]),
         };
 
-        // Filter by relation if specified
-        if let Some(rel) = relation {
-            Ok(edges.into_iter().filter(|e| e.relation == rel).collect())
-        } else {
-            Ok(edges)
+        // Key order is the canonical order
+        self.storage
+            .scan_prefix_iter(cf, &prefix)?
+            .map(|pair| codec::decode_edge(&pair?.1))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::OpenDBOptions;
+    use crate::storage::rocksdb_backend::RocksDBBackend;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_decay_edge_weights() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend =
+            RocksDBBackend::open_with_options(temp_dir.path(), &OpenDBOptions::default())?;
+        let graph = GraphManager::new(Arc::new(backend));
+
+        let hour = 3600;
+        let now = Utc::now().timestamp();
+        let mut fresh = Edge::new("a", "knows", "b");
+        fresh.timestamp = now;
+        let mut one_half_life = Edge::new("a", "knows", "c");
+        one_half_life.timestamp = now - hour;
+        let mut ancient = Edge::new("a", "likes", "d");
+        ancient.timestamp = now - 10 * hour;
+        for edge in [&fresh, &one_half_life, &ancient] {
+            graph.add_edge(edge)?;
         }
+
+        let pruned = graph.decay_edge_weights(hour as f64, Some(0.01))?;
+        assert_eq!(pruned, 1);
+
+        let outgoing = graph.get_outgoing("a", None)?;
+        assert_eq!(outgoing.len(), 2);
+        assert!((outgoing[0].weight - 1.0).abs() < 0.01);
+        assert!((outgoing[1].weight - 0.5).abs() < 0.01);
+
+        // Pruned edges disappear from the backward index too
+        assert!(graph.get_incoming("d", None)?.is_empty());
+        assert!((graph.get_incoming("c", None)?[0].weight - 0.5).abs() < 0.01);
+
+        assert!(graph.decay_edge_weights(0.0, None).is_err());
+
+        // Decay times were recorded, so an immediate second pass barely changes weights
+        assert_eq!(graph.decay_edge_weights(hour as f64, Some(0.01))?, 0);
+        let outgoing = graph.get_outgoing("a", None)?;
+        assert!((outgoing[1].weight - 0.5).abs() < 0.01);
+
+        // Creation timestamps are untouched
+        assert_eq!(outgoing[1].timestamp, now - hour);
+        assert!(outgoing[1].last_decayed.is_some_and(|t| t >= now));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_weight_updates_both_indexes() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend =
+            RocksDBBackend::open_with_options(temp_dir.path(), &OpenDBOptions::default())?;
+        let graph = GraphManager::new(Arc::new(backend));
+
+        graph.link_weighted("a", "knows", "b", 0.2)?;
+        graph.link("a", "likes", "b")?;
+        graph.link("c", "knows", "b")?;
+
+        graph.set_weight("a", "knows", "b", 0.75)?;
+
+        let outgoing = graph.get_outgoing("a", Some("knows"))?;
+        assert_eq!(outgoing[0].weight, 0.75);
+        let incoming = graph.get_incoming("b", None)?;
+        let weights: Vec<(&str, &str, f32)> = incoming
+            .iter()
+            .map(|e| (e.from.as_str(), e.relation.as_str(), e.weight))
+            .collect();
+        assert_eq!(
+            weights,
+            vec![
+                ("a", "knows", 0.75),
+                ("c", "knows", 1.0),
+                ("a", "likes", 1.0)
+            ]
+        );
+
+        // Missing links fail without touching anything
+        assert!(matches!(
+            graph.set_weight("a", "knows", "z", 0.5),
+            Err(Error::Graph(_))
+        ));
+        assert!(matches!(
+            graph.set_weight("b", "knows", "a", 0.5),
+            Err(Error::Graph(_))
+        ));
+        assert!(graph.set_weight("a", "knows", "b", f32::INFINITY).is_err());
+        assert_eq!(graph.get_outgoing("a", Some("knows"))?[0].weight, 0.75);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_one_key_per_edge() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend =
+            RocksDBBackend::open_with_options(temp_dir.path(), &OpenDBOptions::default())?;
+        let storage: SharedStorage = Arc::new(backend);
+        let graph = GraphManager::new(Arc::clone(&storage));
+
+        graph.link("a", "knows", "b")?;
+        graph.link("ab", "knows", "a")?;
+        graph.link("a", "knows", "ab")?;
+
+        // Linking is a single small put per index
+        assert_eq!(
+            storage
+                .scan_prefix(ColumnFamilies::GRAPH_FORWARD, &[])?
+                .len(),
+            3
+        );
+        assert_eq!(
+            storage
+                .scan_prefix(ColumnFamilies::GRAPH_BACKWARD, &[])?
+                .len(),
+            3
+        );
+
+        // A node's prefix never matches a longer id
+        assert_eq!(graph.get_related("a", "knows")?, vec!["ab", "b"]);
+        assert_eq!(graph.get_incoming("a", None)?.len(), 1);
+        assert_eq!(graph.get_related("ab", "knows")?, vec!["a"]);
+
+        graph.unlink("a", "knows", "b")?;
+        assert_eq!(
+            storage
+                .scan_prefix(ColumnFamilies::GRAPH_FORWARD, &[])?
+                .len(),
+            2
+        );
+        assert!(graph.get_incoming("b", None)?.is_empty());
+
+        assert!(matches!(
+            graph.link("a\0b", "knows", "c"),
+            Err(Error::InvalidInput(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_legacy_adjacency_lists() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend =
+            RocksDBBackend::open_with_options(temp_dir.path(), &OpenDBOptions::default())?;
+        let storage: SharedStorage = Arc::new(backend);
+        let graph = GraphManager::new(Arc::clone(&storage));
+
+        // Lists as the old layout stored them, keyed by node id
+        let ab = Edge::new("a", "knows", "b");
+        let ac = Edge::new("a", "likes", "c");
+        storage.put(
+            ColumnFamilies::GRAPH_FORWARD,
+            b"a",
+            &codec::encode_edges(&[ab.clone(), ac.clone()])?,
+        )?;
+        storage.put(
+            ColumnFamilies::GRAPH_BACKWARD,
+            b"b",
+            &codec::encode_edges(std::slice::from_ref(&ab))?,
+        )?;
+        storage.put(
+            ColumnFamilies::GRAPH_BACKWARD,
+            b"c",
+            &codec::encode_edges(std::slice::from_ref(&ac))?,
+        )?;
+
+        assert_eq!(graph.upgrade_layout()?, 3);
+        let ids = |edges: Vec<Edge>| edges.into_iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(graph.get_outgoing("a", None)?),
+            vec![ab.id.clone(), ac.id]
+        );
+        assert_eq!(ids(graph.get_incoming("b", None)?), vec![ab.id]);
+        assert!(storage.get(ColumnFamilies::GRAPH_FORWARD, b"a")?.is_none());
+
+        // Already upgraded: nothing is scanned again
+        assert_eq!(graph.upgrade_layout()?, 0);
+        assert_eq!(graph.graph_stats()?.edge_count, 2);
+
+        Ok(())
     }
 }
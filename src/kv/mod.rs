@@ -1,14 +1,18 @@
 // Key-Value store API
 
-use crate::cache::lru_cache::LruMemoryCache;
-use crate::error::Result;
-use crate::storage::{SharedStorage, column_families::ColumnFamilies};
+pub mod typed;
+
+use crate::cache::{CacheConfig, MemoryCache};
+use crate::error::{Error, Result};
+use crate::storage::{BatchOp, SharedStorage, column_families::ColumnFamilies};
+use crate::types::CacheStats;
+use std::ops::ControlFlow;
 use std::sync::Arc;
 
 /// Key-Value store
 pub struct KvStore {
     storage: SharedStorage,
-    cache: Arc<LruMemoryCache<Vec<u8>, Vec<u8>>>,
+    cache: Arc<MemoryCache<Vec<u8>, Vec<u8>>>,
 }
 
 impl KvStore {
@@ -16,10 +20,20 @@ impl KvStore {
     pub fn new(storage: SharedStorage, cache_capacity: usize) -> Self {
         Self {
             storage,
-            cache: Arc::new(LruMemoryCache::new(cache_capacity)),
+            cache: Arc::new(MemoryCache::new(
+                cache_capacity,
+                CacheConfig::default(),
+                Vec::len,
+            )),
         }
     }
 
+    /// Apply eviction policy and byte budget settings (replaces the cache)
+    pub fn with_cache_config(mut self, config: CacheConfig) -> Self {
+        self.cache = Arc::new(MemoryCache::new(self.cache.capacity(), config, Vec::len));
+        self
+    }
+
     /// Get a value by key
     pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
         // Check cache first
@@ -58,6 +72,94 @@ impl KvStore {
         Ok(())
     }
 
+    /// Drop a cached value so the next read goes to storage
+    pub fn invalidate(&self, key: &[u8]) {
+        self.cache.invalidate(&key.to_vec());
+    }
+
+    /// Delete every key starting with `prefix`, returning how many were deleted
+    ///
+    /// The deletes land in one atomic batch. Transactional RocksDB has no range
+    /// delete, so each key is read once to build the batch.
+    pub fn delete_prefix(&self, prefix: &[u8]) -> Result<usize> {
+        let mut keys = Vec::new();
+        self.storage
+            .for_each_prefix(ColumnFamilies::DEFAULT, prefix, &mut |key, _| {
+                keys.push(key.to_vec());
+                Ok(ControlFlow::Continue(()))
+            })?;
+        let ops: Vec<BatchOp> = keys
+            .iter()
+            .map(|key| BatchOp::delete(ColumnFamilies::DEFAULT, key))
+            .collect();
+        self.storage.write_batch(&ops)?;
+
+        for key in &keys {
+            self.cache.invalidate(key);
+        }
+        Ok(keys.len())
+    }
+
+    /// Atomically add `delta` to the counter under `key`, returning the new total
+    ///
+    /// The value is a little-endian `i64`; a missing key counts as 0. The
+    /// read-modify-write runs in a transaction that locks the key, so
+    /// concurrent increments never lose updates. Fails with
+    /// `Error::InvalidInput` if the stored value isn't 8 bytes or the total
+    /// would overflow.
+    pub fn increment(&self, key: &[u8], delta: i64) -> Result<i64> {
+        let mut txn = self.storage.begin_transaction()?;
+        let current = match txn.get_for_update(ColumnFamilies::DEFAULT, key)? {
+            Some(bytes) => {
+                let bytes: [u8; 8] = bytes.as_slice().try_into().map_err(|_| {
+                    Error::InvalidInput(format!(
+                        "Value is not an 8-byte counter ({} bytes)",
+                        bytes.len()
+                    ))
+                })?;
+                i64::from_le_bytes(bytes)
+            }
+            None => 0,
+        };
+        let total = current
+            .checked_add(delta)
+            .ok_or_else(|| Error::InvalidInput("Counter overflow".to_string()))?;
+
+        txn.put(ColumnFamilies::DEFAULT, key, &total.to_le_bytes())?;
+        txn.commit()?;
+
+        // Concurrent increments may commit in a different order than they
+        // would update the cache, so drop the entry instead
+        self.cache.invalidate(&key.to_vec());
+
+        Ok(total)
+    }
+
+    /// Atomically replace the value under `key` if it currently equals `expected`
+    ///
+    /// `expected = None` means the key must not exist. Returns whether the
+    /// swap happened. The comparison reads the key with a lock held until
+    /// commit, so of two racing swaps from the same value only one succeeds.
+    pub fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool> {
+        let mut txn = self.storage.begin_transaction()?;
+        let current = txn.get_for_update(ColumnFamilies::DEFAULT, key)?;
+        if current.as_deref() != expected {
+            txn.rollback()?;
+            return Ok(false);
+        }
+
+        txn.put(ColumnFamilies::DEFAULT, key, new)?;
+        txn.commit()?;
+        self.cache.invalidate(&key.to_vec());
+
+        Ok(true)
+    }
+
     /// Check if a key exists
     pub fn exists(&self, key: &[u8]) -> Result<bool> {
         Ok(self.get(key)?.is_some())
@@ -67,4 +169,20 @@ impl KvStore {
     pub fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
         self.storage.scan_prefix(ColumnFamilies::DEFAULT, prefix)
     }
+
+    /// Scan keys in `[start, end)`, ascending or descending
+    pub fn scan_range(
+        &self,
+        start: &[u8],
+        end: &[u8],
+        descending: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.storage
+            .scan_range(ColumnFamilies::DEFAULT, start, end, descending)
+    }
+
+    /// Size and occupancy of the cache
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
 }
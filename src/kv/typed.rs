@@ -0,0 +1,142 @@
+// Typed key-value access over the raw KV store
+
+use super::KvStore;
+use crate::error::{Error, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::marker::PhantomData;
+
+/// Separator between a typed store's namespace and its keys
+const NAMESPACE_SEP: u8 = b':';
+
+/// Serde-typed view of one namespace of the KV store
+///
+/// Created by `OpenDB::typed_store`. Every key is stored as
+/// `{namespace}:{encoded key}` and every value as compact JSON, so typed
+/// entries can still be read through the raw byte API.
+///
+/// A key that serializes to a string (such as `String`, a unit enum variant
+/// or a `Uuid`) is encoded as its raw UTF-8 bytes, so string keys sort and
+/// prefix-scan like plain text. Any other key is encoded as compact JSON.
+/// Both encodings are deterministic, so the same key always maps to the same
+/// bytes.
+pub struct TypedStore<'a, K, V> {
+    kv: &'a KvStore,
+    prefix: Vec<u8>,
+    _types: PhantomData<fn() -> (K, V)>,
+}
+
+impl<'a, K, V> TypedStore<'a, K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// Create a typed view of `namespace`
+    pub(crate) fn new(kv: &'a KvStore, namespace: &str) -> Self {
+        let mut prefix = namespace.as_bytes().to_vec();
+        prefix.push(NAMESPACE_SEP);
+        Self {
+            kv,
+            prefix,
+            _types: PhantomData,
+        }
+    }
+
+    /// Get the value stored under `key`
+    pub fn get(&self, key: &K) -> Result<Option<V>> {
+        self.kv
+            .get(&self.storage_key(key)?)?
+            .map(|bytes| decode_value(&bytes))
+            .transpose()
+    }
+
+    /// Store `value` under `key`, replacing any previous value
+    pub fn put(&self, key: &K, value: &V) -> Result<()> {
+        let value = serde_json::to_vec(value)
+            .map_err(|e| Error::Codec(format!("Failed to serialize value: {}", e)))?;
+        self.kv.put(&self.storage_key(key)?, &value)
+    }
+
+    /// Delete the value stored under `key`
+    pub fn delete(&self, key: &K) -> Result<()> {
+        self.kv.delete(&self.storage_key(key)?)
+    }
+
+    /// Get every entry whose encoded key starts with `prefix`, in key order
+    ///
+    /// For string keys this is a plain text prefix; `""` returns the whole
+    /// namespace.
+    pub fn scan_prefix(&self, prefix: &str) -> Result<Vec<(K, V)>> {
+        let mut full_prefix = self.prefix.clone();
+        full_prefix.extend_from_slice(prefix.as_bytes());
+
+        self.kv
+            .scan_prefix(&full_prefix)?
+            .into_iter()
+            .map(|(key, value)| {
+                let key = decode_key(&key[self.prefix.len()..])?;
+                Ok((key, decode_value(&value)?))
+            })
+            .collect()
+    }
+
+    /// Helper: Raw KV key for a typed key
+    fn storage_key(&self, key: &K) -> Result<Vec<u8>> {
+        let mut bytes = self.prefix.clone();
+        bytes.extend_from_slice(&encode_key(key)?);
+        Ok(bytes)
+    }
+}
+
+/// Encode a key: raw UTF-8 if it serializes to a string, else compact JSON
+fn encode_key<K: Serialize>(key: &K) -> Result<Vec<u8>> {
+    let value = serde_json::to_value(key)
+        .map_err(|e| Error::Codec(format!("Failed to serialize key: {}", e)))?;
+    match value {
+        Value::String(text) => Ok(text.into_bytes()),
+        other => Ok(other.to_string().into_bytes()),
+    }
+}
+
+/// Decode a key written by `encode_key`
+///
+/// The key type decides the encoding: a type that deserializes from a
+/// string was written raw, anything else as JSON.
+fn decode_key<K: DeserializeOwned>(bytes: &[u8]) -> Result<K> {
+    if let Ok(text) = std::str::from_utf8(bytes)
+        && let Ok(key) = serde_json::from_value(Value::String(text.to_string()))
+    {
+        return Ok(key);
+    }
+    serde_json::from_slice(bytes).map_err(|e| Error::Codec(format!("Failed to decode key: {}", e)))
+}
+
+/// Deserialize a stored JSON value
+fn decode_value<V: DeserializeOwned>(bytes: &[u8]) -> Result<V> {
+    serde_json::from_slice(bytes)
+        .map_err(|e| Error::Codec(format!("Failed to deserialize value: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_encoding_round_trips() -> Result<()> {
+        assert_eq!(encode_key(&"user:1".to_string())?, b"user:1");
+        assert_eq!(encode_key(&42u64)?, b"42");
+        assert_eq!(encode_key(&("a", 7))?, br#"["a",7]"#);
+
+        assert_eq!(decode_key::<String>(b"user:1")?, "user:1");
+        // A string key that happens to look like JSON stays a string
+        assert_eq!(decode_key::<String>(b"42")?, "42");
+        assert_eq!(decode_key::<u64>(b"42")?, 42);
+        assert_eq!(
+            decode_key::<(String, u32)>(br#"["a",7]"#)?,
+            ("a".to_string(), 7)
+        );
+
+        Ok(())
+    }
+}
@@ -7,7 +7,7 @@
 // - **Structured Records**: Document/row storage with schema
 // - **Graph Database**: Relationships and traversals
 // - **Vector Database**: Semantic search with approximate nearest neighbors
-// - **In-Memory Cache**: LRU cache for hot data
+// - **In-Memory Cache**: LRU or LFU cache for hot data
 // - **ACID Transactions**: Full transactional guarantees
 //
 // Quick Start
@@ -49,11 +49,23 @@
 // - Contact: <contact@muhammadfiaz.com>
 
 // Re-export main types
-pub use database::{OpenDB, OpenDBOptions};
+pub use cache::{CacheConfig, CachePolicy};
+pub use database::{
+    BulkLoader, Coerce, CreateMode, OpenDB, OpenDBOptions, SearchKPolicy, StorageEngine,
+    TransactionScope,
+};
 pub use error::{Error, Result};
+pub use kv::typed::TypedStore;
+pub use storage::rocksdb_backend::{Compression, RocksDBTuning};
 pub use types::{
-    DocumentChunk, FileType, Memory, MemoryMetadata, MultimodalDocument, ProcessingStatus,
+    Bundle, CacheStats, ColumnFamilyStats, ConflictPolicy, DbStats, DedupOutcome, DeletedMemory,
+    DistanceMetric, DocumentChunk, FileType, GraphStats, IdPolicy, Manifest, Memory,
+    MemoryMetadata, MemoryPatch, MergeStats, MultimodalDocument, ProcessingStatus, ReadOpts,
 };
+pub use vector::hnsw_index::HnswParams;
+
+#[cfg(feature = "async")]
+pub use async_db::AsyncOpenDB;
 
 // Core modules
 pub mod database;
@@ -61,8 +73,12 @@ pub mod error;
 pub mod types;
 
 // Internal modules
+#[cfg(feature = "async")]
+pub(crate) mod async_db;
 pub(crate) mod cache;
 pub(crate) mod codec;
+pub(crate) mod documents;
+pub(crate) mod export;
 pub(crate) mod graph;
 pub(crate) mod kv;
 pub(crate) mod records;
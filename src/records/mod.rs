@@ -1,16 +1,108 @@
 // Records management for structured Memory data
 
-use crate::cache::lru_cache::LruMemoryCache;
+use crate::cache::{CacheConfig, MemoryCache};
 use crate::codec;
-use crate::error::Result;
-use crate::storage::{SharedStorage, column_families::ColumnFamilies};
-use crate::types::Memory;
+use crate::error::{Error, Result};
+use crate::storage::{BatchOp, ScanIter, SharedStorage, column_families::ColumnFamilies};
+use crate::types::{CacheStats, Memory};
+use chrono::Utc;
+use std::collections::BTreeSet;
+use std::ops::ControlFlow;
 use std::sync::Arc;
 
 /// Records manager for Memory CRUD operations
+#[derive(Clone)]
 pub struct RecordsManager {
     storage: SharedStorage,
-    cache: Arc<LruMemoryCache<String, Memory>>,
+    cache: Arc<MemoryCache<String, Memory>>,
+    compress: bool,
+    /// Metadata keys with entries in the metadata index
+    indexed_keys: Arc<Vec<String>>,
+    /// Maintain the importance index
+    index_importance: bool,
+    /// Maintain the time index
+    index_time: bool,
+    /// Largest accepted `content` in bytes
+    max_content_bytes: Option<usize>,
+    /// Most accepted metadata entries
+    max_metadata_entries: Option<usize>,
+}
+
+/// Records rewritten per batch while reindexing metadata
+const REINDEX_BATCH_SIZE: usize = 1024;
+
+/// Approximate heap plus inline size of a cached record
+fn memory_cost(memory: &Memory) -> usize {
+    let floats = |v: &Vec<f32>| v.len() * std::mem::size_of::<f32>();
+    std::mem::size_of::<Memory>()
+        + memory.id.len()
+        + memory.content.len()
+        + floats(&memory.embedding)
+        + memory
+            .embeddings
+            .iter()
+            .map(|(name, vector)| name.len() + floats(vector))
+            .sum::<usize>()
+        + memory
+            .metadata
+            .iter()
+            .map(|(key, value)| key.len() + value.len())
+            .sum::<usize>()
+}
+
+/// Metadata index key (`key\0value\0id`)
+fn metadata_index_key(key: &str, value: &str, id: &str) -> Vec<u8> {
+    [key.as_bytes(), &[0], value.as_bytes(), &[0], id.as_bytes()].concat()
+}
+
+/// Metadata key recording that the importance index covers every record
+const IMPORTANCE_INDEX_KEY: &[u8] = b"__opendb_importance_index__";
+
+/// Big-endian bytes of an `f32` whose byte order matches numeric order
+///
+/// Positive values get the sign bit set; negative values have every bit
+/// flipped, so larger magnitudes sort lower.
+fn sortable_importance(importance: f32) -> [u8; 4] {
+    let bits = importance.to_bits();
+    let ordered = if bits & 0x8000_0000 != 0 {
+        !bits
+    } else {
+        bits | 0x8000_0000
+    };
+    ordered.to_be_bytes()
+}
+
+/// Importance index key (sortable importance bytes, then id)
+fn importance_index_key(importance: f32, id: &str) -> Vec<u8> {
+    [&sortable_importance(importance)[..], id.as_bytes()].concat()
+}
+
+/// Metadata key recording that the time index covers every record
+const TIME_INDEX_KEY: &[u8] = b"__opendb_time_index__";
+
+/// Big-endian bytes of an `i64` whose byte order matches numeric order
+///
+/// Flipping the sign bit moves negative values below positive ones.
+fn sortable_timestamp(timestamp: i64) -> [u8; 8] {
+    ((timestamp as u64) ^ (1 << 63)).to_be_bytes()
+}
+
+/// Time index key (sortable timestamp bytes, then id)
+fn time_index_key(timestamp: i64, id: &str) -> Vec<u8> {
+    [&sortable_timestamp(timestamp)[..], id.as_bytes()].concat()
+}
+
+/// Batch writes moving a record's single entry in an index from `old` to `new`
+fn move_index_entry(ops: &mut Vec<BatchOp>, cf: &str, old: Option<Vec<u8>>, new: Option<Vec<u8>>) {
+    if old == new {
+        return;
+    }
+    if let Some(key) = old {
+        ops.push(BatchOp::delete(cf, &key));
+    }
+    if let Some(key) = new {
+        ops.push(BatchOp::put(cf, &key, Vec::new()));
+    }
 }
 
 impl RecordsManager {
@@ -18,17 +110,119 @@ impl RecordsManager {
     pub fn new(storage: SharedStorage, cache_capacity: usize) -> Self {
         Self {
             storage,
-            cache: Arc::new(LruMemoryCache::new(cache_capacity)),
+            cache: Arc::new(MemoryCache::new(
+                cache_capacity,
+                CacheConfig::default(),
+                memory_cost,
+            )),
+            compress: false,
+            indexed_keys: Arc::new(Vec::new()),
+            index_importance: false,
+            index_time: false,
+            max_content_bytes: None,
+            max_metadata_entries: None,
+        }
+    }
+
+    /// Reject records over these content size and metadata count limits
+    pub fn with_limits(
+        mut self,
+        max_content_bytes: Option<usize>,
+        max_metadata_entries: Option<usize>,
+    ) -> Self {
+        self.max_content_bytes = max_content_bytes;
+        self.max_metadata_entries = max_metadata_entries;
+        self
+    }
+
+    /// Check a record against the configured limits before it is written
+    ///
+    /// Importance must lie in `0.0..=1.0`: `Memory::new` clamps it, but
+    /// records built field by field bypass that.
+    pub fn validate(&self, memory: &Memory) -> Result<()> {
+        if !(0.0..=1.0).contains(&memory.importance) {
+            return Err(Error::InvalidInput(format!(
+                "Importance of memory {} must be between 0.0 and 1.0, got {}",
+                memory.id, memory.importance
+            )));
         }
+        if let Some(max) = self.max_content_bytes
+            && memory.content.len() > max
+        {
+            return Err(Error::InvalidInput(format!(
+                "Content of memory {} is {} bytes, over the limit of {} (max_content_bytes)",
+                memory.id,
+                memory.content.len(),
+                max
+            )));
+        }
+        if let Some(max) = self.max_metadata_entries
+            && memory.metadata.len() > max
+        {
+            return Err(Error::InvalidInput(format!(
+                "Memory {} has {} metadata entries, over the limit of {} (max_metadata_entries)",
+                memory.id,
+                memory.metadata.len(),
+                max
+            )));
+        }
+        Ok(())
+    }
+
+    /// Apply eviction policy and byte budget settings (replaces the cache)
+    pub fn with_cache_config(mut self, config: CacheConfig) -> Self {
+        self.cache = Arc::new(MemoryCache::new(self.cache.capacity(), config, memory_cost));
+        self
+    }
+
+    /// Maintain metadata index entries for these metadata keys
+    pub fn with_indexed_metadata_keys(mut self, keys: Vec<String>) -> Self {
+        self.indexed_keys = Arc::new(keys);
+        self
+    }
+
+    /// Maintain the importance index on writes
+    pub fn with_importance_index(mut self, enabled: bool) -> Self {
+        self.index_importance = enabled;
+        self
+    }
+
+    /// Maintain the time index on writes
+    pub fn with_time_index(mut self, enabled: bool) -> Self {
+        self.index_time = enabled;
+        self
+    }
+
+    /// Whether writes maintain the metadata, importance or time index
+    pub fn has_indexes(&self) -> bool {
+        !self.indexed_keys.is_empty() || self.index_importance || self.index_time
+    }
+
+    /// Compress large records with zstd before storing them
+    pub fn with_value_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Encode a record the way this manager stores it
+    pub fn encode(&self, memory: &Memory) -> Result<Vec<u8>> {
+        codec::encode_memory(memory, self.compress)
     }
 
     /// Insert or update a memory record
+    ///
+    /// Fails with `Error::InvalidInput` if the record breaks a limit (see
+    /// `validate`).
     pub fn put(&self, memory: &Memory) -> Result<()> {
-        let key = memory.id.as_bytes();
-        let value = codec::encode_memory(memory)?;
-
-        // Write to storage
-        self.storage.put(ColumnFamilies::RECORDS, key, &value)?;
+        self.validate(memory)?;
+        if self.has_indexes() {
+            // The record and its index entries change together
+            self.storage.write_batch(&self.put_ops(memory)?)?;
+        } else {
+            let key = memory.id.as_bytes();
+            let value = self.encode(memory)?;
+            self.storage.put(ColumnFamilies::RECORDS, key, &value)?;
+        }
 
         // Update cache
         self.cache.insert(memory.id.clone(), memory.clone());
@@ -36,8 +230,291 @@ impl RecordsManager {
         Ok(())
     }
 
+    /// Batch write that stores a memory record
+    ///
+    /// The record is validated like `put`. The cache is not touched;
+    /// invalidate the id once the batch is written.
+    pub fn put_op(&self, memory: &Memory) -> Result<BatchOp> {
+        self.validate(memory)?;
+        Ok(BatchOp::put(
+            ColumnFamilies::RECORDS,
+            memory.id.as_bytes(),
+            self.encode(memory)?,
+        ))
+    }
+
+    /// Batch writes that store a memory record and update its index entries
+    ///
+    /// Like `put_op`, the cache is not touched.
+    pub fn put_ops(&self, memory: &Memory) -> Result<Vec<BatchOp>> {
+        let mut ops = vec![self.put_op(memory)?];
+        if self.has_indexes() {
+            let previous = self.get_including_expired(&memory.id)?;
+            ops.extend(self.index_ops(&memory.id, previous.as_ref(), Some(memory)));
+        }
+        Ok(ops)
+    }
+
+    /// Index writes for the record under `id` going from `old` to `new`
+    ///
+    /// Covers the metadata, importance and time indexes. Only entries that
+    /// actually change are written.
+    pub fn index_ops(&self, id: &str, old: Option<&Memory>, new: Option<&Memory>) -> Vec<BatchOp> {
+        let mut ops = self.metadata_index_ops(id, old, new);
+        if self.index_importance {
+            let entry =
+                |memory: Option<&Memory>| memory.map(|m| importance_index_key(m.importance, id));
+            move_index_entry(
+                &mut ops,
+                ColumnFamilies::IMPORTANCE_INDEX,
+                entry(old),
+                entry(new),
+            );
+        }
+        if self.index_time {
+            let entry = |memory: Option<&Memory>| memory.map(|m| time_index_key(m.timestamp, id));
+            move_index_entry(&mut ops, ColumnFamilies::TIME_INDEX, entry(old), entry(new));
+        }
+        ops
+    }
+
+    /// Metadata index writes for the record under `id` going from `old` to `new`
+    ///
+    /// Keys and values containing a NUL byte are not indexed.
+    fn metadata_index_ops(
+        &self,
+        id: &str,
+        old: Option<&Memory>,
+        new: Option<&Memory>,
+    ) -> Vec<BatchOp> {
+        let entries = |memory: Option<&Memory>| -> BTreeSet<Vec<u8>> {
+            let Some(memory) = memory else {
+                return BTreeSet::new();
+            };
+            self.indexed_keys
+                .iter()
+                .filter_map(|key| Some((key, memory.metadata.get(key)?)))
+                .filter(|(key, value)| !key.contains('\0') && !value.contains('\0'))
+                .map(|(key, value)| metadata_index_key(key, value, id))
+                .collect()
+        };
+        let old = entries(old);
+        let new = entries(new);
+
+        let deletes = old
+            .difference(&new)
+            .map(|key| BatchOp::delete(ColumnFamilies::METADATA_INDEX, key));
+        let puts = new
+            .difference(&old)
+            .map(|key| BatchOp::put(ColumnFamilies::METADATA_INDEX, key, Vec::new()));
+        deletes.chain(puts).collect()
+    }
+
+    /// Ids of records whose metadata maps `key` to `value`, in id order
+    ///
+    /// Answered from the metadata index, so `key` must be an indexed key.
+    pub fn find_by_metadata(&self, key: &str, value: &str) -> Result<Vec<String>> {
+        if !self.indexed_keys.iter().any(|indexed| indexed == key) {
+            return Err(Error::InvalidInput(format!(
+                "Metadata key '{}' is not indexed",
+                key
+            )));
+        }
+        if value.contains('\0') {
+            return Err(Error::InvalidInput(
+                "Metadata values containing NUL are not indexed".to_string(),
+            ));
+        }
+
+        let prefix = [key.as_bytes(), &[0], value.as_bytes(), &[0]].concat();
+        let mut ids = Vec::new();
+        self.storage.for_each_prefix(
+            ColumnFamilies::METADATA_INDEX,
+            &prefix,
+            &mut |index_key, _| {
+                ids.push(String::from_utf8_lossy(&index_key[prefix.len()..]).into_owned());
+                Ok(ControlFlow::Continue(()))
+            },
+        )?;
+        Ok(ids)
+    }
+
+    /// Rebuild the metadata index from the stored records
+    ///
+    /// Drops every entry, then indexes each record under the current keys.
+    /// Returns the number of entries written.
+    pub fn reindex_metadata(&self) -> Result<usize> {
+        let mut stale = Vec::new();
+        self.storage
+            .for_each_prefix(ColumnFamilies::METADATA_INDEX, &[], &mut |key, _| {
+                stale.push(BatchOp::delete(ColumnFamilies::METADATA_INDEX, key));
+                Ok(ControlFlow::Continue(()))
+            })?;
+        for chunk in stale.chunks(REINDEX_BATCH_SIZE) {
+            self.storage.write_batch(chunk)?;
+        }
+
+        let mut written = 0;
+        let mut ops = Vec::new();
+        for pair in self
+            .storage
+            .scan_prefix_iter(ColumnFamilies::RECORDS, &[])?
+        {
+            let (key, value) = pair?;
+            let memory = codec::decode_memory(&value)?;
+            let id = String::from_utf8_lossy(&key);
+            ops.extend(self.metadata_index_ops(&id, None, Some(&memory)));
+            if ops.len() >= REINDEX_BATCH_SIZE {
+                written += ops.len();
+                self.storage.write_batch(&ops)?;
+                ops.clear();
+            }
+        }
+        written += ops.len();
+        self.storage.write_batch(&ops)?;
+        Ok(written)
+    }
+
+    /// Ids of records with importance in `[min, max]`, most important first
+    ///
+    /// Ties come back in reverse id order. Answered from the importance
+    /// index; returns an error if it isn't maintained.
+    pub fn ids_by_importance(&self, min: f32, max: f32) -> Result<Vec<String>> {
+        if !self.index_importance {
+            return Err(Error::InvalidInput(format!(
+                "Importance index is not available (column family '{}' is not open)",
+                ColumnFamilies::IMPORTANCE_INDEX
+            )));
+        }
+        if min.is_nan() || max.is_nan() || min > max {
+            return Ok(Vec::new());
+        }
+
+        // Ids are UTF-8, so no key under `max` continues with a 0xFF byte
+        let start = sortable_importance(min);
+        let end = [&sortable_importance(max)[..], &[0xFF]].concat();
+        Ok(self
+            .storage
+            .scan_range(ColumnFamilies::IMPORTANCE_INDEX, &start, &end, true)?
+            .into_iter()
+            .map(|(key, _)| String::from_utf8_lossy(&key[4..]).into_owned())
+            .collect())
+    }
+
+    /// Ids of records with a timestamp in `[start, end)`, oldest first
+    ///
+    /// `end: None` leaves the window open-ended. With `descending`, the
+    /// newest come first. Records sharing a timestamp come back in id order
+    /// (reversed when descending). Answered from the time index; returns an
+    /// error if it isn't maintained.
+    pub fn ids_by_time(
+        &self,
+        start: i64,
+        end: Option<i64>,
+        descending: bool,
+    ) -> Result<Vec<String>> {
+        if !self.index_time {
+            return Err(Error::InvalidInput(format!(
+                "Time index is not available (column family '{}' is not open)",
+                ColumnFamilies::TIME_INDEX
+            )));
+        }
+        if end.is_some_and(|end| start >= end) {
+            return Ok(Vec::new());
+        }
+
+        let start = sortable_timestamp(start);
+        // Ids are UTF-8 and never contain 0xFF, so every key sorts below
+        // nine 0xFF bytes, including those at `i64::MAX`
+        let end = end.map_or(vec![0xFF; 9], |end| sortable_timestamp(end).to_vec());
+        Ok(self
+            .storage
+            .scan_range(ColumnFamilies::TIME_INDEX, &start, &end, descending)?
+            .into_iter()
+            .map(|(key, _)| String::from_utf8_lossy(&key[8..]).into_owned())
+            .collect())
+    }
+
+    /// Build the importance index for records written before it existed
+    ///
+    /// Runs once per database; later calls return 0 straight away. Returns
+    /// the number of entries written.
+    pub fn upgrade_importance_index(&self) -> Result<usize> {
+        self.upgrade_index(
+            IMPORTANCE_INDEX_KEY,
+            ColumnFamilies::IMPORTANCE_INDEX,
+            |memory| importance_index_key(memory.importance, &memory.id),
+        )
+    }
+
+    /// Build the time index for records written before it existed
+    ///
+    /// Like `upgrade_importance_index`, runs once per database.
+    pub fn upgrade_time_index(&self) -> Result<usize> {
+        self.upgrade_index(TIME_INDEX_KEY, ColumnFamilies::TIME_INDEX, |memory| {
+            time_index_key(memory.timestamp, &memory.id)
+        })
+    }
+
+    /// Rebuild the single-entry index `cf` unless the `marker` metadata key
+    /// says it was already built, then set the marker
+    fn upgrade_index(
+        &self,
+        marker: &[u8],
+        cf: &str,
+        entry: fn(&Memory) -> Vec<u8>,
+    ) -> Result<usize> {
+        if self.storage.exists(ColumnFamilies::METADATA, marker)? {
+            return Ok(0);
+        }
+        let written = self.rebuild_index(cf, entry)?;
+        self.storage.put(ColumnFamilies::METADATA, marker, b"1")?;
+        Ok(written)
+    }
+
+    /// Rebuild an index holding one `entry` key per record in `cf`
+    ///
+    /// Returns the number of entries written.
+    fn rebuild_index(&self, cf: &str, entry: fn(&Memory) -> Vec<u8>) -> Result<usize> {
+        let mut stale = Vec::new();
+        self.storage.for_each_prefix(cf, &[], &mut |key, _| {
+            stale.push(BatchOp::delete(cf, key));
+            Ok(ControlFlow::Continue(()))
+        })?;
+        for chunk in stale.chunks(REINDEX_BATCH_SIZE) {
+            self.storage.write_batch(chunk)?;
+        }
+
+        let mut written = 0;
+        let mut ops = Vec::new();
+        for pair in self
+            .storage
+            .scan_prefix_iter(ColumnFamilies::RECORDS, &[])?
+        {
+            let (_, value) = pair?;
+            let memory = codec::decode_memory(&value)?;
+            ops.push(BatchOp::put(cf, &entry(&memory), Vec::new()));
+            if ops.len() >= REINDEX_BATCH_SIZE {
+                written += ops.len();
+                self.storage.write_batch(&ops)?;
+                ops.clear();
+            }
+        }
+        written += ops.len();
+        self.storage.write_batch(&ops)?;
+        Ok(written)
+    }
+
     /// Get a memory record by ID
+    ///
+    /// An expired record reads as absent and is deleted.
     pub fn get(&self, id: &str) -> Result<Option<Memory>> {
+        let memory = self.get_including_expired(id)?;
+        self.drop_expired(id, memory)
+    }
+
+    /// Get a memory record by ID, even if it has expired
+    pub fn get_including_expired(&self, id: &str) -> Result<Option<Memory>> {
         // Check cache first
         if let Some(memory) = self.cache.get_cloned(&id.to_string()) {
             return Ok(Some(memory));
@@ -54,12 +531,91 @@ impl RecordsManager {
         }
     }
 
+    /// Get a memory record straight from storage, refreshing the cache
+    ///
+    /// Like `get_including_expired`, expired records are returned as-is.
+    pub fn get_fresh(&self, id: &str) -> Result<Option<Memory>> {
+        match self.storage.get(ColumnFamilies::RECORDS, id.as_bytes())? {
+            Some(bytes) => {
+                let memory = codec::decode_memory(&bytes)?;
+                self.cache.insert(id.to_string(), memory.clone());
+                Ok(Some(memory))
+            }
+            None => {
+                self.cache.invalidate(&id.to_string());
+                Ok(None)
+            }
+        }
+    }
+
+    /// Get several memory records, consulting the cache before storage
+    ///
+    /// Cache misses are fetched with one batched storage lookup and cached.
+    /// Results follow `ids`, with `None` for missing ids. Like
+    /// `get_including_expired`, expired records are returned as-is.
+    pub fn get_many(&self, ids: &[&str]) -> Result<Vec<Option<Memory>>> {
+        let mut memories: Vec<Option<Memory>> = ids
+            .iter()
+            .map(|id| self.cache.get_cloned(&id.to_string()))
+            .collect();
+
+        let missing: Vec<usize> = (0..ids.len()).filter(|&i| memories[i].is_none()).collect();
+        if missing.is_empty() {
+            return Ok(memories);
+        }
+
+        let keys: Vec<&[u8]> = missing.iter().map(|&i| ids[i].as_bytes()).collect();
+        let values = self.storage.multi_get(ColumnFamilies::RECORDS, &keys)?;
+        for (i, value) in missing.into_iter().zip(values) {
+            if let Some(bytes) = value {
+                let memory = codec::decode_memory(&bytes)?;
+                self.cache.insert(ids[i].to_string(), memory.clone());
+                memories[i] = Some(memory);
+            }
+        }
+
+        Ok(memories)
+    }
+
+    /// Delete a record that turned out to be expired
+    fn drop_expired(&self, id: &str, memory: Option<Memory>) -> Result<Option<Memory>> {
+        match memory {
+            Some(memory) if memory.is_expired() => {
+                self.delete(id)?;
+                Ok(None)
+            }
+            memory => Ok(memory),
+        }
+    }
+
+    /// Get several memory records as of one point in time
+    ///
+    /// All reads go through a single storage snapshot (bypassing the cache),
+    /// so concurrent writes can't produce a torn view. Results follow `ids`.
+    pub fn get_many_consistent(&self, ids: &[&str]) -> Result<Vec<Option<Memory>>> {
+        let snapshot = self.storage.snapshot()?;
+        ids.iter()
+            .map(|id| {
+                snapshot
+                    .get(ColumnFamilies::RECORDS, id.as_bytes())?
+                    .map(|bytes| codec::decode_memory(&bytes))
+                    .transpose()
+                    .map(|memory| memory.filter(|memory| !memory.is_expired()))
+            })
+            .collect()
+    }
+
     /// Delete a memory record
     pub fn delete(&self, id: &str) -> Result<()> {
-        let key = id.as_bytes();
-
-        // Delete from storage
-        self.storage.delete(ColumnFamilies::RECORDS, key)?;
+        if self.has_indexes() {
+            let previous = self.get_including_expired(id)?;
+            let mut ops = vec![BatchOp::delete(ColumnFamilies::RECORDS, id.as_bytes())];
+            ops.extend(self.index_ops(id, previous.as_ref(), None));
+            self.storage.write_batch(&ops)?;
+        } else {
+            self.storage
+                .delete(ColumnFamilies::RECORDS, id.as_bytes())?;
+        }
 
         // Invalidate cache
         self.cache.invalidate(&id.to_string());
@@ -67,12 +623,68 @@ impl RecordsManager {
         Ok(())
     }
 
+    /// Delete every record whose id starts with `prefix`, returning their ids
+    ///
+    /// Records and their index entries are removed in one atomic batch.
+    /// Expired records are included.
+    pub fn delete_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        let mut ops = Vec::new();
+        self.storage.for_each_prefix(
+            ColumnFamilies::RECORDS,
+            prefix.as_bytes(),
+            &mut |key, value| {
+                ops.push(BatchOp::delete(ColumnFamilies::RECORDS, key));
+                let id = String::from_utf8_lossy(key).into_owned();
+                if self.has_indexes() {
+                    let memory = codec::decode_memory(value)?;
+                    ops.extend(self.index_ops(&id, Some(&memory), None));
+                }
+                ids.push(id);
+                Ok(ControlFlow::Continue(()))
+            },
+        )?;
+        self.storage.write_batch(&ops)?;
+
+        for id in &ids {
+            self.cache.invalidate(id);
+        }
+        Ok(ids)
+    }
+
+    /// Drop a cached record so the next read goes to storage
+    pub fn invalidate(&self, id: &str) {
+        self.cache.invalidate(&id.to_string());
+    }
+
+    /// Size and occupancy of the cache
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
     /// Check if a memory exists
     #[allow(dead_code)]
     pub fn exists(&self, id: &str) -> Result<bool> {
         Ok(self.get(id)?.is_some())
     }
 
+    /// Check if a memory exists without decoding it
+    ///
+    /// Unlike `exists`, a cache miss checks key presence in storage directly
+    /// and never loads the record into the cache.
+    pub fn exists_fast(&self, id: &str) -> Result<bool> {
+        if self.cache.contains(&id.to_string()) {
+            return Ok(true);
+        }
+        self.storage.exists(ColumnFamilies::RECORDS, id.as_bytes())
+    }
+
+    /// Check which of several memories exist, without decoding or caching them
+    pub fn exists_many(&self, ids: &[&str]) -> Result<Vec<bool>> {
+        let keys: Vec<&[u8]> = ids.iter().map(|id| id.as_bytes()).collect();
+        self.storage.exists_many(ColumnFamilies::RECORDS, &keys)
+    }
+
     /// List all memory IDs with a given prefix
     pub fn list_ids(&self, prefix: &str) -> Result<Vec<String>> {
         let prefix_bytes = prefix.as_bytes();
@@ -88,19 +700,285 @@ impl RecordsManager {
         Ok(ids)
     }
 
+    /// Count records with a given prefix, reading keys only
+    ///
+    /// Expired records that haven't been purged yet are included.
+    pub fn count(&self, prefix: &str) -> Result<usize> {
+        let mut count = 0;
+        self.storage
+            .for_each_key(ColumnFamilies::RECORDS, prefix.as_bytes(), &mut |_| {
+                count += 1;
+                Ok(ControlFlow::Continue(()))
+            })?;
+        Ok(count)
+    }
+
     /// List all memories with a given prefix
     pub fn list(&self, prefix: &str) -> Result<Vec<Memory>> {
-        let prefix_bytes = prefix.as_bytes();
+        self.iter(prefix)?.collect()
+    }
+
+    /// Iterate over memories with a given prefix, decoding them lazily
+    ///
+    /// Records are read and decoded one at a time in id order, skipping
+    /// expired ones, so nothing beyond what the caller consumes is loaded.
+    pub fn iter(&self, prefix: &str) -> Result<impl Iterator<Item = Result<Memory>> + '_> {
         let pairs = self
             .storage
-            .scan_prefix(ColumnFamilies::RECORDS, prefix_bytes)?;
+            .scan_prefix_iter(ColumnFamilies::RECORDS, prefix.as_bytes())?;
 
-        let mut memories = Vec::new();
-        for (_, value) in pairs {
-            let memory = codec::decode_memory(&value)?;
-            memories.push(memory);
+        Ok(live_memories(pairs))
+    }
+
+    /// Delete every expired record, returning the deleted ids
+    pub fn purge_expired(&self) -> Result<Vec<String>> {
+        let now = Utc::now().timestamp();
+        let mut expired = Vec::new();
+        self.storage
+            .for_each_prefix(ColumnFamilies::RECORDS, &[], &mut |_, value| {
+                let memory = codec::decode_memory(value)?;
+                if memory.is_expired_at(now) {
+                    expired.push(memory.id);
+                }
+                Ok(ControlFlow::Continue(()))
+            })?;
+
+        for id in &expired {
+            self.delete(id)?;
         }
+        Ok(expired)
+    }
 
-        Ok(memories)
+    /// Get one page of memories with a given prefix, in id order
+    ///
+    /// Skips `offset` live records first, which still reads them; prefer
+    /// `list_after` for deep pages.
+    pub fn list_paged(&self, prefix: &str, offset: usize, limit: usize) -> Result<Vec<Memory>> {
+        self.iter(prefix)?.skip(offset).take(limit).collect()
+    }
+
+    /// Get up to `limit` memories with a given prefix whose ids sort after
+    /// `start_after`, seeking directly to the cursor
+    pub fn list_after(
+        &self,
+        prefix: &str,
+        start_after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Memory>> {
+        // The smallest key greater than the cursor is the cursor plus a 0 byte
+        let from = match start_after {
+            Some(cursor) => [cursor.as_bytes(), &[0]].concat(),
+            None => Vec::new(),
+        };
+        let pairs = self.storage.scan_prefix_iter_from(
+            ColumnFamilies::RECORDS,
+            prefix.as_bytes(),
+            &from,
+        )?;
+
+        live_memories(pairs).take(limit).collect()
+    }
+
+    /// Scan all memories for content containing `needle`, up to `limit` matches
+    pub fn search_contains(
+        &self,
+        needle: &str,
+        limit: usize,
+        ignore_case: bool,
+    ) -> Result<Vec<Memory>> {
+        let needle = if ignore_case {
+            needle.to_lowercase()
+        } else {
+            needle.to_string()
+        };
+
+        let mut matches = Vec::new();
+        if limit == 0 {
+            return Ok(matches);
+        }
+
+        self.storage
+            .for_each_prefix(ColumnFamilies::RECORDS, &[], &mut |_, value| {
+                let memory = codec::decode_memory(value)?;
+                if memory.is_expired() {
+                    return Ok(ControlFlow::Continue(()));
+                }
+                let found = if ignore_case {
+                    memory.content.to_lowercase().contains(&needle)
+                } else {
+                    memory.content.contains(&needle)
+                };
+                if found {
+                    matches.push(memory);
+                    if matches.len() == limit {
+                        return Ok(ControlFlow::Break(()));
+                    }
+                }
+                Ok(ControlFlow::Continue(()))
+            })?;
+
+        Ok(matches)
+    }
+}
+
+/// Decode scanned records, skipping expired ones
+fn live_memories(pairs: ScanIter<'_>) -> impl Iterator<Item = Result<Memory>> + '_ {
+    pairs.filter_map(
+        |pair| match pair.and_then(|(_, value)| codec::decode_memory(&value)) {
+            Ok(memory) if memory.is_expired() => None,
+            result => Some(result),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::OpenDBOptions;
+    use crate::storage::memory_backend::InMemoryBackend;
+    use crate::storage::rocksdb_backend::RocksDBBackend;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_exists_fast_skips_cache() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend =
+            RocksDBBackend::open_with_options(temp_dir.path(), &OpenDBOptions::default())?;
+        let records = RecordsManager::new(Arc::new(backend), 10);
+
+        records.put(&Memory::new("m1", "content", vec![], 0.5))?;
+        records.invalidate("m1");
+
+        assert!(records.exists_fast("m1")?);
+        assert!(!records.exists_fast("missing")?);
+        assert!(!records.cache.contains(&"m1".to_string()));
+
+        // A full read still populates the cache as before
+        assert!(records.get("m1")?.is_some());
+        assert!(records.cache.contains(&"m1".to_string()));
+        assert!(records.exists_fast("m1")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_many_mixes_cache_and_storage() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend =
+            RocksDBBackend::open_with_options(temp_dir.path(), &OpenDBOptions::default())?;
+        let records = RecordsManager::new(Arc::new(backend), 10);
+
+        for id in ["a", "b", "c"] {
+            records.put(&Memory::new(id, id, vec![], 0.5))?;
+        }
+        records.invalidate("b");
+
+        let found = records.get_many(&["c", "missing", "b", "a", "b"])?;
+        let ids: Vec<Option<&str>> = found
+            .iter()
+            .map(|memory| memory.as_ref().map(|m| m.id.as_str()))
+            .collect();
+        assert_eq!(ids, vec![Some("c"), None, Some("b"), Some("a"), Some("b")]);
+
+        // Records fetched from storage are cached for later reads
+        assert!(records.cache.contains(&"b".to_string()));
+        assert!(!records.cache.contains(&"missing".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sortable_importance_order() {
+        let values = [
+            f32::NEG_INFINITY,
+            -1.0,
+            -0.5,
+            -f32::MIN_POSITIVE,
+            0.0,
+            f32::MIN_POSITIVE,
+            1e-7,
+            0.1,
+            0.5,
+            0.8,
+            0.9999,
+            1.0,
+            2.0,
+            f32::INFINITY,
+        ];
+        for pair in values.windows(2) {
+            assert!(
+                sortable_importance(pair[0]) < sortable_importance(pair[1]),
+                "{} should sort before {}",
+                pair[0],
+                pair[1]
+            );
+        }
+
+        // Every step of 0.001 across 0.0..=1.0 keeps its order
+        let keys: Vec<[u8; 4]> = (0..=1000)
+            .map(|i| sortable_importance(i as f32 / 1000.0))
+            .collect();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_upgrade_importance_index() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend: SharedStorage = Arc::new(RocksDBBackend::open_with_options(
+            temp_dir.path(),
+            &OpenDBOptions::default(),
+        )?);
+
+        // Records written without the index
+        let plain = RecordsManager::new(Arc::clone(&backend), 10);
+        for (id, importance) in [("low", 0.1), ("high", 0.9), ("mid", 0.5)] {
+            plain.put(&Memory::new(id, id, vec![], importance))?;
+        }
+
+        let records = RecordsManager::new(backend, 10).with_importance_index(true);
+        assert!(records.ids_by_importance(0.0, 1.0)?.is_empty());
+        assert_eq!(records.upgrade_importance_index()?, 3);
+        assert_eq!(
+            records.ids_by_importance(0.0, 1.0)?,
+            vec!["high", "mid", "low"]
+        );
+
+        // Runs only once
+        assert_eq!(records.upgrade_importance_index()?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sortable_timestamp_order() {
+        let values = [i64::MIN, -86_400, -1, 0, 1, 1_700_000_000, i64::MAX];
+        for pair in values.windows(2) {
+            assert!(sortable_timestamp(pair[0]) < sortable_timestamp(pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_upgrade_time_index() -> Result<()> {
+        let backend: SharedStorage = Arc::new(InMemoryBackend::new());
+
+        // Records written without the index
+        let plain = RecordsManager::new(Arc::clone(&backend), 10);
+        for (id, timestamp) in [("b", 200), ("a", 100), ("c", 300)] {
+            let mut memory = Memory::new(id, id, vec![], 0.5);
+            memory.timestamp = timestamp;
+            plain.put(&memory)?;
+        }
+
+        let records = RecordsManager::new(backend, 10).with_time_index(true);
+        assert!(records.ids_by_time(i64::MIN, None, false)?.is_empty());
+        assert_eq!(records.upgrade_time_index()?, 3);
+        assert_eq!(
+            records.ids_by_time(i64::MIN, None, false)?,
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(records.ids_by_time(100, Some(300), true)?, vec!["b", "a"]);
+
+        // Runs only once
+        assert_eq!(records.upgrade_time_index()?, 0);
+        Ok(())
     }
 }
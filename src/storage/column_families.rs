@@ -12,10 +12,10 @@ impl ColumnFamilies {
     /// Memory records
     pub const RECORDS: &'static str = "records";
 
-    /// Forward graph index (from_id -> [(relation, to_id)])
+    /// Forward graph index (from_id\0relation\0to_id\0edge_id -> edge)
     pub const GRAPH_FORWARD: &'static str = "graph_forward";
 
-    /// Backward graph index (to_id -> [(relation, from_id)])
+    /// Backward graph index (to_id\0relation\0from_id\0edge_id -> edge)
     pub const GRAPH_BACKWARD: &'static str = "graph_backward";
 
     /// Vector index metadata
@@ -27,6 +27,21 @@ impl ColumnFamilies {
     /// Database metadata
     pub const METADATA: &'static str = "metadata";
 
+    /// Multimodal documents (id -> document with chunks)
+    pub const DOCUMENTS: &'static str = "documents";
+
+    /// Memory metadata index (key\0value\0memory_id -> empty)
+    pub const METADATA_INDEX: &'static str = "metadata_index";
+
+    /// Memory importance index (sortable importance bytes + memory_id -> empty)
+    pub const IMPORTANCE_INDEX: &'static str = "records_by_importance";
+
+    /// Memory time index (sortable timestamp bytes + memory_id -> empty)
+    pub const TIME_INDEX: &'static str = "records_by_time";
+
+    /// Recycle bin of soft-deleted memories (id -> deleted memory with edges)
+    pub const DELETED_RECORDS: &'static str = "deleted_records";
+
     /// Get all column family names
     pub fn all() -> Vec<&'static str> {
         vec![
@@ -37,6 +52,11 @@ impl ColumnFamilies {
             Self::VECTOR_INDEX,
             Self::VECTOR_DATA,
             Self::METADATA,
+            Self::DOCUMENTS,
+            Self::METADATA_INDEX,
+            Self::IMPORTANCE_INDEX,
+            Self::DELETED_RECORDS,
+            Self::TIME_INDEX,
         ]
     }
 }
@@ -0,0 +1,233 @@
+// Crash simulation backend for durability tests
+//
+// Wraps another backend and holds every non-transactional write in memory
+// until `flush`, so tests can simulate power loss by discarding them.
+
+use crate::error::Result;
+use crate::storage::{
+    BatchOp, ScanVisitor, SharedStorage, Snapshot, StorageBackend, StorageFootprint, Transaction,
+};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Writes buffered since the last flush: (cf, key) -> value (None = delete)
+type PendingWrites = BTreeMap<(String, Vec<u8>), Option<Vec<u8>>>;
+
+/// Backend wrapper that loses un-flushed writes on simulated power loss
+///
+/// Reads see buffered writes, so the database behaves normally until
+/// `simulate_power_loss` is called. Transactions go straight to the inner
+/// backend and count as durable once committed.
+pub struct CrashSimBackend {
+    inner: SharedStorage,
+    pending: Mutex<PendingWrites>,
+}
+
+impl CrashSimBackend {
+    /// Wrap a backend
+    pub fn new(inner: SharedStorage) -> Self {
+        Self {
+            inner,
+            pending: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Drop every write that hasn't been flushed yet
+    pub fn simulate_power_loss(&self) {
+        self.pending.lock().clear();
+    }
+}
+
+impl StorageBackend for CrashSimBackend {
+    fn get(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if let Some(value) = self.pending.lock().get(&(cf.to_string(), key.to_vec())) {
+            return Ok(value.clone());
+        }
+        self.inner.get(cf, key)
+    }
+
+    fn put(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.pending
+            .lock()
+            .insert((cf.to_string(), key.to_vec()), Some(value.to_vec()));
+        Ok(())
+    }
+
+    fn delete(&self, cf: &str, key: &[u8]) -> Result<()> {
+        self.pending
+            .lock()
+            .insert((cf.to_string(), key.to_vec()), None);
+        Ok(())
+    }
+
+    fn write_batch(&self, ops: &[BatchOp]) -> Result<()> {
+        // Like single writes, a batch is lost on power loss until flushed
+        let mut pending = self.pending.lock();
+        for op in ops {
+            match op {
+                BatchOp::Put { cf, key, value } => {
+                    pending.insert((cf.clone(), key.clone()), Some(value.clone()))
+                }
+                BatchOp::Delete { cf, key } => pending.insert((cf.clone(), key.clone()), None),
+            };
+        }
+        Ok(())
+    }
+
+    fn scan_prefix(&self, cf: &str, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut merged: BTreeMap<Vec<u8>, Vec<u8>> =
+            self.inner.scan_prefix(cf, prefix)?.into_iter().collect();
+
+        for ((pending_cf, key), value) in self.pending.lock().iter() {
+            if pending_cf != cf || !key.starts_with(prefix) {
+                continue;
+            }
+            match value {
+                Some(value) => merged.insert(key.clone(), value.clone()),
+                None => merged.remove(key),
+            };
+        }
+
+        Ok(merged.into_iter().collect())
+    }
+
+    fn scan_range(
+        &self,
+        cf: &str,
+        start: &[u8],
+        end: &[u8],
+        descending: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut merged: BTreeMap<Vec<u8>, Vec<u8>> = self
+            .inner
+            .scan_range(cf, start, end, false)?
+            .into_iter()
+            .collect();
+
+        for ((pending_cf, key), value) in self.pending.lock().iter() {
+            if pending_cf != cf || key.as_slice() < start || key.as_slice() >= end {
+                continue;
+            }
+            match value {
+                Some(value) => merged.insert(key.clone(), value.clone()),
+                None => merged.remove(key),
+            };
+        }
+
+        Ok(if descending {
+            merged.into_iter().rev().collect()
+        } else {
+            merged.into_iter().collect()
+        })
+    }
+
+    fn for_each_prefix(
+        &self,
+        cf: &str,
+        prefix: &[u8],
+        visitor: &mut ScanVisitor<'_>,
+    ) -> Result<()> {
+        for (key, value) in self.scan_prefix(cf, prefix)? {
+            if visitor(&key, &value)?.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self) -> &Path {
+        self.inner.path()
+    }
+
+    fn list_column_families(&self) -> Result<Vec<String>> {
+        self.inner.list_column_families()
+    }
+
+    fn begin_transaction(&self) -> Result<Box<dyn Transaction>> {
+        self.inner.begin_transaction()
+    }
+
+    fn flush(&self) -> Result<()> {
+        let pending = std::mem::take(&mut *self.pending.lock());
+        for ((cf, key), value) in pending {
+            match value {
+                Some(value) => self.inner.put(&cf, &key, &value)?,
+                None => self.inner.delete(&cf, &key)?,
+            }
+        }
+        self.inner.flush()
+    }
+
+    fn footprint(&self) -> Result<StorageFootprint> {
+        self.inner.footprint()
+    }
+
+    fn snapshot(&self) -> Result<Box<dyn Snapshot>> {
+        self.inner.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{OpenDB, OpenDBOptions};
+    use crate::storage::column_families::ColumnFamilies;
+    use crate::storage::rocksdb_backend::RocksDBBackend;
+    use crate::types::Memory;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_power_loss_keeps_only_flushed_writes() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let options = OpenDBOptions::with_dimension(3);
+
+        {
+            let backend = RocksDBBackend::open_with_options(temp_dir.path(), &options)?;
+            let sim = Arc::new(CrashSimBackend::new(Arc::new(backend)));
+            let db = OpenDB::from_storage(sim.clone(), options.clone());
+
+            db.insert_memory(&Memory::new("flushed", "kept", vec![1.0; 3], 0.5))?;
+            db.flush()?;
+            db.insert_memory(&Memory::new("unflushed", "lost", vec![2.0; 3], 0.5))?;
+            assert!(db.get_memory("unflushed")?.is_some());
+
+            sim.simulate_power_loss();
+        }
+
+        let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+        assert_eq!(db.get_memory("flushed")?.unwrap().content, "kept");
+        assert!(db.get_memory("unflushed")?.is_none());
+        assert_eq!(db.search_similar(&[2.0; 3], 10)?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_buffered_writes_visible_before_flush() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend =
+            RocksDBBackend::open_with_options(temp_dir.path(), &OpenDBOptions::default())?;
+        let sim = CrashSimBackend::new(Arc::new(backend));
+
+        sim.put(ColumnFamilies::DEFAULT, b"k1", b"v1")?;
+        sim.flush()?;
+        sim.put(ColumnFamilies::DEFAULT, b"k2", b"v2")?;
+        sim.delete(ColumnFamilies::DEFAULT, b"k1")?;
+
+        assert_eq!(
+            sim.scan_prefix(ColumnFamilies::DEFAULT, b"k")?,
+            vec![(b"k2".to_vec(), b"v2".to_vec())]
+        );
+
+        sim.simulate_power_loss();
+        assert_eq!(
+            sim.get(ColumnFamilies::DEFAULT, b"k1")?,
+            Some(b"v1".to_vec())
+        );
+        assert_eq!(sim.get(ColumnFamilies::DEFAULT, b"k2")?, None);
+
+        Ok(())
+    }
+}
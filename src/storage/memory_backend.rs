@@ -0,0 +1,530 @@
+// In-memory storage backend implementation
+//
+// Keeps every column family in a `BTreeMap` behind a lock, so databases need
+// no filesystem access and disappear when dropped. Useful for tests and
+// throwaway databases; nothing is ever persisted.
+
+use crate::database::OpenDBOptions;
+use crate::error::{Error, Result};
+use crate::storage::{
+    BatchOp, ScanIter, ScanVisitor, Snapshot as SnapshotTrait, StorageBackend, StorageFootprint,
+    Transaction as TransactionTrait, column_families::ColumnFamilies,
+};
+use parking_lot::{Condvar, Mutex, RwLock};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Column family name -> sorted key/value pairs
+type ColumnFamilyMap = BTreeMap<String, BTreeMap<Vec<u8>, Vec<u8>>>;
+
+/// Writes buffered by a transaction: (cf, key) -> value (None = delete)
+type PendingWrites = BTreeMap<(String, Vec<u8>), Option<Vec<u8>>>;
+
+/// How long `get_for_update` waits for a key lock, matching RocksDB's default
+const LOCK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Keys locked by `get_for_update`, held until the owning transaction ends
+#[derive(Default)]
+struct KeyLocks {
+    held: Mutex<HashSet<(String, Vec<u8>)>>,
+    released: Condvar,
+}
+
+/// In-memory storage backend
+pub struct InMemoryBackend {
+    data: Arc<RwLock<ColumnFamilyMap>>,
+    locks: Arc<KeyLocks>,
+}
+
+impl InMemoryBackend {
+    /// Create an empty backend with every reserved column family
+    pub fn new() -> Self {
+        Self::with_column_families(ColumnFamilies::all())
+    }
+
+    /// Create an empty backend with the column families the options ask for
+    ///
+    /// Mirrors the RocksDB backend: `open_column_families` limits the reserved
+    /// column families (`default` and `metadata` are always present) and
+    /// `extra_column_families` are added on top.
+    pub fn with_options(options: &OpenDBOptions) -> Self {
+        let mut names: Vec<&str> = match &options.open_column_families {
+            Some(subset) => [ColumnFamilies::DEFAULT, ColumnFamilies::METADATA]
+                .into_iter()
+                .chain(subset.iter().map(String::as_str))
+                .collect(),
+            None => ColumnFamilies::all(),
+        };
+        names.extend(options.extra_column_families.iter().map(String::as_str));
+        Self::with_column_families(names)
+    }
+
+    fn with_column_families<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        let data = names
+            .into_iter()
+            .map(|name| (name.to_string(), BTreeMap::new()))
+            .collect();
+        Self {
+            data: Arc::new(RwLock::new(data)),
+            locks: Arc::new(KeyLocks::default()),
+        }
+    }
+}
+
+impl Default for InMemoryBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Look up a column family, failing like RocksDB does for unknown names
+fn column_family<'a>(
+    data: &'a ColumnFamilyMap,
+    cf: &str,
+) -> Result<&'a BTreeMap<Vec<u8>, Vec<u8>>> {
+    data.get(cf)
+        .ok_or_else(|| Error::Storage(format!("Column family not found: {}", cf)))
+}
+
+/// Mutable variant of `column_family`
+fn column_family_mut<'a>(
+    data: &'a mut ColumnFamilyMap,
+    cf: &str,
+) -> Result<&'a mut BTreeMap<Vec<u8>, Vec<u8>>> {
+    data.get_mut(cf)
+        .ok_or_else(|| Error::Storage(format!("Column family not found: {}", cf)))
+}
+
+/// Entries of a sorted map whose keys start with `prefix`, in key order
+fn prefix_range<'a>(
+    map: &'a BTreeMap<Vec<u8>, Vec<u8>>,
+    prefix: &'a [u8],
+) -> impl Iterator<Item = (&'a Vec<u8>, &'a Vec<u8>)> + 'a {
+    map.range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+        .take_while(move |(key, _)| key.starts_with(prefix))
+}
+
+impl StorageBackend for InMemoryBackend {
+    fn get(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let data = self.data.read();
+        Ok(column_family(&data, cf)?.get(key).cloned())
+    }
+
+    fn multi_get(&self, cf: &str, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
+        // One lock for the whole batch, so it reads a single point in time
+        let data = self.data.read();
+        let map = column_family(&data, cf)?;
+        Ok(keys.iter().map(|key| map.get(*key).cloned()).collect())
+    }
+
+    fn put(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut data = self.data.write();
+        column_family_mut(&mut data, cf)?.insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, cf: &str, key: &[u8]) -> Result<()> {
+        let mut data = self.data.write();
+        column_family_mut(&mut data, cf)?.remove(key);
+        Ok(())
+    }
+
+    fn write_batch(&self, ops: &[BatchOp]) -> Result<()> {
+        let mut data = self.data.write();
+
+        // Check every column family first so a bad name writes nothing
+        for op in ops {
+            let (BatchOp::Put { cf, .. } | BatchOp::Delete { cf, .. }) = op;
+            column_family(&data, cf)?;
+        }
+        for op in ops {
+            match op {
+                BatchOp::Put { cf, key, value } => {
+                    column_family_mut(&mut data, cf)?.insert(key.clone(), value.clone());
+                }
+                BatchOp::Delete { cf, key } => {
+                    column_family_mut(&mut data, cf)?.remove(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn scan_prefix(&self, cf: &str, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let data = self.data.read();
+        Ok(prefix_range(column_family(&data, cf)?, prefix)
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+
+    fn scan_prefix_iter(&self, cf: &str, prefix: &[u8]) -> Result<ScanIter<'_>> {
+        self.scan_prefix_iter_from(cf, prefix, prefix)
+    }
+
+    fn scan_prefix_iter_from(&self, cf: &str, prefix: &[u8], from: &[u8]) -> Result<ScanIter<'_>> {
+        column_family(&self.data.read(), cf)?;
+        Ok(Box::new(PrefixIter {
+            data: &self.data,
+            cf: cf.to_string(),
+            prefix: prefix.to_vec(),
+            from: from.max(prefix).to_vec(),
+            last: None,
+        }))
+    }
+
+    fn scan_range(
+        &self,
+        cf: &str,
+        start: &[u8],
+        end: &[u8],
+        descending: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let data = self.data.read();
+        let map = column_family(&data, cf)?;
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let range = map
+            .range::<[u8], _>((Bound::Included(start), Bound::Excluded(end)))
+            .map(|(key, value)| (key.clone(), value.clone()));
+        Ok(if descending {
+            range.rev().collect()
+        } else {
+            range.collect()
+        })
+    }
+
+    fn for_each_prefix(
+        &self,
+        cf: &str,
+        prefix: &[u8],
+        visitor: &mut ScanVisitor<'_>,
+    ) -> Result<()> {
+        // Visit a copy so the visitor may write to the backend
+        for (key, value) in self.scan_prefix(cf, prefix)? {
+            if visitor(&key, &value)?.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self) -> &Path {
+        Path::new("")
+    }
+
+    fn list_column_families(&self) -> Result<Vec<String>> {
+        Ok(self.data.read().keys().cloned().collect())
+    }
+
+    fn begin_transaction(&self) -> Result<Box<dyn TransactionTrait>> {
+        Ok(Box::new(InMemoryTransaction {
+            data: Arc::clone(&self.data),
+            pending: Some(BTreeMap::new()),
+            locks: Arc::clone(&self.locks),
+            locked: Vec::new(),
+        }))
+    }
+
+    fn flush(&self) -> Result<()> {
+        // Nothing to make durable
+        Ok(())
+    }
+
+    fn footprint(&self) -> Result<StorageFootprint> {
+        // Everything lives in memory; count key and value bytes as memtable
+        let memtable_bytes = self
+            .data
+            .read()
+            .values()
+            .flat_map(|cf| cf.iter())
+            .map(|(key, value)| (key.len() + value.len()) as u64)
+            .sum();
+        Ok(StorageFootprint {
+            memtable_bytes: Some(memtable_bytes),
+            ..StorageFootprint::default()
+        })
+    }
+
+    fn snapshot(&self) -> Result<Box<dyn SnapshotTrait>> {
+        // A full copy: cheap enough for the small databases this backend targets
+        Ok(Box::new(InMemorySnapshot {
+            data: self.data.read().clone(),
+        }))
+    }
+}
+
+/// Prefix scan that re-seeks past the last returned key on each step
+///
+/// No lock is held between items, so callers may write while iterating;
+/// each item reflects the data at the moment it is read.
+struct PrefixIter<'a> {
+    data: &'a RwLock<ColumnFamilyMap>,
+    cf: String,
+    prefix: Vec<u8>,
+    from: Vec<u8>,
+    last: Option<Vec<u8>>,
+}
+
+impl Iterator for PrefixIter<'_> {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.data.read();
+        let map = match column_family(&data, &self.cf) {
+            Ok(map) => map,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let lower = match &self.last {
+            Some(last) => Bound::Excluded(last.as_slice()),
+            None => Bound::Included(self.from.as_slice()),
+        };
+        let (key, value) = map
+            .range::<[u8], _>((lower, Bound::Unbounded))
+            .next()
+            .filter(|(key, _)| key.starts_with(&self.prefix))?;
+
+        self.last = Some(key.clone());
+        Some(Ok((key.clone(), value.clone())))
+    }
+}
+
+/// Transaction that buffers writes and applies them atomically on commit
+///
+/// Reads see the transaction's own writes and otherwise the latest committed
+/// data: unlike the RocksDB backend there is no snapshot. Apart from keys
+/// locked with `get_for_update` there is no conflict detection: the last
+/// transaction to commit wins.
+struct InMemoryTransaction {
+    data: Arc<RwLock<ColumnFamilyMap>>,
+    pending: Option<PendingWrites>,
+    locks: Arc<KeyLocks>,
+    locked: Vec<(String, Vec<u8>)>,
+}
+
+impl InMemoryTransaction {
+    fn pending_mut(&mut self) -> Result<&mut PendingWrites> {
+        self.pending
+            .as_mut()
+            .ok_or_else(|| Error::Storage("Transaction already completed".to_string()))
+    }
+
+    fn buffer(&mut self, cf: &str, key: &[u8], value: Option<Vec<u8>>) -> Result<()> {
+        column_family(&self.data.read(), cf)?;
+        self.pending_mut()?
+            .insert((cf.to_string(), key.to_vec()), value);
+        Ok(())
+    }
+
+    fn apply(&mut self) -> Result<()> {
+        let pending = self
+            .pending
+            .take()
+            .ok_or_else(|| Error::Storage("Transaction already completed".to_string()))?;
+
+        // Column families are never dropped, so every buffered write applies
+        let mut data = self.data.write();
+        for ((cf, key), value) in pending {
+            let map = column_family_mut(&mut data, &cf)?;
+            match value {
+                Some(value) => map.insert(key, value),
+                None => map.remove(&key),
+            };
+        }
+        drop(data);
+
+        self.release_locks();
+        Ok(())
+    }
+
+    /// Wait for and take the lock on a key, unless this transaction holds it
+    fn lock(&mut self, cf: &str, key: &[u8]) -> Result<()> {
+        let entry = (cf.to_string(), key.to_vec());
+        if self.locked.contains(&entry) {
+            return Ok(());
+        }
+
+        let mut held = self.locks.held.lock();
+        while held.contains(&entry) {
+            if self
+                .locks
+                .released
+                .wait_for(&mut held, LOCK_TIMEOUT)
+                .timed_out()
+                && held.contains(&entry)
+            {
+                return Err(Error::Transaction(
+                    "Timed out waiting for a key lock".to_string(),
+                ));
+            }
+        }
+        held.insert(entry.clone());
+        self.locked.push(entry);
+        Ok(())
+    }
+
+    fn release_locks(&mut self) {
+        if self.locked.is_empty() {
+            return;
+        }
+        let mut held = self.locks.held.lock();
+        for entry in self.locked.drain(..) {
+            held.remove(&entry);
+        }
+        self.locks.released.notify_all();
+    }
+}
+
+impl Drop for InMemoryTransaction {
+    fn drop(&mut self) {
+        self.release_locks();
+    }
+}
+
+impl TransactionTrait for InMemoryTransaction {
+    fn get(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let pending = self
+            .pending
+            .as_ref()
+            .ok_or_else(|| Error::Storage("Transaction already completed".to_string()))?;
+        if let Some(value) = pending.get(&(cf.to_string(), key.to_vec())) {
+            return Ok(value.clone());
+        }
+
+        let data = self.data.read();
+        Ok(column_family(&data, cf)?.get(key).cloned())
+    }
+
+    fn get_for_update(&mut self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.pending_mut()?;
+        column_family(&self.data.read(), cf)?;
+        self.lock(cf, key)?;
+        self.get(cf, key)
+    }
+
+    fn put(&mut self, cf: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        self.buffer(cf, key, Some(value.to_vec()))
+    }
+
+    fn delete(&mut self, cf: &str, key: &[u8]) -> Result<()> {
+        self.buffer(cf, key, None)
+    }
+
+    fn commit(mut self: Box<Self>) -> Result<()> {
+        self.apply()
+    }
+
+    fn commit_synced(mut self: Box<Self>) -> Result<()> {
+        self.apply()
+    }
+
+    fn rollback(mut self: Box<Self>) -> Result<()> {
+        self.pending
+            .take()
+            .ok_or_else(|| Error::Storage("Transaction already completed".to_string()))?;
+        self.release_locks();
+        Ok(())
+    }
+}
+
+/// Point-in-time copy of every column family
+struct InMemorySnapshot {
+    data: ColumnFamilyMap,
+}
+
+impl SnapshotTrait for InMemorySnapshot {
+    fn get(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(column_family(&self.data, cf)?.get(key).cloned())
+    }
+
+    fn for_each_prefix(
+        &self,
+        cf: &str,
+        prefix: &[u8],
+        visitor: &mut ScanVisitor<'_>,
+    ) -> Result<()> {
+        for (key, value) in prefix_range(column_family(&self.data, cf)?, prefix) {
+            if visitor(key, value)?.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_prefix_stays_within_prefix() -> Result<()> {
+        let backend = InMemoryBackend::new();
+        for key in [&b"a"[..], b"ab", b"abc", b"ac", b"b"] {
+            backend.put(ColumnFamilies::DEFAULT, key, key)?;
+        }
+
+        let keys: Vec<Vec<u8>> = backend
+            .scan_prefix(ColumnFamilies::DEFAULT, b"ab")?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, vec![b"ab".to_vec(), b"abc".to_vec()]);
+        assert_eq!(backend.scan_prefix(ColumnFamilies::DEFAULT, b"")?.len(), 5);
+
+        let keys: Vec<Vec<u8>> = backend
+            .scan_range(ColumnFamilies::DEFAULT, b"aa", b"b", true)?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, vec![b"ac".to_vec(), b"abc".to_vec(), b"ab".to_vec()]);
+        assert!(
+            backend
+                .scan_range(ColumnFamilies::DEFAULT, b"b", b"a", false)?
+                .is_empty()
+        );
+        assert!(backend.get("missing_cf", b"a").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transaction_applies_on_commit_only() -> Result<()> {
+        let backend = InMemoryBackend::new();
+        backend.put(ColumnFamilies::DEFAULT, b"gone", b"1")?;
+
+        let mut txn = backend.begin_transaction()?;
+        txn.put(ColumnFamilies::DEFAULT, b"new", b"2")?;
+        txn.delete(ColumnFamilies::DEFAULT, b"gone")?;
+        assert_eq!(
+            txn.get(ColumnFamilies::DEFAULT, b"new")?,
+            Some(b"2".to_vec())
+        );
+        assert_eq!(txn.get(ColumnFamilies::DEFAULT, b"gone")?, None);
+
+        // Nothing is visible outside until commit
+        assert_eq!(backend.get(ColumnFamilies::DEFAULT, b"new")?, None);
+        let snapshot = backend.snapshot()?;
+        txn.commit()?;
+        assert_eq!(
+            backend.get(ColumnFamilies::DEFAULT, b"new")?,
+            Some(b"2".to_vec())
+        );
+        assert_eq!(backend.get(ColumnFamilies::DEFAULT, b"gone")?, None);
+        assert_eq!(
+            snapshot.get(ColumnFamilies::DEFAULT, b"gone")?,
+            Some(b"1".to_vec())
+        );
+
+        let mut txn = backend.begin_transaction()?;
+        txn.put(ColumnFamilies::DEFAULT, b"rolled_back", b"3")?;
+        txn.rollback()?;
+        assert_eq!(backend.get(ColumnFamilies::DEFAULT, b"rolled_back")?, None);
+
+        Ok(())
+    }
+}
@@ -3,11 +3,74 @@
 // This module defines the storage traits that allow pluggable backends.
 
 pub mod column_families;
+#[cfg(test)]
+pub mod crash_sim;
+pub mod memory_backend;
+pub mod read_only_backend;
 pub mod rocksdb_backend;
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+use std::ops::ControlFlow;
+use std::path::Path;
 use std::sync::Arc;
 
+/// Callback for streaming scans: receives (key, value), returns whether to continue
+pub type ScanVisitor<'a> = dyn FnMut(&[u8], &[u8]) -> Result<ControlFlow<()>> + 'a;
+
+/// Callback for key-only scans: receives a key, returns whether to continue
+pub type KeyVisitor<'a> = dyn FnMut(&[u8]) -> Result<ControlFlow<()>> + 'a;
+
+/// Lazily evaluated scan over (key, value) pairs
+pub type ScanIter<'a> = Box<dyn Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>;
+
+/// Disk and memory used by a storage backend (see `StorageBackend::footprint`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StorageFootprint {
+    /// Bytes in SST files on disk
+    pub sst_bytes: u64,
+
+    /// Bytes in write-ahead log files on disk
+    pub wal_bytes: u64,
+
+    /// Bytes held by memtables (for in-memory backends, all stored data)
+    ///
+    /// None when the backend can't report it.
+    pub memtable_bytes: Option<u64>,
+}
+
+/// One write in an atomic batch (see `StorageBackend::write_batch`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOp {
+    /// Put a key-value pair into a column family
+    Put {
+        cf: String,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+
+    /// Delete a key from a column family
+    Delete { cf: String, key: Vec<u8> },
+}
+
+impl BatchOp {
+    /// Create a put operation
+    pub fn put(cf: &str, key: &[u8], value: Vec<u8>) -> Self {
+        Self::Put {
+            cf: cf.to_string(),
+            key: key.to_vec(),
+            value,
+        }
+    }
+
+    /// Create a delete operation
+    pub fn delete(cf: &str, key: &[u8]) -> Self {
+        Self::Delete {
+            cf: cf.to_string(),
+            key: key.to_vec(),
+        }
+    }
+}
+
 /// Storage backend trait
 ///
 /// This trait abstracts the underlying storage engine, allowing
@@ -16,6 +79,14 @@ pub trait StorageBackend: Send + Sync {
     /// Get a value by key from a column family
     fn get(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
 
+    /// Get several values from a column family in one lookup
+    ///
+    /// Results follow `keys`, with `None` for missing keys. The default reads
+    /// keys one by one; backends should batch them.
+    fn multi_get(&self, cf: &str, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
+        keys.iter().map(|key| self.get(cf, key)).collect()
+    }
+
     /// Put a key-value pair into a column family
     fn put(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<()>;
 
@@ -28,17 +99,120 @@ pub trait StorageBackend: Send + Sync {
         Ok(self.get(cf, key)?.is_some())
     }
 
+    /// Check several keys in a column family at once, in order
+    fn exists_many(&self, cf: &str, keys: &[&[u8]]) -> Result<Vec<bool>> {
+        keys.iter().map(|key| self.exists(cf, key)).collect()
+    }
+
+    /// Apply several writes atomically, in order
+    ///
+    /// Either every operation is applied or none is. Backends with a native
+    /// write batch should override the transaction-based default.
+    fn write_batch(&self, ops: &[BatchOp]) -> Result<()> {
+        let mut txn = self.begin_transaction()?;
+        for op in ops {
+            match op {
+                BatchOp::Put { cf, key, value } => txn.put(cf, key, value)?,
+                BatchOp::Delete { cf, key } => txn.delete(cf, key)?,
+            }
+        }
+        txn.commit()
+    }
+
+    /// Apply several writes atomically, skipping the write-ahead log
+    ///
+    /// Faster than `write_batch`, but the writes only survive a crash once
+    /// the backend persists its in-memory state (for RocksDB, a memtable
+    /// flush). Backends without a write-ahead log use `write_batch`.
+    fn write_batch_unlogged(&self, ops: &[BatchOp]) -> Result<()> {
+        self.write_batch(ops)
+    }
+
+    /// Whether every write to this backend fails
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
     /// Iterate over keys in a column family with a prefix
     fn scan_prefix(&self, cf: &str, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
 
+    /// Iterate over keys in a column family with a prefix, reading on demand
+    ///
+    /// Pairs are fetched as the iterator advances, so `take(n)` or an early
+    /// `break` never touches the rest of the range. The default collects
+    /// `scan_prefix` up front; backends should override it.
+    fn scan_prefix_iter(&self, cf: &str, prefix: &[u8]) -> Result<ScanIter<'_>> {
+        Ok(Box::new(self.scan_prefix(cf, prefix)?.into_iter().map(Ok)))
+    }
+
+    /// Like `scan_prefix_iter`, but starting at the first key `>= from`
+    ///
+    /// Backends should seek straight to `from`; the default skips up to it.
+    fn scan_prefix_iter_from(&self, cf: &str, prefix: &[u8], from: &[u8]) -> Result<ScanIter<'_>> {
+        let from = from.to_vec();
+        Ok(Box::new(self.scan_prefix_iter(cf, prefix)?.skip_while(
+            move |pair| matches!(pair, Ok((key, _)) if *key < from),
+        )))
+    }
+
+    /// Collect the keys in `[start, end)` of a column family, in key order
+    ///
+    /// Keys compare as raw bytes (lexicographically). With `descending` they
+    /// come back from the largest down. `start >= end` yields nothing.
+    fn scan_range(
+        &self,
+        cf: &str,
+        start: &[u8],
+        end: &[u8],
+        descending: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+
+    /// Stream keys in a column family with a prefix without collecting them
+    ///
+    /// Stops early when the visitor returns `ControlFlow::Break`.
+    fn for_each_prefix(&self, cf: &str, prefix: &[u8], visitor: &mut ScanVisitor<'_>)
+    -> Result<()>;
+
+    /// Stream the keys in a column family with a prefix, without their values
+    ///
+    /// The default reads values through `for_each_prefix` and drops them;
+    /// backends should override it to skip copying values.
+    fn for_each_key(&self, cf: &str, prefix: &[u8], visitor: &mut KeyVisitor<'_>) -> Result<()> {
+        self.for_each_prefix(cf, prefix, &mut |key, _| visitor(key))
+    }
+
+    /// Directory the database lives in
+    fn path(&self) -> &Path;
+
+    /// List the column families that physically exist in the database
+    fn list_column_families(&self) -> Result<Vec<String>>;
+
     /// Begin a transaction
     fn begin_transaction(&self) -> Result<Box<dyn Transaction>>;
 
+    /// Write a consistent full copy of the database to `dest` without blocking writes
+    fn checkpoint(&self, _dest: &Path) -> Result<()> {
+        Err(Error::Storage(
+            "Checkpoints are not supported by this storage backend".to_string(),
+        ))
+    }
+
+    /// Add a numbered full copy of the database to `backup_dir`
+    fn create_backup(&self, _backup_dir: &Path) -> Result<()> {
+        Err(Error::Storage(
+            "Backups are not supported by this storage backend".to_string(),
+        ))
+    }
+
     /// Flush writes to disk
     fn flush(&self) -> Result<()>;
 
+    /// Report the disk and memory used by stored data
+    fn footprint(&self) -> Result<StorageFootprint> {
+        Ok(StorageFootprint::default())
+    }
+
     /// Create a snapshot for consistent reads
-    #[allow(dead_code)]
     fn snapshot(&self) -> Result<Box<dyn Snapshot>>;
 }
 
@@ -47,6 +221,13 @@ pub trait Transaction: Send {
     /// Get a value within this transaction
     fn get(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
 
+    /// Get a value and lock the key until this transaction ends
+    ///
+    /// Other transactions calling `get_for_update` on the same key wait for
+    /// the lock (or fail after a timeout), so read-modify-write sequences
+    /// can't lose updates.
+    fn get_for_update(&mut self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
     /// Put a key-value pair within this transaction
     fn put(&mut self, cf: &str, key: &[u8], value: &[u8]) -> Result<()>;
 
@@ -56,15 +237,60 @@ pub trait Transaction: Send {
     /// Commit the transaction
     fn commit(self: Box<Self>) -> Result<()>;
 
+    /// Commit the transaction and fsync the write-ahead log before returning
+    fn commit_synced(self: Box<Self>) -> Result<()>;
+
     /// Rollback the transaction
     fn rollback(self: Box<Self>) -> Result<()>;
 }
 
 /// Snapshot trait for consistent point-in-time reads
-#[allow(dead_code)]
 pub trait Snapshot: Send + Sync {
     /// Get a value from this snapshot
     fn get(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Stream keys with a prefix as of this snapshot, in key order
+    ///
+    /// Stops early when the visitor returns `ControlFlow::Break`.
+    fn for_each_prefix(&self, cf: &str, prefix: &[u8], visitor: &mut ScanVisitor<'_>)
+    -> Result<()>;
+}
+
+/// Read options bounding a RocksDB iterator to keys starting with `prefix`
+///
+/// Without an upper bound, an iterator that runs out of live keys under the
+/// prefix keeps skipping the deletion tombstones after it, which makes every
+/// prefix scan over a freshly deleted range as slow as the range is long.
+pub(crate) fn prefix_read_options(prefix: &[u8]) -> rocksdb::ReadOptions {
+    let mut read_opts = rocksdb::ReadOptions::default();
+    // The smallest key above the prefix range: bump the last byte below 0xFF
+    if let Some(last) = prefix.iter().rposition(|&byte| byte != 0xFF) {
+        let mut end = prefix[..=last].to_vec();
+        end[last] += 1;
+        read_opts.set_iterate_upper_bound(end);
+    }
+    read_opts
+}
+
+/// Sum the sizes of the SST and WAL files directly inside a RocksDB directory
+///
+/// Used by the RocksDB backends for `StorageBackend::footprint`. RocksDB's
+/// size properties are per column family, and the transactional database
+/// only exposes those of the default one, so file sizes are read from the
+/// filesystem instead. The WAL has no size property at all.
+pub(crate) fn rocksdb_file_sizes(dir: &Path) -> Result<(u64, u64)> {
+    let mut sst_bytes = 0;
+    let mut wal_bytes = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("sst") => sst_bytes += entry.metadata()?.len(),
+            Some("log") => wal_bytes += entry.metadata()?.len(),
+            _ => {}
+        }
+    }
+    Ok((sst_bytes, wal_bytes))
 }
 
 /// Type alias for a thread-safe storage backend
@@ -0,0 +1,260 @@
+// Read-only RocksDB storage backend
+//
+// Opens an existing database without taking its exclusive LOCK, so several
+// reader processes can share a database that one writer owns. Readers see
+// the data as of the moment they opened; every write fails.
+
+use crate::database::OpenDBOptions;
+use crate::error::{Error, Result};
+use crate::storage::{
+    BatchOp, KeyVisitor, ScanIter, ScanVisitor, Snapshot as SnapshotTrait, StorageBackend,
+    StorageFootprint, Transaction as TransactionTrait, column_families::ColumnFamilies,
+    rocksdb_file_sizes,
+};
+use rocksdb::perf::MemoryUsageBuilder;
+use rocksdb::{DB, Options};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The error every write to a read-only database returns
+fn read_only_error() -> Error {
+    Error::InvalidInput("database opened read-only".to_string())
+}
+
+/// Read-only RocksDB storage backend
+pub struct ReadOnlyRocksDBBackend {
+    db: Arc<DB>,
+}
+
+impl ReadOnlyRocksDBBackend {
+    /// Open an existing RocksDB database for reading
+    ///
+    /// Every column family on disk is opened, or with `open_column_families`
+    /// set, only that subset plus `default` and `metadata`. Nothing in the
+    /// directory is created or modified.
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: &OpenDBOptions) -> Result<Self> {
+        let opts = Options::default();
+        let path = path.as_ref();
+
+        let mut cf_names = DB::list_cf(&opts, path)
+            .map_err(|e| Error::Storage(format!("Failed to open database: {}", e)))?;
+        if let Some(subset) = &options.open_column_families {
+            cf_names.retain(|name| {
+                name == ColumnFamilies::DEFAULT
+                    || name == ColumnFamilies::METADATA
+                    || subset.contains(name)
+            });
+        }
+
+        let db = DB::open_cf_for_read_only(&opts, path, &cf_names, false)
+            .map_err(|e| Error::Storage(format!("Failed to open database: {}", e)))?;
+
+        Ok(Self { db: Arc::new(db) })
+    }
+
+    /// Get a column family handle
+    fn cf_handle(&self, cf: &str) -> Result<&rocksdb::ColumnFamily> {
+        self.db
+            .cf_handle(cf)
+            .ok_or_else(|| Error::Storage(format!("Column family not found: {}", cf)))
+    }
+}
+
+impl StorageBackend for ReadOnlyRocksDBBackend {
+    fn get(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf_handle = self.cf_handle(cf)?;
+        Ok(self.db.get_cf(cf_handle, key)?)
+    }
+
+    fn multi_get(&self, cf: &str, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
+        let cf_handle = self.cf_handle(cf)?;
+        self.db
+            .multi_get_cf(keys.iter().map(|key| (cf_handle, *key)))
+            .into_iter()
+            .map(|value| Ok(value?))
+            .collect()
+    }
+
+    fn exists(&self, cf: &str, key: &[u8]) -> Result<bool> {
+        let cf_handle = self.cf_handle(cf)?;
+        Ok(self.db.get_pinned_cf(cf_handle, key)?.is_some())
+    }
+
+    fn put(&self, _cf: &str, _key: &[u8], _value: &[u8]) -> Result<()> {
+        Err(read_only_error())
+    }
+
+    fn delete(&self, _cf: &str, _key: &[u8]) -> Result<()> {
+        Err(read_only_error())
+    }
+
+    fn write_batch(&self, _ops: &[BatchOp]) -> Result<()> {
+        Err(read_only_error())
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn scan_prefix(&self, cf: &str, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.scan_prefix_iter(cf, prefix)?.collect()
+    }
+
+    fn scan_prefix_iter(&self, cf: &str, prefix: &[u8]) -> Result<ScanIter<'_>> {
+        self.scan_prefix_iter_from(cf, prefix, prefix)
+    }
+
+    fn scan_prefix_iter_from(&self, cf: &str, prefix: &[u8], from: &[u8]) -> Result<ScanIter<'_>> {
+        let cf_handle = self.cf_handle(cf)?;
+        let seek = from.max(prefix);
+        let iter = self.db.iterator_cf(
+            cf_handle,
+            rocksdb::IteratorMode::From(seek, rocksdb::Direction::Forward),
+        );
+
+        let prefix = prefix.to_vec();
+        Ok(Box::new(iter.map_while(move |item| {
+            match item {
+                Ok((key, value)) => key
+                    .starts_with(&prefix)
+                    .then(|| Ok((key.to_vec(), value.to_vec()))),
+                Err(e) => Some(Err(e.into())),
+            }
+        })))
+    }
+
+    fn scan_range(
+        &self,
+        cf: &str,
+        start: &[u8],
+        end: &[u8],
+        descending: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let cf_handle = self.cf_handle(cf)?;
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let mut read_opts = rocksdb::ReadOptions::default();
+        read_opts.set_iterate_lower_bound(start.to_vec());
+        read_opts.set_iterate_upper_bound(end.to_vec());
+        let mode = if descending {
+            rocksdb::IteratorMode::End
+        } else {
+            rocksdb::IteratorMode::Start
+        };
+
+        let mut results = Vec::new();
+        for item in self.db.iterator_cf_opt(cf_handle, read_opts, mode) {
+            let (key, value) = item?;
+            results.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(results)
+    }
+
+    fn for_each_prefix(
+        &self,
+        cf: &str,
+        prefix: &[u8],
+        visitor: &mut ScanVisitor<'_>,
+    ) -> Result<()> {
+        for item in self.scan_prefix_iter(cf, prefix)? {
+            let (key, value) = item?;
+            if visitor(&key, &value)?.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn for_each_key(&self, cf: &str, prefix: &[u8], visitor: &mut KeyVisitor<'_>) -> Result<()> {
+        let cf_handle = self.cf_handle(cf)?;
+
+        let mut iter = self.db.raw_iterator_cf(cf_handle);
+        iter.seek(prefix);
+        while let Some(key) = iter.key() {
+            if !key.starts_with(prefix) || visitor(key)?.is_break() {
+                break;
+            }
+            iter.next();
+        }
+        iter.status()?;
+        Ok(())
+    }
+
+    fn path(&self) -> &Path {
+        self.db.path()
+    }
+
+    fn list_column_families(&self) -> Result<Vec<String>> {
+        Ok(DB::list_cf(&Options::default(), self.db.path())?)
+    }
+
+    fn begin_transaction(&self) -> Result<Box<dyn TransactionTrait>> {
+        Err(read_only_error())
+    }
+
+    fn flush(&self) -> Result<()> {
+        // Nothing is ever written
+        Ok(())
+    }
+
+    fn footprint(&self) -> Result<StorageFootprint> {
+        let (sst_bytes, wal_bytes) = rocksdb_file_sizes(self.db.path())?;
+        // Summed over all column families, like the writable backend
+        let mut usage = MemoryUsageBuilder::new()?;
+        usage.add_db(&*self.db);
+        let memtable_bytes = usage.build()?.approximate_mem_table_unflushed();
+        Ok(StorageFootprint {
+            sst_bytes,
+            wal_bytes,
+            memtable_bytes: Some(memtable_bytes),
+        })
+    }
+
+    fn snapshot(&self) -> Result<Box<dyn SnapshotTrait>> {
+        // A read-only handle never changes, so it is its own snapshot
+        Ok(Box::new(ReadOnlySnapshot {
+            db: Arc::clone(&self.db),
+        }))
+    }
+}
+
+/// Snapshot of a read-only database
+struct ReadOnlySnapshot {
+    db: Arc<DB>,
+}
+
+impl SnapshotTrait for ReadOnlySnapshot {
+    fn get(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf_handle = self
+            .db
+            .cf_handle(cf)
+            .ok_or_else(|| Error::Storage(format!("Column family not found: {}", cf)))?;
+        Ok(self.db.get_cf(cf_handle, key)?)
+    }
+
+    fn for_each_prefix(
+        &self,
+        cf: &str,
+        prefix: &[u8],
+        visitor: &mut ScanVisitor<'_>,
+    ) -> Result<()> {
+        let cf_handle = self
+            .db
+            .cf_handle(cf)
+            .ok_or_else(|| Error::Storage(format!("Column family not found: {}", cf)))?;
+
+        let mode = rocksdb::IteratorMode::From(prefix, rocksdb::Direction::Forward);
+        for item in self.db.iterator_cf(cf_handle, mode) {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            if visitor(&key, &value)?.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -12,20 +12,138 @@
 // **Tradeoff**: RocksDB is C++ with Rust bindings (not pure Rust),
 // but the performance and maturity justify this choice.
 
+use crate::database::{CreateMode, OpenDBOptions};
 use crate::error::{Error, Result};
 use crate::storage::{
-    Snapshot as SnapshotTrait, StorageBackend, Transaction as TransactionTrait,
-    column_families::ColumnFamilies,
+    BatchOp, KeyVisitor, ScanIter, ScanVisitor, Snapshot as SnapshotTrait, StorageBackend,
+    StorageFootprint, Transaction as TransactionTrait, column_families::ColumnFamilies,
+    prefix_read_options, rocksdb_file_sizes,
 };
 use chrono::Utc;
+use rocksdb::perf::MemoryUsageBuilder;
 use rocksdb::{Options, TransactionDB, TransactionDBOptions, TransactionOptions};
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
 
+/// Fsync the WAL, making every write appended so far durable
+///
+/// The C API's `rocksdb_transactiondb_flush_wal` needs the raw database
+/// handle, which the `rocksdb` crate keeps private. A synced write of an
+/// empty batch is still appended to the WAL and fsyncs all of it, without
+/// storing anything.
+fn sync_wal(db: &TransactionDB) -> Result<()> {
+    let mut write_opts = rocksdb::WriteOptions::default();
+    write_opts.set_sync(true);
+    db.write_opt(rocksdb::WriteBatchWithTransaction::default(), &write_opts)?;
+    Ok(())
+}
+
+/// Pairs copied per write batch when checkpointing
+const CHECKPOINT_BATCH_SIZE: usize = 1024;
+
+/// Highest numbered backup in `backup_dir`, if any
+fn latest_backup(backup_dir: &Path) -> Result<Option<u64>> {
+    if !backup_dir.exists() {
+        return Ok(None);
+    }
+    let mut latest = None;
+    for entry in fs::read_dir(backup_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir()
+            && let Some(id) = entry
+                .file_name()
+                .to_str()
+                .and_then(|n| n.parse::<u64>().ok())
+        {
+            latest = latest.max(Some(id));
+        }
+    }
+    Ok(latest)
+}
+
+/// Block compression for RocksDB data files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// Store blocks uncompressed
+    None,
+
+    /// Snappy: fast, moderate ratio
+    Snappy,
+
+    /// LZ4: fast, moderate ratio (default)
+    #[default]
+    Lz4,
+
+    /// Zstandard: slower, best ratio
+    Zstd,
+}
+
+impl From<Compression> for rocksdb::DBCompressionType {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::None => rocksdb::DBCompressionType::None,
+            Compression::Snappy => rocksdb::DBCompressionType::Snappy,
+            Compression::Lz4 => rocksdb::DBCompressionType::Lz4,
+            Compression::Zstd => rocksdb::DBCompressionType::Zstd,
+        }
+    }
+}
+
+/// RocksDB performance settings applied when a database is opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RocksDBTuning {
+    /// Size of one memtable in bytes
+    pub write_buffer_size: usize,
+
+    /// Memtables kept in memory before writes stall
+    pub max_write_buffer_number: i32,
+
+    /// Concurrent background flush and compaction jobs
+    pub max_background_jobs: i32,
+
+    /// Compression for data files
+    pub compression: Compression,
+}
+
+impl Default for RocksDBTuning {
+    fn default() -> Self {
+        Self {
+            write_buffer_size: 128 * 1024 * 1024, // 128MB
+            max_write_buffer_number: 3,
+            max_background_jobs: 4,
+            compression: Compression::Lz4,
+        }
+    }
+}
+
+impl RocksDBTuning {
+    /// Create settings for small embedded deployments
+    pub fn low_memory() -> Self {
+        Self {
+            write_buffer_size: 16 * 1024 * 1024, // 16MB
+            max_write_buffer_number: 2,
+            max_background_jobs: 2,
+            compression: Compression::Zstd,
+        }
+    }
+
+    /// Create settings for write-heavy servers
+    pub fn high_throughput() -> Self {
+        Self {
+            write_buffer_size: 256 * 1024 * 1024, // 256MB
+            max_write_buffer_number: 4,
+            max_background_jobs: 8,
+            compression: Compression::Lz4,
+        }
+    }
+}
+
 /// RocksDB storage backend
 pub struct RocksDBBackend {
     db: Arc<TransactionDB>,
+    /// fsync the WAL on every write (`OpenDBOptions::sync_writes`)
+    sync_writes: bool,
 }
 
 impl RocksDBBackend {
@@ -38,33 +156,165 @@ impl RocksDBBackend {
     /// # Returns
     ///
     /// A new RocksDB backend instance
+    #[allow(dead_code)]
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        opts.create_missing_column_families(true);
+        Self::open_with_options(path, &OpenDBOptions::default())
+    }
 
-        // Performance tuning
-        opts.set_write_buffer_size(128 * 1024 * 1024); // 128MB
-        opts.set_max_write_buffer_number(3);
-        opts.set_target_file_size_base(64 * 1024 * 1024); // 64MB
-        opts.set_level_zero_file_num_compaction_trigger(4);
-        opts.set_max_background_jobs(4);
-        opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
+    /// Open a RocksDB database using the given OpenDB options
+    ///
+    /// The options' `create_mode` decides whether a missing database is
+    /// created, or whether opening fails on a missing/existing directory.
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: &OpenDBOptions) -> Result<Self> {
+        let mut opts = Self::tuned_options(options);
+        match options.create_mode {
+            CreateMode::CreateIfMissing => opts.create_if_missing(true),
+            CreateMode::MustExist => opts.create_if_missing(false),
+            CreateMode::MustNotExist => {
+                opts.create_if_missing(true);
+                opts.set_error_if_exists(true);
+            }
+        }
+        opts.create_missing_column_families(true);
 
         let txn_db_opts = TransactionDBOptions::default();
 
-        // Open with all column families
-        let cf_names = ColumnFamilies::all();
+        let cf_names = Self::column_families_to_open(&opts, path.as_ref(), options)?;
+
         let db = TransactionDB::open_cf(&opts, &txn_db_opts, &path, &cf_names)
             .map_err(|e| Error::Storage(format!("Failed to open database: {}", e)))?;
 
         // Create OpenDB metadata file to identify this as an OpenDB database
-        let backend = Self { db: Arc::new(db) };
+        let backend = Self {
+            db: Arc::new(db),
+            sync_writes: options.sync_writes,
+        };
         backend.create_opendb_metadata(&path)?;
 
         Ok(backend)
     }
 
+    /// RocksDB options carrying the performance tuning of `options`
+    fn tuned_options(options: &OpenDBOptions) -> Options {
+        let mut opts = Options::default();
+        let tuning = &options.rocksdb_tuning;
+        opts.set_write_buffer_size(tuning.write_buffer_size);
+        opts.set_max_write_buffer_number(tuning.max_write_buffer_number);
+        opts.set_target_file_size_base(64 * 1024 * 1024); // 64MB
+        opts.set_level_zero_file_num_compaction_trigger(4);
+        opts.set_max_background_jobs(tuning.max_background_jobs);
+        opts.set_compression_type(tuning.compression.into());
+
+        // Cap background flush/compaction I/O if requested
+        if let Some(rate) = options.compaction_rate_bytes_per_sec {
+            opts.set_ratelimiter(rate, 100_000, 10);
+        }
+        opts
+    }
+
+    /// Compact a closed database at `path`
+    ///
+    /// Every column family is compacted, or only `cf` when given, over the
+    /// keys from `start` to `end` (`None` bounds extend to the first or last
+    /// key). The bindings only expose manual compaction for plain databases,
+    /// so the directory is opened as one (which takes its lock), compacted
+    /// and closed. Returns the bytes of SST and WAL files reclaimed.
+    pub fn compact_offline(
+        path: &Path,
+        options: &OpenDBOptions,
+        cf: Option<&str>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> Result<u64> {
+        let mut opts = Self::tuned_options(options);
+        opts.create_if_missing(false);
+        let cf_names = rocksdb::DB::list_cf(&opts, path)
+            .map_err(|e| Error::Storage(format!("Failed to open database: {}", e)))?;
+        if let Some(cf) = cf
+            && !cf_names.iter().any(|name| name == cf)
+        {
+            return Err(Error::InvalidInput(format!(
+                "Column family not found: {}",
+                cf
+            )));
+        }
+
+        let (sst_before, wal_before) = rocksdb_file_sizes(path)?;
+        {
+            let db = rocksdb::DB::open_cf(&opts, path, &cf_names)
+                .map_err(|e| Error::Storage(format!("Failed to open database: {}", e)))?;
+            for name in &cf_names {
+                if cf.is_some_and(|cf| name != cf) {
+                    continue;
+                }
+                if let Some(cf_handle) = db.cf_handle(name) {
+                    db.compact_range_cf(cf_handle, start, end);
+                }
+            }
+        }
+        let (sst_after, wal_after) = rocksdb_file_sizes(path)?;
+
+        Ok((sst_before + wal_before).saturating_sub(sst_after + wal_after))
+    }
+
+    /// Decide which column families to open
+    ///
+    /// By default these are the reserved column families, any requested
+    /// extras, and whatever already exists on disk (RocksDB refuses to open a
+    /// database without all of its column families). With
+    /// `open_column_families` set, only that subset plus `default` and
+    /// `metadata` is opened, and omitting an existing column family is an error.
+    fn column_families_to_open(
+        opts: &Options,
+        path: &Path,
+        options: &OpenDBOptions,
+    ) -> Result<Vec<String>> {
+        let existing = <TransactionDB>::list_cf(opts, path).unwrap_or_default();
+
+        let mut cf_names: Vec<String> = match &options.open_column_families {
+            Some(subset) => {
+                let mut names = vec![
+                    ColumnFamilies::DEFAULT.to_string(),
+                    ColumnFamilies::METADATA.to_string(),
+                ];
+                names.extend(subset.iter().cloned());
+                names
+            }
+            None => ColumnFamilies::all()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        };
+        cf_names.extend(options.extra_column_families.iter().cloned());
+
+        if options.open_column_families.is_some() {
+            let omitted: Vec<&str> = existing
+                .iter()
+                .filter(|name| !cf_names.contains(name))
+                .map(String::as_str)
+                .collect();
+            if !omitted.is_empty() {
+                return Err(Error::InvalidInput(format!(
+                    "Column families exist on disk but were not listed in open_column_families: {}",
+                    omitted.join(", ")
+                )));
+            }
+        } else {
+            cf_names.extend(existing);
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        cf_names.retain(|name| seen.insert(name.clone()));
+        Ok(cf_names)
+    }
+
+    /// Write options for every write through this backend
+    fn write_options(&self) -> rocksdb::WriteOptions {
+        let mut write_opts = rocksdb::WriteOptions::default();
+        write_opts.set_sync(self.sync_writes);
+        write_opts
+    }
+
     /// Get a column family handle
     fn cf_handle(&self, cf: &str) -> Result<&rocksdb::ColumnFamily> {
         self.db
@@ -161,11 +411,19 @@ impl RocksDBBackend {
                  | `CURRENT` | Points to active MANIFEST |\n\
                  | `LOCK` | Prevents concurrent access |\n\
                  | `OPENDB_INFO` | OpenDB metadata |\n\
+                 | `MANIFEST.opendb` | Versioned layout manifest (JSON) for tooling |\n\
                  | `README.md` | This file |\n\
                  \n\
                  ## 💾 Backup Instructions\n\
                  \n\
-                 To backup this database:\n\
+                 While an application has the database open, take a hot backup\n\
+                 from it instead of copying files:\n\
+                 \n\
+                 - `db.full_checkpoint(\"/path/to/copy\")` writes a consistent, openable copy\n\
+                 - `db.create_full_backup(\"/path/to/backups\")` adds a numbered full copy;\n\
+                   restore the latest with `OpenDB::restore_full_backup`\n\
+                 \n\
+                 To backup this database by copying it:\n\
                  \n\
                  1. **Stop all applications** using this database\n\
                  2. **Copy the entire folder** to your backup location\n\
@@ -272,6 +530,29 @@ impl RocksDBBackend {
 
         Ok(())
     }
+
+    /// Restore the latest backup in `backup_dir` into the new directory `dest`
+    ///
+    /// On failure, `dest` is removed again rather than left half-copied.
+    pub fn restore_latest_backup(backup_dir: &Path, dest: &Path) -> Result<()> {
+        let id = latest_backup(backup_dir)?
+            .ok_or_else(|| Error::not_found("Backup", backup_dir.display().to_string()))?;
+
+        fs::create_dir_all(dest)?;
+        let copy = || -> Result<()> {
+            // A checkpoint directory is flat, so a file-by-file copy restores it
+            for entry in fs::read_dir(backup_dir.join(id.to_string()))? {
+                let entry = entry?;
+                fs::copy(entry.path(), dest.join(entry.file_name()))?;
+            }
+            Ok(())
+        };
+        let result = copy();
+        if result.is_err() {
+            fs::remove_dir_all(dest)?;
+        }
+        result
+    }
 }
 
 impl StorageBackend for RocksDBBackend {
@@ -280,21 +561,80 @@ impl StorageBackend for RocksDBBackend {
         Ok(self.db.get_cf(cf_handle, key)?)
     }
 
+    fn multi_get(&self, cf: &str, keys: &[&[u8]]) -> Result<Vec<Option<Vec<u8>>>> {
+        let cf_handle = self.cf_handle(cf)?;
+        self.db
+            .multi_get_cf(keys.iter().map(|key| (cf_handle, *key)))
+            .into_iter()
+            .map(|value| Ok(value?))
+            .collect()
+    }
+
+    fn exists(&self, cf: &str, key: &[u8]) -> Result<bool> {
+        // Pinned reads avoid copying the value out of the block cache
+        let cf_handle = self.cf_handle(cf)?;
+        Ok(self.db.get_pinned_cf(cf_handle, key)?.is_some())
+    }
+
+    fn exists_many(&self, cf: &str, keys: &[&[u8]]) -> Result<Vec<bool>> {
+        // One batched lookup instead of a point read per key
+        let cf_handle = self.cf_handle(cf)?;
+        self.db
+            .multi_get_cf(keys.iter().map(|key| (cf_handle, *key)))
+            .into_iter()
+            .map(|value| Ok(value?.is_some()))
+            .collect()
+    }
+
     fn put(&self, cf: &str, key: &[u8], value: &[u8]) -> Result<()> {
         let cf_handle = self.cf_handle(cf)?;
-        self.db.put_cf(cf_handle, key, value)?;
+        self.db
+            .put_cf_opt(cf_handle, key, value, &self.write_options())?;
         Ok(())
     }
 
     fn delete(&self, cf: &str, key: &[u8]) -> Result<()> {
         let cf_handle = self.cf_handle(cf)?;
-        self.db.delete_cf(cf_handle, key)?;
+        self.db
+            .delete_cf_opt(cf_handle, key, &self.write_options())?;
+        Ok(())
+    }
+
+    fn write_batch(&self, ops: &[BatchOp]) -> Result<()> {
+        // Resolve every column family first so a bad name writes nothing
+        let mut batch = rocksdb::WriteBatchWithTransaction::<true>::default();
+        for op in ops {
+            match op {
+                BatchOp::Put { cf, key, value } => batch.put_cf(self.cf_handle(cf)?, key, value),
+                BatchOp::Delete { cf, key } => batch.delete_cf(self.cf_handle(cf)?, key),
+            }
+        }
+        self.db.write_opt(batch, &self.write_options())?;
+        Ok(())
+    }
+
+    fn write_batch_unlogged(&self, ops: &[BatchOp]) -> Result<()> {
+        let mut batch = rocksdb::WriteBatchWithTransaction::<true>::default();
+        for op in ops {
+            match op {
+                BatchOp::Put { cf, key, value } => batch.put_cf(self.cf_handle(cf)?, key, value),
+                BatchOp::Delete { cf, key } => batch.delete_cf(self.cf_handle(cf)?, key),
+            }
+        }
+        // A synced write must go through the WAL, so `sync_writes` is ignored
+        let mut write_opts = rocksdb::WriteOptions::default();
+        write_opts.disable_wal(true);
+        self.db.write_opt(batch, &write_opts)?;
         Ok(())
     }
 
     fn scan_prefix(&self, cf: &str, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
         let cf_handle = self.cf_handle(cf)?;
-        let mut iter = self.db.prefix_iterator_cf(cf_handle, prefix);
+        let mut iter = self.db.iterator_cf_opt(
+            cf_handle,
+            prefix_read_options(prefix),
+            rocksdb::IteratorMode::From(prefix, rocksdb::Direction::Forward),
+        );
         let mut results = Vec::new();
 
         while let Some(Ok((key, value))) = iter.next() {
@@ -307,36 +647,243 @@ impl StorageBackend for RocksDBBackend {
         Ok(results)
     }
 
+    fn scan_prefix_iter(&self, cf: &str, prefix: &[u8]) -> Result<ScanIter<'_>> {
+        self.scan_prefix_iter_from(cf, prefix, prefix)
+    }
+
+    fn scan_prefix_iter_from(&self, cf: &str, prefix: &[u8], from: &[u8]) -> Result<ScanIter<'_>> {
+        let cf_handle = self.cf_handle(cf)?;
+        let seek = from.max(prefix);
+        let iter = self.db.iterator_cf_opt(
+            cf_handle,
+            prefix_read_options(prefix),
+            rocksdb::IteratorMode::From(seek, rocksdb::Direction::Forward),
+        );
+
+        let prefix = prefix.to_vec();
+        Ok(Box::new(iter.map_while(move |item| {
+            match item {
+                Ok((key, value)) => key
+                    .starts_with(&prefix)
+                    .then(|| Ok((key.to_vec(), value.to_vec()))),
+                Err(e) => Some(Err(e.into())),
+            }
+        })))
+    }
+
+    fn scan_range(
+        &self,
+        cf: &str,
+        start: &[u8],
+        end: &[u8],
+        descending: bool,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let cf_handle = self.cf_handle(cf)?;
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        // Bounds let RocksDB stop at the range edges instead of filtering
+        let mut read_opts = rocksdb::ReadOptions::default();
+        read_opts.set_iterate_lower_bound(start.to_vec());
+        read_opts.set_iterate_upper_bound(end.to_vec());
+        let mode = if descending {
+            rocksdb::IteratorMode::End
+        } else {
+            rocksdb::IteratorMode::Start
+        };
+
+        let mut results = Vec::new();
+        for item in self.db.iterator_cf_opt(cf_handle, read_opts, mode) {
+            let (key, value) = item?;
+            results.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(results)
+    }
+
+    fn for_each_prefix(
+        &self,
+        cf: &str,
+        prefix: &[u8],
+        visitor: &mut ScanVisitor<'_>,
+    ) -> Result<()> {
+        let cf_handle = self.cf_handle(cf)?;
+
+        let mode = rocksdb::IteratorMode::From(prefix, rocksdb::Direction::Forward);
+        for item in self
+            .db
+            .iterator_cf_opt(cf_handle, prefix_read_options(prefix), mode)
+        {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            if visitor(&key, &value)?.is_break() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn for_each_key(&self, cf: &str, prefix: &[u8], visitor: &mut KeyVisitor<'_>) -> Result<()> {
+        let cf_handle = self.cf_handle(cf)?;
+
+        let mut iter = self
+            .db
+            .raw_iterator_cf_opt(cf_handle, prefix_read_options(prefix));
+        iter.seek(prefix);
+        while let Some(key) = iter.key() {
+            if !key.starts_with(prefix) || visitor(key)?.is_break() {
+                break;
+            }
+            iter.next();
+        }
+        iter.status()?;
+
+        Ok(())
+    }
+
+    fn path(&self) -> &Path {
+        self.db.path()
+    }
+
+    fn list_column_families(&self) -> Result<Vec<String>> {
+        let names = <TransactionDB>::list_cf(&Options::default(), self.db.path())?;
+        Ok(names)
+    }
+
     fn begin_transaction(&self) -> Result<Box<dyn TransactionTrait>> {
         let txn_opts = TransactionOptions::default();
-        let write_opts = rocksdb::WriteOptions::default();
+        let write_opts = self.write_options();
 
         let txn = self.db.transaction_opt(&write_opts, &txn_opts);
+        let snapshot = self.db.snapshot();
 
         Ok(Box::new(RocksDBTransaction {
-            txn: Some(unsafe { std::mem::transmute(txn) }),
+            txn: Some(unsafe {
+                std::mem::transmute::<
+                    rocksdb::Transaction<'_, TransactionDB>,
+                    rocksdb::Transaction<'static, TransactionDB>,
+                >(txn)
+            }),
+            // SAFETY: as for `txn`, `db` outlives the snapshot, which is
+            // declared before it and so released first
+            snapshot: unsafe {
+                std::mem::transmute::<
+                    rocksdb::SnapshotWithThreadMode<'_, TransactionDB>,
+                    rocksdb::SnapshotWithThreadMode<'static, TransactionDB>,
+                >(snapshot)
+            },
             db: Arc::clone(&self.db),
         }))
     }
 
     fn flush(&self) -> Result<()> {
-        // RocksDB automatically flushes, manual flush is optional
+        // Memtables flush in the background; durability only needs the WAL
+        sync_wal(&self.db)
+    }
+
+    fn checkpoint(&self, dest: &Path) -> Result<()> {
+        // The C API has a native checkpoint for transactional databases, but
+        // the `rocksdb` crate keeps the raw `TransactionDB` handle private and
+        // only wraps `Checkpoint` for plain databases. Copy every column
+        // family out of one snapshot instead.
+        let cf_names = self.list_column_families()?;
+        if let Some(closed) = cf_names
+            .iter()
+            .find(|name| self.db.cf_handle(name).is_none())
+        {
+            return Err(Error::InvalidInput(format!(
+                "Column family {} is not open; open all column families to checkpoint",
+                closed
+            )));
+        }
+
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let target = rocksdb::DB::open_cf(&opts, dest, &cf_names)?;
+
+        let snapshot = self.db.snapshot();
+        for name in &cf_names {
+            let source_cf = self.cf_handle(name)?;
+            let target_cf = target
+                .cf_handle(name)
+                .ok_or_else(|| Error::Storage(format!("Column family not found: {}", name)))?;
+
+            let mut batch = rocksdb::WriteBatch::default();
+            for item in snapshot.iterator_cf(source_cf, rocksdb::IteratorMode::Start) {
+                let (key, value) = item?;
+                batch.put_cf(target_cf, key, value);
+                if batch.len() >= CHECKPOINT_BATCH_SIZE {
+                    target.write(std::mem::take(&mut batch))?;
+                }
+            }
+            target.write(batch)?;
+        }
+
+        target.flush_wal(true)?;
         Ok(())
     }
 
+    fn create_backup(&self, backup_dir: &Path) -> Result<()> {
+        fs::create_dir_all(backup_dir)?;
+        let id = latest_backup(backup_dir)?.map_or(1, |id| id + 1);
+
+        // Only a finished copy gets a numbered name, so restores never see a partial one
+        let staging = backup_dir.join(format!("{}.tmp", id));
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        self.checkpoint(&staging)?;
+        fs::rename(&staging, backup_dir.join(id.to_string()))?;
+        Ok(())
+    }
+
+    fn footprint(&self) -> Result<StorageFootprint> {
+        let (sst_bytes, wal_bytes) = rocksdb_file_sizes(self.db.path())?;
+
+        // `property_int_value` on a transactional database only reads the
+        // default column family; the memory usage API sums
+        // `rocksdb.cur-size-all-mem-tables` over all of them
+        let mut usage = MemoryUsageBuilder::new()?;
+        usage.add_tx_db(&*self.db);
+        let memtable_bytes = usage.build()?.approximate_mem_table_unflushed();
+
+        Ok(StorageFootprint {
+            sst_bytes,
+            wal_bytes,
+            memtable_bytes: Some(memtable_bytes),
+        })
+    }
+
     fn snapshot(&self) -> Result<Box<dyn SnapshotTrait>> {
-        // For simplicity, we'll implement snapshots by cloning data
-        // A proper snapshot would require wrapping RocksDB's snapshot API
-        // This is a trade-off for simpler lifetime management
+        let snapshot = self.db.snapshot();
+
         Ok(Box::new(RocksDBSnapshot {
+            // SAFETY: the snapshot borrows the database, which `db` keeps alive
+            // for as long as the wrapper exists; `snapshot` is declared first so
+            // it is released before that Arc is dropped.
+            snapshot: unsafe {
+                std::mem::transmute::<
+                    rocksdb::SnapshotWithThreadMode<'_, TransactionDB>,
+                    rocksdb::SnapshotWithThreadMode<'static, TransactionDB>,
+                >(snapshot)
+            },
             db: Arc::clone(&self.db),
         }))
     }
 }
 
 /// RocksDB transaction wrapper
+///
+/// `get` reads the transaction's own writes over the snapshot taken when it
+/// began, so concurrent commits stay invisible. `get_for_update` reads the
+/// latest committed value instead, since it locks the key for a write.
 struct RocksDBTransaction {
     txn: Option<rocksdb::Transaction<'static, TransactionDB>>,
+    snapshot: rocksdb::SnapshotWithThreadMode<'static, TransactionDB>,
     db: Arc<TransactionDB>,
 }
 
@@ -348,7 +895,22 @@ impl TransactionTrait for RocksDBTransaction {
             .ok_or_else(|| Error::Storage(format!("Column family not found: {}", cf)))?;
 
         if let Some(txn) = &self.txn {
-            Ok(txn.get_cf(cf_handle, key)?)
+            let mut read_opts = rocksdb::ReadOptions::default();
+            read_opts.set_snapshot(&self.snapshot);
+            Ok(txn.get_cf_opt(cf_handle, key, &read_opts)?)
+        } else {
+            Err(Error::Storage("Transaction already completed".to_string()))
+        }
+    }
+
+    fn get_for_update(&mut self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf_handle = self
+            .db
+            .cf_handle(cf)
+            .ok_or_else(|| Error::Storage(format!("Column family not found: {}", cf)))?;
+
+        if let Some(txn) = &self.txn {
+            Ok(txn.get_for_update_cf(cf_handle, key, true)?)
         } else {
             Err(Error::Storage("Transaction already completed".to_string()))
         }
@@ -391,6 +953,20 @@ impl TransactionTrait for RocksDBTransaction {
         }
     }
 
+    fn commit_synced(mut self: Box<Self>) -> Result<()> {
+        let txn = self
+            .txn
+            .take()
+            .ok_or_else(|| Error::Storage("Transaction already completed".to_string()))?;
+        txn.commit()?;
+
+        // Write options are fixed when a RocksDB transaction begins, so force
+        // durability afterwards
+        sync_wal(&self.db)?;
+
+        Ok(())
+    }
+
     fn rollback(mut self: Box<Self>) -> Result<()> {
         if let Some(txn) = self.txn.take() {
             txn.rollback()?;
@@ -404,6 +980,7 @@ impl TransactionTrait for RocksDBTransaction {
 /// RocksDB snapshot wrapper
 #[allow(dead_code)]
 struct RocksDBSnapshot {
+    snapshot: rocksdb::SnapshotWithThreadMode<'static, TransactionDB>,
     db: Arc<TransactionDB>,
 }
 
@@ -414,6 +991,151 @@ impl SnapshotTrait for RocksDBSnapshot {
             .cf_handle(cf)
             .ok_or_else(|| Error::Storage(format!("Column family not found: {}", cf)))?;
 
-        Ok(self.db.get_cf(cf_handle, key)?)
+        Ok(self.snapshot.get_cf(cf_handle, key)?)
+    }
+
+    fn for_each_prefix(
+        &self,
+        cf: &str,
+        prefix: &[u8],
+        visitor: &mut ScanVisitor<'_>,
+    ) -> Result<()> {
+        let cf_handle = self
+            .db
+            .cf_handle(cf)
+            .ok_or_else(|| Error::Storage(format!("Column family not found: {}", cf)))?;
+
+        let mode = rocksdb::IteratorMode::From(prefix, rocksdb::Direction::Forward);
+        for item in self.snapshot.iterator_cf(cf_handle, mode) {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            if visitor(&key, &value)?.is_break() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::ControlFlow;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_batch_is_all_or_nothing() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = RocksDBBackend::open(temp_dir.path())?;
+        backend.put(ColumnFamilies::DEFAULT, b"gone", b"1")?;
+
+        backend.write_batch(&[
+            BatchOp::put(ColumnFamilies::DEFAULT, b"a", b"1".to_vec()),
+            BatchOp::put(ColumnFamilies::RECORDS, b"b", b"2".to_vec()),
+            BatchOp::delete(ColumnFamilies::DEFAULT, b"gone"),
+        ])?;
+        assert_eq!(
+            backend.get(ColumnFamilies::DEFAULT, b"a")?,
+            Some(b"1".to_vec())
+        );
+        assert_eq!(
+            backend.get(ColumnFamilies::RECORDS, b"b")?,
+            Some(b"2".to_vec())
+        );
+        assert_eq!(backend.get(ColumnFamilies::DEFAULT, b"gone")?, None);
+
+        // An unknown column family fails the batch before anything is written
+        let result = backend.write_batch(&[
+            BatchOp::put(ColumnFamilies::DEFAULT, b"c", b"3".to_vec()),
+            BatchOp::put("missing_cf", b"d", b"4".to_vec()),
+        ]);
+        assert!(result.is_err());
+        assert_eq!(backend.get(ColumnFamilies::DEFAULT, b"c")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_flush_stores_no_keys() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let backend = RocksDBBackend::open(temp_dir.path())?;
+            let metadata = backend.scan_prefix(ColumnFamilies::METADATA, &[])?;
+            backend.put(ColumnFamilies::DEFAULT, b"a", b"1")?;
+            backend.flush()?;
+            assert_eq!(
+                backend.scan_prefix(ColumnFamilies::METADATA, &[])?,
+                metadata
+            );
+        }
+
+        let backend = RocksDBBackend::open(temp_dir.path())?;
+        assert_eq!(
+            backend.get(ColumnFamilies::DEFAULT, b"a")?,
+            Some(b"1".to_vec())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_for_each_key_stays_in_prefix() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = RocksDBBackend::open(temp_dir.path())?;
+        for key in [&b"a1"[..], b"b1", b"b2", b"b3", b"c1"] {
+            backend.put(ColumnFamilies::DEFAULT, key, b"value")?;
+        }
+        backend.delete(ColumnFamilies::DEFAULT, b"b2")?;
+
+        let mut keys = Vec::new();
+        backend.for_each_key(ColumnFamilies::DEFAULT, b"b", &mut |key| {
+            keys.push(key.to_vec());
+            Ok(ControlFlow::Continue(()))
+        })?;
+        assert_eq!(keys, vec![b"b1".to_vec(), b"b3".to_vec()]);
+
+        let mut visited = 0;
+        backend.for_each_key(ColumnFamilies::DEFAULT, &[], &mut |_| {
+            visited += 1;
+            Ok(ControlFlow::Break(()))
+        })?;
+        assert_eq!(visited, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_ignores_later_writes() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend =
+            RocksDBBackend::open_with_options(temp_dir.path(), &OpenDBOptions::default())?;
+
+        backend.put(ColumnFamilies::DEFAULT, b"a", b"a1")?;
+        backend.put(ColumnFamilies::DEFAULT, b"b", b"b1")?;
+        let snapshot = backend.snapshot()?;
+
+        // Writes after the snapshot (e.g. an update racing a multi-key read)
+        backend.put(ColumnFamilies::DEFAULT, b"a", b"a2")?;
+        backend.delete(ColumnFamilies::DEFAULT, b"b")?;
+        backend.put(ColumnFamilies::DEFAULT, b"c", b"c1")?;
+
+        assert_eq!(
+            snapshot.get(ColumnFamilies::DEFAULT, b"a")?,
+            Some(b"a1".to_vec())
+        );
+        assert_eq!(
+            snapshot.get(ColumnFamilies::DEFAULT, b"b")?,
+            Some(b"b1".to_vec())
+        );
+        assert_eq!(snapshot.get(ColumnFamilies::DEFAULT, b"c")?, None);
+        assert_eq!(
+            backend.get(ColumnFamilies::DEFAULT, b"a")?,
+            Some(b"a2".to_vec())
+        );
+
+        Ok(())
     }
 }
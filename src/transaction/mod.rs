@@ -4,13 +4,61 @@
 
 pub mod manager;
 
+use crate::codec;
+use crate::database::Coerce;
 use crate::error::Result;
-use crate::storage::Transaction as StorageTransaction;
+use crate::records::RecordsManager;
+use crate::storage::column_families::ColumnFamilies;
+use crate::storage::{BatchOp, Transaction as StorageTransaction};
+use crate::types::Memory;
+use crate::vector::{self, VectorManager};
 
 /// Transaction handle for ACID operations
 pub struct Transaction {
     inner: Option<Box<dyn StorageTransaction>>,
     active: bool,
+    memories: Option<MemoryWrites>,
+}
+
+/// Memory writes buffered in a transaction, applied to the caches on commit
+pub(crate) struct MemoryWrites {
+    records: RecordsManager,
+    vector: VectorManager,
+    coercion: Coerce,
+    dimension: usize,
+    inserted: Vec<Memory>,
+    deleted: Vec<String>,
+}
+
+impl MemoryWrites {
+    /// Track memory writes against the given managers
+    pub(crate) fn new(
+        records: RecordsManager,
+        vector: VectorManager,
+        coercion: Coerce,
+        dimension: usize,
+    ) -> Self {
+        Self {
+            records,
+            vector,
+            coercion,
+            dimension,
+            inserted: Vec::new(),
+            deleted: Vec::new(),
+        }
+    }
+
+    /// Bring the caches and the vector index in line with the committed writes
+    fn apply(self) -> Result<()> {
+        for memory in &self.inserted {
+            self.records.invalidate(&memory.id);
+        }
+        for id in &self.deleted {
+            self.records.invalidate(id);
+        }
+        self.vector.index_batch(&self.inserted)?;
+        self.vector.unindex(&self.deleted)
+    }
 }
 
 impl Transaction {
@@ -19,10 +67,21 @@ impl Transaction {
         Self {
             inner: Some(txn),
             active: true,
+            memories: None,
         }
     }
 
+    /// Allow `insert_memory` and `delete_memory` in this transaction
+    pub(crate) fn with_memory_writes(mut self, memories: MemoryWrites) -> Self {
+        self.memories = Some(memories);
+        self
+    }
+
     /// Get a value within this transaction
+    ///
+    /// Writes made earlier in this transaction are visible. With the RocksDB
+    /// backend, everything else is read as of the moment the transaction
+    /// began, so commits by other transactions in the meantime aren't seen.
     pub fn get(&self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
         if !self.active {
             return Err(crate::error::Error::Transaction(
@@ -35,6 +94,23 @@ impl Transaction {
             .get(cf, key)
     }
 
+    /// Get a value and lock the key until this transaction commits or rolls back
+    ///
+    /// Use this for read-modify-write: a concurrent transaction calling
+    /// `get_for_update` on the same key waits until this one finishes.
+    /// Unlike `get`, this reads the latest committed value.
+    pub fn get_for_update(&mut self, cf: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if !self.active {
+            return Err(crate::error::Error::Transaction(
+                "Transaction not active".to_string(),
+            ));
+        }
+        self.inner
+            .as_mut()
+            .ok_or_else(|| crate::error::Error::Transaction("Transaction not active".to_string()))?
+            .get_for_update(cf, key)
+    }
+
     /// Put a key-value pair within this transaction
     pub fn put(&mut self, cf: &str, key: &[u8], value: &[u8]) -> Result<()> {
         if !self.active {
@@ -61,6 +137,97 @@ impl Transaction {
             .delete(cf, key)
     }
 
+    /// Insert or update a memory record and its vectors within this transaction
+    ///
+    /// The record and its embeddings are written in this transaction, so a
+    /// rollback undoes both. Embeddings are validated (after
+    /// `dimension_coercion`) right away; caches and the vector index are only
+    /// updated once the transaction commits.
+    pub fn insert_memory(&mut self, memory: &Memory) -> Result<()> {
+        let memories = self.memory_writes()?;
+        let coerced = memories.coercion.apply(memory, memories.dimension);
+        let memory = coerced.unwrap_or_else(|| memory.clone());
+
+        let mut ops = memories.vector.batch_ops(std::slice::from_ref(&memory))?;
+        ops.push(memories.records.put_op(&memory)?);
+        let index_ops = self.metadata_index_ops(&memory.id, Some(&memory))?;
+        ops.extend(index_ops);
+
+        let memories = self.memory_writes()?;
+        memories.inserted.retain(|pending| pending.id != memory.id);
+        memories.deleted.retain(|id| *id != memory.id);
+        memories.inserted.push(memory);
+        self.write_ops(&ops)
+    }
+
+    /// Delete a memory record and its vectors within this transaction
+    ///
+    /// Like `insert_memory`, nothing outside the transaction changes until it
+    /// commits. Graph edges are left in place.
+    pub fn delete_memory(&mut self, id: &str) -> Result<()> {
+        let mut ops = self.metadata_index_ops(id, None)?;
+        let memories = self.memory_writes()?;
+        ops.extend(memories.vector.delete_ops(id)?);
+        ops.push(BatchOp::delete(ColumnFamilies::RECORDS, id.as_bytes()));
+
+        // Field vectors written earlier in this transaction aren't in storage yet
+        if let Some(pending) = memories.inserted.iter().find(|memory| memory.id == id) {
+            for field in pending.embeddings.keys() {
+                ops.push(BatchOp::delete(
+                    ColumnFamilies::VECTOR_DATA,
+                    &vector::field_key(id, field),
+                ));
+            }
+        }
+        memories.inserted.retain(|memory| memory.id != id);
+        if !memories.deleted.iter().any(|deleted| deleted == id) {
+            memories.deleted.push(id.to_string());
+        }
+        self.write_ops(&ops)
+    }
+
+    /// Metadata, importance and time index writes for replacing the record
+    /// under `id` with `new`
+    ///
+    /// The previous record is read through this transaction, so writes made
+    /// earlier in it are accounted for, and locked at its latest version.
+    fn metadata_index_ops(&mut self, id: &str, new: Option<&Memory>) -> Result<Vec<BatchOp>> {
+        if !self.memory_writes()?.records.has_indexes() {
+            return Ok(Vec::new());
+        }
+        let previous = self
+            .get_for_update(ColumnFamilies::RECORDS, id.as_bytes())?
+            .map(|bytes| codec::decode_memory(&bytes))
+            .transpose()?;
+        let records = &self.memory_writes()?.records;
+        Ok(records.index_ops(id, previous.as_ref(), new))
+    }
+
+    /// Memory write tracking, if this transaction came from `OpenDB`
+    fn memory_writes(&mut self) -> Result<&mut MemoryWrites> {
+        if !self.active {
+            return Err(crate::error::Error::Transaction(
+                "Transaction not active".to_string(),
+            ));
+        }
+        self.memories.as_mut().ok_or_else(|| {
+            crate::error::Error::Transaction(
+                "Memory operations are not available in this transaction".to_string(),
+            )
+        })
+    }
+
+    /// Buffer batch operations in the underlying transaction
+    pub(crate) fn write_ops(&mut self, ops: &[BatchOp]) -> Result<()> {
+        for op in ops {
+            match op {
+                BatchOp::Put { cf, key, value } => self.put(cf, key, value)?,
+                BatchOp::Delete { cf, key } => self.delete(cf, key)?,
+            }
+        }
+        Ok(())
+    }
+
     /// Commit the transaction
     pub fn commit(mut self) -> Result<()> {
         if !self.active {
@@ -72,7 +239,35 @@ impl Transaction {
         self.inner
             .take()
             .ok_or_else(|| crate::error::Error::Transaction("Transaction not active".to_string()))?
-            .commit()
+            .commit()?;
+        self.apply_memory_writes()
+    }
+
+    /// Commit the transaction and fsync it to disk before returning
+    ///
+    /// `commit` is fast: the changes are in the write-ahead log but may still
+    /// sit in the OS page cache. `commit_synced` additionally waits for an
+    /// fsync, so the transaction survives power loss once this returns.
+    pub fn commit_synced(mut self) -> Result<()> {
+        if !self.active {
+            return Err(crate::error::Error::Transaction(
+                "Transaction already completed".to_string(),
+            ));
+        }
+        self.active = false;
+        self.inner
+            .take()
+            .ok_or_else(|| crate::error::Error::Transaction("Transaction not active".to_string()))?
+            .commit_synced()?;
+        self.apply_memory_writes()
+    }
+
+    /// Update caches for memories written in a committed transaction
+    fn apply_memory_writes(&mut self) -> Result<()> {
+        match self.memories.take() {
+            Some(memories) => memories.apply(),
+            None => Ok(()),
+        }
     }
 
     /// Rollback the transaction
@@ -93,9 +288,11 @@ impl Transaction {
 impl Drop for Transaction {
     fn drop(&mut self) {
         // Auto-rollback if not committed
-        if self.active {
-            // Consume self.inner without calling methods
-            // The underlying transaction will handle cleanup
+        if self.active
+            && let Some(inner) = self.inner.take()
+        {
+            // Drop can't report failure; the backend discards the writes either way
+            let _ = inner.rollback();
         }
     }
 }
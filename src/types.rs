@@ -21,8 +21,17 @@ pub struct Memory {
     pub content: String,
 
     /// Vector embedding for semantic search
+    ///
+    /// Leave empty for text-only records that are never searched semantically.
     pub embedding: Vec<f32>,
 
+    /// Named per-facet embeddings (e.g. "title", "body")
+    ///
+    /// Each field is indexed separately and searched with
+    /// `OpenDB::search_similar_field`. Empty vectors are not indexed.
+    #[serde(default)]
+    pub embeddings: HashMap<String, Vec<f32>>,
+
     /// Importance score (0.0 to 1.0)
     pub importance: f32,
 
@@ -31,6 +40,18 @@ pub struct Memory {
 
     /// Arbitrary key-value metadata
     pub metadata: HashMap<String, String>,
+
+    /// Unix timestamp (seconds) at which the memory expires, if ever
+    ///
+    /// Expired memories read as absent and are deleted lazily on access or
+    /// by `OpenDB::purge_expired`.
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+
+    /// Unix timestamp (seconds) at which `OpenDB::decay_memories` last
+    /// lowered the importance, if ever
+    #[serde(default)]
+    pub last_decayed: Option<i64>,
 }
 
 impl Memory {
@@ -60,23 +81,62 @@ impl Memory {
         content: impl Into<String>,
         embedding: Vec<f32>,
         importance: f32,
+    ) -> Self {
+        Self::new_at(id, content, embedding, importance, Utc::now().timestamp())
+    }
+
+    /// Create a Memory record with an explicit timestamp (Unix seconds)
+    ///
+    /// For importing historical data or deterministic tests; `new` stamps
+    /// the current time. Writes keep the timestamp as given.
+    pub fn new_at(
+        id: impl Into<String>,
+        content: impl Into<String>,
+        embedding: Vec<f32>,
+        importance: f32,
+        timestamp: i64,
     ) -> Self {
         Self {
             id: id.into(),
             content: content.into(),
             embedding,
+            embeddings: HashMap::new(),
             importance: importance.clamp(0.0, 1.0),
-            timestamp: Utc::now().timestamp(),
+            timestamp,
             metadata: HashMap::new(),
+            expires_at: None,
+            last_decayed: None,
         }
     }
 
+    /// Expire this memory `seconds` from now
+    pub fn with_ttl(mut self, seconds: i64) -> Self {
+        self.expires_at = Some(Utc::now().timestamp().saturating_add(seconds));
+        self
+    }
+
+    /// Whether this memory has expired
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(Utc::now().timestamp())
+    }
+
+    /// Whether this memory has expired as of `now` (Unix seconds)
+    pub fn is_expired_at(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
     /// Add metadata to this memory
     pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.metadata.insert(key.into(), value.into());
         self
     }
 
+    /// Add a named field embedding to this memory
+    pub fn with_field_embedding(mut self, field: impl Into<String>, embedding: Vec<f32>) -> Self {
+        self.embeddings.insert(field.into(), embedding);
+        self
+    }
+
     /// Update the timestamp to now
     pub fn touch(&mut self) {
         self.timestamp = Utc::now().timestamp();
@@ -86,10 +146,120 @@ impl Memory {
 /// Metadata associated with Memory records
 pub type MemoryMetadata = HashMap<String, String>;
 
+/// Per-call options for reading memory records
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOpts {
+    /// Skip the record cache and read from storage, refreshing the cache
+    ///
+    /// Useful right after out-of-band writes (e.g. raw transactions) that
+    /// the cache can't see. Default reads may return a stale cached record.
+    pub bypass_cache: bool,
+}
+
+impl ReadOpts {
+    /// Options that force a fresh read from storage
+    pub fn fresh() -> Self {
+        Self { bypass_cache: true }
+    }
+}
+
+/// Partial update for a Memory record
+///
+/// Only the fields that are set are applied by `OpenDB::patch_memory`; the
+/// vector index is only touched when a new embedding is provided.
+///
+/// # Example
+///
+/// ```
+/// use opendb::MemoryPatch;
+///
+/// let patch = MemoryPatch::new()
+///     .importance(0.9)
+///     .set_metadata("reviewed", "true")
+///     .remove_metadata("draft");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryPatch {
+    /// New content, if changing
+    pub content: Option<String>,
+
+    /// New embedding, if changing (re-indexes the vector)
+    pub embedding: Option<Vec<f32>>,
+
+    /// New importance score, if changing (clamped to 0.0..=1.0)
+    pub importance: Option<f32>,
+
+    /// Metadata entries to add or overwrite
+    pub metadata_set: HashMap<String, String>,
+
+    /// Metadata keys to remove
+    pub metadata_remove: Vec<String>,
+}
+
+impl MemoryPatch {
+    /// Create an empty patch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the content
+    pub fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    /// Replace the embedding
+    pub fn embedding(mut self, embedding: Vec<f32>) -> Self {
+        self.embedding = Some(embedding);
+        self
+    }
+
+    /// Replace the importance score
+    pub fn importance(mut self, importance: f32) -> Self {
+        self.importance = Some(importance);
+        self
+    }
+
+    /// Add or overwrite a metadata entry
+    pub fn set_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata_set.insert(key.into(), value.into());
+        self
+    }
+
+    /// Remove a metadata entry
+    pub fn remove_metadata(mut self, key: impl Into<String>) -> Self {
+        self.metadata_remove.push(key.into());
+        self
+    }
+
+    /// Apply this patch to a memory
+    pub fn apply_to(self, memory: &mut Memory) {
+        if let Some(content) = self.content {
+            memory.content = content;
+        }
+        if let Some(embedding) = self.embedding {
+            memory.embedding = embedding;
+        }
+        if let Some(importance) = self.importance {
+            memory.importance = importance.clamp(0.0, 1.0);
+        }
+        for key in &self.metadata_remove {
+            memory.metadata.remove(key);
+        }
+        memory.metadata.extend(self.metadata_set);
+    }
+}
+
 /// Graph edge representing a relationship between two entities
 #[derive(Debug, Clone, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
 #[archive(check_bytes)]
 pub struct Edge {
+    /// Unique edge ID, distinguishing parallel edges in multigraph mode
+    ///
+    /// Empty for edges written before edge IDs existed.
+    #[serde(default)]
+    pub id: String,
+
     /// Source node ID
     pub from: String,
 
@@ -104,6 +274,14 @@ pub struct Edge {
 
     /// Creation timestamp
     pub timestamp: i64,
+
+    /// Arbitrary key-value metadata, e.g. provenance or confidence
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+
+    /// Unix timestamp (seconds) of the last `decay_edge_weights` pass, if any
+    #[serde(default)]
+    pub last_decayed: Option<i64>,
 }
 
 impl Edge {
@@ -112,13 +290,28 @@ impl Edge {
         from: impl Into<String>,
         relation: impl Into<String>,
         to: impl Into<String>,
+    ) -> Self {
+        Self::new_at(from, relation, to, Utc::now().timestamp())
+    }
+
+    /// Create an edge with an explicit timestamp (Unix seconds)
+    ///
+    /// `OpenDB::link_with` and `OpenDB::add_edge` store it as given.
+    pub fn new_at(
+        from: impl Into<String>,
+        relation: impl Into<String>,
+        to: impl Into<String>,
+        timestamp: i64,
     ) -> Self {
         Self {
+            id: uuid::Uuid::new_v4().to_string(),
             from: from.into(),
             relation: relation.into(),
             to: to.into(),
             weight: 1.0,
-            timestamp: Utc::now().timestamp(),
+            timestamp,
+            metadata: HashMap::new(),
+            last_decayed: None,
         }
     }
 
@@ -127,21 +320,453 @@ impl Edge {
         self.weight = weight;
         self
     }
+
+    /// Add metadata to this edge
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// A soft-deleted memory waiting in the recycle bin
+///
+/// Produced by `OpenDB::soft_delete_memory`, listed by `OpenDB::list_deleted`
+/// and brought back by `OpenDB::restore_memory`.
+#[derive(Debug, Clone, Serialize, Deserialize, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+pub struct DeletedMemory {
+    /// The memory as it was when deleted
+    pub memory: Memory,
+
+    /// Edges into and out of the memory, restored along with it
+    pub edges: Vec<Edge>,
+
+    /// Unix timestamp (seconds) of the deletion
+    pub deleted_at: i64,
+}
+
+/// A memory together with its surrounding subgraph, portable between databases
+///
+/// Produced by `OpenDB::export_bundle` and consumed by `OpenDB::import_bundle`.
+/// Serializable with serde, e.g. to JSON via `serde_json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Bundle {
+    /// ID of the memory the bundle was exported around
+    pub root_id: String,
+
+    /// Memories within the exported depth (root first)
+    pub memories: Vec<Memory>,
+
+    /// Edges whose endpoints both lie within the exported depth
+    pub edges: Vec<Edge>,
+}
+
+/// How `OpenDB::import_bundle` assigns IDs to imported memories and edges
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum IdPolicy {
+    /// Keep the original IDs, overwriting any existing memories (default)
+    #[default]
+    Preserve,
+
+    /// Keep the original IDs, but fail before writing anything if one exists
+    FailOnConflict,
+
+    /// Prepend a prefix to every ID, leaving existing data untouched
+    Prefix(String),
+}
+
+impl IdPolicy {
+    /// Map an exported ID to the ID it is imported under
+    pub fn apply(&self, id: &str) -> String {
+        match self {
+            IdPolicy::Prefix(prefix) => format!("{}{}", prefix, id),
+            _ => id.to_string(),
+        }
+    }
+}
+
+/// How `OpenDB::merge_from` resolves items that exist in both databases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Keep the item already in the target database (default)
+    #[default]
+    KeepExisting,
+
+    /// Replace it with the merged item
+    Overwrite,
+
+    /// Replace it only if the merged item's timestamp is later
+    ///
+    /// KV pairs have no timestamp, so existing ones are kept.
+    KeepNewerTimestamp,
+}
+
+impl ConflictPolicy {
+    /// Whether an incoming item replaces an existing one
+    ///
+    /// Timestamps are `None` for items that don't carry one.
+    pub fn replaces(&self, existing: Option<i64>, incoming: Option<i64>) -> bool {
+        match self {
+            ConflictPolicy::KeepExisting => false,
+            ConflictPolicy::Overwrite => true,
+            ConflictPolicy::KeepNewerTimestamp => {
+                matches!((existing, incoming), (Some(existing), Some(incoming)) if incoming > existing)
+            }
+        }
+    }
+}
+
+/// What `OpenDB::merge_from` did, counted over memories, edges, KV pairs
+/// and documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MergeStats {
+    /// Items that didn't exist in the target and were copied
+    pub inserted: usize,
+
+    /// Conflicting items left as they were
+    pub skipped: usize,
+
+    /// Conflicting items replaced by the merged ones
+    pub overwritten: usize,
+}
+
+impl MergeStats {
+    /// Count one merged item and return whether it should be written
+    ///
+    /// `existing` is `None` when the target has no such item, otherwise
+    /// the stored item's timestamp (if it has one).
+    pub(crate) fn record(
+        &mut self,
+        policy: ConflictPolicy,
+        existing: Option<Option<i64>>,
+        incoming: Option<i64>,
+    ) -> bool {
+        match existing {
+            None => {
+                self.inserted += 1;
+                true
+            }
+            Some(existing) if policy.replaces(existing, incoming) => {
+                self.overwritten += 1;
+                true
+            }
+            Some(_) => {
+                self.skipped += 1;
+                false
+            }
+        }
+    }
+}
+
+/// What `OpenDB::insert_memory_dedup` did with a memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DedupOutcome {
+    /// No similar memory existed; the memory was inserted under this id
+    Inserted(String),
+
+    /// The memory was merged into the existing memory with this id
+    MergedInto(String),
+}
+
+impl DedupOutcome {
+    /// ID of the memory that was written
+    pub fn id(&self) -> &str {
+        match self {
+            DedupOutcome::Inserted(id) | DedupOutcome::MergedInto(id) => id,
+        }
+    }
+}
+
+/// Versioned description of a database's on-disk layout
+///
+/// Returned by `OpenDB::manifest` and written as JSON to `MANIFEST.opendb`
+/// in the database directory on open, without the counts, so external tools
+/// (backup, restore, migration) can check compatibility without opening it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Version of this manifest format
+    pub format_version: u32,
+
+    /// OpenDB crate version that wrote the manifest
+    pub opendb_version: String,
+
+    /// Configured embedding dimension
+    pub vector_dimension: usize,
+
+    /// Configured vector distance metric
+    pub distance_metric: DistanceMetric,
+
+    /// Whether parallel edges are allowed
+    pub multigraph: bool,
+
+    /// Column families present in the database
+    pub column_families: Vec<String>,
+
+    /// Number of memory records (absent from `MANIFEST.opendb`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_count: Option<usize>,
+
+    /// Number of graph edges (absent from `MANIFEST.opendb`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge_count: Option<usize>,
+}
+
+/// Summary counts for the graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GraphStats {
+    /// Number of distinct entities with at least one edge
+    pub node_count: usize,
+
+    /// Number of edges
+    pub edge_count: usize,
+}
+
+/// Key count of one column family
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnFamilyStats {
+    /// Column family name
+    pub name: String,
+
+    /// Exact number of keys, counted by walking the column family
+    pub keys: u64,
+}
+
+/// Configured size and current occupancy of an in-process cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Maximum number of entries
+    pub capacity: usize,
+
+    /// Number of cached entries
+    pub entries: usize,
+
+    /// Byte budget, if one is configured
+    pub max_bytes: Option<usize>,
+
+    /// Estimated bytes held (0 without a byte budget)
+    pub used_bytes: usize,
+}
+
+/// Storage footprint and cache occupancy, returned by `OpenDB::stats`
+///
+/// `Display` renders a compact multi-line summary for logs; the struct
+/// also serializes to JSON for admin endpoints.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct DbStats {
+    /// Key count of every opened column family
+    pub column_families: Vec<ColumnFamilyStats>,
+
+    /// Total size of the SST files on disk
+    pub sst_bytes: u64,
+
+    /// Total size of the write-ahead log files on disk
+    pub wal_bytes: u64,
+
+    /// Memory held by memtables, when the storage backend reports it
+    pub memtable_bytes: Option<u64>,
+
+    /// KV cache
+    pub kv_cache: CacheStats,
+
+    /// Memory record cache
+    pub record_cache: CacheStats,
+}
+
+impl DbStats {
+    /// Total number of keys across all column families
+    pub fn total_keys(&self) -> u64 {
+        self.column_families.iter().map(|cf| cf.keys).sum()
+    }
+}
+
+impl std::fmt::Display for DbStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "keys: {} | sst: {} B | wal: {} B | memtables: {}",
+            self.total_keys(),
+            self.sst_bytes,
+            self.wal_bytes,
+            self.memtable_bytes
+                .map_or("n/a".to_string(), |bytes| format!("{} B", bytes))
+        )?;
+        for cf in &self.column_families {
+            writeln!(f, "  {}: {} keys", cf.name, cf.keys)?;
+        }
+        for (name, cache) in [
+            ("kv cache", &self.kv_cache),
+            ("record cache", &self.record_cache),
+        ] {
+            write!(f, "{}: {}/{} entries", name, cache.entries, cache.capacity)?;
+            if let Some(max_bytes) = cache.max_bytes {
+                write!(f, ", {}/{} B", cache.used_bytes, max_bytes)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Distance metric used by vector search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DistanceMetric {
+    /// Euclidean (L2) distance, range 0..∞ (default)
+    #[default]
+    Euclidean,
+
+    /// Cosine distance (`1 - cosine similarity`), range 0..2
+    Cosine,
+
+    /// Negated dot product (`-(a · b)`), unbounded
+    ///
+    /// For maximum inner product search: the largest dot product has the
+    /// lowest distance and ranks first, so magnitude counts, not just
+    /// direction. Distances are usually negative.
+    DotProduct,
+}
+
+impl DistanceMetric {
+    /// Distance between two vectors under this metric (lower is more similar)
+    pub fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            DistanceMetric::Euclidean => a
+                .iter()
+                .zip(b.iter())
+                .map(|(x, y)| (x - y).powi(2))
+                .sum::<f32>()
+                .sqrt(),
+            DistanceMetric::Cosine => {
+                let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+                let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+                let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    // Zero vectors have no direction; treat them as unrelated
+                    1.0
+                } else {
+                    1.0 - dot / (norm_a * norm_b)
+                }
+            }
+            DistanceMetric::DotProduct => -a.iter().zip(b.iter()).map(|(x, y)| x * y).sum::<f32>(),
+        }
+    }
+
+    /// Distance between two vectors, or `None` if it exceeds `max_distance`
+    ///
+    /// For Euclidean distance the running squared sum only grows, so the loop
+    /// stops as soon as it passes `max_distance` instead of visiting every
+    /// component. The accept/reject decision (and any returned distance) is
+    /// identical to comparing `distance(a, b) <= max_distance`. Cosine and
+    /// dot product can't bound a partial sum, so they are computed in full.
+    pub fn distance_within(&self, a: &[f32], b: &[f32], max_distance: f32) -> Option<f32> {
+        /// Components summed between early-exit checks
+        const CHECK_EVERY: usize = 16;
+
+        match self {
+            DistanceMetric::Euclidean => {
+                let max_squared = max_distance * max_distance;
+                let mut sum = 0.0f32;
+                for (i, (x, y)) in a.iter().zip(b.iter()).enumerate() {
+                    sum += (x - y).powi(2);
+                    // The sqrt re-check keeps rounding in `max_squared` from
+                    // rejecting a vector the full computation would accept
+                    if (i + 1) % CHECK_EVERY == 0 && sum > max_squared && sum.sqrt() > max_distance
+                    {
+                        return None;
+                    }
+                }
+                let distance = sum.sqrt();
+                (distance <= max_distance).then_some(distance)
+            }
+            DistanceMetric::Cosine | DistanceMetric::DotProduct => {
+                let distance = self.distance(a, b);
+                (distance <= max_distance).then_some(distance)
+            }
+        }
+    }
+
+    /// Whether zero vectors are meaningless under this metric
+    ///
+    /// Cosine distance divides by the vector norm, so a zero vector has no
+    /// defined distance to anything.
+    pub fn requires_nonzero_norm(&self) -> bool {
+        matches!(self, DistanceMetric::Cosine)
+    }
+
+    /// Map a distance under this metric to a 0..1 relevance score
+    ///
+    /// - Euclidean: `1 / (1 + d)`, so identical vectors score 1.0 and the
+    ///   score falls toward 0 as distance grows.
+    /// - Cosine: `max(0, 1 - d)`, i.e. the cosine similarity clamped at 0, so
+    ///   orthogonal or opposing vectors score 0.
+    /// - DotProduct: `1 / (1 + e^d)`, the logistic function of the dot
+    ///   product, so orthogonal vectors score 0.5.
+    pub fn normalize(&self, distance: f32) -> f32 {
+        match self {
+            DistanceMetric::Euclidean => 1.0 / (1.0 + distance.max(0.0)),
+            DistanceMetric::Cosine => (1.0 - distance).clamp(0.0, 1.0),
+            DistanceMetric::DotProduct => 1.0 / (1.0 + distance.exp()),
+        }
+    }
 }
 
 /// Search result with distance score
+///
+/// Hits on document chunks have an `id` of `{document_id}#{chunk_id}` (see
+/// `MultimodalDocument::chunk_vector_id`) and a `memory` built from the chunk:
+/// its content, embedding and metadata, plus the `parent_doc` metadata key
+/// naming the document.
 #[derive(Debug, Clone)]
 pub struct SearchResult {
-    /// Memory ID
+    /// Memory ID, or chunk vector ID for document chunks
     pub id: String,
 
     /// Distance score (lower is more similar)
     pub distance: f32,
 
+    /// Metric the distance was computed with
+    pub metric: DistanceMetric,
+
+    /// Relevance in 0..1, filled in by searches (see `relevance`)
+    pub score: Option<f32>,
+
     /// The memory record itself
     pub memory: Memory,
 }
 
+/// Document chunk hit from `OpenDB::search_chunks`
+///
+/// Carries the chunk itself, so its metadata (page numbers, timestamps, ...)
+/// can be cited alongside the parent document id.
+#[derive(Debug, Clone)]
+pub struct ChunkSearchResult {
+    /// ID of the document the chunk belongs to
+    pub document_id: String,
+
+    /// The matching chunk
+    pub chunk: DocumentChunk,
+
+    /// Distance score (lower is more similar)
+    pub distance: f32,
+}
+
+impl SearchResult {
+    /// Relevance in 0..1 (higher is more similar), comparable across metrics
+    ///
+    /// See `DistanceMetric::normalize` for the per-metric formula.
+    pub fn normalized_score(&self) -> f32 {
+        self.metric.normalize(self.distance)
+    }
+
+    /// Relevance in 0..1 (higher is more similar)
+    ///
+    /// Returns the `score` set at search time, or computes it from the
+    /// distance for results built by hand. Prefer this over deriving a score
+    /// from `distance` yourself, which only suits one metric.
+    pub fn relevance(&self) -> f32 {
+        self.score.unwrap_or_else(|| self.normalized_score())
+    }
+}
+
 // ==============================================================================
 // Multimodal File Support for AI/LLM Systems
 // ==============================================================================
@@ -261,6 +886,27 @@ impl MultimodalDocument {
         file_size: usize,
         extracted_text: impl Into<String>,
         embedding: Vec<f32>,
+    ) -> Self {
+        Self::new_at(
+            id,
+            filename,
+            file_type,
+            file_size,
+            extracted_text,
+            embedding,
+            Utc::now().timestamp(),
+        )
+    }
+
+    /// Create a multimodal document with an explicit timestamp (Unix seconds)
+    pub fn new_at(
+        id: impl Into<String>,
+        filename: impl Into<String>,
+        file_type: FileType,
+        file_size: usize,
+        extracted_text: impl Into<String>,
+        embedding: Vec<f32>,
+        timestamp: i64,
     ) -> Self {
         Self {
             id: id.into(),
@@ -270,11 +916,28 @@ impl MultimodalDocument {
             extracted_text: extracted_text.into(),
             chunks: Vec::new(),
             embedding,
-            timestamp: Utc::now().timestamp(),
+            timestamp,
             metadata: HashMap::new(),
         }
     }
 
+    /// Separator between document and chunk id in chunk vector ids
+    ///
+    /// Chunk embeddings are indexed as `{document_id}#{chunk_id}`, which is
+    /// the `id` reported in `SearchResult`s for chunk hits. Document ids
+    /// therefore may not contain `#`.
+    pub const CHUNK_ID_SEPARATOR: char = '#';
+
+    /// Vector id under which a chunk's embedding is indexed
+    pub fn chunk_vector_id(document_id: &str, chunk_id: &str) -> String {
+        format!("{}{}{}", document_id, Self::CHUNK_ID_SEPARATOR, chunk_id)
+    }
+
+    /// Split a chunk vector id into (document id, chunk id)
+    pub fn split_chunk_vector_id(vector_id: &str) -> Option<(&str, &str)> {
+        vector_id.split_once(Self::CHUNK_ID_SEPARATOR)
+    }
+
     /// Add a chunk to this document
     pub fn add_chunk(&mut self, chunk: DocumentChunk) {
         self.chunks.push(chunk);
@@ -316,6 +979,9 @@ pub struct DocumentChunk {
 }
 
 impl DocumentChunk {
+    /// Metadata key linking a chunk's Memory record to its parent document ID
+    pub const PARENT_METADATA_KEY: &'static str = "parent_doc";
+
     /// Create a new document chunk
     pub fn new(
         chunk_id: impl Into<String>,
@@ -334,6 +1000,22 @@ impl DocumentChunk {
         }
     }
 
+    /// Memory view of this chunk, as returned by vector search
+    pub fn to_memory(&self, document: &MultimodalDocument) -> Memory {
+        let mut memory = Memory::new(
+            MultimodalDocument::chunk_vector_id(&document.id, &self.chunk_id),
+            self.content.clone(),
+            self.embedding.clone(),
+            0.5,
+        );
+        memory.timestamp = document.timestamp;
+        memory.metadata = self.metadata.clone();
+        memory
+            .metadata
+            .insert(Self::PARENT_METADATA_KEY.to_string(), document.id.clone());
+        memory
+    }
+
     /// Add chunk metadata (e.g., page number for PDF, timestamp for video)
     pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.metadata.insert(key.into(), value.into());
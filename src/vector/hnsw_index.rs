@@ -1,7 +1,17 @@
 // HNSW index wrapper and utilities
 
+use super::quantization::QuantizedVector;
+use crate::types::DistanceMetric;
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Highest layer a node can be assigned to
+const MAX_LEVEL: usize = 16;
+
 /// HNSW search parameters
-#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct HnswParams {
     /// Maximum number of connections per layer (M)
     pub max_connections: usize,
@@ -23,7 +33,6 @@ impl Default for HnswParams {
     }
 }
 
-#[allow(dead_code)]
 impl HnswParams {
     /// Create params optimized for accuracy
     pub fn high_accuracy() -> Self {
@@ -43,3 +52,550 @@ impl HnswParams {
         }
     }
 }
+
+/// A graph node as persisted in the vector index column family
+///
+/// Live nodes don't carry their vector (it lives in `vector_data`); deleted
+/// nodes keep theirs because they still route searches until compaction.
+#[derive(Debug, bincode::Encode, bincode::Decode)]
+pub(crate) struct NodeRecord {
+    pub id: String,
+    pub neighbors: Vec<Vec<u32>>,
+    pub deleted: bool,
+    pub vector: Option<Vec<f32>>,
+}
+
+/// A node's vector, at full precision or int8-quantized
+enum NodeVector {
+    Full(Vec<f32>),
+    Quantized(QuantizedVector),
+}
+
+impl NodeVector {
+    /// The vector at full precision (approximate when quantized)
+    fn to_f32(&self) -> Cow<'_, [f32]> {
+        match self {
+            NodeVector::Full(vector) => Cow::Borrowed(vector),
+            NodeVector::Quantized(vector) => Cow::Owned(vector.to_vec()),
+        }
+    }
+}
+
+/// In-memory graph node
+struct Node {
+    id: String,
+    vector: NodeVector,
+    /// Neighbor indexes per layer, bottom layer first
+    neighbors: Vec<Vec<u32>>,
+    deleted: bool,
+}
+
+impl Node {
+    fn level(&self) -> usize {
+        self.neighbors.len() - 1
+    }
+}
+
+/// Node index ordered by distance to the query
+#[derive(Clone, Copy)]
+struct Candidate {
+    distance: f32,
+    index: u32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+/// Hierarchical Navigable Small World graph for approximate k-NN
+///
+/// Nodes are addressed by insertion index. Deleting or replacing an id
+/// tombstones its node: it stays in the graph as a waypoint but is never
+/// returned, until the index is rebuilt.
+///
+/// With quantization on, node vectors are held as int8 and every distance
+/// the graph computes is approximate.
+pub struct HnswIndex {
+    params: HnswParams,
+    metric: DistanceMetric,
+    quantize: bool,
+    nodes: Vec<Node>,
+    live: HashMap<String, u32>,
+    entry_point: Option<u32>,
+}
+
+impl HnswIndex {
+    /// Create an empty index
+    pub fn new(params: HnswParams, metric: DistanceMetric) -> Self {
+        Self {
+            params,
+            metric,
+            quantize: false,
+            nodes: Vec::new(),
+            live: HashMap::new(),
+            entry_point: None,
+        }
+    }
+
+    /// Reassemble an index from persisted nodes and their vectors
+    pub(crate) fn from_parts(
+        params: HnswParams,
+        metric: DistanceMetric,
+        entry_point: Option<u32>,
+        nodes: Vec<(NodeRecord, Vec<f32>)>,
+    ) -> Self {
+        let mut live = HashMap::new();
+        let nodes = nodes
+            .into_iter()
+            .enumerate()
+            .map(|(index, (record, vector))| {
+                if !record.deleted {
+                    live.insert(record.id.clone(), index as u32);
+                }
+                Node {
+                    id: record.id,
+                    vector: NodeVector::Full(vector),
+                    neighbors: record.neighbors,
+                    deleted: record.deleted,
+                }
+            })
+            .collect();
+
+        Self {
+            params,
+            metric,
+            quantize: false,
+            nodes,
+            live,
+            entry_point,
+        }
+    }
+
+    /// Hold node vectors int8-quantized, converting any already present
+    pub fn with_quantization(mut self, quantize: bool) -> Self {
+        self.quantize = quantize;
+        if quantize {
+            for node in &mut self.nodes {
+                if let NodeVector::Full(vector) = &node.vector {
+                    node.vector = NodeVector::Quantized(QuantizedVector::new(vector));
+                }
+            }
+        }
+        self
+    }
+
+    /// Whether node vectors are int8-quantized
+    pub fn is_quantized(&self) -> bool {
+        self.quantize
+    }
+
+    /// Number of live (searchable) vectors
+    pub fn len(&self) -> usize {
+        self.live.len()
+    }
+
+    /// Number of deleted nodes still held as waypoints
+    pub fn tombstones(&self) -> usize {
+        self.nodes.len() - self.live.len()
+    }
+
+    /// Total number of graph nodes, including tombstones
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Entry point for searches (a node on the top layer)
+    pub fn entry_point(&self) -> Option<u32> {
+        self.entry_point
+    }
+
+    /// Persisted form of a node
+    pub(crate) fn node_record(&self, index: u32) -> NodeRecord {
+        let node = &self.nodes[index as usize];
+        NodeRecord {
+            id: node.id.clone(),
+            neighbors: node.neighbors.clone(),
+            deleted: node.deleted,
+            vector: node.deleted.then(|| node.vector.to_f32().into_owned()),
+        }
+    }
+
+    /// Live vectors, in insertion order
+    ///
+    /// Quantized vectors are dequantized, so they are approximate.
+    pub fn iter_live(&self) -> impl Iterator<Item = (&str, Cow<'_, [f32]>)> {
+        self.nodes
+            .iter()
+            .filter(|node| !node.deleted)
+            .map(|node| (node.id.as_str(), node.vector.to_f32()))
+    }
+
+    /// Insert or replace a vector, returning the indexes of every changed node
+    pub fn insert(&mut self, id: &str, vector: Vec<f32>) -> Vec<u32> {
+        let mut dirty = Vec::new();
+        if let Some(old) = self.remove(id) {
+            dirty.push(old);
+        }
+
+        let index = self.nodes.len() as u32;
+        let level = self.random_level(id, index);
+        let vector = if self.quantize {
+            NodeVector::Quantized(QuantizedVector::new(&vector))
+        } else {
+            NodeVector::Full(vector)
+        };
+        self.nodes.push(Node {
+            id: id.to_string(),
+            vector,
+            neighbors: vec![Vec::new(); level + 1],
+            deleted: false,
+        });
+        self.live.insert(id.to_string(), index);
+        dirty.push(index);
+
+        // With only tombstones left there is nothing to link to, so restart
+        // the graph from this node
+        let entry = match self.entry_point {
+            Some(entry) if self.live.len() > 1 => entry,
+            _ => {
+                self.entry_point = Some(index);
+                return dirty;
+            }
+        };
+
+        let top = self.nodes[entry as usize].level();
+        let query = self.nodes[index as usize].vector.to_f32().into_owned();
+
+        // Greedy descent through the layers above the new node
+        let mut entries = vec![entry];
+        for layer in (level + 1..=top).rev() {
+            entries = vec![self.search_layer(&query, &entries, 1, layer)[0].index];
+        }
+
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&query, &entries, self.params.ef_construction, layer);
+            let cap = self.max_connections(layer);
+
+            let live: Vec<Candidate> = found
+                .iter()
+                .filter(|c| c.index != index && !self.nodes[c.index as usize].deleted)
+                .copied()
+                .collect();
+            let selected = self.select_neighbors(&live, cap);
+
+            for &neighbor in &selected {
+                let links = &mut self.nodes[neighbor as usize].neighbors[layer];
+                links.push(index);
+                if links.len() > cap {
+                    self.prune(neighbor, layer, cap);
+                }
+                dirty.push(neighbor);
+            }
+            self.nodes[index as usize].neighbors[layer] = selected;
+
+            entries = found.iter().map(|c| c.index).collect();
+        }
+
+        if level > top {
+            self.entry_point = Some(index);
+        }
+
+        dirty.sort_unstable();
+        dirty.dedup();
+        dirty
+    }
+
+    /// Tombstone the node holding `id`, returning its index
+    pub fn remove(&mut self, id: &str) -> Option<u32> {
+        let index = self.live.remove(id)?;
+        self.nodes[index as usize].deleted = true;
+        Some(index)
+    }
+
+    /// Find the `k` nearest live vectors, exploring `ef` candidates
+    ///
+    /// Small indexes (no more than `max(ef, k)` live vectors) are scanned
+    /// exhaustively, since the graph search would visit them all anyway.
+    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Vec<(String, f32)> {
+        let ef = ef.max(k);
+        if self.live.len() <= ef {
+            let mut results: Vec<(String, f32)> = self
+                .nodes
+                .iter()
+                .filter(|node| !node.deleted)
+                .map(|node| (node.id.clone(), self.vector_distance(query, &node.vector)))
+                .collect();
+            results.sort_by(|a, b| a.1.total_cmp(&b.1));
+            results.truncate(k);
+            return results;
+        }
+
+        let Some(entry) = self.entry_point else {
+            return Vec::new();
+        };
+
+        let mut entries = vec![entry];
+        for layer in (1..=self.nodes[entry as usize].level()).rev() {
+            entries = vec![self.search_layer(query, &entries, 1, layer)[0].index];
+        }
+
+        self.search_layer(query, &entries, ef, 0)
+            .into_iter()
+            .filter(|c| !self.nodes[c.index as usize].deleted)
+            .take(k)
+            .map(|c| (self.nodes[c.index as usize].id.clone(), c.distance))
+            .collect()
+    }
+
+    /// Best-first search of one layer, returning up to `ef` nodes closest first
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entries: &[u32],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<u32> = entries.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut results = BinaryHeap::new();
+
+        for &index in entries {
+            let candidate = Candidate {
+                distance: self.distance_to(query, index),
+                index,
+            };
+            candidates.push(Reverse(candidate));
+            results.push(candidate);
+        }
+        while results.len() > ef.max(1) {
+            results.pop();
+        }
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            if let Some(worst) = results.peek()
+                && results.len() >= ef
+                && current.distance > worst.distance
+            {
+                break;
+            }
+
+            let Some(links) = self.nodes[current.index as usize].neighbors.get(layer) else {
+                continue;
+            };
+            for &neighbor in links {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let candidate = Candidate {
+                    distance: self.distance_to(query, neighbor),
+                    index: neighbor,
+                };
+                let improves = results
+                    .peek()
+                    .is_none_or(|worst| candidate.distance < worst.distance);
+                if results.len() < ef || improves {
+                    candidates.push(Reverse(candidate));
+                    results.push(candidate);
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        results.into_sorted_vec()
+    }
+
+    /// Keep only the `cap` best links of a node on one layer
+    ///
+    /// Live neighbors are chosen with the selection heuristic; tombstones are
+    /// kept only if there is room left.
+    fn prune(&mut self, index: u32, layer: usize, cap: usize) {
+        let origin = self.nodes[index as usize].vector.to_f32();
+        let (mut live, mut deleted): (Vec<Candidate>, Vec<Candidate>) = self.nodes[index as usize]
+            .neighbors[layer]
+            .iter()
+            .map(|&n| Candidate {
+                distance: self.distance_to(&origin, n),
+                index: n,
+            })
+            .partition(|c| !self.nodes[c.index as usize].deleted);
+        live.sort();
+        deleted.sort();
+
+        let mut links = self.select_neighbors(&live, cap);
+        links.extend(deleted.iter().take(cap - links.len()).map(|c| c.index));
+        self.nodes[index as usize].neighbors[layer] = links;
+    }
+
+    /// Pick up to `cap` neighbors from candidates sorted by distance
+    ///
+    /// A candidate is preferred only if it is closer to the base node than to
+    /// every neighbor picked so far, which spreads links across directions
+    /// and keeps tight clusters connected to the rest of the graph. Remaining
+    /// slots are filled with the closest skipped candidates.
+    fn select_neighbors(&self, candidates: &[Candidate], cap: usize) -> Vec<u32> {
+        let mut selected: Vec<u32> = Vec::with_capacity(cap);
+        let mut skipped = Vec::new();
+        for candidate in candidates {
+            if selected.len() == cap {
+                break;
+            }
+            let vector = self.nodes[candidate.index as usize].vector.to_f32();
+            let diverse = selected
+                .iter()
+                .all(|&s| self.distance_to(&vector, s) > candidate.distance);
+            if diverse {
+                selected.push(candidate.index);
+            } else {
+                skipped.push(candidate.index);
+            }
+        }
+
+        let room = cap - selected.len();
+        selected.extend(skipped.into_iter().take(room));
+        selected
+    }
+
+    /// Link budget per node: doubled on the dense bottom layer
+    fn max_connections(&self, layer: usize) -> usize {
+        let m = self.params.max_connections.max(1);
+        if layer == 0 { 2 * m } else { m }
+    }
+
+    fn distance_to(&self, query: &[f32], index: u32) -> f32 {
+        self.vector_distance(query, &self.nodes[index as usize].vector)
+    }
+
+    fn vector_distance(&self, query: &[f32], vector: &NodeVector) -> f32 {
+        match vector {
+            NodeVector::Full(vector) => self.metric.distance(query, vector),
+            NodeVector::Quantized(vector) => vector.distance(self.metric, query),
+        }
+    }
+
+    /// Draw a layer from the usual exponential distribution
+    ///
+    /// Seeded from the id and insertion index, so rebuilding from the same
+    /// data in the same order reproduces the same graph.
+    fn random_level(&self, id: &str, index: u32) -> usize {
+        let mut hasher = DefaultHasher::new();
+        (id, index).hash(&mut hasher);
+        let uniform = ((hasher.finish() >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+
+        let level_mult = 1.0 / (self.params.max_connections.max(2) as f64).ln();
+        ((-uniform.ln() * level_mult) as usize).min(MAX_LEVEL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random vectors
+    fn vectors(count: usize, dimension: usize) -> Vec<Vec<f32>> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..count)
+            .map(|_| {
+                (0..dimension)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        (state % 1000) as f32 / 1000.0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_search_recall_against_brute_force() {
+        let data = vectors(2000, 16);
+        let mut index = HnswIndex::new(HnswParams::default(), DistanceMetric::Euclidean);
+        for (i, vector) in data.iter().enumerate() {
+            index.insert(&format!("v{}", i), vector.clone());
+        }
+
+        let mut hits = 0;
+        for query in &data[..50] {
+            let approximate = index.search(query, 10, 50);
+            let mut exact: Vec<(usize, f32)> = data
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i, DistanceMetric::Euclidean.distance(query, v)))
+                .collect();
+            exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+            let exact_ids: HashSet<String> =
+                exact[..10].iter().map(|(i, _)| format!("v{}", i)).collect();
+            hits += approximate
+                .iter()
+                .filter(|(id, _)| exact_ids.contains(id))
+                .count();
+        }
+
+        let recall = hits as f32 / 500.0;
+        assert!(recall >= 0.9, "recall too low: {}", recall);
+    }
+
+    #[test]
+    fn test_replace_and_remove_tombstone_nodes() {
+        let data = vectors(300, 8);
+        let mut index = HnswIndex::new(HnswParams::default(), DistanceMetric::Euclidean);
+        for (i, vector) in data.iter().enumerate() {
+            index.insert(&format!("v{}", i), vector.clone());
+        }
+
+        // Replacing v0 moves it next to v1
+        index.insert("v0", data[1].clone());
+        assert!(index.remove("v2").is_some());
+        assert!(index.remove("v2").is_none());
+        assert_eq!(index.len(), 299);
+        assert_eq!(index.tombstones(), 2);
+
+        let results = index.search(&data[1], 2, 10);
+        let ids: HashSet<&str> = results.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, HashSet::from(["v0", "v1"]));
+        assert!(
+            index
+                .search(&data[2], 5, 10)
+                .iter()
+                .all(|(id, _)| id != "v2")
+        );
+    }
+
+    #[test]
+    fn test_insert_after_only_tombstones_remain() {
+        let data = vectors(100, 8);
+        let mut index = HnswIndex::new(HnswParams::default(), DistanceMetric::Euclidean);
+        index.insert("v0", data[50].clone());
+
+        // Replacing the only vector leaves nothing live to link against
+        for (i, vector) in data.iter().enumerate() {
+            index.insert(&format!("v{}", i), vector.clone());
+        }
+
+        for (i, vector) in data.iter().enumerate() {
+            assert_eq!(index.search(vector, 1, 10)[0].0, format!("v{}", i));
+        }
+    }
+}
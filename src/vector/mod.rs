@@ -1,18 +1,59 @@
 // Vector search functionality with HNSW
 
 pub mod hnsw_index;
+pub mod quantization;
 
 use crate::error::{Error, Result};
-use crate::storage::{SharedStorage, column_families::ColumnFamilies};
-use crate::types::Memory;
+use crate::storage::{BatchOp, SharedStorage, Transaction, column_families::ColumnFamilies};
+use crate::types::{DistanceMetric, Memory};
+use hnsw_index::{HnswIndex, HnswParams, NodeRecord};
 use parking_lot::RwLock;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::ControlFlow;
 use std::sync::Arc;
 
+/// Per-field vector caches, built lazily on first search of each field
+type FieldCaches = HashMap<String, Vec<(String, Vec<f32>)>>;
+
+/// Key prefix separating named field vectors from default embeddings
+const FIELD_KEY_PREFIX: u8 = 0;
+
+/// Vector index key holding the HNSW graph metadata
+const INDEX_META_KEY: &[u8] = b"hnsw_meta";
+
+/// Vector index key prefix for HNSW nodes (followed by the big-endian index)
+const INDEX_NODE_PREFIX: &[u8] = b"hnsw_node:";
+
+/// Persisted HNSW graph layout version
+const INDEX_FORMAT_VERSION: u8 = 1;
+
+/// Persisted HNSW graph metadata
+#[derive(Debug, bincode::Encode, bincode::Decode)]
+struct IndexMeta {
+    version: u8,
+    max_connections: u32,
+    node_count: u32,
+    entry_point: Option<u32>,
+}
+
 /// Vector manager for semantic search
+#[derive(Clone)]
 pub struct VectorManager {
     storage: SharedStorage,
-    cache: Arc<RwLock<Option<Vec<(String, Vec<f32>)>>>>,
+    index: Arc<RwLock<Option<HnswIndex>>>,
+    field_caches: Arc<RwLock<FieldCaches>>,
     dimension: usize,
+    field_dimensions: HashMap<String, usize>,
+    metric: DistanceMetric,
+    params: HnswParams,
+    persist_index: bool,
+    normalize: bool,
+    quantize: bool,
+    rerank_factor: usize,
+    /// Ids of stored default vectors left out of the index for having the
+    /// wrong dimension, as of the last index build
+    mismatched: Arc<RwLock<Vec<String>>>,
 }
 
 impl VectorManager {
@@ -20,126 +61,1064 @@ impl VectorManager {
     pub fn new(storage: SharedStorage, dimension: usize) -> Self {
         Self {
             storage,
-            cache: Arc::new(RwLock::new(None)),
+            index: Arc::new(RwLock::new(None)),
+            field_caches: Arc::new(RwLock::new(HashMap::new())),
             dimension,
+            field_dimensions: HashMap::new(),
+            metric: DistanceMetric::default(),
+            params: HnswParams::default(),
+            persist_index: true,
+            normalize: false,
+            quantize: false,
+            rerank_factor: 1,
+            mismatched: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
+    /// Set the HNSW graph parameters
+    pub fn with_hnsw_params(mut self, params: HnswParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Set whether the HNSW graph is persisted to the vector index column family
+    ///
+    /// Without persistence the graph is rebuilt from the vectors on first use.
+    pub fn with_index_persistence(mut self, persist: bool) -> Self {
+        self.persist_index = persist;
+        self
+    }
+
+    /// Set the distance metric used for search
+    pub fn with_metric(mut self, metric: DistanceMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Set whether vectors and queries are L2-normalized before use
+    pub fn with_normalization(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Set whether the in-memory graph holds int8-quantized vectors
+    ///
+    /// Searches fetch `rerank_factor` times as many candidates and re-rank
+    /// them against the full-precision vectors in storage; a factor of 1
+    /// returns the approximate distances as they are.
+    pub fn with_quantization(mut self, quantize: bool, rerank_factor: usize) -> Self {
+        self.quantize = quantize;
+        self.rerank_factor = rerank_factor.max(1);
+        self
+    }
+
+    /// Helper: The form of a vector that is stored and searched
+    ///
+    /// With normalization on, this is the unit-length vector; zero vectors
+    /// are left as-is.
+    fn prepare<'a>(&self, embedding: &'a [f32]) -> Cow<'a, [f32]> {
+        if !self.normalize {
+            return Cow::Borrowed(embedding);
+        }
+        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm == 0.0 || (norm - 1.0).abs() <= f32::EPSILON {
+            return Cow::Borrowed(embedding);
+        }
+        Cow::Owned(embedding.iter().map(|x| x / norm).collect())
+    }
+
+    /// Distance metric used for search
+    pub fn metric(&self) -> DistanceMetric {
+        self.metric
+    }
+
+    /// Set per-field dimensions; unlisted fields use the default dimension
+    pub fn with_field_dimensions(mut self, field_dimensions: HashMap<String, usize>) -> Self {
+        self.field_dimensions = field_dimensions;
+        self
+    }
+
+    /// Expected dimension for a named field
+    pub fn field_dimension(&self, field: &str) -> usize {
+        self.field_dimensions
+            .get(field)
+            .copied()
+            .unwrap_or(self.dimension)
+    }
+
     /// Insert a memory with its vector embedding
     pub fn insert(&self, memory: &Memory) -> Result<()> {
-        if memory.embedding.len() != self.dimension {
-            return Err(Error::VectorIndex(format!(
-                "Expected dimension {}, got {}",
-                self.dimension,
-                memory.embedding.len()
-            )));
+        self.insert_embedding(&memory.id, &memory.embedding)
+    }
+
+    /// Index an embedding under an arbitrary vector id
+    ///
+    /// Adds a single node to the HNSW graph; the vector and the changed graph
+    /// nodes are written in one transaction.
+    pub fn insert_embedding(&self, id: &str, embedding: &[f32]) -> Result<()> {
+        self.check_embedding(embedding)?;
+        self.ensure_index_loaded()?;
+        let embedding = self.prepare(embedding);
+
+        let mut guard = self.index.write();
+        let index = guard
+            .as_mut()
+            .ok_or_else(|| Error::VectorIndex("Index not loaded".to_string()))?;
+        let mut txn = self.storage.begin_transaction()?;
+        let dirty = index.insert(id, embedding.to_vec());
+        txn.put(
+            ColumnFamilies::VECTOR_DATA,
+            id.as_bytes(),
+            &encode_embedding(&embedding)?,
+        )?;
+        self.commit_index_update(txn, &mut guard, &dirty)
+    }
+
+    /// Check that an embedding can be stored in the default index
+    pub fn check_embedding(&self, embedding: &[f32]) -> Result<()> {
+        if embedding.len() != self.dimension {
+            return Err(Error::DimensionMismatch {
+                expected: self.dimension,
+                got: embedding.len(),
+                field: None,
+            });
         }
 
-        // Store the embedding
-        let key = memory.id.as_bytes();
-        let embedding_bytes =
-            bincode::encode_to_vec(&memory.embedding, bincode::config::standard())
-                .map_err(|e| Error::Codec(format!("Failed to serialize embedding: {}", e)))?;
+        self.check_norm(embedding)
+    }
 
-        self.storage
-            .put(ColumnFamilies::VECTOR_DATA, key, &embedding_bytes)?;
+    /// Reject zero vectors when the metric can't compare them
+    ///
+    /// A zero embedding almost always comes from a failed embedding call.
+    fn check_norm(&self, embedding: &[f32]) -> Result<()> {
+        if self.metric.requires_nonzero_norm() && embedding.iter().all(|x| *x == 0.0) {
+            return Err(Error::InvalidInput(format!(
+                "Zero-norm embedding is not allowed with the {:?} metric",
+                self.metric
+            )));
+        }
+        Ok(())
+    }
 
-        // Invalidate cache
-        *self.cache.write() = None;
+    /// Count stored default vectors, reading keys only
+    ///
+    /// Named field vectors are not included. Vectors of the wrong dimension
+    /// are, even though they are left out of the index.
+    pub fn count(&self) -> Result<usize> {
+        let mut count = 0;
+        self.storage
+            .for_each_key(ColumnFamilies::VECTOR_DATA, &[], &mut |key| {
+                if key.first() != Some(&FIELD_KEY_PREFIX) {
+                    count += 1;
+                }
+                Ok(ControlFlow::Continue(()))
+            })?;
+        Ok(count)
+    }
 
+    /// Fail when every stored vector was left out for its dimension
+    ///
+    /// Searching would silently return nothing. When only some vectors
+    /// mismatch, the rest are searched and `mismatched_vectors` lists the
+    /// skipped ones.
+    fn check_searchable(&self, index: &HnswIndex) -> Result<()> {
+        let mismatched = self.mismatched.read();
+        if index.len() == 0 && !mismatched.is_empty() {
+            return Err(Error::VectorIndex(format!(
+                "None of the {} stored vectors has the configured dimension {}; \
+                 reopen with the dimension they were written with",
+                mismatched.len(),
+                self.dimension
+            )));
+        }
         Ok(())
     }
 
+    /// Ids of stored vectors whose dimension differs from the configured one
+    ///
+    /// These are skipped when the index is built, so they never appear in
+    /// search results.
+    pub fn mismatched_vectors(&self) -> Result<Vec<String>> {
+        self.ensure_index_loaded()?;
+        Ok(self.mismatched.read().clone())
+    }
+
     /// Search for similar vectors
     pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<(String, f32)>> {
         if query.len() != self.dimension {
-            return Err(Error::VectorIndex(format!(
-                "Query dimension mismatch: expected {}, got {}",
-                self.dimension,
-                query.len()
-            )));
+            return Err(Error::DimensionMismatch {
+                expected: self.dimension,
+                got: query.len(),
+                field: None,
+            });
         }
 
-        // Ensure cache is built
-        self.ensure_cache_built()?;
+        self.ensure_index_loaded()?;
+        let query = self.prepare(query);
 
-        let cache = self.cache.read();
-        let vectors = cache
+        let guard = self.index.read();
+        let index = guard
             .as_ref()
-            .ok_or_else(|| Error::VectorIndex("Cache not built".to_string()))?;
+            .ok_or_else(|| Error::VectorIndex("Index not loaded".to_string()))?;
+        self.check_searchable(index)?;
+
+        self.search_index(index, &query, k)
+    }
 
-        if vectors.is_empty() {
+    /// Search the loaded graph, re-ranking quantized results at full precision
+    fn search_index(
+        &self,
+        index: &HnswIndex,
+        query: &[f32],
+        k: usize,
+    ) -> Result<Vec<(String, f32)>> {
+        if !index.is_quantized() || self.rerank_factor == 1 {
+            return Ok(index.search(query, k, self.params.ef_search));
+        }
+
+        let candidates = index.search(
+            query,
+            k.saturating_mul(self.rerank_factor),
+            self.params.ef_search,
+        );
+        let mut results = Vec::with_capacity(candidates.len());
+        for (id, approximate) in candidates {
+            let distance = match self
+                .storage
+                .get(ColumnFamilies::VECTOR_DATA, id.as_bytes())?
+            {
+                Some(bytes) => self.metric.distance(query, &decode_embedding(&bytes)?),
+                None => approximate,
+            };
+            results.push((id, distance));
+        }
+        results.sort_by(|a, b| a.1.total_cmp(&b.1));
+        results.truncate(k);
+        Ok(results)
+    }
+
+    /// Search for the `k` nearest vectors of each query
+    ///
+    /// Every query's dimension is checked before any search runs. The index
+    /// is loaded and read-locked once for the whole batch, and the queries
+    /// are split across scoped threads. Results are in query order.
+    pub fn search_batch(&self, queries: &[Vec<f32>], k: usize) -> Result<Vec<Vec<(String, f32)>>> {
+        if let Some(query) = queries.iter().find(|query| query.len() != self.dimension) {
+            return Err(Error::DimensionMismatch {
+                expected: self.dimension,
+                got: query.len(),
+                field: None,
+            });
+        }
+        if queries.is_empty() {
             return Ok(Vec::new());
         }
 
-        // Brute-force k-NN search
+        self.ensure_index_loaded()?;
+        let prepared: Vec<Cow<[f32]>> = queries.iter().map(|query| self.prepare(query)).collect();
+
+        let guard = self.index.read();
+        let index = guard
+            .as_ref()
+            .ok_or_else(|| Error::VectorIndex("Index not loaded".to_string()))?;
+        self.check_searchable(index)?;
+
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(prepared.len());
+        if threads <= 1 {
+            return prepared
+                .iter()
+                .map(|query| self.search_index(index, query, k))
+                .collect();
+        }
+
+        let chunk_size = prepared.len().div_ceil(threads);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = prepared
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|query| self.search_index(index, query, k))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("batch search thread panicked"))
+                .collect()
+        })
+    }
+
+    /// Find all vectors within `max_distance` of the query, closest first
+    ///
+    /// With `early_exit`, distance computations are abandoned once they pass
+    /// the threshold (see `DistanceMetric::distance_within`); the results are
+    /// the same either way. A quantized graph can't bound its error, so the
+    /// full-precision vectors are read from storage instead.
+    pub fn search_within(
+        &self,
+        query: &[f32],
+        max_distance: f32,
+        early_exit: bool,
+    ) -> Result<Vec<(String, f32)>> {
+        if query.len() != self.dimension {
+            return Err(Error::DimensionMismatch {
+                expected: self.dimension,
+                got: query.len(),
+                field: None,
+            });
+        }
+
+        self.ensure_index_loaded()?;
+        let query = self.prepare(query);
+
+        let guard = self.index.read();
+        let index = guard
+            .as_ref()
+            .ok_or_else(|| Error::VectorIndex("Index not loaded".to_string()))?;
+        self.check_searchable(index)?;
+
+        let stored;
+        let vectors: Vec<(&str, Cow<[f32]>)> = if index.is_quantized() {
+            stored = self.default_vectors()?;
+            stored
+                .iter()
+                .map(|(id, embedding)| (id.as_str(), Cow::Borrowed(embedding.as_slice())))
+                .collect()
+        } else {
+            index.iter_live().collect()
+        };
+
+        // Exhaustive: the threshold must hold for every returned vector
         let mut results: Vec<(String, f32)> = vectors
-            .iter()
-            .map(|(id, embedding)| {
-                let distance = euclidean_distance(query, embedding);
-                (id.clone(), distance)
+            .into_iter()
+            .filter_map(|(id, embedding)| {
+                let distance = if early_exit {
+                    self.metric
+                        .distance_within(&query, &embedding, max_distance)
+                } else {
+                    Some(self.metric.distance(&query, &embedding))
+                        .filter(|distance| *distance <= max_distance)
+                };
+                distance.map(|distance| (id.to_string(), distance))
             })
             .collect();
 
-        // Sort by distance and take top k
         results.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
-        results.truncate(k);
-
         Ok(results)
     }
 
-    /// Ensure cache is built from storage
-    fn ensure_cache_built(&self) -> Result<()> {
-        // Check if cache exists
-        if self.cache.read().is_some() {
+    /// Replace the named field vectors of a memory
+    ///
+    /// Fields the memory no longer carries are removed from the index.
+    pub fn set_fields(&self, memory: &Memory) -> Result<()> {
+        self.check_fields(memory)?;
+
+        self.delete_fields(&memory.id)?;
+        for (field, embedding) in &memory.embeddings {
+            if embedding.is_empty() {
+                continue;
+            }
+            self.storage.put(
+                ColumnFamilies::VECTOR_DATA,
+                &field_key(&memory.id, field),
+                &encode_embedding(&self.prepare(embedding))?,
+            )?;
+        }
+
+        self.field_caches.write().clear();
+
+        Ok(())
+    }
+
+    /// Check that every named field vector of a memory can be stored
+    fn check_fields(&self, memory: &Memory) -> Result<()> {
+        for (field, embedding) in &memory.embeddings {
+            let expected = self.field_dimension(field);
+            if !embedding.is_empty() && embedding.len() != expected {
+                return Err(Error::DimensionMismatch {
+                    expected,
+                    got: embedding.len(),
+                    field: Some(field.clone()),
+                });
+            }
+            if !embedding.is_empty() {
+                self.check_norm(embedding)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Batch writes that store the vectors of several memories
+    ///
+    /// Every embedding is validated before any operation is produced. Each
+    /// memory's default vector is put (or deleted when empty) and its named
+    /// field vectors replaced. Once the batch is written, call `index_batch`
+    /// with the same memories to update the graph.
+    pub fn batch_ops(&self, memories: &[Memory]) -> Result<Vec<BatchOp>> {
+        for memory in memories {
+            if !memory.embedding.is_empty() {
+                self.check_embedding(&memory.embedding)?;
+            }
+            self.check_fields(memory)?;
+        }
+
+        // Load the graph now so it reflects storage before the batch lands
+        self.ensure_index_loaded()?;
+
+        let mut ops = Vec::new();
+        for memory in memories {
+            let id = memory.id.as_bytes();
+            if memory.embedding.is_empty() {
+                ops.push(BatchOp::delete(ColumnFamilies::VECTOR_DATA, id));
+            } else {
+                ops.push(BatchOp::put(
+                    ColumnFamilies::VECTOR_DATA,
+                    id,
+                    encode_embedding(&self.prepare(&memory.embedding))?,
+                ));
+            }
+
+            let prefix = field_key(&memory.id, "");
+            for (key, _) in self
+                .storage
+                .scan_prefix(ColumnFamilies::VECTOR_DATA, &prefix)?
+            {
+                ops.push(BatchOp::delete(ColumnFamilies::VECTOR_DATA, &key));
+            }
+            for (field, embedding) in &memory.embeddings {
+                if !embedding.is_empty() {
+                    ops.push(BatchOp::put(
+                        ColumnFamilies::VECTOR_DATA,
+                        &field_key(&memory.id, field),
+                        encode_embedding(&self.prepare(embedding))?,
+                    ));
+                }
+            }
+        }
+        Ok(ops)
+    }
+
+    /// Add a written batch (see `batch_ops`) to the graph in one update
+    ///
+    /// If the graph was unloaded in the meantime, the persisted one no longer
+    /// matches the vector data and is rebuilt on next use instead.
+    pub fn index_batch(&self, memories: &[Memory]) -> Result<()> {
+        self.field_caches.write().clear();
+
+        let mut guard = self.index.write();
+        let Some(index) = guard.as_mut() else {
             return Ok(());
+        };
+
+        let mut dirty = BTreeSet::new();
+        for memory in memories {
+            if memory.embedding.is_empty() {
+                dirty.extend(index.remove(&memory.id));
+            } else {
+                let embedding = self.prepare(&memory.embedding).into_owned();
+                dirty.extend(index.insert(&memory.id, embedding));
+            }
+        }
+
+        let dirty: Vec<u32> = dirty.into_iter().collect();
+        let txn = self.storage.begin_transaction()?;
+        self.commit_index_update(txn, &mut guard, &dirty)
+    }
+
+    /// Batch writes that remove every stored vector of a memory
+    ///
+    /// Once the batch is written, call `unindex` with the same ids.
+    pub fn delete_ops(&self, id: &str) -> Result<Vec<BatchOp>> {
+        // Load the graph now so it reflects storage before the batch lands
+        self.ensure_index_loaded()?;
+
+        let mut ops = vec![BatchOp::delete(ColumnFamilies::VECTOR_DATA, id.as_bytes())];
+        for (key, _) in self
+            .storage
+            .scan_prefix(ColumnFamilies::VECTOR_DATA, &field_key(id, ""))?
+        {
+            ops.push(BatchOp::delete(ColumnFamilies::VECTOR_DATA, &key));
+        }
+        Ok(ops)
+    }
+
+    /// Remove written deletions (see `delete_ops`) from the graph in one update
+    pub fn unindex(&self, ids: &[String]) -> Result<()> {
+        self.field_caches.write().clear();
+
+        let mut guard = self.index.write();
+        let Some(index) = guard.as_mut() else {
+            return Ok(());
+        };
+
+        let dirty: BTreeSet<u32> = ids.iter().flat_map(|id| index.remove(id)).collect();
+        let dirty: Vec<u32> = dirty.into_iter().collect();
+        let txn = self.storage.begin_transaction()?;
+        self.commit_index_update(txn, &mut guard, &dirty)
+    }
+
+    /// Remove all named field vectors of a memory
+    pub fn delete_fields(&self, id: &str) -> Result<()> {
+        let prefix = field_key(id, "");
+        for (key, _) in self
+            .storage
+            .scan_prefix(ColumnFamilies::VECTOR_DATA, &prefix)?
+        {
+            self.storage.delete(ColumnFamilies::VECTOR_DATA, &key)?;
+        }
+
+        self.field_caches.write().clear();
+
+        Ok(())
+    }
+
+    /// Delete stored vectors whose owner fails `is_live`
+    ///
+    /// Default vectors are checked by id and named field vectors by the id
+    /// of the memory they belong to. Returns how many vectors were deleted.
+    pub fn remove_orphans(&self, is_live: &mut dyn FnMut(&str) -> Result<bool>) -> Result<usize> {
+        let mut keys = Vec::new();
+        self.storage
+            .for_each_key(ColumnFamilies::VECTOR_DATA, &[], &mut |key| {
+                keys.push(key.to_vec());
+                Ok(ControlFlow::Continue(()))
+            })?;
+
+        let mut removed = 0;
+        for key in keys {
+            if key.first() == Some(&FIELD_KEY_PREFIX) {
+                let Some((id, _)) = split_field_key(&key) else {
+                    continue;
+                };
+                if !is_live(&id)? {
+                    self.storage.delete(ColumnFamilies::VECTOR_DATA, &key)?;
+                    self.field_caches.write().clear();
+                    removed += 1;
+                }
+            } else {
+                let id = String::from_utf8_lossy(&key).into_owned();
+                if !is_live(&id)? {
+                    self.delete(&id)?;
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Search for similar vectors within one named field
+    pub fn search_field(&self, field: &str, query: &[f32], k: usize) -> Result<Vec<(String, f32)>> {
+        let expected = self.field_dimension(field);
+        if query.len() != expected {
+            return Err(Error::DimensionMismatch {
+                expected,
+                got: query.len(),
+                field: Some(field.to_string()),
+            });
+        }
+
+        let query = self.prepare(query);
+        if let Some(vectors) = self.field_caches.read().get(field) {
+            return Ok(nearest(&query, vectors, k, self.metric));
         }
 
-        // Build cache
+        // Build this field's cache
         let mut values = Vec::new();
+        for (key, value) in self
+            .storage
+            .scan_prefix(ColumnFamilies::VECTOR_DATA, &[FIELD_KEY_PREFIX])?
+        {
+            let Some((id, key_field)) = split_field_key(&key) else {
+                continue;
+            };
+            if key_field == field {
+                let embedding = decode_embedding(&value)?;
+                // Skipped like mismatched default vectors
+                if embedding.len() == expected {
+                    values.push((id, embedding));
+                }
+            }
+        }
+
+        let results = nearest(&query, &values, k, self.metric);
+        self.field_caches.write().insert(field.to_string(), values);
+
+        Ok(results)
+    }
+
+    /// Load the HNSW graph, from the vector index if valid, else by rebuilding
+    fn ensure_index_loaded(&self) -> Result<()> {
+        if self.index.read().is_some() {
+            return Ok(());
+        }
+
+        let mut guard = self.index.write();
+        if guard.is_some() {
+            return Ok(());
+        }
+
+        let loaded = if self.persist_index {
+            self.load_persisted_index()?
+        } else {
+            None
+        };
+        let index = match loaded {
+            Some(index) => index,
+            None => {
+                let index = self.build_index()?;
+                // A read-only database keeps the rebuilt graph in memory only
+                if !self.storage.is_read_only() {
+                    self.persist_whole_index(&index)?;
+                }
+                index
+            }
+        };
+        *guard = Some(index);
+
+        Ok(())
+    }
+
+    /// Build a fresh graph from the default vectors in storage
+    fn build_index(&self) -> Result<HnswIndex> {
+        self.build_index_with_progress(&|_, _| {})
+    }
+
+    /// Build a fresh graph, reporting `(processed, total)` after each vector
+    fn build_index_with_progress(&self, progress: &dyn Fn(usize, usize)) -> Result<HnswIndex> {
+        let vectors = self.default_vectors()?;
+        let total = vectors.len();
+        progress(0, total);
+        let mut index = HnswIndex::new(self.params, self.metric).with_quantization(self.quantize);
+        for (processed, (id, embedding)) in vectors.into_iter().enumerate() {
+            index.insert(&id, embedding);
+            progress(processed + 1, total);
+        }
+        Ok(index)
+    }
 
-        // Scan all vectors
-        let pairs = self.storage.scan_prefix(ColumnFamilies::VECTOR_DATA, &[])?;
+    /// All default (non-field) vectors in storage, in key order
+    ///
+    /// Vectors of the wrong dimension are skipped and recorded in
+    /// `mismatched`; comparing them against a query would be meaningless.
+    fn default_vectors(&self) -> Result<Vec<(String, Vec<f32>)>> {
+        let mut values = Vec::new();
+        let mut mismatched = Vec::new();
+        for (key, value) in self.storage.scan_prefix(ColumnFamilies::VECTOR_DATA, &[])? {
+            // Named field vectors live under their own prefix
+            if key.first() == Some(&FIELD_KEY_PREFIX) {
+                continue;
+            }
 
-        for (key, value) in pairs {
             let id = String::from_utf8(key)
                 .map_err(|e| Error::VectorIndex(format!("Invalid key: {}", e)))?;
 
-            let (embedding, _): (Vec<f32>, usize) =
-                bincode::decode_from_slice(&value, bincode::config::standard())
-                    .map_err(|e| Error::Codec(format!("Failed to deserialize embedding: {}", e)))?;
+            let embedding = decode_embedding(&value)?;
+            if embedding.len() == self.dimension {
+                values.push((id, embedding));
+            } else {
+                mismatched.push(id);
+            }
+        }
+        *self.mismatched.write() = mismatched;
+        Ok(values)
+    }
 
-            values.push((id, embedding));
+    /// Reassemble the persisted graph
+    ///
+    /// Returns `None` when there is no usable graph: none was persisted, it
+    /// was built with other parameters, or its live nodes don't match the
+    /// stored vectors exactly (e.g. after a crash or an out-of-band write).
+    /// Live node vectors are always taken from the vector data.
+    fn load_persisted_index(&self) -> Result<Option<HnswIndex>> {
+        let Some(meta_bytes) = self
+            .storage
+            .get(ColumnFamilies::VECTOR_INDEX, INDEX_META_KEY)?
+        else {
+            return Ok(None);
+        };
+        let meta: IndexMeta = decode_index_value(&meta_bytes)?;
+        if meta.version != INDEX_FORMAT_VERSION
+            || meta.max_connections as usize != self.params.max_connections
+        {
+            return Ok(None);
         }
 
-        *self.cache.write() = Some(values);
+        let mut vectors: HashMap<String, Vec<f32>> = self.default_vectors()?.into_iter().collect();
+        let mut nodes = Vec::with_capacity(meta.node_count as usize);
+        let mut consistent = true;
+        self.storage.for_each_prefix(
+            ColumnFamilies::VECTOR_INDEX,
+            INDEX_NODE_PREFIX,
+            &mut |key, value| {
+                let record: NodeRecord = decode_index_value(value)?;
+                let vector = if record.deleted {
+                    record.vector.clone()
+                } else {
+                    vectors.remove(&record.id)
+                };
+                let expected_key = node_key(nodes.len() as u32);
+                match vector {
+                    Some(vector) if key == expected_key.as_slice() => {
+                        nodes.push((record, vector));
+                        Ok(ControlFlow::Continue(()))
+                    }
+                    _ => {
+                        consistent = false;
+                        Ok(ControlFlow::Break(()))
+                    }
+                }
+            },
+        )?;
+
+        if !consistent || !vectors.is_empty() || nodes.len() != meta.node_count as usize {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            HnswIndex::from_parts(self.params, self.metric, meta.entry_point, nodes)
+                .with_quantization(self.quantize),
+        ))
+    }
+
+    /// Replace the persisted graph with `index`
+    fn persist_whole_index(&self, index: &HnswIndex) -> Result<()> {
+        if !self.persist_index {
+            return Ok(());
+        }
+
+        let mut txn = self.storage.begin_transaction()?;
+        for (key, _) in self
+            .storage
+            .scan_prefix(ColumnFamilies::VECTOR_INDEX, &[])?
+        {
+            txn.delete(ColumnFamilies::VECTOR_INDEX, &key)?;
+        }
+        let all: Vec<u32> = (0..index.node_count() as u32).collect();
+        self.write_index_nodes(txn.as_mut(), index, &all)?;
+        txn.commit()
+    }
+
+    /// Write changed graph nodes plus metadata into a transaction
+    fn write_index_nodes(
+        &self,
+        txn: &mut dyn Transaction,
+        index: &HnswIndex,
+        dirty: &[u32],
+    ) -> Result<()> {
+        if !self.persist_index {
+            return Ok(());
+        }
+
+        for &node in dirty {
+            txn.put(
+                ColumnFamilies::VECTOR_INDEX,
+                &node_key(node),
+                &encode_index_value(&index.node_record(node))?,
+            )?;
+        }
+        let meta = IndexMeta {
+            version: INDEX_FORMAT_VERSION,
+            max_connections: self.params.max_connections as u32,
+            node_count: index.node_count() as u32,
+            entry_point: index.entry_point(),
+        };
+        txn.put(
+            ColumnFamilies::VECTOR_INDEX,
+            INDEX_META_KEY,
+            &encode_index_value(&meta)?,
+        )
+    }
+
+    /// Persist an in-memory graph change together with its vector write
+    ///
+    /// On failure the in-memory graph is dropped so it can't drift from
+    /// storage; it is reloaded on next use. When deleted nodes outnumber live
+    /// ones the graph is compacted by rebuilding it.
+    fn commit_index_update(
+        &self,
+        mut txn: Box<dyn Transaction>,
+        guard: &mut Option<HnswIndex>,
+        dirty: &[u32],
+    ) -> Result<()> {
+        let Some(index) = guard.as_ref() else {
+            return Err(Error::VectorIndex("Index not loaded".to_string()));
+        };
+
+        let result = self
+            .write_index_nodes(txn.as_mut(), index, dirty)
+            .and_then(|()| txn.commit());
+        if result.is_err() {
+            *guard = None;
+            return result;
+        }
+
+        if index.tombstones() > index.len().max(64) {
+            let compacted = self.build_index()?;
+            self.persist_whole_index(&compacted)?;
+            *guard = Some(compacted);
+        }
 
         Ok(())
     }
 
     /// Delete a vector
     pub fn delete(&self, id: &str) -> Result<()> {
-        let key = id.as_bytes();
-        self.storage.delete(ColumnFamilies::VECTOR_DATA, key)?;
+        self.ensure_index_loaded()?;
 
-        // Invalidate cache
-        *self.cache.write() = None;
+        let mut guard = self.index.write();
+        let index = guard
+            .as_mut()
+            .ok_or_else(|| Error::VectorIndex("Index not loaded".to_string()))?;
+        let mut txn = self.storage.begin_transaction()?;
+        let dirty: Vec<u32> = index.remove(id).into_iter().collect();
+        txn.delete(ColumnFamilies::VECTOR_DATA, id.as_bytes())?;
+        self.commit_index_update(txn, &mut guard, &dirty)
+    }
 
+    /// Invalidate in-memory state after out-of-band writes to the vector data
+    ///
+    /// The persisted graph is discarded too, so it is rebuilt on next use.
+    pub fn invalidate_cache(&self) -> Result<()> {
+        let mut guard = self.index.write();
+        *guard = None;
+        self.field_caches.write().clear();
+        if self.persist_index {
+            self.storage
+                .delete(ColumnFamilies::VECTOR_INDEX, INDEX_META_KEY)?;
+        }
         Ok(())
     }
 
-    /// Force rebuild the cache
-    pub fn rebuild_index(&self) -> Result<()> {
-        *self.cache.write() = None;
-        self.ensure_cache_built()
+    /// Free the in-memory index; the next search reloads it from storage
+    pub fn release_cache(&self) {
+        drop(self.index.write().take());
+        *self.field_caches.write() = HashMap::new();
+    }
+
+    /// Whether the default index is loaded and searches won't load it
+    pub fn is_ready(&self) -> bool {
+        self.index.read().is_some()
+    }
+
+    /// Rebuild the HNSW graph from the stored vectors
+    ///
+    /// The new graph is built aside while the current one stays in place,
+    /// then persisted in a single transaction replacing the old one, and only
+    /// then swapped in. A failure or crash at any point leaves the previous
+    /// graph. Returns the number of vectors indexed.
+    pub fn rebuild_index(&self, progress: &dyn Fn(usize, usize)) -> Result<usize> {
+        let mut guard = self.index.write();
+        self.field_caches.write().clear();
+        let index = self.build_index_with_progress(progress)?;
+        self.persist_whole_index(&index)?;
+        let indexed = index.len();
+        *guard = Some(index);
+        Ok(indexed)
     }
 }
 
-/// Calculate Euclidean distance between two vectors
-fn euclidean_distance(a: &[f32], b: &[f32]) -> f32 {
-    a.iter()
-        .zip(b.iter())
-        .map(|(x, y)| (x - y).powi(2))
-        .sum::<f32>()
-        .sqrt()
+/// Vector index key of an HNSW node
+fn node_key(index: u32) -> Vec<u8> {
+    let mut key = INDEX_NODE_PREFIX.to_vec();
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+/// Serialize an HNSW graph value
+fn encode_index_value<T: bincode::Encode>(value: &T) -> Result<Vec<u8>> {
+    bincode::encode_to_vec(value, bincode::config::standard())
+        .map_err(|e| Error::Codec(format!("Failed to serialize vector index: {}", e)))
+}
+
+/// Deserialize an HNSW graph value
+fn decode_index_value<T: bincode::Decode<()>>(bytes: &[u8]) -> Result<T> {
+    let (value, _) = bincode::decode_from_slice(bytes, bincode::config::standard())
+        .map_err(|e| Error::Codec(format!("Failed to deserialize vector index: {}", e)))?;
+    Ok(value)
+}
+
+/// Storage key for a named field vector: `\0{id}\0{field}`
+pub(crate) fn field_key(id: &str, field: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(id.len() + field.len() + 2);
+    key.push(FIELD_KEY_PREFIX);
+    key.extend_from_slice(id.as_bytes());
+    key.push(FIELD_KEY_PREFIX);
+    key.extend_from_slice(field.as_bytes());
+    key
+}
+
+/// Split a named field key back into (id, field)
+fn split_field_key(key: &[u8]) -> Option<(String, String)> {
+    let rest = key.strip_prefix(&[FIELD_KEY_PREFIX])?;
+    let sep = rest.iter().position(|&b| b == FIELD_KEY_PREFIX)?;
+    let id = String::from_utf8(rest[..sep].to_vec()).ok()?;
+    let field = String::from_utf8(rest[sep + 1..].to_vec()).ok()?;
+    Some((id, field))
+}
+
+/// Serialize an embedding for the vector data column family
+pub(crate) fn encode_embedding(embedding: &[f32]) -> Result<Vec<u8>> {
+    bincode::encode_to_vec(embedding, bincode::config::standard())
+        .map_err(|e| Error::Codec(format!("Failed to serialize embedding: {}", e)))
+}
+
+/// Deserialize an embedding from the vector data column family
+fn decode_embedding(bytes: &[u8]) -> Result<Vec<f32>> {
+    let (embedding, _): (Vec<f32>, usize) =
+        bincode::decode_from_slice(bytes, bincode::config::standard())
+            .map_err(|e| Error::Codec(format!("Failed to deserialize embedding: {}", e)))?;
+    Ok(embedding)
+}
+
+/// Brute-force k-NN over a set of cached vectors
+fn nearest(
+    query: &[f32],
+    vectors: &[(String, Vec<f32>)],
+    k: usize,
+    metric: DistanceMetric,
+) -> Vec<(String, f32)> {
+    let mut results: Vec<(String, f32)> = vectors
+        .iter()
+        .map(|(id, embedding)| {
+            let distance = metric.distance(query, embedding);
+            (id.clone(), distance)
+        })
+        .collect();
+
+    // Sort by distance and take top k
+    results.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    results.truncate(k);
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::OpenDBOptions;
+    use crate::storage::rocksdb_backend::RocksDBBackend;
+    use tempfile::TempDir;
+
+    fn manager(storage: &SharedStorage) -> VectorManager {
+        VectorManager::new(Arc::clone(storage), 4).with_hnsw_params(HnswParams {
+            ef_search: 8,
+            ..HnswParams::default()
+        })
+    }
+
+    fn embedding(i: usize) -> Vec<f32> {
+        vec![i as f32, (i % 7) as f32, (i % 3) as f32, 1.0]
+    }
+
+    #[test]
+    fn test_quantized_recall_against_exact_search() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend =
+            RocksDBBackend::open_with_options(temp_dir.path(), &OpenDBOptions::default())?;
+        let storage: SharedStorage = Arc::new(backend);
+
+        // Deterministic pseudo-random vectors
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let data: Vec<Vec<f32>> = (0..1000)
+            .map(|_| {
+                (0..16)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        (state % 1000) as f32 / 1000.0
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let quantized = |rerank_factor| {
+            VectorManager::new(Arc::clone(&storage), 16).with_quantization(true, rerank_factor)
+        };
+        let approximate = quantized(1);
+        for (i, vector) in data.iter().enumerate() {
+            approximate.insert_embedding(&format!("v{}", i), vector)?;
+        }
+        let reranked = quantized(4);
+
+        let recall_at_10 = |vectors: &VectorManager| -> Result<f32> {
+            let mut hits = 0;
+            for query in &data[..50] {
+                let mut exact: Vec<(usize, f32)> = data
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i, DistanceMetric::Euclidean.distance(query, v)))
+                    .collect();
+                exact.sort_by(|a, b| a.1.total_cmp(&b.1));
+                let exact_ids: Vec<String> =
+                    exact[..10].iter().map(|(i, _)| format!("v{}", i)).collect();
+
+                hits += vectors
+                    .search(query, 10)?
+                    .iter()
+                    .filter(|(id, _)| exact_ids.contains(id))
+                    .count();
+            }
+            Ok(hits as f32 / 500.0)
+        };
+
+        let approximate_recall = recall_at_10(&approximate)?;
+        let reranked_recall = recall_at_10(&reranked)?;
+        assert!(
+            approximate_recall >= 0.8,
+            "recall too low: {}",
+            approximate_recall
+        );
+        assert!(
+            reranked_recall >= 0.9,
+            "recall too low: {}",
+            reranked_recall
+        );
+        assert!(reranked_recall >= approximate_recall);
+
+        // Re-ranked distances are exact
+        let results = reranked.search(&data[7], 1)?;
+        assert_eq!(results[0], ("v7".to_string(), 0.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_persisted_graph_is_reused() -> Result<()> {
+        let temp_dir = TempDir::new().unwrap();
+        let backend =
+            RocksDBBackend::open_with_options(temp_dir.path(), &OpenDBOptions::default())?;
+        let storage: SharedStorage = Arc::new(backend);
+
+        let vectors = manager(&storage);
+        for i in 0..200 {
+            vectors.insert_embedding(&format!("v{}", i), &embedding(i))?;
+        }
+        vectors.delete("v10")?;
+        let before = vectors.search(&embedding(42), 5)?;
+        assert_eq!(before[0].0, "v42");
+
+        // A fresh manager picks up the stored graph instead of rebuilding
+        let reopened = manager(&storage);
+        let loaded = reopened.load_persisted_index()?.expect("graph should load");
+        assert_eq!(loaded.len(), 199);
+        assert_eq!(loaded.tombstones(), 1);
+        assert_eq!(reopened.search(&embedding(42), 5)?, before);
+
+        // Vectors written behind the index's back make the graph stale
+        storage.put(
+            ColumnFamilies::VECTOR_DATA,
+            b"sneaky",
+            &encode_embedding(&embedding(1))?,
+        )?;
+        assert!(manager(&storage).load_persisted_index()?.is_none());
+        assert_eq!(manager(&storage).search(&embedding(1), 2)?.len(), 2);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,100 @@
+// Scalar int8 quantization of in-memory vectors
+
+use crate::types::DistanceMetric;
+
+/// Largest quantized magnitude; -128 is unused so the range is symmetric
+const LEVELS: f32 = 127.0;
+
+/// An int8-quantized vector with one scale factor
+///
+/// Component `i` approximates `values[i] * scale`. The scale maps the
+/// largest magnitude to 127, so the rounding error of each component is at
+/// most `scale / 2`.
+#[derive(Debug, Clone)]
+pub struct QuantizedVector {
+    scale: f32,
+    values: Vec<i8>,
+}
+
+impl QuantizedVector {
+    /// Quantize a vector
+    pub fn new(vector: &[f32]) -> Self {
+        let max = vector.iter().fold(0.0f32, |max, x| max.max(x.abs()));
+        let scale = if max > 0.0 && max.is_finite() {
+            max / LEVELS
+        } else {
+            1.0
+        };
+        let values = vector
+            .iter()
+            .map(|x| (x / scale).round().clamp(-LEVELS, LEVELS) as i8)
+            .collect();
+        Self { scale, values }
+    }
+
+    /// Approximate full-precision components
+    pub fn iter(&self) -> impl Iterator<Item = f32> + '_ {
+        self.values.iter().map(|&v| v as f32 * self.scale)
+    }
+
+    /// Approximate full-precision vector
+    pub fn to_vec(&self) -> Vec<f32> {
+        self.iter().collect()
+    }
+
+    /// Distance from a full-precision query to this vector
+    ///
+    /// Components are dequantized on the fly, so no vector is allocated.
+    pub fn distance(&self, metric: DistanceMetric, query: &[f32]) -> f32 {
+        let pairs = query.iter().zip(self.iter());
+        match metric {
+            DistanceMetric::Euclidean => pairs.map(|(x, y)| (x - y).powi(2)).sum::<f32>().sqrt(),
+            DistanceMetric::Cosine => {
+                let (dot, norm_a, norm_b) = pairs.fold((0.0f32, 0.0f32, 0.0f32), |acc, (x, y)| {
+                    (acc.0 + x * y, acc.1 + x * x, acc.2 + y * y)
+                });
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    1.0
+                } else {
+                    1.0 - dot / (norm_a.sqrt() * norm_b.sqrt())
+                }
+            }
+            DistanceMetric::DotProduct => -pairs.map(|(x, y)| x * y).sum::<f32>(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_error_is_bounded() {
+        let vector = vec![0.5, -1.25, 0.0, 3.0, -0.01];
+        let quantized = QuantizedVector::new(&vector);
+        assert_eq!(quantized.to_vec().len(), vector.len());
+
+        let half_step = 3.0 / LEVELS / 2.0;
+        for (original, restored) in vector.iter().zip(quantized.iter()) {
+            assert!((original - restored).abs() <= half_step + 1e-6);
+        }
+
+        let zero = QuantizedVector::new(&[0.0, 0.0]);
+        assert_eq!(zero.to_vec(), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_distance_matches_dequantized_vector() {
+        let query = [0.3, -0.7, 0.2, 0.9];
+        let quantized = QuantizedVector::new(&[0.1, 0.4, -0.8, 0.6]);
+        let restored = quantized.to_vec();
+        for metric in [
+            DistanceMetric::Euclidean,
+            DistanceMetric::Cosine,
+            DistanceMetric::DotProduct,
+        ] {
+            let expected = metric.distance(&query, &restored);
+            assert!((quantized.distance(metric, &query) - expected).abs() < 1e-5);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 // ACID compliance tests
 
-use opendb::{Memory, OpenDB, OpenDBOptions, Result};
+use opendb::{DedupOutcome, Memory, OpenDB, OpenDBOptions, Result};
 use std::sync::Arc;
 use std::thread;
 use tempfile::TempDir;
@@ -61,6 +61,87 @@ fn test_rollback() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_drop_rolls_back() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let in_memory = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+
+    for db in [&db, &in_memory] {
+        {
+            let mut txn = db.begin_transaction()?;
+            txn.put("default", b"dropped", b"value")?;
+            txn.insert_memory(&Memory::new("dropped_mem", "x", vec![1.0, 0.0, 0.0], 0.5))?;
+            // Dropped without commit
+        }
+
+        assert!(db.get(b"dropped")?.is_none());
+        assert!(db.get_memory("dropped_mem")?.is_none());
+
+        // Locks taken by the dropped transaction were released
+        let mut txn = db.begin_transaction()?;
+        txn.get_for_update("default", b"dropped")?;
+        txn.put("default", b"dropped", b"value")?;
+        txn.commit()?;
+        assert_eq!(db.get(b"dropped")?, Some(b"value".to_vec()));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_transactional_memory_insert() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    db.insert_memory(&Memory::new("anchor", "anchor", vec![0.0, 0.0, 1.0], 0.5))?;
+
+    // A rolled-back insert leaves neither the record nor its vector behind
+    let mut txn = db.begin_transaction()?;
+    txn.insert_memory(&Memory::new(
+        "txn_mem",
+        "rolled back",
+        vec![1.0, 0.0, 0.0],
+        0.5,
+    ))?;
+    assert!(txn.get("records", b"txn_mem")?.is_some());
+    assert!(txn.get("vector_data", b"txn_mem")?.is_some());
+    txn.rollback()?;
+
+    assert!(db.get_memory("txn_mem")?.is_none());
+    let txn = db.begin_transaction()?;
+    assert!(txn.get("vector_data", b"txn_mem")?.is_none());
+    txn.rollback()?;
+    let results = db.search_similar(&[1.0, 0.0, 0.0], 5)?;
+    assert!(results.iter().all(|r| r.memory.id != "txn_mem"));
+
+    // Nothing is visible outside the transaction until it commits
+    let mut txn = db.begin_transaction()?;
+    txn.insert_memory(&Memory::new(
+        "txn_mem",
+        "committed",
+        vec![1.0, 0.0, 0.0],
+        0.5,
+    ))?;
+    txn.delete_memory("anchor")?;
+    assert!(db.get_memory("txn_mem")?.is_none());
+    assert!(db.get_memory("anchor")?.is_some());
+    txn.commit()?;
+
+    assert_eq!(db.get_memory("txn_mem")?.unwrap().content, "committed");
+    assert!(db.get_memory("anchor")?.is_none());
+    let results = db.search_similar(&[1.0, 0.0, 0.0], 5)?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].memory.id, "txn_mem");
+
+    // Invalid embeddings are rejected before anything is buffered
+    let mut txn = db.begin_transaction()?;
+    assert!(
+        txn.insert_memory(&Memory::new("bad", "bad", vec![1.0; 7], 0.5))
+            .is_err()
+    );
+    txn.rollback()?;
+
+    Ok(())
+}
+
 #[test]
 fn test_consistency() -> Result<()> {
     let (db, _temp) = setup_test_db()?;
@@ -90,8 +171,55 @@ fn test_isolation_via_snapshot() -> Result<()> {
     // Initial state
     db.put(b"counter", b"0")?;
 
-    // TODO: Once we expose snapshot API, test snapshot isolation here
-    // For now, RocksDB transactions provide snapshot isolation automatically
+    let mut txn1 = db.begin_transaction()?;
+    assert_eq!(txn1.get("default", b"counter")?, Some(b"0".to_vec()));
+
+    // Another transaction commits in the meantime
+    let mut txn2 = db.begin_transaction()?;
+    txn2.put("default", b"counter", b"5")?;
+    txn2.put("default", b"created", b"x")?;
+    txn2.commit()?;
+
+    // txn1 keeps reading its snapshot, including for keys it hadn't read yet
+    assert_eq!(txn1.get("default", b"counter")?, Some(b"0".to_vec()));
+    assert_eq!(txn1.get("default", b"created")?, None);
+
+    // get_for_update locks the latest committed value
+    assert_eq!(
+        txn1.get_for_update("default", b"counter")?,
+        Some(b"5".to_vec())
+    );
+    txn1.rollback()?;
+
+    // A transaction begun after the commit sees it
+    let txn3 = db.begin_transaction()?;
+    assert_eq!(txn3.get("default", b"counter")?, Some(b"5".to_vec()));
+    txn3.rollback()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_read_your_writes() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let in_memory = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+
+    for db in [db, in_memory] {
+        let mut txn = db.begin_transaction()?;
+        txn.put("default", b"k", b"v1")?;
+        assert_eq!(txn.get("default", b"k")?, Some(b"v1".to_vec()));
+        txn.put("default", b"k", b"v2")?;
+        assert_eq!(txn.get("default", b"k")?, Some(b"v2".to_vec()));
+        txn.delete("default", b"k")?;
+        assert_eq!(txn.get("default", b"k")?, None);
+        txn.put("default", b"k", b"v3")?;
+        assert_eq!(txn.get_for_update("default", b"k")?, Some(b"v3".to_vec()));
+
+        // Nothing is visible outside until commit
+        assert_eq!(db.get(b"k")?, None);
+        txn.commit()?;
+        assert_eq!(db.get(b"k")?, Some(b"v3".to_vec()));
+    }
 
     Ok(())
 }
@@ -132,8 +260,8 @@ fn test_concurrent_reads() -> Result<()> {
     // Insert test data
     for i in 0..10 {
         let mem = Memory::new(
-            &format!("mem_{}", i),
-            &format!("content {}", i),
+            format!("mem_{}", i),
+            format!("content {}", i),
             vec![i as f32; 3],
             0.5,
         );
@@ -163,6 +291,195 @@ fn test_concurrent_reads() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_concurrent_increments() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let in_memory = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+
+    for db in [Arc::new(db), Arc::new(in_memory)] {
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let db = Arc::clone(&db);
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        db.increment(b"hits", 1).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(db.increment(b"hits", 0)?, 8000);
+        assert_eq!(db.get(b"hits")?, Some(8000i64.to_le_bytes().to_vec()));
+        assert_eq!(db.increment(b"hits", -8001)?, -1);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_concurrent_compare_and_swap() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let db = Arc::new(db);
+
+    // Every thread tries to claim the same lock key; exactly one wins
+    let handles: Vec<_> = (0..8u8)
+        .map(|i| {
+            let db = Arc::clone(&db);
+            thread::spawn(move || db.compare_and_swap(b"leader", None, &[i]).unwrap())
+        })
+        .collect();
+    let winners: Vec<u8> = handles
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, handle)| handle.join().unwrap().then_some(i as u8))
+        .collect();
+    assert_eq!(winners.len(), 1);
+    assert_eq!(db.get(b"leader")?, Some(winners.clone()));
+
+    // Swaps chain on the current value; the cache follows every success
+    assert!(!db.compare_and_swap(b"leader", Some(&[99]), &[1])?);
+    assert!(db.compare_and_swap(b"leader", Some(&winners), b"next")?);
+    assert_eq!(db.get(b"leader")?, Some(b"next".to_vec()));
+    assert!(!db.compare_and_swap(b"leader", None, b"again")?);
+    assert_eq!(db.get(b"leader")?, Some(b"next".to_vec()));
+
+    Ok(())
+}
+
+#[test]
+fn test_concurrent_links_from_same_source() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let in_memory = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+
+    for db in [Arc::new(db), Arc::new(in_memory)] {
+        // Every thread adds its own edges to one hub node; none may be lost
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let db = Arc::clone(&db);
+                thread::spawn(move || {
+                    for i in 0..50 {
+                        db.link("hub", "links_to", &format!("node_{}_{}", t, i))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let related = db.get_related("hub", "links_to")?;
+        assert_eq!(related.len(), 400);
+        for t in 0..8 {
+            for i in 0..50 {
+                let target = format!("node_{}_{}", t, i);
+                assert!(related.contains(&target), "lost edge to {}", target);
+                assert_eq!(db.get_incoming(&target)?.len(), 1);
+            }
+        }
+
+        // Concurrent unlinks from the same source leave the other edges alone
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let db = Arc::clone(&db);
+                thread::spawn(move || {
+                    for i in (0..50).step_by(2) {
+                        db.unlink("hub", "links_to", &format!("node_{}_{}", t, i))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(db.get_related("hub", "links_to")?.len(), 200);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_concurrent_get_or_insert_memory() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let in_memory = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+
+    for db in [Arc::new(db), Arc::new(in_memory)] {
+        // Every thread races to create the same id; exactly one inserts
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let db = Arc::clone(&db);
+                thread::spawn(move || {
+                    let memory =
+                        Memory::new("shared", format!("writer {}", i), vec![1.0, 0.0, 0.0], 0.5);
+                    db.get_or_insert_memory(&memory).unwrap()
+                })
+            })
+            .collect();
+        let outcomes: Vec<(Memory, bool)> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+
+        let winners: Vec<&Memory> = outcomes
+            .iter()
+            .filter(|(_, inserted)| *inserted)
+            .map(|(memory, _)| memory)
+            .collect();
+        assert_eq!(winners.len(), 1);
+        for (memory, _) in &outcomes {
+            assert_eq!(memory.content, winners[0].content);
+        }
+        assert_eq!(
+            db.get_memory("shared")?.unwrap().content,
+            winners[0].content
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_concurrent_insert_memory_dedup() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let db = Arc::new(db);
+
+    // Every thread observes the same fact under its own id
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let db = Arc::clone(&db);
+            thread::spawn(move || {
+                let memory = Memory::new(
+                    format!("fact_{}", i),
+                    "the build is green",
+                    vec![1.0, 0.0, 0.0],
+                    0.5,
+                );
+                db.insert_memory_dedup(&memory, 0.95).unwrap()
+            })
+        })
+        .collect();
+    let outcomes: Vec<DedupOutcome> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+
+    let inserted: Vec<&DedupOutcome> = outcomes
+        .iter()
+        .filter(|outcome| matches!(outcome, DedupOutcome::Inserted(_)))
+        .collect();
+    assert_eq!(inserted.len(), 1);
+    for outcome in &outcomes {
+        assert_eq!(outcome.id(), inserted[0].id());
+    }
+    assert_eq!(db.count_memories("")?, 1);
+
+    Ok(())
+}
+
 #[test]
 fn test_write_after_read_consistency() -> Result<()> {
     let (db, _temp) = setup_test_db()?;
@@ -186,3 +503,48 @@ fn test_write_after_read_consistency() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_commit_synced_survives_reopen() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().to_path_buf();
+
+    {
+        let db = OpenDB::open_with_options(&db_path, OpenDBOptions::with_dimension(3))?;
+        let mut txn = db.begin_transaction()?;
+        txn.put("default", b"synced_key", b"synced_value")?;
+        txn.commit_synced()?;
+    }
+
+    {
+        let db = OpenDB::open_with_options(&db_path, OpenDBOptions::with_dimension(3))?;
+        assert_eq!(db.get(b"synced_key")?, Some(b"synced_value".to_vec()));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_in_memory_transaction_atomicity() -> Result<()> {
+    let db = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+    db.put(b"key1", b"original")?;
+
+    let mut txn = db.begin_transaction()?;
+    txn.put("default", b"key1", b"modified")?;
+    txn.put("default", b"key2", b"new")?;
+    assert_eq!(txn.get("default", b"key1")?, Some(b"modified".to_vec()));
+    assert_eq!(db.get(b"key2")?, None);
+    txn.rollback()?;
+    assert_eq!(db.get(b"key1")?, Some(b"original".to_vec()));
+    assert_eq!(db.get(b"key2")?, None);
+
+    let mut txn = db.begin_transaction()?;
+    txn.put("default", b"key2", b"new")?;
+    txn.put("default", b"key3", b"new")?;
+    txn.delete("default", b"key3")?;
+    txn.commit()?;
+    assert_eq!(db.get(b"key2")?, Some(b"new".to_vec()));
+    assert_eq!(db.get(b"key3")?, None);
+
+    Ok(())
+}
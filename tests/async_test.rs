@@ -0,0 +1,116 @@
+// Tests for the async API (run with `--features async`)
+
+#![cfg(feature = "async")]
+
+use opendb::{AsyncOpenDB, Memory, OpenDB, OpenDBOptions, Result};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use tempfile::TempDir;
+
+/// Wakes a parked thread
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Minimal executor: poll on the current thread, parking until woken
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+fn setup_async_db() -> Result<(AsyncOpenDB, TempDir)> {
+    let temp_dir = TempDir::new().unwrap();
+    let db = OpenDB::open_with_options(temp_dir.path(), OpenDBOptions::with_dimension(3))?;
+    Ok((AsyncOpenDB::with_threads(db, 2)?, temp_dir))
+}
+
+#[test]
+fn test_async_operations() -> Result<()> {
+    let (db, _temp) = setup_async_db()?;
+
+    block_on(async {
+        db.put(b"key", b"value").await?;
+        assert_eq!(db.get(b"key").await?, Some(b"value".to_vec()));
+
+        let memory = Memory::new("mem_1", "async", vec![1.0, 0.0, 0.0], 0.5);
+        db.insert_memory(&memory).await?;
+        assert_eq!(db.get_memory("mem_1").await?.unwrap().content, "async");
+
+        db.link("mem_1", "related", "mem_2").await?;
+        assert_eq!(db.get_related("mem_1", "related").await?, vec!["mem_2"]);
+
+        let results = db.search_similar(&[1.0, 0.0, 0.0], 1).await?;
+        assert_eq!(results[0].id, "mem_1");
+
+        // Sync methods without an async counterpart go through `run`
+        let count = db.run(|db| db.count_memories("")).await?;
+        assert_eq!(count, 1);
+
+        db.delete_memory("mem_1").await?;
+        assert!(db.get_memory("mem_1").await?.is_none());
+        Ok(())
+    })
+}
+
+#[test]
+fn test_async_concurrent_callers() -> Result<()> {
+    let (db, _temp) = setup_async_db()?;
+
+    // More callers than workers; queued jobs still complete
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let db = db.clone();
+            thread::spawn(move || block_on(db.put(format!("key_{}", i).as_bytes(), b"v")))
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap()?;
+    }
+
+    for i in 0..8 {
+        assert!(db.sync().get(format!("key_{}", i).as_bytes())?.is_some());
+    }
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "boom")]
+fn test_async_panic_propagates() {
+    let (db, _temp) = setup_async_db().unwrap();
+    let _ = block_on(db.run(|_| -> Result<()> { panic!("boom") }));
+}
+
+#[test]
+fn test_async_drop_waits_for_queued_writes() -> Result<()> {
+    let (db, temp) = setup_async_db()?;
+
+    // `run` queues the job right away, even if the future is never polled
+    for i in 0..4 {
+        drop(db.run(move |db| {
+            thread::sleep(std::time::Duration::from_millis(20));
+            db.put(format!("key_{}", i).as_bytes(), b"v")
+        }));
+    }
+    drop(db);
+
+    // The workers finished and released the database before the drop returned
+    let reopened = OpenDB::open_with_options(temp.path(), OpenDBOptions::with_dimension(3))?;
+    for i in 0..4 {
+        assert!(reopened.get(format!("key_{}", i).as_bytes())?.is_some());
+    }
+    Ok(())
+}
@@ -1,6 +1,13 @@
 // Integration tests for OpenDB
 
-use opendb::{Memory, OpenDB, OpenDBOptions, Result};
+use opendb::types::Edge;
+use opendb::{
+    Bundle, Coerce, Compression, ConflictPolicy, CreateMode, DedupOutcome, DistanceMetric,
+    DocumentChunk, Error, FileType, HnswParams, IdPolicy, Memory, MemoryPatch, MergeStats,
+    MultimodalDocument, OpenDB, OpenDBOptions, ReadOpts, Result, RocksDBTuning, SearchKPolicy,
+    StorageEngine,
+};
+use std::ops::ControlFlow;
 use tempfile::TempDir;
 
 fn setup_test_db() -> Result<(OpenDB, TempDir)> {
@@ -90,9 +97,11 @@ fn test_graph_operations() -> Result<()> {
     let referenced = db.get_related("mem1", "references")?;
     assert_eq!(referenced, vec!["mem3"]);
 
-    // Outgoing edges
+    // Outgoing edges (canonically ordered by relation, then target)
     let outgoing = db.get_outgoing("mem1")?;
     assert_eq!(outgoing.len(), 2);
+    assert_eq!(outgoing[0].relation, "references");
+    assert_eq!(outgoing[1].relation, "related_to");
 
     // Incoming edges
     let incoming = db.get_incoming("mem3")?;
@@ -106,6 +115,131 @@ fn test_graph_operations() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_weighted_links() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.link_weighted("a", "similar_to", "b", 0.3)?;
+    db.link_weighted("a", "similar_to", "c", 0.9)?;
+    db.link_weighted("a", "similar_to", "d", 0.3)?;
+    db.link("a", "similar_to", "e")?;
+    db.link_weighted("a", "other", "f", 5.0)?;
+
+    // Strongest first; equal weights keep id order; other relations excluded
+    let related = db.get_related_weighted("a", "similar_to")?;
+    assert_eq!(
+        related,
+        vec![
+            ("e".to_string(), 1.0),
+            ("c".to_string(), 0.9),
+            ("b".to_string(), 0.3),
+            ("d".to_string(), 0.3),
+        ]
+    );
+
+    // Plain get_related is unaffected
+    assert_eq!(db.get_related("a", "similar_to")?, vec!["b", "c", "d", "e"]);
+
+    assert!(db.link_weighted("a", "similar_to", "g", f32::NAN).is_err());
+    assert!(db.get_related_weighted("missing", "similar_to")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_link_with_metadata() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    let edge = Edge::new("a", "cites", "b")
+        .with_weight(0.8)
+        .with_metadata("source", "paper.pdf")
+        .with_metadata("confidence", "0.92");
+    db.link_with(&edge)?;
+
+    let outgoing = db.get_outgoing("a")?;
+    assert_eq!(outgoing.len(), 1);
+    assert_eq!(outgoing[0].id, edge.id);
+    assert_eq!(outgoing[0].weight, 0.8);
+    assert_eq!(outgoing[0].metadata, edge.metadata);
+    assert_eq!(db.get_incoming("b")?[0].metadata, edge.metadata);
+
+    // Updating the weight keeps the metadata
+    db.set_weight("a", "cites", "b", 0.5)?;
+    let outgoing = db.get_outgoing("a")?;
+    assert_eq!(
+        outgoing[0].metadata.get("source").map(String::as_str),
+        Some("paper.pdf")
+    );
+
+    // Plain links carry no metadata; invalid weights are rejected
+    db.link("a", "knows", "c")?;
+    assert!(db.get_incoming("c")?[0].metadata.is_empty());
+    assert!(
+        db.link_with(&Edge::new("a", "cites", "d").with_weight(f32::INFINITY))
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_link_many() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.link("a", "knows", "b")?;
+    let added = db.link_many(&[
+        ("a", "knows", "b"), // already linked
+        ("a", "knows", "c"),
+        ("a", "likes", "b"),
+        ("a", "knows", "c"), // repeated in the batch
+        ("d", "knows", "b"),
+    ])?;
+    assert_eq!(added, 3);
+
+    assert_eq!(db.get_related("a", "knows")?, vec!["b", "c"]);
+    assert_eq!(db.get_related("a", "likes")?, vec!["b"]);
+    let incoming: Vec<(String, String)> = db
+        .get_incoming("b")?
+        .into_iter()
+        .map(|e| (e.from, e.relation))
+        .collect();
+    assert_eq!(
+        incoming,
+        vec![
+            ("a".to_string(), "knows".to_string()),
+            ("d".to_string(), "knows".to_string()),
+            ("a".to_string(), "likes".to_string()),
+        ]
+    );
+    assert_eq!(db.graph_stats()?.edge_count, 4);
+
+    assert_eq!(db.link_many(&[])?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_relation_types() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    assert!(db.all_relations()?.is_empty());
+
+    db.link("a", "references", "b")?;
+    db.link("a", "causes", "c")?;
+    db.link("a", "references", "c")?;
+    db.link("b", "part_of", "c")?;
+
+    assert_eq!(db.relations_of("a")?, vec!["causes", "references"]);
+    // Only outgoing edges count
+    assert!(db.relations_of("c")?.is_empty());
+    assert_eq!(db.all_relations()?, vec!["causes", "part_of", "references"]);
+
+    db.unlink("b", "part_of", "c")?;
+    assert_eq!(db.all_relations()?, vec!["causes", "references"]);
+
+    Ok(())
+}
+
 #[test]
 fn test_vector_search() -> Result<()> {
     let (db, _temp) = setup_test_db()?;
@@ -200,3 +334,3168 @@ fn test_metadata() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_create_mode_create_if_missing() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("fresh");
+
+    let options = OpenDBOptions::with_dimension(3).with_create_mode(CreateMode::CreateIfMissing);
+    let db = OpenDB::open_with_options(&path, options.clone())?;
+    db.put(b"key", b"value")?;
+    drop(db);
+
+    // Reopening an existing database is also fine
+    let db = OpenDB::open_with_options(&path, options)?;
+    assert_eq!(db.get(b"key")?, Some(b"value".to_vec()));
+
+    Ok(())
+}
+
+#[test]
+fn test_create_mode_must_exist() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("missing");
+
+    // Missing database is rejected
+    let options = OpenDBOptions::with_dimension(3).with_create_mode(CreateMode::MustExist);
+    assert!(OpenDB::open_with_options(&path, options.clone()).is_err());
+
+    // Once created, it opens fine
+    drop(OpenDB::open_with_options(
+        &path,
+        OpenDBOptions::with_dimension(3),
+    )?);
+    assert!(OpenDB::open_with_options(&path, options).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn test_create_mode_must_not_exist() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("fresh_only");
+
+    // A fresh path opens fine
+    let options = OpenDBOptions::with_dimension(3).with_create_mode(CreateMode::MustNotExist);
+    drop(OpenDB::open_with_options(&path, options.clone())?);
+
+    // An existing database is rejected
+    assert!(OpenDB::open_with_options(&path, options).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_without_embedding() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.insert_memory(&Memory::new(
+        "vec_mem",
+        "searchable",
+        vec![1.0, 0.0, 0.0],
+        0.5,
+    ))?;
+    db.insert_memory(&Memory::new("text_only", "plain fact", Vec::new(), 0.5))?;
+
+    // Text-only memory is stored and retrievable
+    let retrieved = db.get_memory("text_only")?.unwrap();
+    assert_eq!(retrieved.content, "plain fact");
+    assert!(retrieved.embedding.is_empty());
+
+    // But never shows up in vector search
+    let results = db.search_similar(&[1.0, 0.0, 0.0], 10)?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, "vec_mem");
+
+    // Dropping the embedding of an existing memory removes it from search
+    db.insert_memory(&Memory::new("vec_mem", "searchable", Vec::new(), 0.5))?;
+    assert!(db.search_similar(&[1.0, 0.0, 0.0], 10)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_swap_memories() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.insert_memory(&Memory::new("a", "content a", vec![1.0, 0.0, 0.0], 0.2))?;
+    db.insert_memory(&Memory::new("b", "content b", vec![0.0, 1.0, 0.0], 0.9))?;
+
+    // Warm the caches so we know they are refreshed by the swap
+    db.get_memory("a")?;
+    db.search_similar(&[1.0, 0.0, 0.0], 1)?;
+
+    db.swap_memories("a", "b")?;
+
+    let a = db.get_memory("a")?.unwrap();
+    let b = db.get_memory("b")?.unwrap();
+    assert_eq!(a.id, "a");
+    assert_eq!(a.content, "content b");
+    assert_eq!(a.embedding, vec![0.0, 1.0, 0.0]);
+    assert_eq!(a.importance, 0.9);
+    assert_eq!(b.id, "b");
+    assert_eq!(b.content, "content a");
+
+    // Vector entries moved along with the content
+    let results = db.search_similar(&[1.0, 0.0, 0.0], 1)?;
+    assert_eq!(results[0].id, "b");
+
+    // Missing ids are rejected
+    assert!(db.swap_memories("a", "missing").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_max_search_k() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3)
+        .with_max_search_k(2)
+        .with_search_k_policy(SearchKPolicy::Reject);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+
+    for i in 0..5 {
+        let mem = Memory::new(format!("k_{}", i), "content", vec![i as f32; 3], 0.5);
+        db.insert_memory(&mem)?;
+    }
+
+    // Within the limit
+    assert_eq!(db.search_similar(&[0.0; 3], 2)?.len(), 2);
+
+    // Too large k is rejected
+    let err = db.search_similar(&[0.0; 3], usize::MAX).unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+
+    Ok(())
+}
+
+#[test]
+fn test_max_search_k_clamp() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3).with_max_search_k(2);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+
+    for i in 0..5 {
+        let mem = Memory::new(format!("k_{}", i), "content", vec![i as f32; 3], 0.5);
+        db.insert_memory(&mem)?;
+    }
+
+    // Default policy clamps to the maximum
+    assert_eq!(db.search_similar(&[0.0; 3], usize::MAX)?.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_edge_canonical_ordering() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    // Link out of order
+    db.link("hub", "tags", "z")?;
+    db.link("hub", "knows", "c")?;
+    db.link("hub", "tags", "a")?;
+    db.link("hub", "knows", "b")?;
+
+    let outgoing: Vec<(String, String)> = db
+        .get_outgoing("hub")?
+        .into_iter()
+        .map(|e| (e.relation, e.to))
+        .collect();
+    assert_eq!(
+        outgoing,
+        vec![
+            ("knows".to_string(), "b".to_string()),
+            ("knows".to_string(), "c".to_string()),
+            ("tags".to_string(), "a".to_string()),
+            ("tags".to_string(), "z".to_string()),
+        ]
+    );
+
+    // Backward index is ordered by relation, then source
+    db.link("y", "likes", "target")?;
+    db.link("x", "likes", "target")?;
+    db.link("w", "follows", "target")?;
+    let incoming: Vec<String> = db
+        .get_incoming("target")?
+        .into_iter()
+        .map(|e| e.from)
+        .collect();
+    assert_eq!(incoming, vec!["w", "x", "y"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_patch_memory() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    let mem = Memory::new("patch_me", "original", vec![1.0, 0.0, 0.0], 0.3)
+        .with_metadata("draft", "yes")
+        .with_metadata("source", "chat");
+    db.insert_memory(&mem)?;
+    db.insert_memory(&Memory::new("other", "other", vec![0.0, 1.0, 0.0], 0.3))?;
+
+    // Patch only importance and metadata
+    let patched = db.patch_memory(
+        "patch_me",
+        MemoryPatch::new()
+            .importance(0.9)
+            .remove_metadata("draft")
+            .set_metadata("reviewed", "true"),
+    )?;
+    assert_eq!(patched.importance, 0.9);
+
+    let stored = db.get_memory("patch_me")?.unwrap();
+    assert_eq!(stored.content, "original");
+    assert_eq!(stored.embedding, vec![1.0, 0.0, 0.0]);
+    assert_eq!(stored.importance, 0.9);
+    assert!(!stored.metadata.contains_key("draft"));
+    assert_eq!(stored.metadata.get("source"), Some(&"chat".to_string()));
+    assert_eq!(stored.metadata.get("reviewed"), Some(&"true".to_string()));
+
+    // Vector index still points at the original embedding
+    let results = db.search_similar(&[1.0, 0.0, 0.0], 1)?;
+    assert_eq!(results[0].id, "patch_me");
+    assert!(results[0].distance < 0.01);
+
+    // Patching the embedding re-indexes
+    db.patch_memory(
+        "patch_me",
+        MemoryPatch::new().embedding(vec![0.0, 0.0, 1.0]),
+    )?;
+    let results = db.search_similar(&[0.0, 0.0, 1.0], 1)?;
+    assert_eq!(results[0].id, "patch_me");
+
+    // Missing ids are reported
+    let err = db.patch_memory("missing", MemoryPatch::new().importance(0.1));
+    assert!(matches!(
+        err,
+        Err(Error::NotFound { kind: "Memory", ref id }) if id == "missing"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_compaction_rate_limit() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3).with_compaction_rate_bytes_per_sec(1024 * 1024);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+
+    for i in 0..100 {
+        let key = format!("rate_{}", i);
+        db.put(key.as_bytes(), b"value")?;
+    }
+    db.insert_memory(&Memory::new("rate_mem", "content", vec![1.0; 3], 0.5))?;
+    db.flush()?;
+
+    assert_eq!(db.get(b"rate_42")?, Some(b"value".to_vec()));
+    assert!(db.get_memory("rate_mem")?.is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_outgoing_by_relation() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.link("card", "authored", "post_2")?;
+    db.link("card", "knows", "alice")?;
+    db.link("card", "authored", "post_1")?;
+    db.link("card", "knows", "bob")?;
+    db.link("card", "lives_in", "paris")?;
+    db.link("other", "knows", "carol")?;
+
+    let grouped = db.outgoing_by_relation("card")?;
+    assert_eq!(grouped.len(), 3);
+    assert_eq!(grouped["authored"], vec!["post_1", "post_2"]);
+    assert_eq!(grouped["knows"], vec!["alice", "bob"]);
+    assert_eq!(grouped["lives_in"], vec!["paris"]);
+
+    assert!(db.outgoing_by_relation("nobody")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_dimension_coercion() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+
+    // Default rejects mismatched embeddings
+    let strict = OpenDB::open_with_options(
+        temp_dir.path().join("strict"),
+        OpenDBOptions::with_dimension(4),
+    )?;
+    let long = Memory::new("long", "too long", vec![1.0, 2.0, 3.0, 4.0, 5.0], 0.5);
+    assert!(matches!(
+        strict.insert_memory(&long),
+        Err(Error::DimensionMismatch {
+            expected: 4,
+            got: 5,
+            field: None
+        })
+    ));
+
+    // Truncate trims long embeddings
+    let db = OpenDB::open_with_options(
+        temp_dir.path().join("truncate"),
+        OpenDBOptions::with_dimension(4).with_dimension_coercion(Coerce::Truncate),
+    )?;
+    db.insert_memory(&long)?;
+    let stored = db.get_memory("long")?.unwrap();
+    assert_eq!(stored.embedding, vec![1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(
+        db.search_similar(&[1.0, 2.0, 3.0, 4.0], 1)?[0].memory.id,
+        "long"
+    );
+
+    let short = Memory::new("short", "too short", vec![1.0, 2.0, 3.0], 0.5);
+    assert!(db.insert_memory(&short).is_err());
+
+    // ZeroPad extends short embeddings
+    let db = OpenDB::open_with_options(
+        temp_dir.path().join("pad"),
+        OpenDBOptions::with_dimension(4).with_dimension_coercion(Coerce::ZeroPad),
+    )?;
+    db.insert_memory(&short)?;
+    let stored = db.get_memory("short")?.unwrap();
+    assert_eq!(stored.embedding, vec![1.0, 2.0, 3.0, 0.0]);
+    assert_eq!(
+        db.search_similar(&[1.0, 2.0, 3.0, 0.0], 1)?[0].memory.id,
+        "short"
+    );
+
+    // Patched embeddings are coerced the same way
+    let patched = db.patch_memory("short", MemoryPatch::new().embedding(vec![0.0, 1.0]))?;
+    assert_eq!(patched.embedding, vec![0.0, 1.0, 0.0, 0.0]);
+    assert_eq!(
+        db.get_memory("short")?.unwrap().embedding,
+        vec![0.0, 1.0, 0.0, 0.0]
+    );
+    assert_eq!(
+        db.search_similar(&[0.0, 1.0, 0.0, 0.0], 1)?[0].memory.id,
+        "short"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_ping() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.ping()?;
+    db.ping()?;
+
+    // Ping leaves user data untouched
+    assert!(db.scan_prefix(b"")?.is_empty());
+    assert!(db.list_memory_ids("")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_ping_read_only() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    drop(OpenDB::open(temp_dir.path())?);
+
+    let reader = OpenDB::open_read_only(temp_dir.path())?;
+    reader.ping()?;
+    reader.ping()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_stats() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = || OpenDBOptions::with_dimension(3).with_max_cache_bytes(1 << 20);
+    let keys_in = |stats: &opendb::DbStats, cf: &str| {
+        stats
+            .column_families
+            .iter()
+            .find(|stats| stats.name == cf)
+            .map(|stats| stats.keys)
+    };
+
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), options())?;
+        for i in 0..3 {
+            db.insert_memory(&Memory::new(format!("m{}", i), "content", vec![], 0.5))?;
+        }
+        db.put(b"a", b"1")?;
+        db.put(b"b", b"2")?;
+        db.get_memory("m0")?;
+
+        let stats = db.stats()?;
+        assert_eq!(keys_in(&stats, "records"), Some(3));
+        assert_eq!(keys_in(&stats, "default"), Some(2));
+        assert!(stats.total_keys() >= 5);
+        assert!(stats.wal_bytes > 0);
+        // Unflushed writes sit in the memtables of several column families
+        assert!(stats.memtable_bytes.is_some_and(|bytes| bytes > 0));
+        assert!(stats.record_cache.entries >= 1);
+        assert_eq!(stats.record_cache.max_bytes, Some(1 << 20));
+        assert!(stats.record_cache.used_bytes > 0);
+        assert!(stats.to_string().contains("records: 3 keys"));
+    }
+
+    // Reopening replays the WAL into SST files
+    let db = OpenDB::open_with_options(temp_dir.path(), options())?;
+    let stats = db.stats()?;
+    assert!(stats.sst_bytes > 0);
+    assert_eq!(keys_in(&stats, "records"), Some(3));
+    assert_eq!(stats.record_cache.entries, 0);
+
+    // In memory there are no files, but the data size is reported
+    let db = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+    db.put(b"key", b"value")?;
+    let stats = db.stats()?;
+    assert_eq!((stats.sst_bytes, stats.wal_bytes), (0, 0));
+    assert!(stats.memtable_bytes.is_some_and(|bytes| bytes >= 8));
+    assert_eq!(keys_in(&stats, "default"), Some(1));
+
+    Ok(())
+}
+
+#[test]
+fn test_search_similar_field() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3).with_field_dimension("title", 2);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+
+    // Titles and bodies rank the two records in opposite order
+    let rust = Memory::new("rust", "Rust ownership", vec![0.0, 0.0, 1.0], 0.5)
+        .with_field_embedding("title", vec![1.0, 0.0])
+        .with_field_embedding("body", vec![0.0, 1.0, 0.0]);
+    let go = Memory::new("go", "Go channels", vec![0.0, 0.0, 1.0], 0.5)
+        .with_field_embedding("title", vec![0.0, 1.0])
+        .with_field_embedding("body", vec![1.0, 0.0, 0.0]);
+    db.insert_memory(&rust)?;
+    db.insert_memory(&go)?;
+
+    let by_title = db.search_similar_field("title", &[0.9, 0.1], 2)?;
+    assert_eq!(by_title[0].id, "rust");
+    assert_eq!(by_title[1].id, "go");
+
+    let by_body = db.search_similar_field("body", &[0.9, 0.1, 0.0], 2)?;
+    assert_eq!(by_body[0].id, "go");
+    assert_eq!(by_body[1].id, "rust");
+
+    // Field vectors stay out of the default index
+    assert_eq!(db.search_similar(&[0.0, 0.0, 1.0], 10)?.len(), 2);
+
+    // Wrong field dimension is rejected
+    assert!(matches!(
+        db.search_similar_field("title", &[1.0, 0.0, 0.0], 1),
+        Err(Error::DimensionMismatch { expected: 2, got: 3, field: Some(ref field) })
+            if field == "title"
+    ));
+    let bad = Memory::new("bad", "bad", vec![0.0, 0.0, 1.0], 0.5)
+        .with_field_embedding("title", vec![1.0, 0.0, 0.0]);
+    assert!(db.insert_memory(&bad).is_err());
+
+    // Dropping a field or deleting the memory removes it from that field's index
+    db.insert_memory(&Memory::new("go", "Go channels", vec![0.0, 0.0, 1.0], 0.5))?;
+    assert_eq!(db.search_similar_field("title", &[0.0, 1.0], 10)?.len(), 1);
+    db.delete_memory("rust")?;
+    assert!(
+        db.search_similar_field("title", &[0.0, 1.0], 10)?
+            .is_empty()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_for_each_edge() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    // Triangle a -> b -> c -> a, plus a pendant edge
+    db.link("a", "knows", "b")?;
+    db.link("b", "knows", "c")?;
+    db.link("c", "knows", "a")?;
+    db.link("a", "likes", "d")?;
+
+    let mut count = 0;
+    db.for_each_edge(|_| {
+        count += 1;
+        ControlFlow::Continue(())
+    })?;
+
+    let stats = db.graph_stats()?;
+    assert_eq!(count, stats.edge_count);
+    assert_eq!(stats.edge_count, 4);
+    assert_eq!(stats.node_count, 4);
+
+    // Breaking stops the walk early
+    let mut seen = 0;
+    db.for_each_edge(|_| {
+        seen += 1;
+        ControlFlow::Break(())
+    })?;
+    assert_eq!(seen, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_bundle_round_trip() -> Result<()> {
+    let (src, _src_temp) = setup_test_db()?;
+
+    for id in ["root", "near", "far", "beyond"] {
+        src.insert_memory(&Memory::new(
+            id,
+            format!("{} content", id),
+            vec![1.0; 3],
+            0.5,
+        ))?;
+    }
+    src.link("root", "cites", "near")?;
+    src.link("far", "cites", "near")?;
+    src.link("far", "cites", "beyond")?;
+
+    // Depth 2 reaches "far" through an incoming edge, but not "beyond"
+    let bundle = src.export_bundle("root", 2)?;
+    let mut ids: Vec<_> = bundle.memories.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids[0], "root");
+    ids.sort();
+    assert_eq!(ids, vec!["far", "near", "root"]);
+    assert_eq!(bundle.edges.len(), 2);
+
+    // Survives a JSON round trip
+    let json = serde_json::to_string(&bundle).unwrap();
+    let bundle: Bundle = serde_json::from_str(&json).unwrap();
+
+    let (dst, _dst_temp) = setup_test_db()?;
+    let root = dst.import_bundle(&bundle, IdPolicy::Preserve)?;
+    assert_eq!(root, "root");
+    assert_eq!(dst.get_memory("far")?.unwrap().content, "far content");
+    assert!(dst.get_memory("beyond")?.is_none());
+    assert_eq!(dst.get_related("root", "cites")?, vec!["near"]);
+    assert_eq!(dst.get_incoming("near")?.len(), 2);
+
+    // Conflicts are detected before anything is written
+    assert!(
+        dst.import_bundle(&bundle, IdPolicy::FailOnConflict)
+            .is_err()
+    );
+
+    // Prefixing imports a disjoint copy
+    let root = dst.import_bundle(&bundle, IdPolicy::Prefix("copy/".to_string()))?;
+    assert_eq!(root, "copy/root");
+    assert_eq!(dst.get_related("copy/root", "cites")?, vec!["copy/near"]);
+    assert_eq!(dst.graph_stats()?.edge_count, 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_search_chunks_dedup() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    let chunk = |id: &str, parent: &str, embedding: Vec<f32>| {
+        Memory::new(id, id, embedding, 0.5).with_metadata("parent_doc", parent)
+    };
+
+    // doc_a has three chunks closer to the query than anything in doc_b
+    db.insert_memory(&chunk("a_0", "doc_a", vec![1.0, 0.0, 0.0]))?;
+    db.insert_memory(&chunk("a_1", "doc_a", vec![0.99, 0.01, 0.0]))?;
+    db.insert_memory(&chunk("a_2", "doc_a", vec![0.98, 0.02, 0.0]))?;
+    db.insert_memory(&chunk("b_0", "doc_b", vec![0.5, 0.5, 0.0]))?;
+    db.insert_memory(&chunk("b_1", "doc_b", vec![0.0, 1.0, 0.0]))?;
+
+    // Non-chunk memories never appear
+    db.insert_memory(&Memory::new("loose", "loose", vec![1.0, 0.0, 0.0], 0.5))?;
+
+    let results = db.search_chunks_dedup(&[1.0, 0.0, 0.0], 2)?;
+    let ids: Vec<_> = results.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec!["a_0", "b_0"]);
+
+    // Asking for more documents than exist returns what there is
+    assert_eq!(db.search_chunks_dedup(&[1.0, 0.0, 0.0], 10)?.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_search_similar_filtered() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    // Twenty "fact" memories crowd the query; the preferences lie further out
+    for i in 0..20 {
+        let memory = Memory::new(
+            format!("fact_{}", i),
+            "fact",
+            vec![i as f32 * 0.01, 0.0, 0.0],
+            0.5,
+        )
+        .with_metadata("category", "fact");
+        db.insert_memory(&memory)?;
+    }
+    for i in 0..3 {
+        let memory = Memory::new(
+            format!("pref_{}", i),
+            "pref",
+            vec![5.0 + i as f32, 0.0, 0.0],
+            0.5,
+        )
+        .with_metadata("category", "preference");
+        db.insert_memory(&memory)?;
+    }
+
+    let is_preference =
+        |memory: &Memory| memory.metadata.get("category").map(String::as_str) == Some("preference");
+
+    let results = db.search_similar_filtered(&[0.0, 0.0, 0.0], 2, is_preference)?;
+    let ids: Vec<_> = results.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec!["pref_0", "pref_1"]);
+
+    // Fewer matches than k returns all of them once the index is exhausted
+    assert_eq!(
+        db.search_similar_filtered(&[0.0, 0.0, 0.0], 10, is_preference)?
+            .len(),
+        3
+    );
+    assert!(
+        db.search_similar_filtered(&[0.0, 0.0, 0.0], 5, |_| false)?
+            .is_empty()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_search_similar_batch() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    for i in 0..40 {
+        let angle = i as f32 * 0.15;
+        let memory = Memory::new(
+            format!("mem_{}", i),
+            "content",
+            vec![angle.cos(), angle.sin(), i as f32 * 0.05],
+            0.5,
+        );
+        db.insert_memory(&memory)?;
+    }
+
+    let queries: Vec<Vec<f32>> = (0..10)
+        .map(|i| vec![1.0 - i as f32 * 0.2, i as f32 * 0.1, 0.5])
+        .collect();
+    let batch = db.search_similar_batch(&queries, 5)?;
+    assert_eq!(batch.len(), queries.len());
+    for (query, results) in queries.iter().zip(&batch) {
+        let single = db.search_similar(query, 5)?;
+        let batch_ids: Vec<_> = results.iter().map(|r| r.id.as_str()).collect();
+        let single_ids: Vec<_> = single.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(batch_ids, single_ids);
+    }
+
+    assert!(db.search_similar_batch(&[], 5)?.is_empty());
+
+    // A bad dimension anywhere in the batch fails the whole call
+    let mut bad = queries.clone();
+    bad.push(vec![1.0, 0.0]);
+    assert!(db.search_similar_batch(&bad, 5).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_open_in_memory() -> Result<()> {
+    let db = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+
+    db.put(b"key", b"value")?;
+    assert_eq!(db.get(b"key")?, Some(b"value".to_vec()));
+    assert_eq!(db.scan_prefix(b"ke")?.len(), 1);
+
+    db.insert_memory(&Memory::new("a", "first", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("b", "second", vec![0.0, 1.0, 0.0], 0.5))?;
+    db.link("a", "related_to", "b")?;
+
+    assert_eq!(db.get_memory("a")?.unwrap().content, "first");
+    assert_eq!(db.get_related("a", "related_to")?, vec!["b".to_string()]);
+    let results = db.search_similar(&[0.9, 0.1, 0.0], 1)?;
+    assert_eq!(results[0].id, "a");
+
+    // Each in-memory database is independent
+    let other = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+    assert!(other.get_memory("a")?.is_none());
+
+    let temp_dir = TempDir::new().unwrap();
+    assert!(matches!(
+        db.close_and_promote(temp_dir.path().join("promoted")),
+        Err(Error::InvalidInput(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_get_or_insert_memory() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    let rich = Memory::new("mem", "full record", vec![1.0, 0.0, 0.0], 0.9)
+        .with_metadata("source", "crawler");
+    let (stored, inserted) = db.get_or_insert_memory(&rich)?;
+    assert!(inserted);
+    assert_eq!(stored.content, "full record");
+
+    // A retry with a placeholder gets the existing record back unchanged
+    let placeholder = Memory::new("mem", "placeholder", vec![0.0, 1.0, 0.0], 0.1);
+    let (stored, inserted) = db.get_or_insert_memory(&placeholder)?;
+    assert!(!inserted);
+    assert_eq!(stored.content, "full record");
+    assert_eq!(stored.metadata.get("source"), Some(&"crawler".to_string()));
+    assert_eq!(db.get_memory("mem")?.unwrap().content, "full record");
+
+    // The vector index still holds the original embedding
+    let results = db.search_similar(&[1.0, 0.0, 0.0], 1)?;
+    assert_eq!(results[0].id, "mem");
+    assert!(results[0].distance < 1e-6);
+
+    // Invalid embeddings are rejected without storing anything
+    let invalid = Memory::new("bad", "bad", vec![1.0, 2.0], 0.5);
+    assert!(db.get_or_insert_memory(&invalid).is_err());
+    assert!(db.get_memory("bad")?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_insert_memory_dedup() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    let first = Memory::new("first", "sky is blue", vec![1.0, 0.0, 0.0], 0.4)
+        .with_metadata("source", "chat")
+        .with_metadata("lang", "en");
+    assert_eq!(
+        db.insert_memory_dedup(&first, 0.9)?,
+        DedupOutcome::Inserted("first".to_string())
+    );
+
+    // A near-duplicate is merged into the existing memory
+    let mut again = Memory::new("again", "the sky is blue", vec![0.99, 0.05, 0.0], 0.7)
+        .with_metadata("source", "web")
+        .with_metadata("seen", "2");
+    again.timestamp = first.timestamp + 60;
+    let outcome = db.insert_memory_dedup(&again, 0.9)?;
+    assert_eq!(outcome, DedupOutcome::MergedInto("first".to_string()));
+    assert_eq!(outcome.id(), "first");
+    assert!(db.get_memory("again")?.is_none());
+
+    let merged = db.get_memory("first")?.unwrap();
+    assert_eq!(merged.content, "sky is blue");
+    assert_eq!(merged.embedding, vec![1.0, 0.0, 0.0]);
+    assert_eq!(merged.importance, 0.7);
+    assert_eq!(merged.timestamp, first.timestamp + 60);
+    assert_eq!(merged.metadata.get("source"), Some(&"web".to_string()));
+    assert_eq!(merged.metadata.get("lang"), Some(&"en".to_string()));
+    assert_eq!(merged.metadata.get("seen"), Some(&"2".to_string()));
+
+    // A lower importance doesn't demote the existing memory
+    let weak = Memory::new("weak", "sky blue", vec![1.0, 0.01, 0.0], 0.1);
+    db.insert_memory_dedup(&weak, 0.9)?;
+    assert_eq!(db.get_memory("first")?.unwrap().importance, 0.7);
+
+    // A distinct memory is inserted
+    let other = Memory::new("other", "grass is green", vec![0.0, 1.0, 0.0], 0.5);
+    assert_eq!(
+        db.insert_memory_dedup(&other, 0.9)?,
+        DedupOutcome::Inserted("other".to_string())
+    );
+    assert_eq!(db.count_memories("")?, 2);
+
+    // Text-only memories can't be compared and are always inserted
+    let text = Memory::new("text", "sky is blue", Vec::new(), 0.5);
+    assert_eq!(
+        db.insert_memory_dedup(&text, 0.0)?,
+        DedupOutcome::Inserted("text".to_string())
+    );
+
+    assert!(matches!(
+        db.insert_memory_dedup(&other, 1.5),
+        Err(Error::InvalidInput(_))
+    ));
+    assert!(matches!(
+        db.insert_memory_dedup(&other, f32::NAN),
+        Err(Error::InvalidInput(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_closure_transaction_is_atomic() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    db.put(b"k", b"old")?;
+    assert_eq!(db.get(b"k")?, Some(b"old".to_vec()));
+
+    // A failure partway through leaves nothing written
+    let failed: Result<()> = db.transaction(|tx| {
+        tx.insert_memory(&Memory::new("a", "a", vec![1.0, 0.0, 0.0], 0.5))?;
+        tx.link("a", "related_to", "b")?;
+        tx.put(b"k", b"new")?;
+        assert_eq!(tx.get(b"k")?, Some(b"new".to_vec()));
+        tx.insert_memory(&Memory::new("bad", "bad", vec![1.0, 2.0], 0.5))
+    });
+    assert!(matches!(failed, Err(Error::DimensionMismatch { .. })));
+    assert!(db.get_memory("a")?.is_none());
+    assert!(db.search_similar(&[1.0, 0.0, 0.0], 5)?.is_empty());
+    assert!(db.get_outgoing("a")?.is_empty());
+    assert!(db.get_incoming("b")?.is_empty());
+    assert_eq!(db.get(b"k")?, Some(b"old".to_vec()));
+
+    // So does a panic
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        db.transaction(|tx| -> Result<()> {
+            tx.link("a", "related_to", "b")?;
+            tx.put(b"k", b"new")?;
+            panic!("closure panicked");
+        })
+    }));
+    assert!(panicked.is_err());
+    assert!(db.get_outgoing("a")?.is_empty());
+    assert_eq!(db.get(b"k")?, Some(b"old".to_vec()));
+
+    // A successful closure commits everything and returns its value
+    let linked = db.transaction(|tx| {
+        tx.insert_memory(&Memory::new("a", "a", vec![1.0, 0.0, 0.0], 0.5))?;
+        tx.link("a", "related_to", "b")?;
+        tx.link("a", "related_to", "b")?;
+        tx.put(b"k", b"new")?;
+        Ok(2)
+    })?;
+    assert_eq!(linked, 2);
+    assert!(db.get_memory("a")?.is_some());
+    assert_eq!(db.search_similar(&[1.0, 0.0, 0.0], 1)?[0].id, "a");
+    assert_eq!(db.get_outgoing("a")?.len(), 1);
+    assert_eq!(db.get(b"k")?, Some(b"new".to_vec()));
+
+    Ok(())
+}
+
+#[test]
+fn test_bulk_load() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3);
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), options.clone())?;
+        db.put(b"cached", b"old")?;
+        assert_eq!(db.get(b"cached")?, Some(b"old".to_vec()));
+
+        db.bulk_load(|loader| {
+            loader.put(b"cached", b"new")?;
+            for i in 0..3_000 {
+                let memory = Memory::new(
+                    format!("mem_{:05}", i),
+                    "bulk",
+                    vec![i as f32, 1.0, 0.0],
+                    0.5,
+                );
+                loader.insert_memory(&memory)?;
+            }
+            // A repeated id replaces the earlier record
+            loader.insert_memory(&Memory::new("mem_00000", "again", vec![0.0, 1.0, 0.0], 0.9))?;
+            loader.link("mem_00000", "next", "mem_00001")?;
+            loader.link("mem_00000", "next", "mem_00001")?;
+            Ok(())
+        })?;
+
+        assert_eq!(db.get(b"cached")?, Some(b"new".to_vec()));
+        assert_eq!(db.count_memories("")?, 3_000);
+        assert_eq!(db.get_memory("mem_00000")?.unwrap().content, "again");
+        assert_eq!(db.get_outgoing("mem_00000")?.len(), 1);
+        assert_eq!(db.top_memories(1)?[0].id, "mem_00000");
+        assert_eq!(db.search_similar(&[42.0, 1.0, 0.0], 1)?[0].id, "mem_00042");
+
+        // Invalid records are rejected as they are added
+        let invalid = db.bulk_load(|loader| {
+            loader.insert_memory(&Memory::new("bad", "bad", vec![1.0, 2.0], 0.5))
+        });
+        assert!(invalid.is_err());
+        assert!(db.get_memory("bad")?.is_none());
+    }
+
+    // Bulk loads are only durable after a clean close, which the drop above
+    // performed: everything loaded without the WAL is there after reopening
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+    assert_eq!(db.count_memories("")?, 3_000);
+    assert_eq!(db.get(b"cached")?, Some(b"new".to_vec()));
+    assert_eq!(db.search_similar(&[7.0, 1.0, 0.0], 1)?[0].id, "mem_00007");
+
+    // In-memory databases load the same way
+    let in_memory = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+    in_memory.bulk_load(|loader| {
+        loader.put(b"k", b"v")?;
+        loader.insert_memory(&Memory::new("m", "m", vec![1.0, 0.0, 0.0], 0.5))
+    })?;
+    assert_eq!(in_memory.get(b"k")?, Some(b"v".to_vec()));
+    assert!(in_memory.get_memory("m")?.is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_insert_memories_batch() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let embedding = |i: usize| vec![i as f32, (i % 7) as f32, 1.0];
+
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), OpenDBOptions::with_dimension(3))?;
+        db.insert_memory(&Memory::new("m0", "old", embedding(100), 0.5))?;
+        // Load the graph so the batch extends it in place
+        assert_eq!(db.search_similar(&embedding(100), 1)?[0].id, "m0");
+
+        let batch: Vec<Memory> = (0..200)
+            .map(|i| {
+                Memory::new(
+                    format!("m{}", i),
+                    format!("content {}", i),
+                    embedding(i),
+                    0.5,
+                )
+            })
+            .collect();
+        db.insert_memories(&batch)?;
+
+        assert_eq!(db.list_memories("m")?.len(), 200);
+        assert_eq!(db.get_memory("m0")?.unwrap().content, "content 0");
+        let results = db.search_similar(&embedding(150), 1)?;
+        assert_eq!(results[0].id, "m150");
+        assert_eq!(db.search_similar(&embedding(0), 1)?[0].id, "m0");
+
+        // One bad embedding rejects the whole batch
+        let invalid = vec![
+            Memory::new("new_1", "ok", embedding(1), 0.5),
+            Memory::new("new_2", "bad", vec![1.0, 2.0], 0.5),
+        ];
+        assert!(db.insert_memories(&invalid).is_err());
+        assert!(db.get_memory("new_1")?.is_none());
+
+        let duplicated = vec![
+            Memory::new("dup", "a", embedding(1), 0.5),
+            Memory::new("dup", "b", embedding(2), 0.5),
+        ];
+        assert!(matches!(
+            db.insert_memories(&duplicated),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(db.get_memory("dup")?.is_none());
+    }
+
+    // The persisted graph includes the batch
+    let db = OpenDB::open_with_options(temp_dir.path(), OpenDBOptions::with_dimension(3))?;
+    assert_eq!(db.search_similar(&embedding(42), 1)?[0].id, "m42");
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_ttl_expiry() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    let fresh = Memory::new("fresh", "stays", vec![1.0, 0.0, 0.0], 0.5).with_ttl(3600);
+    db.insert_memory(&fresh)?;
+    db.insert_memory(&Memory::new("read", "gone", vec![0.0, 1.0, 0.0], 0.5).with_ttl(-1))?;
+    db.insert_memory(&Memory::new("swept", "gone", vec![0.0, 0.0, 1.0], 0.5).with_ttl(-1))?;
+    db.insert_memory(&Memory::new("forever", "stays", vec![1.0, 1.0, 0.0], 0.5))?;
+
+    assert!(fresh.expires_at.is_some());
+    assert_eq!(db.get_memory("fresh")?.unwrap().content, "stays");
+
+    // Reading an expired memory deletes it
+    assert!(db.get_memory("read")?.is_none());
+    assert!(!db.list_memory_ids("")?.contains(&"read".to_string()));
+
+    // Listings skip expired memories even before they are purged
+    let listed: Vec<String> = db.list_memories("")?.into_iter().map(|m| m.id).collect();
+    assert_eq!(listed, vec!["forever", "fresh"]);
+
+    assert_eq!(db.purge_expired()?, 1);
+    assert_eq!(db.purge_expired()?, 0);
+    assert!(!db.list_memory_ids("")?.contains(&"swept".to_string()));
+    let results = db.search_similar(&[0.0, 0.0, 1.0], 4)?;
+    let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids.len(), 2);
+    assert!(!ids.contains(&"swept") && !ids.contains(&"read"));
+
+    Ok(())
+}
+
+#[test]
+fn test_increment_rejects_non_counter_values() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    assert_eq!(db.increment(b"counter", 5)?, 5);
+    assert_eq!(db.increment(b"counter", -2)?, 3);
+
+    db.put(b"text", b"hello")?;
+    assert!(matches!(
+        db.increment(b"text", 1),
+        Err(Error::InvalidInput(_))
+    ));
+    assert_eq!(db.get(b"text")?, Some(b"hello".to_vec()));
+
+    db.put(b"max", &i64::MAX.to_le_bytes())?;
+    assert!(matches!(
+        db.increment(b"max", 1),
+        Err(Error::InvalidInput(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_scan_range() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    for month in ["2023-12", "2024-01", "2024-03", "2024-06", "2024-07"] {
+        db.put(month.as_bytes(), b"x")?;
+    }
+    let keys = |pairs: Vec<(Vec<u8>, Vec<u8>)>| -> Vec<String> {
+        pairs
+            .into_iter()
+            .map(|(key, _)| String::from_utf8(key).unwrap())
+            .collect()
+    };
+
+    // End is exclusive
+    assert_eq!(
+        keys(db.scan_range(b"2024-01", b"2024-06")?),
+        vec!["2024-01", "2024-03"]
+    );
+    assert_eq!(
+        keys(db.scan_range_rev(b"2024-01", b"2024-07")?),
+        vec!["2024-06", "2024-03", "2024-01"]
+    );
+
+    // A start between existing keys begins at the next key
+    assert_eq!(
+        keys(db.scan_range(b"2024-02", b"2024-99")?),
+        vec!["2024-03", "2024-06", "2024-07"]
+    );
+
+    // Empty and inverted ranges return nothing
+    assert!(db.scan_range(b"2024-04", b"2024-05")?.is_empty());
+    assert!(db.scan_range(b"2024-03", b"2024-03")?.is_empty());
+    assert!(db.scan_range_rev(b"2024-07", b"2024-01")?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_typed_store() -> Result<()> {
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Profile {
+        name: String,
+        age: u32,
+    }
+
+    let (db, _temp) = setup_test_db()?;
+    let profiles = db.typed_store::<String, Profile>("profiles");
+    let alice = Profile {
+        name: "Alice".to_string(),
+        age: 30,
+    };
+    profiles.put(&"user:1".to_string(), &alice)?;
+    profiles.put(
+        &"user:2".to_string(),
+        &Profile {
+            name: "Bob".to_string(),
+            age: 25,
+        },
+    )?;
+    profiles.put(
+        &"admin:1".to_string(),
+        &Profile {
+            name: "Root".to_string(),
+            age: 99,
+        },
+    )?;
+
+    assert_eq!(profiles.get(&"user:1".to_string())?, Some(alice));
+    assert_eq!(profiles.get(&"user:3".to_string())?, None);
+
+    let users = profiles.scan_prefix("user:")?;
+    let names: Vec<(&str, &str)> = users
+        .iter()
+        .map(|(id, profile)| (id.as_str(), profile.name.as_str()))
+        .collect();
+    assert_eq!(names, vec![("user:1", "Alice"), ("user:2", "Bob")]);
+    assert_eq!(profiles.scan_prefix("")?.len(), 3);
+
+    // Entries are plain KV pairs under the namespace
+    assert!(db.exists(b"profiles:user:1")?);
+
+    // Namespaces don't see each other's keys
+    let counts = db.typed_store::<u64, Vec<String>>("counts");
+    counts.put(&7, &vec!["a".to_string()])?;
+    assert_eq!(counts.scan_prefix("")?, vec![(7, vec!["a".to_string()])]);
+    assert_eq!(profiles.scan_prefix("")?.len(), 3);
+
+    profiles.delete(&"user:2".to_string())?;
+    assert_eq!(profiles.scan_prefix("user:")?.len(), 1);
+
+    // Values of the wrong shape fail to decode
+    db.put(b"profiles:broken", b"not json")?;
+    assert!(matches!(
+        profiles.get(&"broken".to_string()),
+        Err(Error::Codec(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_delete_prefix() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    for tenant in ["tenant_a", "tenant_b"] {
+        for i in 0..3 {
+            db.put(format!("{}:key{}", tenant, i).as_bytes(), b"v")?;
+            let memory = Memory::new(
+                format!("{}:mem{}", tenant, i),
+                "m",
+                vec![1.0, i as f32, 0.0],
+                0.5,
+            )
+            .with_metadata("tenant", tenant);
+            db.insert_memory(&memory)?;
+        }
+        db.link(
+            &format!("{}:mem0", tenant),
+            "next",
+            &format!("{}:mem1", tenant),
+        )?;
+    }
+    db.link("tenant_b:mem2", "mentions", "tenant_a:mem2")?;
+    // Cached before the delete
+    assert!(db.get(b"tenant_a:key0")?.is_some());
+    assert!(db.get_memory("tenant_a:mem0")?.is_some());
+
+    assert_eq!(db.delete_prefix(b"tenant_a:")?, 3);
+    assert_eq!(db.delete_memories_prefix("tenant_a:")?, 3);
+
+    assert!(db.get(b"tenant_a:key0")?.is_none());
+    assert!(db.scan_prefix(b"tenant_a:")?.is_empty());
+    assert_eq!(db.scan_prefix(b"tenant_b:")?.len(), 3);
+
+    assert!(db.get_memory("tenant_a:mem0")?.is_none());
+    assert_eq!(db.list_memory_ids("")?.len(), 3);
+    assert!(
+        db.top_memories(10)?
+            .iter()
+            .all(|memory| memory.id.starts_with("tenant_b:"))
+    );
+    assert!(
+        db.search_similar(&[1.0, 0.0, 0.0], 10)?
+            .iter()
+            .all(|result| result.id.starts_with("tenant_b:"))
+    );
+
+    // Edges touching tenant_a are gone, tenant_b's own edges stay
+    assert!(db.get_related("tenant_b:mem2", "mentions")?.is_empty());
+    assert!(db.get_related("tenant_a:mem0", "next")?.is_empty());
+    assert_eq!(
+        db.get_related("tenant_b:mem0", "next")?,
+        vec!["tenant_b:mem1"]
+    );
+
+    assert_eq!(db.delete_prefix(b"tenant_a:")?, 0);
+    assert!(matches!(db.delete_prefix(b""), Err(Error::InvalidInput(_))));
+    assert!(matches!(
+        db.delete_memories_prefix(""),
+        Err(Error::InvalidInput(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_iter_memories_is_lazy_and_ordered() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let in_memory = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+
+    for db in [&db, &in_memory] {
+        for i in 0..50 {
+            db.insert_memory(&Memory::new(format!("a_{:02}", i), "a", vec![], 0.5))?;
+        }
+        db.insert_memory(&Memory::new("b_00", "b", vec![], 0.5))?;
+        db.insert_memory(&Memory::new("a_99", "expired", vec![], 0.5).with_ttl(-1))?;
+
+        let first: Vec<String> = db
+            .iter_memories("a_")?
+            .take(3)
+            .map(|memory| memory.map(|m| m.id))
+            .collect::<Result<_>>()?;
+        assert_eq!(first, vec!["a_00", "a_01", "a_02"]);
+
+        // Stops at the prefix boundary and skips expired records
+        let all: Vec<Memory> = db.iter_memories("a_")?.collect::<Result<_>>()?;
+        assert_eq!(all.len(), 50);
+        assert_eq!(all.last().unwrap().id, "a_49");
+        assert_eq!(db.iter_memories("")?.count(), 51);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_count_api() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let in_memory = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+
+    for db in [&db, &in_memory] {
+        for i in 0..10 {
+            db.insert_memory(&Memory::new(
+                format!("a_{}", i),
+                "a",
+                vec![i as f32, 1.0, 0.0],
+                0.5,
+            ))?;
+        }
+        // Memories without an embedding have no vector
+        db.insert_memory(&Memory::new("b_0", "b", vec![], 0.5))?;
+        db.link("a_0", "related", "a_1")?;
+        db.link("a_1", "related", "a_2")?;
+
+        assert_eq!(db.count_memories("")?, 11);
+        assert_eq!(db.count_memories("a_")?, 10);
+        assert_eq!(db.count_memories("c_")?, 0);
+        assert_eq!(db.count_vectors()?, 10);
+        assert_eq!(db.count_edges()?, 2);
+
+        db.delete_memory("a_0")?;
+        assert_eq!(db.count_memories("a_")?, 9);
+        assert_eq!(db.count_vectors()?, 9);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_record_limits() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3)
+        .with_max_content_bytes(10)
+        .with_max_metadata_entries(2);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+    let invalid = |result: Result<()>| matches!(result, Err(Error::InvalidInput(_)));
+
+    // Content: exactly at the limit passes, one byte over fails
+    db.insert_memory(&Memory::new("fits", "0123456789", vec![1.0, 0.0, 0.0], 0.5))?;
+    let long = Memory::new("long", "0123456789a", vec![1.0, 0.0, 0.0], 0.5);
+    assert!(invalid(db.insert_memory(&long)));
+    assert!(db.get_memory("long")?.is_none());
+    assert_eq!(db.search_similar(&[1.0, 0.0, 0.0], 10)?.len(), 1);
+
+    // Metadata: two entries pass, three fail
+    let two = Memory::new("two", "m", vec![], 0.5)
+        .with_metadata("a", "1")
+        .with_metadata("b", "2");
+    db.insert_memory(&two)?;
+    let three = two.clone().with_metadata("c", "3");
+    assert!(invalid(db.insert_memory(&three)));
+    assert_eq!(db.get_memory("two")?.unwrap().metadata.len(), 2);
+
+    // Importance: both ends of 0..=1 pass; anything outside or NaN fails
+    for (id, importance, ok) in [
+        ("zero", 0.0, true),
+        ("one", 1.0, true),
+        ("negative", -0.01, false),
+        ("above", 1.01, false),
+        ("nan", f32::NAN, false),
+    ] {
+        let mut memory = Memory::new(id, "m", vec![], 0.5);
+        memory.importance = importance;
+        assert_eq!(
+            db.insert_memory(&memory).is_ok(),
+            ok,
+            "importance {}",
+            importance
+        );
+    }
+
+    // Batches reject everything if one record is invalid
+    let batch = vec![
+        Memory::new("batch_ok", "ok", vec![], 0.5),
+        Memory::new("batch_long", "far too long", vec![], 0.5),
+    ];
+    assert!(invalid(db.insert_memories(&batch)));
+    assert!(db.get_memory("batch_ok")?.is_none());
+
+    // Patches are validated before the vector index changes
+    let patch = MemoryPatch::new()
+        .content("far too long")
+        .embedding(vec![0.0, 0.0, 1.0]);
+    assert!(matches!(
+        db.patch_memory("fits", patch),
+        Err(Error::InvalidInput(_))
+    ));
+    assert_eq!(db.search_similar(&[1.0, 0.0, 0.0], 1)?[0].id, "fits");
+    assert!(db.search_similar(&[1.0, 0.0, 0.0], 1)?[0].distance < 1e-6);
+
+    Ok(())
+}
+
+#[test]
+fn test_explicit_timestamps_are_kept() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let imported_at = 1_500_000_000;
+
+    db.insert_memory(&Memory::new_at(
+        "old",
+        "imported",
+        vec![1.0, 0.0, 0.0],
+        0.5,
+        imported_at,
+    ))?;
+    assert_eq!(db.get_memory("old")?.unwrap().timestamp, imported_at);
+
+    db.link_with(&Edge::new_at("old", "cites", "other", imported_at + 1))?;
+    assert_eq!(db.get_outgoing("old")?[0].timestamp, imported_at + 1);
+
+    let document = MultimodalDocument::new_at(
+        "doc",
+        "notes.txt",
+        FileType::Text,
+        5,
+        "notes",
+        vec![],
+        imported_at + 2,
+    );
+    db.insert_document(&document)?;
+    assert_eq!(db.get_document("doc")?.unwrap().timestamp, imported_at + 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_time_range_queries() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    let at = |id: &str, timestamp: i64| Memory::new_at(id, id, vec![], 0.5, timestamp);
+    db.insert_memory(&at("t100", 100))?;
+    db.insert_memory(&at("t200_b", 200))?;
+    db.insert_memory(&at("t200_a", 200))?;
+    db.insert_memory(&at("t300", 300))?;
+    db.insert_memory(&at("early", -50))?;
+
+    let ids = |memories: Vec<Memory>| memories.into_iter().map(|m| m.id).collect::<Vec<_>>();
+
+    // Start inclusive, end exclusive; shared timestamps in id order
+    assert_eq!(
+        ids(db.memories_between(100, 300)?),
+        vec!["t100", "t200_a", "t200_b"]
+    );
+    assert_eq!(
+        ids(db.memories_between(101, 301)?),
+        vec!["t200_a", "t200_b", "t300"]
+    );
+    assert_eq!(
+        ids(db.memories_between(200, 201)?),
+        vec!["t200_a", "t200_b"]
+    );
+    assert!(db.memories_between(200, 200)?.is_empty());
+    assert!(db.memories_between(300, 100)?.is_empty());
+    assert_eq!(ids(db.memories_between(i64::MIN, 0)?), vec!["early"]);
+
+    assert_eq!(
+        ids(db.recent_memories(3)?),
+        vec!["t300", "t200_b", "t200_a"]
+    );
+    assert_eq!(db.recent_memories(10)?.len(), 5);
+    assert!(db.recent_memories(0)?.is_empty());
+
+    // Updates move the entry; deletes remove it
+    db.insert_memory(&at("t100", 400))?;
+    assert_eq!(ids(db.recent_memories(1)?), vec!["t100"]);
+    assert!(db.memories_between(100, 101)?.is_empty());
+    db.delete_memory("t300")?;
+    assert_eq!(ids(db.memories_between(250, 500)?), vec!["t100"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_soft_delete_and_restore() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.insert_memory(&Memory::new("keep", "kept", vec![0.0, 1.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new(
+        "oops",
+        "deleted by mistake",
+        vec![1.0, 0.0, 0.0],
+        0.7,
+    ))?;
+    db.link("oops", "related_to", "keep")?;
+    db.link("keep", "mentions", "oops")?;
+
+    db.soft_delete_memory("oops")?;
+    assert!(db.get_memory("oops")?.is_none());
+    assert!(db.get_outgoing("oops")?.is_empty());
+    assert!(db.get_outgoing("keep")?.is_empty());
+    assert_eq!(db.search_similar(&[1.0, 0.0, 0.0], 1)?[0].id, "keep");
+
+    let bin = db.list_deleted()?;
+    assert_eq!(bin.len(), 1);
+    assert_eq!(bin[0].memory.content, "deleted by mistake");
+    assert_eq!(bin[0].edges.len(), 2);
+    assert!(matches!(
+        db.soft_delete_memory("missing"),
+        Err(Error::NotFound { .. })
+    ));
+
+    // Restoring re-indexes the vector and relinks both edges
+    db.restore_memory("oops")?;
+    assert_eq!(
+        db.get_memory("oops")?.unwrap().content,
+        "deleted by mistake"
+    );
+    assert_eq!(db.search_similar(&[1.0, 0.0, 0.0], 1)?[0].id, "oops");
+    assert_eq!(db.get_related("oops", "related_to")?, vec!["keep"]);
+    assert_eq!(db.get_related("keep", "mentions")?, vec!["oops"]);
+    assert!(db.list_deleted()?.is_empty());
+    assert!(matches!(
+        db.restore_memory("oops"),
+        Err(Error::NotFound { .. })
+    ));
+
+    // A live record with the same id blocks the restore and stays untouched
+    db.soft_delete_memory("oops")?;
+    db.insert_memory(&Memory::new(
+        "oops",
+        "replacement",
+        vec![1.0, 0.0, 0.0],
+        0.1,
+    ))?;
+    assert!(matches!(
+        db.restore_memory("oops"),
+        Err(Error::InvalidInput(_))
+    ));
+    assert_eq!(db.get_memory("oops")?.unwrap().content, "replacement");
+    assert_eq!(db.list_deleted()?.len(), 1);
+
+    // Purging only removes entries older than the cutoff
+    assert_eq!(db.purge_deleted(3600)?, 0);
+    assert_eq!(db.purge_deleted(0)?, 1);
+    assert!(db.list_deleted()?.is_empty());
+    assert!(db.purge_deleted(-1).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_importance_queries() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    // Importance 0.00, 0.01, ... 1.00, inserted out of order
+    for i in (0..=100).rev().step_by(2).chain((1..100).step_by(2)) {
+        let mut memory = Memory::new(format!("m{:03}", i), "content", vec![], 0.0);
+        memory.importance = i as f32 / 100.0;
+        db.insert_memory(&memory)?;
+    }
+
+    let top = db.top_memories(3)?;
+    let ids: Vec<&str> = top.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, vec!["m100", "m099", "m098"]);
+
+    // Every record comes back in descending importance order
+    let all = db.memories_in_importance_range(0.0, 1.0)?;
+    assert_eq!(all.len(), 101);
+    assert!(all.windows(2).all(|w| w[0].importance > w[1].importance));
+
+    // Bounds are inclusive
+    let range = db.memories_in_importance_range(0.8, 1.0)?;
+    assert_eq!(range.len(), 21);
+    assert_eq!(range.last().unwrap().id, "m080");
+    assert!(db.memories_in_importance_range(0.9, 0.1)?.is_empty());
+
+    // Updates and deletes move or drop index entries
+    let mut promoted = db.get_memory("m000")?.unwrap();
+    promoted.importance = 0.995;
+    db.insert_memory(&promoted)?;
+    db.delete_memory("m100")?;
+    let ids: Vec<String> = db.top_memories(2)?.into_iter().map(|m| m.id).collect();
+    assert_eq!(ids, vec!["m000", "m099"]);
+    assert!(db.memories_in_importance_range(0.0, 0.0)?.is_empty());
+
+    // Decay rewrites the index along with the records
+    let mut old = Memory::new("old", "content", vec![], 0.0);
+    old.importance = 0.805;
+    old.timestamp -= 3600;
+    db.insert_memory(&old)?;
+    db.decay_memories(3600)?;
+    let ids = |lo, hi| -> Result<Vec<String>> {
+        Ok(db
+            .memories_in_importance_range(lo, hi)?
+            .into_iter()
+            .map(|m| m.id)
+            .collect())
+    };
+    assert!(!ids(0.8, 0.81)?.contains(&"old".to_string()));
+    assert_eq!(ids(0.401, 0.404)?, vec!["old"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_metadata_index() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let memory = |id: &str, category: &str| {
+        Memory::new(id, id, vec![], 0.5).with_metadata("category", category)
+    };
+
+    // Records written before the key was indexed
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), OpenDBOptions::with_dimension(3))?;
+        db.insert_memory(&memory("old", "preference"))?;
+    }
+
+    let options = OpenDBOptions::with_dimension(3).with_indexed_metadata_keys(&["category"]);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+    assert!(db.find_by_metadata("category", "preference")?.is_empty());
+    assert_eq!(db.reindex_metadata()?, 1);
+    assert_eq!(db.find_by_metadata("category", "preference")?, vec!["old"]);
+
+    db.insert_memory(&memory("m1", "preference"))?;
+    db.insert_memories(&[memory("m2", "fact"), memory("m3", "preference")])?;
+    assert_eq!(
+        db.find_by_metadata("category", "preference")?,
+        vec!["m1", "m3", "old"]
+    );
+    assert_eq!(db.find_by_metadata("category", "fact")?, vec!["m2"]);
+
+    // Updates move the entry, deletes drop it
+    db.insert_memory(&memory("m1", "fact"))?;
+    db.delete_memory("old")?;
+    assert_eq!(db.find_by_metadata("category", "preference")?, vec!["m3"]);
+    assert_eq!(db.find_by_metadata("category", "fact")?, vec!["m1", "m2"]);
+
+    db.swap_memories("m2", "m3")?;
+    assert_eq!(db.find_by_metadata("category", "preference")?, vec!["m2"]);
+
+    let mut txn = db.begin_transaction()?;
+    txn.insert_memory(&memory("m4", "preference"))?;
+    txn.delete_memory("m2")?;
+    txn.commit()?;
+    assert_eq!(db.find_by_metadata("category", "preference")?, vec!["m4"]);
+
+    assert!(matches!(
+        db.find_by_metadata("source", "chat"),
+        Err(Error::InvalidInput(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_sync_writes() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3).with_sync_writes(true);
+
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), options.clone())?;
+        db.put(b"key", b"value")?;
+        db.insert_memory(&Memory::new("mem_1", "synced", vec![1.0, 0.0, 0.0], 0.5))?;
+        let mut txn = db.begin_transaction()?;
+        txn.put("default", b"txn_key", b"txn_value")?;
+        txn.commit()?;
+        db.flush()?;
+    }
+
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+    assert_eq!(db.get(b"key")?, Some(b"value".to_vec()));
+    assert_eq!(db.get(b"txn_key")?, Some(b"txn_value".to_vec()));
+    assert_eq!(db.get_memory("mem_1")?.unwrap().content, "synced");
+
+    Ok(())
+}
+
+#[test]
+fn test_storage_engine_selection() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("db");
+
+    let options = OpenDBOptions::with_dimension(3).with_storage_engine(StorageEngine::InMemory);
+    {
+        let db = OpenDB::open_with_options(&path, options.clone())?;
+        db.insert_memory(&Memory::new("mem_1", "volatile", vec![1.0, 0.0, 0.0], 0.5))?;
+        assert!(db.get_memory("mem_1")?.is_some());
+        assert_eq!(db.search_similar(&[1.0, 0.0, 0.0], 1)?[0].id, "mem_1");
+    }
+    // Nothing was written to the path, and nothing survives the drop
+    assert!(!path.exists());
+    let db = OpenDB::open_with_options(&path, options.clone())?;
+    assert!(db.get_memory("mem_1")?.is_none());
+
+    assert!(matches!(
+        OpenDB::open_read_only_with_options(&path, options),
+        Err(Error::InvalidInput(_))
+    ));
+
+    // The default engine is RocksDB on disk
+    let db = OpenDB::open_with_options(&path, OpenDBOptions::with_dimension(3))?;
+    db.put(b"key", b"value")?;
+    assert!(path.exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_rebuild_vector_index_with_progress() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3);
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), options.clone())?;
+        for i in 0..10 {
+            let memory = Memory::new(format!("mem_{}", i), "m", vec![i as f32, 1.0, 0.0], 0.5);
+            db.insert_memory(&memory)?;
+        }
+        db.insert_memory(&Memory::new("text_only", "t", Vec::new(), 0.5))?;
+
+        let reports = std::sync::Mutex::new(Vec::new());
+        let indexed = db.rebuild_vector_index_with_progress(|processed, total| {
+            reports.lock().unwrap().push((processed, total));
+        })?;
+        assert_eq!(indexed, 10);
+
+        let reports = reports.into_inner().unwrap();
+        let expected: Vec<(usize, usize)> = (0..=10).map(|i| (i, 10)).collect();
+        assert_eq!(reports, expected);
+        assert_eq!(db.search_similar(&[4.0, 1.0, 0.0], 1)?[0].id, "mem_4");
+    }
+
+    // The rebuilt graph was persisted and is loaded as is
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+    assert_eq!(db.search_similar(&[7.0, 1.0, 0.0], 1)?[0].id, "mem_7");
+    assert_eq!(db.rebuild_vector_index_with_progress(|_, _| {})?, 10);
+
+    Ok(())
+}
+
+#[test]
+fn test_repair_vector_index() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), OpenDBOptions::with_dimension(3))?;
+        for id in ["kept", "orphan_1", "orphan_2"] {
+            db.insert_memory(
+                &Memory::new(id, id, vec![1.0, 0.0, 0.0], 0.5)
+                    .with_field_embedding("title", vec![0.0, 1.0, 0.0]),
+            )?;
+        }
+        let mut pdf =
+            MultimodalDocument::new("doc", "paper.pdf", FileType::Pdf, 10, "text", vec![]);
+        pdf.add_chunk(DocumentChunk::new("0", "intro", vec![0.0, 0.0, 1.0], 0, 5));
+        db.insert_document(&pdf)?;
+
+        // Simulate a crash between the record delete and the vector delete
+        let mut txn = db.begin_transaction()?;
+        txn.delete("records", b"orphan_1")?;
+        txn.commit()?;
+        assert_eq!(db.count_vectors()?, 4);
+        assert_eq!(db.repair_vector_index()?, 2);
+        assert_eq!(db.count_vectors()?, 3);
+        assert_eq!(db.repair_vector_index()?, 0);
+
+        let mut txn = db.begin_transaction()?;
+        txn.delete("records", b"orphan_2")?;
+        txn.commit()?;
+    }
+
+    let options = OpenDBOptions::with_dimension(3).with_repair_on_open(true);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+    assert_eq!(db.count_vectors()?, 2);
+
+    // Memories and document chunks keep their vectors
+    let ids: Vec<String> = db
+        .search_similar(&[1.0, 0.0, 0.0], 10)?
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+    assert_eq!(ids, vec!["kept", "doc#0"]);
+    assert_eq!(
+        db.search_similar_field("title", &[0.0, 1.0, 0.0], 10)?
+            .len(),
+        1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_list_memories_paged() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let in_memory = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+
+    for db in [&db, &in_memory] {
+        // Insert out of order; pages follow id order regardless
+        for i in (0..25).rev() {
+            db.insert_memory(&Memory::new(format!("p_{:02}", i), "p", vec![], 0.5))?;
+        }
+        db.insert_memory(&Memory::new("q_00", "q", vec![], 0.5))?;
+
+        let ids = |page: &[Memory]| page.iter().map(|m| m.id.clone()).collect::<Vec<_>>();
+
+        let mut by_offset = Vec::new();
+        for offset in (0..30).step_by(10) {
+            let page = db.list_memories_paged("p_", offset, 10)?;
+            assert!(page.len() <= 10);
+            by_offset.extend(ids(&page));
+        }
+
+        let mut by_cursor = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = db.list_memories_after("p_", cursor.as_deref(), 10)?;
+            if page.is_empty() {
+                break;
+            }
+            cursor = page.last().map(|m| m.id.clone());
+            by_cursor.extend(ids(&page));
+        }
+
+        // Pages don't overlap, cover every record and are stable across calls
+        let expected: Vec<String> = (0..25).map(|i| format!("p_{:02}", i)).collect();
+        assert_eq!(by_offset, expected);
+        assert_eq!(by_cursor, expected);
+        assert_eq!(ids(&db.list_memories_paged("p_", 10, 5)?), expected[10..15]);
+        assert_eq!(
+            ids(&db.list_memories_after("p_", Some("p_09"), 5)?),
+            expected[10..15]
+        );
+
+        // A cursor outside the prefix still stays within it
+        assert_eq!(db.list_memories_after("p_", Some("a"), 1)?[0].id, "p_00");
+        assert!(db.list_memories_after("p_", Some("p_24"), 10)?.is_empty());
+        assert!(db.list_memories_paged("p_", 0, 0)?.is_empty());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_list_column_families() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let reserved = [
+        "default",
+        "records",
+        "graph_forward",
+        "graph_backward",
+        "vector_index",
+        "vector_data",
+        "metadata",
+        "documents",
+        "metadata_index",
+        "records_by_importance",
+        "deleted_records",
+        "records_by_time",
+    ];
+
+    {
+        let options = OpenDBOptions::with_dimension(3).with_extra_column_family("audit_log");
+        let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+        let cfs = db.list_column_families()?;
+        for name in reserved.iter().chain(["audit_log"].iter()) {
+            assert!(cfs.iter().any(|cf| cf == name), "missing {}", name);
+        }
+    }
+
+    // Reopening without the option still opens and reports the extra CF
+    let db = OpenDB::open_with_options(temp_dir.path(), OpenDBOptions::with_dimension(3))?;
+    let cfs = db.list_column_families()?;
+    assert_eq!(cfs.len(), reserved.len() + 1);
+    assert!(cfs.iter().any(|cf| cf == "audit_log"));
+
+    Ok(())
+}
+
+#[test]
+fn test_normalized_score() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3).with_distance_metric(DistanceMetric::Cosine);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+
+    db.insert_memory(&Memory::new("x", "x axis", vec![2.0, 0.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("y", "y axis", vec![0.0, 3.0, 0.0], 0.5))?;
+
+    let results = db.search_similar(&[1.0, 0.0, 0.0], 2)?;
+    assert_eq!(results[0].id, "x");
+    assert!((results[0].normalized_score() - 1.0).abs() < 1e-5);
+    assert_eq!(results[1].id, "y");
+    assert!(results[1].normalized_score().abs() < 1e-5);
+
+    // Euclidean exact matches also score ~1.0
+    let (db, _temp) = setup_test_db()?;
+    db.insert_memory(&Memory::new("x", "x axis", vec![2.0, 0.0, 0.0], 0.5))?;
+    let results = db.search_similar(&[2.0, 0.0, 0.0], 1)?;
+    assert!((results[0].normalized_score() - 1.0).abs() < 1e-5);
+
+    // Searches fill in the score that `relevance` reports
+    let results = db.search_similar(&[0.0, 0.0, 0.0], 1)?;
+    assert_eq!(results[0].score, Some(results[0].normalized_score()));
+    assert!((results[0].relevance() - 1.0 / 3.0).abs() < 1e-5);
+
+    let mut unscored = results[0].clone();
+    unscored.score = None;
+    assert_eq!(unscored.relevance(), results[0].relevance());
+
+    Ok(())
+}
+
+#[test]
+fn test_dot_product_metric() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3).with_distance_metric(DistanceMetric::DotProduct);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+
+    // "near" is closer to the query in euclidean terms, but "long" has the
+    // larger projection onto it
+    db.insert_memory(&Memory::new("near", "near", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("long", "long", vec![3.0, 2.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("away", "away", vec![-1.0, 0.0, 0.0], 0.5))?;
+
+    let results = db.search_similar(&[1.0, 0.0, 0.0], 3)?;
+    let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec!["long", "near", "away"]);
+    assert_eq!(results[0].distance, -3.0);
+    assert_eq!(results[1].distance, -1.0);
+
+    // Higher dot product, higher relevance
+    assert!(results[0].relevance() > results[1].relevance());
+    assert!(results[1].relevance() > 0.5);
+    assert!(results[2].relevance() < 0.5);
+
+    // Thresholds apply to the negated dot product
+    let within: Vec<String> = db
+        .search_within_distance(&[1.0, 0.0, 0.0], -0.5)?
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+    assert_eq!(within, vec!["long", "near"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_open_column_family_subset() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let kv_only = || OpenDBOptions::new().with_open_column_families(&["default"]);
+
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), kv_only())?;
+        db.put(b"config:mode", b"inspect")?;
+        db.flush()?;
+    }
+
+    // Reopen with the same reduced set and read the KV data back
+    let db = OpenDB::open_with_options(temp_dir.path(), kv_only())?;
+    assert_eq!(db.get(b"config:mode")?, Some(b"inspect".to_vec()));
+    let mut cfs = db.list_column_families()?;
+    cfs.sort();
+    assert_eq!(cfs, vec!["default", "metadata"]);
+
+    // Column families that weren't opened are unavailable
+    let memory = Memory::new("m", "m", vec![0.0; 384], 0.5);
+    assert!(matches!(db.insert_memory(&memory), Err(Error::Storage(_))));
+    drop(db);
+
+    // A full database can't be opened with a subset that omits existing CFs
+    let full_dir = TempDir::new().unwrap();
+    drop(OpenDB::open(full_dir.path())?);
+    let err = OpenDB::open_with_options(full_dir.path(), kv_only()).err();
+    assert!(matches!(err, Some(Error::InvalidInput(msg)) if msg.contains("records")));
+
+    Ok(())
+}
+
+#[test]
+fn test_search_similar_percentile() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    // A tight cluster near the query, then a clear gap
+    for (i, x) in [0.0, 0.1, 0.2].iter().enumerate() {
+        db.insert_memory(&Memory::new(
+            format!("near_{}", i),
+            "near",
+            vec![*x, 0.0, 0.0],
+            0.5,
+        ))?;
+    }
+    for (i, x) in [5.0, 5.1, 5.2].iter().enumerate() {
+        db.insert_memory(&Memory::new(
+            format!("far_{}", i),
+            "far",
+            vec![*x, 0.0, 0.0],
+            0.5,
+        ))?;
+    }
+
+    let results = db.search_similar_percentile(&[0.0, 0.0, 0.0], 6, 50.0)?;
+    let mut ids: Vec<_> = results.iter().map(|r| r.id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["near_0", "near_1", "near_2"]);
+
+    assert_eq!(
+        db.search_similar_percentile(&[0.0, 0.0, 0.0], 6, 100.0)?
+            .len(),
+        6
+    );
+    assert!(
+        db.search_similar_percentile(&[0.0, 0.0, 0.0], 6, 0.0)
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_close_and_promote() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let staging = temp_dir.path().join("staging");
+    let live = temp_dir.path().join("live");
+
+    // An old database is already live
+    {
+        let old = OpenDB::open_with_options(&live, OpenDBOptions::with_dimension(3))?;
+        old.put(b"version", b"old")?;
+    }
+
+    let db = OpenDB::open_with_options(&staging, OpenDBOptions::with_dimension(3))?;
+    db.put(b"version", b"new")?;
+    db.insert_memory(&Memory::new("m1", "promoted", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.close_and_promote(&live)?;
+
+    assert!(!staging.exists());
+    let db = OpenDB::open_with_options(&live, OpenDBOptions::with_dimension(3))?;
+    assert_eq!(db.get(b"version")?, Some(b"new".to_vec()));
+    assert_eq!(db.get_memory("m1")?.unwrap().content, "promoted");
+
+    Ok(())
+}
+
+#[test]
+fn test_compaction() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = || OpenDBOptions::with_dimension(3);
+
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), options())?;
+        for i in 0..2000 {
+            db.put(format!("key_{:05}", i).as_bytes(), &[0u8; 100])?;
+        }
+        for i in 0..1990 {
+            db.delete(format!("key_{:05}", i).as_bytes())?;
+        }
+
+        // Open databases keep their lock
+        assert!(OpenDB::compact_offline(temp_dir.path(), &options()).is_err());
+    }
+
+    let reclaimed = OpenDB::compact_offline(temp_dir.path(), &options())?;
+    assert!(reclaimed > 0);
+
+    let db = OpenDB::open_with_options(temp_dir.path(), options())?;
+    assert_eq!(db.scan_prefix(b"key_")?.len(), 10);
+    assert_eq!(db.get(b"key_01999")?, Some(vec![0u8; 100]));
+
+    // An open database is compacted by closing and reopening it
+    for i in 0..2000 {
+        db.put(format!("tmp_{:05}", i).as_bytes(), &[0u8; 100])?;
+    }
+    db.delete_prefix(b"tmp_")?;
+    let (db, reclaimed) = db.compact()?;
+    assert!(reclaimed > 0);
+    assert!(db.scan_prefix(b"tmp_")?.is_empty());
+    assert_eq!(db.get(b"key_01999")?, Some(vec![0u8; 100]));
+
+    let (db, _) = db.compact_cf("default")?;
+    let (db, _) = db.compact_range("default", Some(b"key_"), Some(b"key_99999"))?;
+    assert_eq!(db.scan_prefix(b"key_")?.len(), 10);
+    assert!(matches!(
+        db.compact_cf("no_such_cf"),
+        Err(Error::InvalidInput(_))
+    ));
+
+    // In memory there is nothing to compact
+    let db = OpenDB::open_in_memory_with_options(options())?;
+    db.put(b"k", b"v")?;
+    let (db, reclaimed) = db.compact()?;
+    assert_eq!(reclaimed, 0);
+    assert_eq!(db.get(b"k")?, Some(b"v".to_vec()));
+    assert!(db.compact_cf("no_such_cf").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_checkpoint_and_backup() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = || OpenDBOptions::with_dimension(3);
+    let db = OpenDB::open_with_options(temp_dir.path().join("live"), options())?;
+    db.put(b"version", b"1")?;
+    db.insert_memory(&Memory::new("m1", "first", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.link("m1", "self", "m1")?;
+
+    // A checkpoint is taken while the database stays open
+    let checkpoint = temp_dir.path().join("checkpoint");
+    db.full_checkpoint(&checkpoint)?;
+    assert!(matches!(
+        db.full_checkpoint(&checkpoint),
+        Err(Error::InvalidInput(_))
+    ));
+
+    let backups = temp_dir.path().join("backups");
+    db.create_full_backup(&backups)?;
+    db.put(b"version", b"2")?;
+    db.insert_memory(&Memory::new("m2", "second", vec![0.0, 1.0, 0.0], 0.5))?;
+    db.create_full_backup(&backups)?;
+    db.put(b"version", b"3")?;
+
+    {
+        let copy = OpenDB::open_with_options(&checkpoint, options())?;
+        assert_eq!(copy.get(b"version")?, Some(b"1".to_vec()));
+        assert_eq!(copy.get_memory("m1")?.unwrap().content, "first");
+        assert_eq!(copy.get_related("m1", "self")?, vec!["m1"]);
+        assert_eq!(copy.search_similar(&[1.0, 0.0, 0.0], 1)?[0].id, "m1");
+    }
+
+    // Restoring picks the latest backup
+    let restored = temp_dir.path().join("restored");
+    OpenDB::restore_full_backup(&backups, &restored)?;
+    assert!(matches!(
+        OpenDB::restore_full_backup(&backups, &restored),
+        Err(Error::InvalidInput(_))
+    ));
+    let restored = OpenDB::open_with_options(&restored, options())?;
+    assert_eq!(restored.get(b"version")?, Some(b"2".to_vec()));
+    assert_eq!(restored.get_memory("m2")?.unwrap().content, "second");
+
+    // A failed restore doesn't leave a partial database behind
+    std::fs::create_dir(backups.join("3"))?;
+    std::fs::create_dir(backups.join("3").join("not_a_file"))?;
+    let partial = temp_dir.path().join("partial");
+    assert!(OpenDB::restore_full_backup(&backups, &partial).is_err());
+    assert!(!partial.exists());
+
+    let in_memory = OpenDB::open_in_memory_with_options(options())?;
+    assert!(
+        in_memory
+            .full_checkpoint(temp_dir.path().join("none"))
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_open_read_only() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = || OpenDBOptions::with_dimension(3);
+    let writer = OpenDB::open_with_options(temp_dir.path(), options())?;
+    writer.put(b"key", b"value")?;
+    writer.insert_memory(&Memory::new("m1", "first", vec![1.0, 0.0, 0.0], 0.5))?;
+    writer.insert_memory(&Memory::new("m2", "second", vec![0.0, 1.0, 0.0], 0.5))?;
+    writer.link("m1", "next", "m2")?;
+    writer.flush()?;
+
+    // Readers open alongside the writer, which still holds the lock
+    let reader = OpenDB::open_read_only_with_options(temp_dir.path(), options())?;
+    let other = OpenDB::open_read_only_with_options(temp_dir.path(), options())?;
+    assert!(reader.is_read_only());
+    assert!(!writer.is_read_only());
+
+    assert_eq!(reader.get(b"key")?, Some(b"value".to_vec()));
+    assert_eq!(reader.get_memory("m1")?.unwrap().content, "first");
+    assert_eq!(reader.get_related("m1", "next")?, vec!["m2"]);
+    assert_eq!(reader.search_similar(&[0.0, 1.0, 0.0], 1)?[0].id, "m2");
+    assert_eq!(other.list_memory_ids("")?, vec!["m1", "m2"]);
+
+    let read_only = |result: Result<()>| matches!(result, Err(Error::InvalidInput(_)));
+    assert!(read_only(reader.put(b"key", b"other")));
+    assert!(read_only(reader.delete(b"key")));
+    assert!(read_only(reader.insert_memory(&Memory::new(
+        "m3",
+        "third",
+        vec![0.0, 0.0, 1.0],
+        0.5
+    ))));
+    assert!(read_only(reader.delete_memory("m1")));
+    assert!(read_only(reader.link("m2", "next", "m1")));
+    assert!(read_only(reader.begin_transaction().map(drop)));
+
+    // Failed writes leave the reader's view untouched
+    assert_eq!(reader.get(b"key")?, Some(b"value".to_vec()));
+    assert_eq!(reader.search_similar(&[0.0, 0.0, 1.0], 5)?.len(), 2);
+    drop((reader, other, writer));
+
+    // Nothing in the directory is created by a reader
+    let info = temp_dir.path().join("OPENDB_INFO");
+    std::fs::remove_file(&info)?;
+    drop(OpenDB::open_read_only_with_options(
+        temp_dir.path(),
+        options(),
+    )?);
+    assert!(!info.exists());
+
+    let missing = temp_dir.path().join("missing");
+    assert!(OpenDB::open_read_only(&missing).is_err());
+    assert!(!missing.exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_rocksdb_tuning_presets() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    assert_eq!(
+        OpenDBOptions::default().rocksdb_tuning,
+        RocksDBTuning::default()
+    );
+
+    // Tuning isn't persisted, so a database can be reopened with another preset
+    for tuning in [
+        RocksDBTuning::low_memory(),
+        RocksDBTuning::high_throughput(),
+        RocksDBTuning {
+            compression: Compression::None,
+            ..RocksDBTuning::default()
+        },
+    ] {
+        let options = OpenDBOptions::with_dimension(3).with_rocksdb_tuning(tuning);
+        let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+        db.insert_memory(&Memory::new(
+            format!("{:?}", tuning.compression),
+            "tuned",
+            vec![1.0, 0.0, 0.0],
+            0.5,
+        ))?;
+    }
+
+    let db = OpenDB::open_with_options(temp_dir.path(), OpenDBOptions::with_dimension(3))?;
+    assert_eq!(db.list_memory_ids("")?, vec!["Lz4", "None", "Zstd"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_get_memories() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    db.insert_memory(&Memory::new("m1", "one", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("m2", "two", vec![0.0, 1.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("old", "gone", vec![0.0, 0.0, 1.0], 0.5).with_ttl(-1))?;
+
+    let found = db.get_memories(&["m2", "missing", "old", "m1"])?;
+    let contents: Vec<Option<&str>> = found
+        .iter()
+        .map(|memory| memory.as_ref().map(|m| m.content.as_str()))
+        .collect();
+    assert_eq!(contents, vec![Some("two"), None, None, Some("one")]);
+    assert!(db.get_memories(&[])?.is_empty());
+
+    // Search results are hydrated the same way
+    let results = db.search_similar(&[0.0, 0.0, 1.0], 3)?;
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| r.memory.id != "old"));
+
+    Ok(())
+}
+
+#[test]
+fn test_decay_and_evict_memories() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+    let day = 24 * 60 * 60;
+    let now = chrono::Utc::now().timestamp();
+
+    let mut old = Memory::new("old", "old", vec![1.0, 0.0, 0.0], 0.5);
+    old.timestamp = now - day;
+    let mut ancient = Memory::new("ancient", "ancient", vec![0.0, 1.0, 0.0], 0.8);
+    ancient.timestamp = now - 3 * day;
+    let fresh = Memory::new("fresh", "fresh", vec![0.0, 0.0, 1.0], 0.9);
+    db.insert_memories(&[old, ancient, fresh])?;
+    db.link("fresh", "mentions", "ancient")?;
+    db.link("ancient", "follows", "old")?;
+
+    assert!(db.decay_memories(0).is_err());
+
+    // One half-life halves importance; three divide it by eight
+    assert_eq!(db.decay_memories(day)?, 2);
+    let importance = |id| -> Result<f32> { Ok(db.get_memory(id)?.unwrap().importance) };
+    assert!((importance("old")? - 0.25).abs() < 1e-3);
+    assert!((importance("ancient")? - 0.1).abs() < 1e-3);
+    assert_eq!(importance("fresh")?, 0.9);
+
+    // Decayed records start a new age, so an immediate rerun changes nothing
+    assert_eq!(db.decay_memories(day)?, 0);
+
+    // Creation times are kept, so decayed records don't look recent
+    let old = db.get_memory("old")?.unwrap();
+    assert_eq!(old.timestamp, now - day);
+    assert!(old.last_decayed.is_some_and(|t| t >= now));
+    assert_eq!(db.recent_memories(1)?[0].id, "fresh");
+
+    assert_eq!(db.evict_below(0.2)?, 1);
+    assert!(db.get_memory("ancient")?.is_none());
+    assert!(db.get_related("fresh", "mentions")?.is_empty());
+    assert!(db.get_incoming("old")?.is_empty());
+    let results = db.search_similar(&[0.0, 1.0, 0.0], 5)?;
+    assert!(results.iter().all(|r| r.id != "ancient"));
+    assert_eq!(db.list_memory_ids("")?, vec!["fresh", "old"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_exists() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.insert_memory(&Memory::new("present", "here", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("deleted", "gone", vec![0.0, 1.0, 0.0], 0.5))?;
+    db.delete_memory("deleted")?;
+
+    for id in ["present", "deleted", "never"] {
+        assert_eq!(
+            db.memory_exists(id)?,
+            db.get_memory(id)?.is_some(),
+            "{}",
+            id
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_release_vector_cache() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.insert_memory(&Memory::new("x", "x", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("y", "y", vec![0.0, 1.0, 0.0], 0.5))?;
+    let before = db.search_similar(&[0.9, 0.1, 0.0], 2)?;
+    assert!(db.is_vector_index_ready());
+
+    db.release_vector_cache();
+    assert!(!db.is_vector_index_ready());
+
+    let after = db.search_similar(&[0.9, 0.1, 0.0], 2)?;
+    assert!(db.is_vector_index_ready());
+    let ids = |results: &[opendb::types::SearchResult]| {
+        results.iter().map(|r| r.id.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&after), ids(&before));
+    assert_eq!(after[0].id, "x");
+
+    Ok(())
+}
+
+#[test]
+fn test_pagerank() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    // Every spoke points at the hub; the hub points back at one spoke
+    for spoke in ["a", "b", "c", "d"] {
+        db.link(spoke, "cites", "hub")?;
+    }
+    db.link("hub", "cites", "a")?;
+
+    let scores = db.pagerank(0.85, 50)?;
+    assert_eq!(scores.len(), 5);
+    let top = scores
+        .iter()
+        .max_by(|x, y| x.1.partial_cmp(y.1).unwrap())
+        .unwrap();
+    assert_eq!(top.0, "hub");
+    assert!(scores["a"] > scores["b"]);
+    assert!((scores.values().sum::<f32>() - 1.0).abs() < 1e-3);
+
+    assert!(db.pagerank(1.5, 10).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_get_memory_bypass_cache() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.insert_memory(&Memory::new("a", "original", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("b", "replacement", vec![0.0, 1.0, 0.0], 0.5))?;
+    assert_eq!(db.get_memory("a")?.unwrap().content, "original");
+
+    // Out-of-band write the cache doesn't see
+    let mut txn = db.begin_transaction()?;
+    let bytes = txn.get("records", b"b")?.unwrap();
+    txn.put("records", b"a", &bytes)?;
+    txn.commit()?;
+
+    let stale = db.get_memory_opts("a", ReadOpts::default())?.unwrap();
+    assert_eq!(stale.content, "original");
+
+    let fresh = db
+        .get_memory_opts("a", ReadOpts { bypass_cache: true })?
+        .unwrap();
+    assert_eq!(fresh.content, "replacement");
+
+    // The bypass read refreshed the cache for later plain reads
+    assert_eq!(db.get_memory("a")?.unwrap().content, "replacement");
+
+    Ok(())
+}
+
+#[test]
+fn test_memories_exist() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.insert_memory(&Memory::new("m1", "one", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("m3", "three", vec![0.0, 1.0, 0.0], 0.5))?;
+
+    let flags = db.memories_exist(&["m1", "m2", "m3", "m4", "m1"])?;
+    assert_eq!(flags, vec![true, false, true, false, true]);
+    assert!(db.memories_exist(&[])?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_multigraph_edges() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3).with_multigraph(true);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+
+    let first = Edge::new("alice", "paid", "bob").with_weight(10.0);
+    let second = Edge::new("alice", "paid", "bob").with_weight(25.0);
+    db.add_edge(&first)?;
+    db.add_edge(&second)?;
+    db.link("alice", "paid", "bob")?;
+
+    assert_eq!(db.get_outgoing("alice")?.len(), 3);
+    assert_eq!(db.get_incoming("bob")?.len(), 3);
+
+    // Triples are ambiguous, so removal goes by id
+    assert!(matches!(
+        db.unlink("alice", "paid", "bob"),
+        Err(Error::InvalidInput(_))
+    ));
+    assert!(db.unlink_edge("alice", &second.id)?);
+    assert!(!db.unlink_edge("alice", &second.id)?);
+
+    let remaining = db.get_outgoing("alice")?;
+    assert_eq!(remaining.len(), 2);
+    assert!(remaining.iter().all(|e| e.id != second.id));
+    assert!(
+        remaining
+            .iter()
+            .any(|e| e.id == first.id && e.weight == 10.0)
+    );
+    assert_eq!(db.get_incoming("bob")?.len(), 2);
+
+    // Default mode keeps edges unique per triple
+    let (simple, _temp) = setup_test_db()?;
+    simple.add_edge(&first)?;
+    simple.add_edge(&second)?;
+    assert_eq!(simple.get_outgoing("alice")?.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_manifest() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3).with_distance_metric(DistanceMetric::Cosine);
+
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), options.clone())?;
+        assert!(temp_dir.path().join("MANIFEST.opendb").exists());
+
+        db.insert_memory(&Memory::new("a", "a", vec![1.0, 0.0, 0.0], 0.5))?;
+        db.insert_memory(&Memory::new("b", "b", vec![0.0, 1.0, 0.0], 0.5))?;
+        db.link("a", "related_to", "b")?;
+
+        let manifest = db.manifest()?;
+        assert_eq!(manifest.format_version, 1);
+        assert_eq!(manifest.vector_dimension, 3);
+        assert_eq!(manifest.distance_metric, DistanceMetric::Cosine);
+        assert!(!manifest.multigraph);
+        assert!(manifest.column_families.iter().any(|cf| cf == "records"));
+        assert_eq!(manifest.memory_count, Some(2));
+        assert_eq!(manifest.edge_count, Some(1));
+    }
+
+    // The file only describes the layout, so writing it never scans records
+    let json = std::fs::read_to_string(temp_dir.path().join("MANIFEST.opendb")).unwrap();
+    let manifest: opendb::Manifest = serde_json::from_str(&json).unwrap();
+    assert_eq!(manifest.memory_count, None);
+    assert_eq!(manifest.edge_count, None);
+    assert_eq!(manifest.distance_metric, DistanceMetric::Cosine);
+    assert!(manifest.column_families.iter().any(|cf| cf == "records"));
+
+    Ok(())
+}
+
+#[test]
+fn test_zero_norm_embedding() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let zero = Memory::new("zero", "failed embedding", vec![0.0; 3], 0.5);
+
+    let cosine = OpenDB::open_with_options(
+        temp_dir.path().join("cosine"),
+        OpenDBOptions::with_dimension(3).with_distance_metric(DistanceMetric::Cosine),
+    )?;
+    assert!(matches!(
+        cosine.insert_memory(&zero),
+        Err(Error::InvalidInput(_))
+    ));
+
+    let (euclidean, _temp) = setup_test_db()?;
+    euclidean.insert_memory(&zero)?;
+    assert_eq!(euclidean.search_similar(&[0.0; 3], 1)?[0].id, "zero");
+
+    Ok(())
+}
+
+#[test]
+fn test_get_memories_consistent() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.insert_memory(&Memory::new("a", "a v1", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("b", "b v1", vec![0.0, 1.0, 0.0], 0.5))?;
+
+    let view = db.get_memories_consistent(&["b", "missing", "a"])?;
+    assert_eq!(view.len(), 3);
+    assert_eq!(view[0].as_ref().unwrap().content, "b v1");
+    assert!(view[1].is_none());
+    assert_eq!(view[2].as_ref().unwrap().content, "a v1");
+
+    // Later updates are picked up by the next consistent read
+    db.insert_memory(&Memory::new("a", "a v2", vec![1.0, 0.0, 0.0], 0.5))?;
+    db.insert_memory(&Memory::new("b", "b v2", vec![0.0, 1.0, 0.0], 0.5))?;
+    let view = db.get_memories_consistent(&["a", "b"])?;
+    assert_eq!(view[0].as_ref().unwrap().content, "a v2");
+    assert_eq!(view[1].as_ref().unwrap().content, "b v2");
+
+    Ok(())
+}
+
+#[test]
+fn test_search_within_distance_early_exit_matches_full() -> Result<()> {
+    let dimension = 64;
+    let mut results = Vec::new();
+
+    for early_exit in [true, false] {
+        let temp_dir = TempDir::new().unwrap();
+        let options =
+            OpenDBOptions::with_dimension(dimension).with_threshold_early_exit(early_exit);
+        let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+
+        for i in 0..50 {
+            let embedding: Vec<f32> = (0..dimension)
+                .map(|j| ((i * 7 + j * 3) % 11) as f32 / 10.0)
+                .collect();
+            db.insert_memory(&Memory::new(format!("mem_{}", i), "m", embedding, 0.5))?;
+        }
+
+        let query: Vec<f32> = (0..dimension).map(|j| (j % 11) as f32 / 10.0).collect();
+        let hits = db.search_within_distance(&query, 3.0)?;
+        assert!(hits.iter().all(|hit| hit.distance <= 3.0));
+        assert!(hits.windows(2).all(|w| w[0].distance <= w[1].distance));
+
+        results.push(
+            hits.into_iter()
+                .map(|hit| (hit.id, hit.distance))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    assert!(!results[0].is_empty());
+    assert!(results[0].len() < 50);
+    assert_eq!(results[0], results[1]);
+
+    Ok(())
+}
+
+#[test]
+fn test_search_text_contains() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    let contents = [
+        ("m1", "Rust has a borrow checker"),
+        ("m2", "Python is dynamically typed"),
+        ("m3", "The BORROW checker prevents data races"),
+        ("m4", "Borrowing rules in Rust"),
+    ];
+    for (id, content) in contents {
+        db.insert_memory(&Memory::new(id, content, vec![0.1, 0.2, 0.3], 0.5))?;
+    }
+
+    let ids = |memories: Vec<Memory>| memories.into_iter().map(|m| m.id).collect::<Vec<_>>();
+
+    assert_eq!(
+        ids(db.search_text_contains("borrow", 10, false)?),
+        vec!["m1"]
+    );
+    assert_eq!(
+        ids(db.search_text_contains("borrow", 10, true)?),
+        vec!["m1", "m3", "m4"]
+    );
+    assert_eq!(
+        ids(db.search_text_contains("borrow", 2, true)?),
+        vec!["m1", "m3"]
+    );
+    assert!(db.search_text_contains("golang", 10, true)?.is_empty());
+    assert!(db.search_text_contains("Rust", 0, false)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_traverse_page_wide_graph() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    // Root fans out to 25 hubs, each with 2 leaves: 75 reachable nodes
+    for i in 0..25 {
+        let hub = format!("hub_{:02}", i);
+        db.link("root", "has", &hub)?;
+        for j in 0..2 {
+            db.link(&hub, "has", &format!("leaf_{:02}_{}", i, j))?;
+        }
+    }
+    db.link("leaf_00_0", "has", "root")?; // cycle back to the start
+
+    let mut seen = std::collections::HashSet::new();
+    let mut all = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page = db.traverse_page("root", Some("has"), 2, offset, 10)?;
+        if page.is_empty() {
+            break;
+        }
+        assert!(page.len() <= 10);
+        for (id, _) in &page {
+            assert!(seen.insert(id.clone()), "{} appeared on two pages", id);
+        }
+        offset += page.len();
+        all.extend(page);
+    }
+
+    assert_eq!(all.len(), 75);
+    assert!(!seen.contains("root"));
+    // Breadth-first: all hubs (1 hop) precede all leaves (2 hops)
+    assert!(
+        all[..25]
+            .iter()
+            .all(|(id, hops)| id.starts_with("hub_") && *hops == 1)
+    );
+    assert!(all[25..].iter().all(|(_, hops)| *hops == 2));
+
+    // Pages are reproducible
+    assert_eq!(
+        db.traverse_page("root", Some("has"), 2, 30, 5)?,
+        all[30..35].to_vec()
+    );
+    assert!(db.traverse_page("root", Some("has"), 1, 25, 10)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_reopen_config_mismatch() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3);
+    {
+        let db = OpenDB::open_with_options(temp_dir.path(), options.clone())?;
+        db.insert_memory(&Memory::new("m", "c", vec![1.0, 0.0, 0.0], 0.5))?;
+    }
+
+    match OpenDB::open_with_options(temp_dir.path(), OpenDBOptions::with_dimension(4)) {
+        Err(Error::ConfigMismatch {
+            field,
+            expected,
+            got,
+        }) => {
+            assert_eq!(field, "vector_dimension");
+            assert_eq!(expected, "3");
+            assert_eq!(got, "4");
+        }
+        other => panic!("expected ConfigMismatch, got {:?}", other.err()),
+    }
+
+    let cosine = options.clone().with_distance_metric(DistanceMetric::Cosine);
+    match OpenDB::open_with_options(temp_dir.path(), cosine) {
+        Err(Error::ConfigMismatch { field, .. }) => assert_eq!(field, "distance_metric"),
+        other => panic!("expected ConfigMismatch, got {:?}", other.err()),
+    }
+
+    // The matching configuration still opens, with data intact
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+    assert!(db.get_memory("m")?.is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_document_insert_get_and_chunk_search() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    let mut pdf =
+        MultimodalDocument::new("pdf_1", "paper.pdf", FileType::Pdf, 1024, "text", vec![]);
+    pdf.add_chunk(
+        DocumentChunk::new("0", "intro", vec![1.0, 0.0, 0.0], 0, 5).with_metadata("page", "1"),
+    );
+    pdf.add_chunk(DocumentChunk::new(
+        "1",
+        "results",
+        vec![0.0, 1.0, 0.0],
+        5,
+        12,
+    ));
+    db.insert_document(&pdf)?;
+    db.insert_memory(&Memory::new("mem", "note", vec![0.0, 0.0, 1.0], 0.5))?;
+
+    let stored = db.get_document("pdf_1")?.unwrap();
+    assert_eq!(stored.filename, "paper.pdf");
+    assert_eq!(stored.chunks.len(), 2);
+    assert_eq!(db.list_documents("pdf_")?.len(), 1);
+
+    // Chunks participate in search and map back to their document
+    let results = db.search_similar(&[0.0, 0.9, 0.1], 3)?;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].id, "pdf_1#1");
+    assert_eq!(results[0].memory.content, "results");
+    assert_eq!(
+        results[0]
+            .memory
+            .metadata
+            .get(DocumentChunk::PARENT_METADATA_KEY),
+        Some(&"pdf_1".to_string())
+    );
+    let intro = results.iter().find(|r| r.id == "pdf_1#0").unwrap();
+    assert_eq!(intro.memory.metadata.get("page"), Some(&"1".to_string()));
+
+    // Replacing the document drops chunk vectors it no longer has
+    let mut replaced = pdf.clone();
+    replaced.chunks.truncate(1);
+    db.insert_document(&replaced)?;
+    let results = db.search_similar(&[0.0, 1.0, 0.0], 10)?;
+    assert!(results.iter().all(|r| r.id != "pdf_1#1"));
+    assert_eq!(results.len(), 2);
+
+    db.delete_document("pdf_1")?;
+    assert!(db.get_document("pdf_1")?.is_none());
+    let results = db.search_similar(&[1.0, 0.0, 0.0], 10)?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, "mem");
+
+    // Ids containing the chunk separator are rejected
+    let bad = MultimodalDocument::new("a#b", "x.txt", FileType::Text, 1, "x", vec![]);
+    assert!(matches!(
+        db.insert_document(&bad),
+        Err(Error::InvalidInput(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_search_chunks() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    let mut paper =
+        MultimodalDocument::new("paper", "paper.pdf", FileType::Pdf, 1024, "text", vec![]);
+    paper.add_chunk(
+        DocumentChunk::new("0", "abstract", vec![1.0, 0.0, 0.0], 0, 8).with_metadata("page", "1"),
+    );
+    paper.add_chunk(
+        DocumentChunk::new("1", "method", vec![0.0, 1.0, 0.0], 8, 14).with_metadata("page", "4"),
+    );
+    let mut talk = MultimodalDocument::new("talk", "talk.mp4", FileType::Video, 2048, "", vec![]);
+    talk.add_chunk(
+        DocumentChunk::new("0", "q&a", vec![0.1, 0.9, 0.2], 0, 3)
+            .with_metadata("timestamp", "00:42:10"),
+    );
+    db.insert_document(&paper)?;
+    db.insert_document(&talk)?;
+
+    // Memories closer to the query than any chunk are skipped
+    db.insert_memory(&Memory::new("note", "note", vec![0.0, 1.0, 0.0], 0.5))?;
+
+    let results = db.search_chunks(&[0.0, 1.0, 0.0], 2)?;
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].document_id, "paper");
+    assert_eq!(results[0].chunk.content, "method");
+    assert_eq!(
+        results[0].chunk.metadata.get("page"),
+        Some(&"4".to_string())
+    );
+    assert!(results[0].distance < 1e-6);
+    assert_eq!(results[1].document_id, "talk");
+    assert_eq!(
+        results[1].chunk.metadata.get("timestamp"),
+        Some(&"00:42:10".to_string())
+    );
+
+    let results = db.search_chunks(&[1.0, 0.0, 0.0], 10)?;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].chunk.content, "abstract");
+
+    Ok(())
+}
+
+#[test]
+fn test_traverse_multi_hop() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.link("rust", "has_feature", "ownership")?;
+    db.link("ownership", "enables", "memory_safety")?;
+    db.link("memory_safety", "enables", "rust")?; // cycle
+    db.link("memory_safety", "enables", "fearless_concurrency")?;
+    db.link("rust", "related_to", "cpp")?;
+
+    let reached = db.traverse("rust", None, 3)?;
+    assert_eq!(
+        reached,
+        vec![
+            ("ownership".to_string(), 1),
+            ("cpp".to_string(), 1),
+            ("memory_safety".to_string(), 2),
+            ("fearless_concurrency".to_string(), 3),
+        ]
+    );
+
+    assert_eq!(db.traverse("rust", None, 2)?.len(), 3);
+    assert_eq!(
+        db.traverse("rust", Some("has_feature"), 3)?,
+        vec![("ownership".to_string(), 1)]
+    );
+    assert!(db.traverse("nowhere", None, 3)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_traverse_node_cap() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(3).with_max_traversal_nodes(5);
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+
+    for i in 0..20 {
+        db.link("hub", "to", &format!("n{:02}", i))?;
+    }
+
+    let reached = db.traverse("hub", None, 1)?;
+    assert_eq!(reached.len(), 5);
+    assert_eq!(reached[0], ("n00".to_string(), 1));
+
+    Ok(())
+}
+
+#[test]
+fn test_subgraph_collects_edges_between_reached_nodes() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.link("rust", "has_feature", "ownership")?;
+    db.link("ownership", "enables", "memory_safety")?;
+    db.link("memory_safety", "enables", "rust")?; // cycle
+    db.link("memory_safety", "enables", "fearless_concurrency")?;
+    db.link("rust", "related_to", "cpp")?;
+
+    let triples = |edges: &[Edge]| -> Vec<(String, String, String)> {
+        edges
+            .iter()
+            .map(|e| (e.from.clone(), e.relation.clone(), e.to.clone()))
+            .collect()
+    };
+    let triple = |from: &str, relation: &str, to: &str| {
+        (from.to_string(), relation.to_string(), to.to_string())
+    };
+
+    // Edges back into the subgraph are kept; edges leaving it are not
+    let (nodes, edges) = db.subgraph("rust", 2, 10)?;
+    assert_eq!(nodes, vec!["rust", "ownership", "cpp", "memory_safety"]);
+    assert_eq!(
+        triples(&edges),
+        vec![
+            triple("rust", "has_feature", "ownership"),
+            triple("rust", "related_to", "cpp"),
+            triple("ownership", "enables", "memory_safety"),
+            triple("memory_safety", "enables", "rust"),
+        ]
+    );
+
+    // The node cap counts the root
+    let (nodes, edges) = db.subgraph("rust", 3, 3)?;
+    assert_eq!(nodes, vec!["rust", "ownership", "cpp"]);
+    assert_eq!(edges.len(), 2);
+
+    let (nodes, edges) = db.subgraph("rust", 3, 0)?;
+    assert!(nodes.is_empty() && edges.is_empty());
+    let (nodes, edges) = db.subgraph("nowhere", 3, 10)?;
+    assert_eq!(nodes, vec!["nowhere"]);
+    assert!(edges.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_unlink_self_loop_keeps_other_edges() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.link("a", "rel", "b")?;
+    db.link("a", "rel", "a")?;
+
+    db.unlink("a", "rel", "a")?;
+    let outgoing: Vec<String> = db.get_outgoing("a")?.into_iter().map(|e| e.to).collect();
+    assert_eq!(outgoing, vec!["b"]);
+    assert_eq!(db.get_incoming("b")?.len(), 1);
+    assert!(db.get_incoming("a")?.is_empty());
+
+    db.link("a", "rel", "a")?;
+    db.unlink("a", "rel", "b")?;
+    let outgoing: Vec<String> = db.get_outgoing("a")?.into_iter().map(|e| e.to).collect();
+    assert_eq!(outgoing, vec!["a"]);
+    assert!(db.get_incoming("b")?.is_empty());
+    assert_eq!(db.get_incoming("a")?.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_hnsw_search_survives_reopen() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let options = OpenDBOptions::with_dimension(8).with_hnsw_params(HnswParams {
+        ef_search: 16,
+        ..HnswParams::default()
+    });
+    let embedding = |i: usize| -> Vec<f32> {
+        (0..8)
+            .map(|j| ((i * 7919 + j * 104_729) % 10_007) as f32 / 10_007.0)
+            .collect()
+    };
+
+    let expected = {
+        let db = OpenDB::open_with_options(temp_dir.path(), options.clone())?;
+        for i in 0..500 {
+            db.insert_memory(&Memory::new(format!("m{}", i), "c", embedding(i), 0.5))?;
+        }
+        db.delete_memory("m7")?;
+
+        let results = db.search_similar(&embedding(123), 5)?;
+        assert_eq!(results[0].id, "m123");
+        assert_eq!(results[0].distance, 0.0);
+        assert!(
+            db.search_similar(&embedding(7), 10)?
+                .iter()
+                .all(|r| r.id != "m7")
+        );
+        results.into_iter().map(|r| r.id).collect::<Vec<_>>()
+    };
+
+    let db = OpenDB::open_with_options(temp_dir.path(), options)?;
+    let results = db.search_similar(&embedding(123), 5)?;
+    assert_eq!(
+        results.into_iter().map(|r| r.id).collect::<Vec<_>>(),
+        expected
+    );
+
+    // Updates after reopening extend the same graph
+    db.insert_memory(&Memory::new("m123", "moved", embedding(400), 0.5))?;
+    let results = db.search_similar(&embedding(400), 2)?;
+    let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+    assert!(ids.contains(&"m123") && ids.contains(&"m400"));
+
+    Ok(())
+}
+
+#[test]
+fn test_export_import_json() -> Result<()> {
+    let (db, _temp) = setup_test_db()?;
+
+    db.insert_memory(
+        &Memory::new("m1", "first", vec![1.0, 0.0, 0.0], 0.7).with_metadata("topic", "rust"),
+    )?;
+    db.insert_memory(&Memory::new("m2", "text only", vec![], 0.2))?;
+    db.link_weighted("m1", "related_to", "m2", 0.4)?;
+    db.put(b"greeting", b"hello")?;
+    db.put(&[0xff, 0x00], &[0xfe, 0x01])?;
+    let mut pdf = MultimodalDocument::new("doc", "paper.pdf", FileType::Pdf, 10, "text", vec![]);
+    pdf.add_chunk(DocumentChunk::new("0", "intro", vec![0.0, 1.0, 0.0], 0, 5));
+    db.insert_document(&pdf)?;
+
+    let mut json = Vec::new();
+    db.export_json(&mut json)?;
+
+    // A single human-readable JSON document
+    let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(value["opendb_export"], 1);
+    assert_eq!(value["memories"].as_array().unwrap().len(), 2);
+    assert_eq!(value["kv"][0]["key"], "greeting");
+    assert_eq!(value["kv"][1]["key"], serde_json::json!([255, 0]));
+
+    let copy = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3))?;
+    copy.import_json(json.as_slice())?;
+
+    let m1 = copy.get_memory("m1")?.unwrap();
+    assert_eq!(m1.content, "first");
+    assert_eq!(m1.metadata.get("topic").map(String::as_str), Some("rust"));
+    assert_eq!(copy.get_memory("m2")?.unwrap().content, "text only");
+    assert_eq!(
+        copy.get_related_weighted("m1", "related_to")?,
+        vec![("m2".to_string(), 0.4)]
+    );
+    assert_eq!(copy.get_incoming("m2")?.len(), 1);
+    assert_eq!(copy.get(b"greeting")?, Some(b"hello".to_vec()));
+    assert_eq!(copy.get(&[0xff, 0x00])?, Some(vec![0xfe, 0x01]));
+    assert_eq!(copy.get_document("doc")?.unwrap().chunks.len(), 1);
+
+    // Vectors were re-indexed, including document chunks
+    assert_eq!(copy.search_similar(&[1.0, 0.0, 0.0], 1)?[0].id, "m1");
+    assert_eq!(copy.search_similar(&[0.0, 1.0, 0.0], 1)?[0].id, "doc#0");
+
+    // Unknown versions and malformed documents are rejected
+    assert!(matches!(
+        copy.import_json(&b"{\"opendb_export\":99}"[..]),
+        Err(Error::Codec(_))
+    ));
+    assert!(copy.import_json(&b"{\"memories\":[1]}"[..]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_merge_from_conflict_policies() -> Result<()> {
+    let open = || OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(3));
+
+    let source = open()?;
+    source.insert_memory(&Memory::new_at(
+        "shared",
+        "source",
+        vec![1.0, 0.0, 0.0],
+        0.5,
+        200,
+    ))?;
+    source.insert_memory(&Memory::new_at(
+        "only_source",
+        "new",
+        vec![0.0, 1.0, 0.0],
+        0.5,
+        100,
+    ))?;
+    source.link_with(&Edge::new_at("shared", "rel", "only_source", 50))?;
+    source.put(b"k", b"source")?;
+    source.put(b"k2", b"x")?;
+    source.insert_document(&MultimodalDocument::new_at(
+        "doc",
+        "a.txt",
+        FileType::Text,
+        1,
+        "a",
+        vec![],
+        300,
+    ))?;
+
+    let target = || -> Result<OpenDB> {
+        let db = open()?;
+        db.insert_memory(&Memory::new_at(
+            "shared",
+            "target",
+            vec![1.0, 0.0, 0.0],
+            0.5,
+            100,
+        ))?;
+        db.link_with(&Edge::new_at("shared", "rel", "only_source", 10))?;
+        db.put(b"k", b"target")?;
+        Ok(db)
+    };
+    let content = |db: &OpenDB| db.get_memory("shared").map(|m| m.unwrap().content);
+
+    let db = target()?;
+    let stats = db.merge_from(&source, ConflictPolicy::KeepExisting)?;
+    assert_eq!(
+        stats,
+        MergeStats {
+            inserted: 3,
+            skipped: 3,
+            overwritten: 0
+        }
+    );
+    assert_eq!(content(&db)?, "target");
+    assert_eq!(db.get(b"k")?, Some(b"target".to_vec()));
+    assert_eq!(db.get(b"k2")?, Some(b"x".to_vec()));
+    assert!(db.get_document("doc")?.is_some());
+    // Merged vectors are indexed
+    assert_eq!(db.search_similar(&[0.0, 1.0, 0.0], 1)?[0].id, "only_source");
+
+    let db = target()?;
+    let stats = db.merge_from(&source, ConflictPolicy::Overwrite)?;
+    assert_eq!(
+        (stats.inserted, stats.skipped, stats.overwritten),
+        (3, 0, 3)
+    );
+    assert_eq!(content(&db)?, "source");
+    assert_eq!(db.get(b"k")?, Some(b"source".to_vec()));
+    let edges = db.get_outgoing("shared")?;
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0].timestamp, 50);
+    assert_eq!(db.get_incoming("only_source")?.len(), 1);
+
+    // KV pairs carry no timestamp, so the existing one stays
+    let db = target()?;
+    let stats = db.merge_from(&source, ConflictPolicy::KeepNewerTimestamp)?;
+    assert_eq!(
+        (stats.inserted, stats.skipped, stats.overwritten),
+        (3, 1, 2)
+    );
+    assert_eq!(content(&db)?, "source");
+    assert_eq!(db.get(b"k")?, Some(b"target".to_vec()));
+    assert_eq!(db.get_outgoing("shared")?[0].timestamp, 50);
+
+    // Nothing is written when the dimensions differ
+    let other = OpenDB::open_in_memory_with_options(OpenDBOptions::with_dimension(4))?;
+    other.put(b"k3", b"y")?;
+    let db = target()?;
+    assert!(matches!(
+        db.merge_from(&other, ConflictPolicy::Overwrite),
+        Err(Error::DimensionMismatch {
+            expected: 3,
+            got: 4,
+            ..
+        })
+    ));
+    assert!(db.get(b"k3")?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_export_json_consistent() -> Result<()> {
+    /// Writer that inserts a KV pair into the database on every write
+    struct Meddler<'a> {
+        db: &'a OpenDB,
+        tag: &'static str,
+        out: Vec<u8>,
+        writes: usize,
+    }
+
+    impl std::io::Write for Meddler<'_> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.db
+                .put(format!("{}_{}", self.tag, self.writes).as_bytes(), b"x")
+                .map_err(std::io::Error::other)?;
+            self.writes += 1;
+            self.out.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let (db, _temp) = setup_test_db()?;
+    // Enough data that the export flushes several times before the kv section
+    for i in 0..200 {
+        let memory = Memory::new(format!("m{}", i), "x".repeat(200), vec![], 0.5);
+        db.insert_memory(&memory)?;
+    }
+    db.put(b"before", b"1")?;
+
+    let kv_keys = |json: &[u8]| -> Vec<String> {
+        let value: serde_json::Value = serde_json::from_slice(json).unwrap();
+        value["kv"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["key"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // A live export sees writes made while it runs...
+    let mut live = Meddler {
+        db: &db,
+        tag: "live",
+        out: Vec::new(),
+        writes: 0,
+    };
+    db.export_json(&mut live)?;
+    assert!(live.writes > 1);
+    assert!(kv_keys(&live.out).len() > 1);
+
+    // ...a consistent one only sees the data present when it started
+    let mut consistent = Meddler {
+        db: &db,
+        tag: "snapshot",
+        out: Vec::new(),
+        writes: 0,
+    };
+    db.export_json_consistent(&mut consistent)?;
+    assert!(consistent.writes > 1);
+    let keys = kv_keys(&consistent.out);
+    assert_eq!(keys.len(), 1 + live.writes);
+    assert!(keys.iter().all(|key| !key.starts_with("snapshot_")));
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&consistent.out).unwrap()["memories"]
+            .as_array()
+            .unwrap()
+            .len(),
+        200
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_normalize_embeddings() -> Result<()> {
+    let options = || {
+        OpenDBOptions::with_dimension(3)
+            .with_distance_metric(DistanceMetric::Euclidean)
+            .with_normalize_embeddings(true)
+    };
+    let raw = OpenDB::open_in_memory_with_options(options())?;
+    let unit = OpenDB::open_in_memory_with_options(options())?;
+
+    let vectors = [
+        ("a", vec![3.0, 4.0, 0.0]),
+        ("b", vec![0.0, 10.0, 1.0]),
+        ("c", vec![-2.0, 0.5, 0.5]),
+        ("d", vec![100.0, 1.0, 0.0]),
+    ];
+    let normalize = |v: &[f32]| {
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        v.iter().map(|x| x / norm).collect::<Vec<f32>>()
+    };
+    for (id, vector) in &vectors {
+        raw.insert_memory(&Memory::new(*id, "", vector.clone(), 0.5))?;
+        unit.insert_memory(&Memory::new(*id, "", normalize(vector), 0.5))?;
+    }
+
+    // Same ranking and distances whether or not the caller normalized
+    let query = [1.0, 1.0, 0.0];
+    let hits = |db: &OpenDB, q: &[f32]| -> Result<Vec<(String, f32)>> {
+        Ok(db
+            .search_similar(q, 4)?
+            .into_iter()
+            .map(|r| (r.id, r.distance))
+            .collect())
+    };
+    let expected = hits(&unit, &normalize(&query))?;
+    for (db, q) in [
+        (&raw, query.to_vec()),
+        (&unit, query.to_vec()),
+        (&raw, normalize(&query)),
+    ] {
+        let got = hits(db, &q)?;
+        assert_eq!(got.len(), expected.len());
+        for ((id, distance), (expected_id, expected_distance)) in got.iter().zip(&expected) {
+            assert_eq!(id, expected_id);
+            assert!((distance - expected_distance).abs() < 1e-5);
+        }
+    }
+    assert_eq!(expected[0].0, "a");
+
+    // Records keep the caller's vector
+    assert_eq!(raw.get_memory("a")?.unwrap().embedding, vec![3.0, 4.0, 0.0]);
+
+    Ok(())
+}